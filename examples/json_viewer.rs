@@ -3,313 +3,300 @@
 
 extern crate time;
 extern crate gtk;
+extern crate glib;
 extern crate cairo;
 extern crate lspace;
 extern crate rustc_serialize;
 
 use std::io::prelude::*;
 use std::io::{self, BufReader};
-use std::fs::File;
+use std::fs::{self, File};
 use std::string::String;
-use std::rc::Rc;
 use std::env;
+use std::process;
+use std::thread;
+use std::rc::{Rc, Weak};
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::os::unix::net::UnixListener;
+use std::os::unix::fs::FileTypeExt;
 
-use rustc_serialize::json::{self, Json};
+use rustc_serialize::json::{Json, Object};
 
 use gtk::traits::*;
 use gtk::signal::Inhibit;
 
-use lspace::graphics::border::Border;
-use lspace::geom::colour::Colour;
-use lspace::layout::flow_layout::FlowIndent;
-use lspace::elements::text_element::{TextStyleParams, TextWeight, TextSlant};
 use lspace::pres::pres::Pres;
-use lspace::pres::primitive;
+use lspace::pres::primitive::Text;
+use lspace::pres::json_format;
+use lspace::pres::json_stream::{self, JsonDocument};
+use lspace::pres::style::{StyleValues, ApplyStyleSheet};
+use lspace::input::keyboard::{KeyEventType, KeyEvent, TKeyboardInteractor};
+use lspace::input::element_events::{ElementEvent, ElementEventType, ElementEventDispatcher,
+                                    TElementEventListener};
+use lspace::elements::debug_overlay::DebugOverlay;
+use lspace::lspace_area::LSpaceArea;
 use lspace::lspace_widget::LSpaceWidget;
+use lspace::inspector::Inspector;
 
 
-/// Style sheet
-struct StyleSheet {
-    text: Rc<TextStyleParams>,
+const KEY_F12: u32 = 0xffc9;
 
-    column_y_spacing: f64,
-
-    row_x_spacing: f64,
-
-    flow_x_spacing: f64,
-    flow_y_spacing: f64,
-    flow_indentation: FlowIndent,
+/// Toggles the layout debug overlay when F12 is pressed
+struct DebugOverlayToggle {
+    area: Weak<LSpaceArea>,
 }
 
-impl StyleSheet {
-    fn default() -> StyleSheet {
-        StyleSheet{
-            text: Rc::new(TextStyleParams::new("Sans serif".to_string(),
-                TextWeight::Normal, TextSlant::Normal, 12.0,
-                &Colour{r: 0.0, g: 0.0, b: 0.0, a: 1.0})),
-
-            column_y_spacing: 0.0,
-
-            row_x_spacing: 0.0,
-
-            flow_x_spacing: 0.0,
-            flow_y_spacing: 0.0,
-            flow_indentation: FlowIndent::NoIndent,
+impl TKeyboardInteractor for DebugOverlayToggle {
+    fn on_key_event(&self, event: &KeyEvent) {
+        if event.event_type() == KeyEventType::Press && event.key_val() == KEY_F12 {
+            match self.area.upgrade() {
+                Some(area) => {
+                    let overlay = match area.debug_overlay() {
+                        None => Some(DebugOverlay::new()),
+                        Some(_) => None
+                    };
+                    area.set_debug_overlay(overlay);
+                },
+                None => {}
+            }
         }
-
     }
+}
 
-    fn with_values(&self,
-                   text_font_family: Option<String>,
-                   text_weight: Option<TextWeight>,
-                   text_slant: Option<TextSlant>,
-                   text_size: Option<f64>,
-                   text_colour: Option<Colour>,
-
-                   column_y_spacing: Option<f64>,
-
-                   row_x_spacing: Option<f64>,
-
-                   flow_x_spacing: Option<f64>,
-                   flow_y_spacing: Option<f64>,
-                   flow_indentation: Option<FlowIndent>) -> StyleSheet {
-        let text = match (text_font_family, text_weight, text_slant, text_size, text_colour) {
-            (None, None, None, None, None) => self.text.clone(),
-            (a, b, c, d, e) => Rc::new(TextStyleParams{
-                font_family: a.map_or(self.text.font_family.clone(), |v| v.clone()),
-                weight: b.unwrap_or(self.text.weight),
-                slant: c.unwrap_or(self.text.slant),
-                size: d.unwrap_or(self.text.size),
-                colour: e.unwrap_or(self.text.colour)})
-        };
-
-        StyleSheet{
-            text: text,
-
-            column_y_spacing: column_y_spacing.unwrap_or(self.column_y_spacing),
-
-            row_x_spacing: row_x_spacing.unwrap_or(self.row_x_spacing),
 
-            flow_x_spacing: flow_x_spacing.unwrap_or(self.flow_x_spacing),
-            flow_y_spacing: flow_y_spacing.unwrap_or(self.flow_y_spacing),
-            flow_indentation: flow_indentation.unwrap_or(self.flow_indentation),
-        }
+/// Encode an element event as a JSON object
+fn event_json(event: &ElementEvent) -> Json {
+    let mut obj = Object::new();
+    obj.insert("event".to_string(), Json::String(event.event_type.name().to_string()));
+    obj.insert("name".to_string(), Json::String(event.name.clone()));
+    obj.insert("id".to_string(), match event.id {
+        Some(ref id) => Json::String(id.clone()),
+        None => Json::Null
+    });
+    let m = &event.mod_state;
+    let modifiers = [("ctrl", m.control_key()), ("shift", m.shift_key()),
+                     ("alt", m.alt_key()), ("super", m.super_key())];
+    obj.insert("modifiers".to_string(), Json::Array(
+        modifiers.iter().filter(|&&(_, down)| down).map(
+            |&(name, _)| Json::String(name.to_string())).collect()));
+    match event.position {
+        Some(p) => {
+            obj.insert("x".to_string(), Json::F64(p.x));
+            obj.insert("y".to_string(), Json::F64(p.y));
+        },
+        None => {}
     }
-
-    fn use_column(&self) -> StyleSheet {
-        StyleSheet{
-            text: self.text.clone(),
-            column_y_spacing: 0.0,
-            row_x_spacing: self.row_x_spacing,
-            flow_x_spacing: self.flow_x_spacing,
-            flow_y_spacing: self.flow_y_spacing,
-            flow_indentation: self.flow_indentation
-        }
+    match event.event_type {
+        ElementEventType::Click => {
+            obj.insert("button".to_string(), Json::U64(event.button as u64));
+        },
+        ElementEventType::KeyPress => {
+            obj.insert("key_val".to_string(), Json::U64(event.key_val as u64));
+            obj.insert("key_string".to_string(), Json::String(event.key_string.clone()));
+        },
+        _ => {}
     }
+    Json::Object(obj)
+}
 
-    fn use_row(&self) -> StyleSheet {
-        StyleSheet{
-            text: self.text.clone(),
-            column_y_spacing: self.column_y_spacing,
-            row_x_spacing: 0.0,
-            flow_x_spacing: self.flow_x_spacing,
-            flow_y_spacing: self.flow_y_spacing,
-            flow_indentation: self.flow_indentation
-        }
-    }
+/// Writes element events to STDOUT as newline-delimited JSON, so that the host process can
+/// respond to them
+struct EventPrinter;
 
-    fn use_flow(&self) -> StyleSheet {
-        StyleSheet{
-            text: self.text.clone(),
-            column_y_spacing: self.column_y_spacing,
-            row_x_spacing: self.row_x_spacing,
-            flow_x_spacing: 0.0,
-            flow_y_spacing: 0.0,
-            flow_indentation: FlowIndent::NoIndent
-        }
+impl TElementEventListener for EventPrinter {
+    fn on_element_event(&self, event: &ElementEvent) {
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        writeln!(out, "{}", event_json(event)).unwrap();
+        out.flush().unwrap();
     }
 }
 
 
-/// Helper function for converting JSON data to a Rust struct
-fn json_to_struct<T, F>(j: &Json, field_names: &Vec<&str>, convert: F) -> T
-        where F: Fn(&Vec<&Json>, &json::Object) -> T {
-    let obj = j.as_object().unwrap();
-    let field_values = field_names.iter().map(|x|
-            obj.get(&x.to_string()).unwrap()).collect();
-    convert(&field_values, obj)
+/// Send each line read from `reader` to `tx`
+fn send_lines<R: BufRead>(reader: R, tx: &Sender<String>) {
+    for line in reader.lines() {
+        match line {
+            Ok(l) => {
+                if tx.send(l).is_err() {
+                    return;
+                }
+            },
+            Err(_) => return
+        }
+    }
 }
 
-/// Helper function for converting JSON data to a Rust enum
-fn json_to_enum_struct<T, F>(j: &Json, field_name: &str, convert: F) -> T
-        where F: Fn(&str, &json::Object) -> T {
-    let obj = j.as_object().unwrap();
-    let string_value = obj.get(field_name).unwrap().as_string().unwrap();
-    convert(string_value, obj)
+/// Read update messages from STDIN on a background thread
+fn stream_from_stdin() -> Receiver<String> {
+    let (tx, rx) = channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        send_lines(stdin.lock(), &tx);
+    });
+    rx
 }
 
-/// Helper function for converting JSON data to a Rust enum
-fn json_str_to_enum<T, F>(j: &Json, convert: F) -> T
-        where F: Fn(&str) -> T {
-    let string_value = j.as_string().unwrap();
-    convert(string_value)
+/// Remove the socket at `path`, if there is one; other kinds of file are left alone
+fn remove_socket(path: &str) {
+    match fs::symlink_metadata(path) {
+        Ok(m) => if m.file_type().is_socket() {
+            fs::remove_file(path).unwrap_or(());
+        },
+        Err(_) => {}
+    }
 }
 
-/// Convert JSON representation TextWeight
-fn json_to_text_weight(j: &Json) -> TextWeight {
-    json_str_to_enum(j, |str_value| {
-        match str_value {
-            "normal" => TextWeight::Normal,
-            "bold" => TextWeight::Bold,
-            _ => panic!(format!("Unknown TextWeight weight - {}", str_value))
+/// Read update messages from clients of a Unix socket on a background thread; clients are
+/// served one at a time. A socket left behind by a previous run is replaced.
+fn stream_from_socket(path: &str) -> Receiver<String> {
+    remove_socket(path);
+    let listener = match UnixListener::bind(path) {
+        Ok(l) => l,
+        Err(e) => {
+            writeln!(io::stderr(), "Could not listen on {}: {}", path, e).unwrap();
+            process::exit(1);
         }
-    })
-}
-
-/// Convert JSON representation TextSlant
-fn json_to_text_slant(j: &Json) -> TextSlant {
-    json_str_to_enum(j, |str_value| {
-        match str_value {
-            "normal" => TextSlant::Normal,
-            "italic" => TextSlant::Italic,
-            _ => panic!(format!("Unknown TextSlant slant - {}", str_value))
+    };
+    let (tx, rx) = channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(s) => send_lines(BufReader::new(s), &tx),
+                Err(_) => {}
+            }
         }
-    })
-}
-
-/// Convert JSON representation Colour
-fn json_to_colour(j: &Json) -> Colour {
-    json_to_struct(j, &vec!["r", "g", "b", "a"], |vals, obj| {
-        Colour::new(vals[0].as_f64().unwrap() as f32, vals[1].as_f64().unwrap() as f32,
-                    vals[2].as_f64().unwrap() as f32, vals[3].as_f64().unwrap() as f32)
-    })
+    });
+    rx
 }
 
-/// Convert JSON representation FlowIndent
-fn json_to_flow_indent(j: &Json) -> FlowIndent {
-    json_to_enum_struct(j, "indent_type", |str_value, obj| {
-        match str_value {
-            "no_indent" => FlowIndent::NoIndent,
-            "first" => FlowIndent::First{indent: obj.get("indent").unwrap().as_f64().unwrap()},
-            "except_first" =>
-                    FlowIndent::ExceptFirst{indent: obj.get("indent").unwrap().as_f64().unwrap()},
-            _ => panic!(format!("Unknown FlowIndent indent_type - {}", str_value))
+/// Apply pending update messages to `doc`, returning true if the document changed
+fn apply_updates(doc: &mut JsonDocument, rx: &Receiver<String>) -> bool {
+    let mut changed = false;
+    while let Ok(line) = rx.try_recv() {
+        if line.trim().is_empty() {
+            continue;
         }
-    })
-}
-
-/// Convert JSON representation Border
-fn json_to_border(j: &Json) -> Border
-{
-    json_to_enum_struct(j, "border_type", |str_value, obj| {
-        match str_value {
-            "solid" => Border::new_solid(
-                obj.get("thickness").unwrap().as_f64().unwrap(),
-                obj.get("inset").unwrap().as_f64().unwrap(),
-                obj.get("rounding").unwrap().as_f64().unwrap(),
-                json_to_colour(obj.get("colour").unwrap()),
-                obj.get("background_colour").map(|x| json_to_colour(x))
-            ),
-            "filled" => Border::new_filled(
-                obj.get("left_margin").unwrap().as_f64().unwrap(),
-                obj.get("right_margin").unwrap().as_f64().unwrap(),
-                obj.get("top_margin").unwrap().as_f64().unwrap(),
-                obj.get("bottom_margin").unwrap().as_f64().unwrap(),
-                obj.get("rounding").unwrap().as_f64().unwrap(),
-                obj.get("background_colour").map(|x| json_to_colour(x))
-            ),
-            _ => panic!(format!("Unknown Border border_type - {}", str_value))
+        match json_stream::decode_message_str(line.as_str()).and_then(|msg| doc.apply(&msg)) {
+            Ok(()) => {changed = true;},
+            Err(e) => {writeln!(io::stderr(), "Could not apply update: {}", e).unwrap();}
         }
-    })
+    }
+    changed
 }
 
-/// Convert Json input data to presentation types
-fn json_to_pres(j: &Json, style: &StyleSheet) -> Pres {
-    let obj = j.as_object().unwrap();
-    let obj_type = obj.get("__type__").unwrap().as_string().unwrap();
-    match obj_type {
-        "Text" => primitive::Text::new(obj.get("text").unwrap().as_string().unwrap().to_string(),
-                            style.text.clone()),
-        "Column" => {
-            let children = obj.get("children").unwrap().as_array().unwrap().iter().map(|x|
-                    json_to_pres(&x, &style.use_column())).collect();
-            primitive::Column::new_full(children, style.column_y_spacing)
-        },
-        "Row" => {
-            let children = obj.get("children").unwrap().as_array().unwrap().iter().map(|x|
-                    json_to_pres(&x, &style.use_row())).collect();
-            primitive::Row::new_full(children, style.row_x_spacing)
-        },
-        "Flow" => {
-            let children = obj.get("children").unwrap().as_array().unwrap().iter().map(|x|
-                    json_to_pres(&x, &style.use_flow())).collect();
-            primitive::Flow::new_full(children, style.flow_x_spacing, style.flow_y_spacing,
-                           style.flow_indentation)
-        },
-        "Border" => {
-            let child = json_to_pres(&obj.get("child").unwrap(), &style);
-            let border = Rc::new(json_to_border(j));
-            primitive::Border::new(child, border)
-        },
-        "ApplyStyleSheet" => {
-            json_to_pres(obj.get("child").unwrap(), &style.with_values(
-                obj.get("text_font_family").and_then(|x| x.as_string()).map(|x| x.to_string()),
-                obj.get("text_weight").map(|x| json_to_text_weight(x)),
-                obj.get("text_slant").map(|x| json_to_text_slant(x)),
-                obj.get("text_size").and_then(|x| x.as_f64()),
-                obj.get("text_colour").map(|x| json_to_colour(x)),
-
-                obj.get("column_y_spacing").and_then(|x| x.as_f64()),
-
-                obj.get("row_x_spacing").and_then(|x| x.as_f64()),
-
-                obj.get("flow_x_spacing").and_then(|x| x.as_f64()),
-                obj.get("flow_y_spacing").and_then(|x| x.as_f64()),
-                obj.get("flow_indentation").map(|x| json_to_flow_indent(x)),
-            ))
+/// Present a streamed document; updates are checked as they are applied, so this should not
+/// fail, but if it does the error is shown in place of the document
+fn doc_pres(doc: &JsonDocument) -> Pres {
+    match doc.pres() {
+        Ok(p) => p,
+        Err(e) => {
+            writeln!(io::stderr(), "Could not present document: {}", e).unwrap();
+            Text::new_inherited(format!("Could not present document: {}", e))
         }
-        _ => panic!(format!("Unknown __type__ - {}", obj_type))
     }
 }
 
+fn viewer_content(doc: Pres) -> Pres {
+    // The viewer's default text size is smaller than the library default
+    ApplyStyleSheet::new(doc, StyleValues{text_size: Some(12.0), ..StyleValues::new()})
+}
+
 
 fn main() {
     // Initialise GTK
     gtk::init().unwrap_or_else(|_| panic!("Failed to initialize GTK."));
 
-    // String buffer for JSON data
-    let mut encoded: String = String::new();
+    // Command line: `[--inspect] [--stream | --socket <path> | <path>]`
+    // With no path, a single document is read from STDIN. `--stream` reads newline-delimited
+    // update messages (see `lspace::pres::json_stream`) from STDIN, and `--socket` reads them
+    // from clients of a Unix socket. `--inspect` opens the element inspector.
+    // Interactions with nodes that bind events are written to STDOUT as newline-delimited JSON,
+    // so progress messages go to STDERR.
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let inspect = args.iter().any(|a| a == "--inspect");
+    args.retain(|a| a != "--inspect");
+
+    let area = Rc::new(LSpaceArea::new());
+
+    let socket_path = match args.get(0).map(|a| a.as_str()) {
+        Some("--socket") => args.get(1).cloned(),
+        _ => None
+    };
+    let updates = match args.get(0).map(|a| a.as_str()) {
+        Some("--stream") => Some(stream_from_stdin()),
+        Some("--socket") => match socket_path {
+            Some(ref path) => Some(stream_from_socket(path.as_str())),
+            None => {
+                writeln!(io::stderr(), "--socket requires a path").unwrap();
+                process::exit(1);
+            }
+        },
+        _ => None
+    };
 
-    // Get the path of the file to load from the command line arguments, or read data from STDIN
-    // if not path was provided
-    let mut args = env::args();
-    args.next();
-    match args.next() {
-        None => {
-            // No path; read from STDIN
-            io::stdin().read_to_string(&mut encoded).unwrap();
+    match updates {
+        Some(rx) => {
+            // Start with an empty document and apply updates as they arrive
+            let mut doc = JsonDocument::empty();
+            area.set_content_pres(viewer_content(doc_pres(&doc)));
+            let area_clone = area.clone();
+            glib::timeout_add(20, move || {
+                if apply_updates(&mut doc, &rx) {
+                    area_clone.update_content_pres(viewer_content(doc_pres(&doc)));
+                }
+                glib::Continue(true)
+            });
         },
-        Some(path) =>  {
-            // Load the file
-            println!("Loading {}....", path);
-            let mut f = File::open(path).unwrap();
-            let mut reader = BufReader::new(f);
-            reader.read_to_string(&mut encoded).unwrap();
+        None => {
+            // String buffer for JSON data
+            let mut encoded: String = String::new();
+            match args.into_iter().next() {
+                None => {
+                    // No path; read from STDIN
+                    io::stdin().read_to_string(&mut encoded).unwrap();
+                },
+                Some(path) =>  {
+                    // Load the file
+                    writeln!(io::stderr(), "Loading {}....", path).unwrap();
+                    let mut f = File::open(path).unwrap();
+                    let mut reader = BufReader::new(f);
+                    reader.read_to_string(&mut encoded).unwrap();
+                }
+            };
+
+            // Decode the JSON content to create the presentation
+            writeln!(io::stderr(), "Creating presentation...").unwrap();
+            let doc = match json_format::decode_str(encoded.as_str()) {
+                Ok(p) => p,
+                Err(e) => {
+                    writeln!(io::stderr(), "Could not load presentation: {}", e).unwrap();
+                    process::exit(1);
+                }
+            };
+            area.set_content_pres(viewer_content(doc));
         }
-    };
+    }
 
-    // Load the JSON content and decode
-    let j = Json::from_str(encoded.as_str()).unwrap();
+    // F12 toggles the layout debug overlay
+    let toggle: Rc<TKeyboardInteractor> = Rc::new(DebugOverlayToggle{
+        area: Rc::downgrade(&area)});
+    area.keyboard().add_interactor(&toggle);
 
-    // Create the presentation from the loaded structure
-    println!("Creating presentation...");
-    let style = StyleSheet::default();
-    let content = json_to_pres(&j, &style);
+    // Report interactions with nodes that bind events
+    let printer: Rc<TElementEventListener> = Rc::new(EventPrinter);
+    let _dispatcher = ElementEventDispatcher::attach(&area, &printer);
 
     // Create the LSpace widget, showing our content
-    println!("Displaying....");
-    let lspace = LSpaceWidget::new(content);
+    writeln!(io::stderr(), "Displaying....").unwrap();
+    let lspace = LSpaceWidget::new_with_area(area);
     let widget = lspace.gtk_widget();
+    widget.grab_focus();
+
+    let inspector = if inspect {Inspector::attach(&lspace)} else {None};
+    if inspect && inspector.is_none() {
+        writeln!(io::stderr(), "Could not open the inspector window").unwrap();
+    }
 
     // Create a GTK window in which to place it
     let window = gtk::Window::new(gtk::WindowType::Toplevel).unwrap();
@@ -328,5 +315,10 @@ fn main() {
 
     // Enter GTK main loop
     gtk::main();
+
+    match socket_path {
+        Some(path) => remove_socket(path.as_str()),
+        None => {}
+    }
 }
 
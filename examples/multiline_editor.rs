@@ -7,193 +7,36 @@ extern crate cairo;
 extern crate lspace;
 extern crate regex;
 
-use std::string::String;
 use std::rc::Rc;
-use std::cell::{RefCell};
-use std::cmp::min;
 
 use gtk::traits::*;
 use gtk::signal::Inhibit;
 
-// for a list of key names see: http://gtk-rs.org/docs/gdk/enums/key/index.html
-use gdk::enums::key;
-
-use lspace::geom::colour::Colour;
-use lspace::elements::element::{ElementRef, elem_as_ref};
-use lspace::elements::container_sequence::TContainerSequenceElement;
-use lspace::elements::{text_element, row, column};
-use lspace::input::keyboard::{KeyEventType, KeyEvent, TKeyboardInteractor};
+use lspace::elements::element::ElementRef;
+use lspace::elements::{text_element, text_editor_element};
 use lspace::lspace_widget::LSpaceWidget;
 use lspace::lspace_area::LSpaceArea;
 
 
-struct MultilineEditorMut {
-    text_elements: Vec<ElementRef>,
-    current_line: usize,
-    column: usize,
-}
-
-struct MultiLineEditor {
-    m: RefCell<MultilineEditorMut>,
-    area: Rc<LSpaceArea>,
-    text_style: Rc<text_element::TextStyleParams>,
-    content: ElementRef
-}
-
-
-impl MultiLineEditor {
-    pub fn new(area: &Rc<LSpaceArea>) -> Rc<MultiLineEditor> {
-        let text_style = Rc::new(text_element::TextStyleParams::default());
-
-        let m = MultilineEditorMut{
-            text_elements: Vec::new(),
-            current_line: 0,
-            column: 0
-        };
-
-        let _self = Rc::new(MultiLineEditor{
-            area: area.clone(),
-            m: RefCell::new(m),
-            text_style: text_style,
-            content: elem_as_ref(column::ColumnElement::new(0.0))
-        });
-        _self.new_line();
-        _self.area.set_content_element(_self.content.clone());
-        _self
-    }
-
-    fn get_content(&self) -> &TContainerSequenceElement {
-        self.content.as_container_sequence().unwrap()
-    }
-
-    fn new_line(&self) -> ElementRef {
-        let line = elem_as_ref(text_element::TextElement::new_in_area(
-            String::from(""), self.text_style.clone(), &self.area));
-        let mut mm = self.m.borrow_mut();
-        let mut index = mm.current_line;
-        if index < mm.text_elements.len() {
-            index = index + 1;
-        }
-        if mm.text_elements.len() > 0 {
-            mm.current_line += 1;
-        }
-        mm.column = 0;
-        mm.text_elements.insert(index, line.clone());
-        self.get_content().set_children(&self.content, &mm.text_elements);
-
-        line
-    }
-
-    fn cursor_left(&self) {
-        let mut mm = self.m.borrow_mut();
-        if mm.column > 0 {
-            mm.column -= 1;
-        }
-    }
-
-    fn cursor_right(&self) {
-        let mut mm = self.m.borrow_mut();
-        let mut text = mm.text_elements[mm.current_line].as_text_element().unwrap().get_text().clone();
-
-        if mm.column < text.len() {
-            mm.column += 1;
-        }
-    }
-
-    fn cursor_up(&self) {
-        let mut mm = self.m.borrow_mut();
-        if mm.current_line == 0 {
-            mm.column = 0;
-        } else {
-            mm.current_line -= 1;
-            let text = mm.text_elements[mm.current_line].as_text_element().unwrap().get_text().clone();
-            mm.column = min(mm.column, text.len());
-        }
-    }
-
-    fn cursor_down(&self) {
-        let mut mm = self.m.borrow_mut();
-        // last line
-        if mm.current_line + 1 >= mm.text_elements.len() {
-            let text = mm.text_elements[mm.current_line].as_text_element().unwrap().get_text().clone();
-            mm.column = text.len();
-        } else {
-            mm.current_line += 1;
-            let text = mm.text_elements[mm.current_line].as_text_element().unwrap().get_text().clone();
-            mm.column = min(mm.column, text.len());
-        }
-    }
-}
-
-impl TKeyboardInteractor for MultiLineEditor {
-    fn on_key_event(&self, event: &KeyEvent) {
-        if event.event_type() == KeyEventType::Press {
-            match event.key_val() as i32 {
-                key::BackSpace => {
-                    let mut mm = self.m.borrow_mut();
-                    let pos = mm.column;
-                    let mut text = mm.text_elements[mm.current_line].as_text_element().unwrap().get_text().clone();
-
-                    let n = text.len();
-                    if n > 0 {
-                        println!("{:?}", vec![n, pos]);
-                        let new_text = String::from(text[0..pos-1].to_string() + &text[pos..n]);
-                        mm.text_elements[mm.current_line].as_text_element().unwrap().set_text(new_text);
-                        mm.column -= 1;
-                    }
-                },
-
-                key::Left => {
-                    self.cursor_left();
-                },
-                key::Right => {
-                    self.cursor_right();
-                },
-                key::Up => {
-                    self.cursor_up();
-                },
-                key::Down => {
-                    self.cursor_down();
-                },
-
-                key::Return => {
-                    self.new_line();
-                },
-
-                // we can ignore shift
-                key::Shift_L => {},
-                key::Shift_R => {},
-
-                _ => {
-                    let mut mm = self.m.borrow_mut();
-                    let mut text = mm.text_elements[mm.current_line].as_text_element().unwrap().get_text().clone();
-                    let n = text.len();
-
-                    println!("Inserting {:?}", event.key_string());
-                    let new_text = text[0..mm.column].to_string() + event.key_string() + &text[mm.column..n];
-                    mm.column += 1;
-                    mm.text_elements[mm.current_line].as_text_element().unwrap().set_text(new_text);
-                }
-            }
-        }
-    }
-}
-
-
 fn main() {
     // Initialise GTK
     gtk::init().unwrap_or_else(|_| panic!("Failed to initialize GTK."));
 
-    let eol_style = Rc::new(text_element::TextStyleParams::new(String::from("Helvetica"),
-                                                               text_element::TextWeight::Normal, text_element::TextSlant::Normal, 11.0, &Colour::new(0.0, 0.5, 1.0, 1.0)));
-
-
     let lspace = Rc::new(LSpaceArea::new());
 
-    let editor_as_interactor: Rc<TKeyboardInteractor> = MultiLineEditor::new(&lspace);
-
-    lspace.keyboard().add_interactor(&editor_as_interactor);
-
+    // The editor handles the keyboard itself while it has the focus, including undo (Ctrl+Z)
+    // and redo (Ctrl+Shift+Z)
+    let text_style = Rc::new(text_element::TextStyleParams::default());
+    let editor = text_editor_element::TextEditorElement::new("", text_style);
+    editor.set_view_height(Some(400.0));
+    editor.set_min_width(600.0);
+    editor.set_on_change(Some(Rc::new(|text: &str| {
+        println!("{} lines", text.split('\n').count());
+    })));
+    let editor: ElementRef = editor;
+
+    lspace.set_content_element(editor.clone());
+    lspace.set_focus(Some(&editor));
 
     // Create the LSpace widget, showing our content
     let lsw = LSpaceWidget::new_with_area(lspace);
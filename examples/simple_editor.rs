@@ -12,56 +12,14 @@ use std::rc::Rc;
 use gtk::traits::*;
 use gtk::signal::Inhibit;
 
-use gdk::enums::key;
-
 use lspace::geom::colour::Colour;
-use lspace::elements::element::{ElementRef, elem_as_ref};
+use lspace::elements::element::elem_as_ref;
 use lspace::elements::container_sequence::TContainerSequenceElement;
-use lspace::elements::{text_element, row, column};
-use lspace::input::keyboard::{KeyEventType, KeyEvent, TKeyboardInteractor};
+use lspace::elements::{text_element, text_entry_element, row, column};
 use lspace::lspace_widget::LSpaceWidget;
 use lspace::lspace_area::LSpaceArea;
 
 
-struct LineEditor {
-    text_elem: ElementRef
-}
-
-impl LineEditor {
-    pub fn new(elem: &ElementRef) -> Rc<LineEditor> {
-        Rc::new(LineEditor{text_elem: elem.clone()})
-    }
-}
-
-impl TKeyboardInteractor for LineEditor {
-    fn on_key_event(&self, event: &KeyEvent) {
-        if event.event_type() == KeyEventType::Press {
-            let mut text = self.text_elem.as_text_element().unwrap().get_text().clone();
-            println!("Key val = {}", event.key_val());
-            match event.key_val() as i32 {
-                key::BackSpace => {
-                    println!("Deleting");
-                    let n = text.len();
-                    let new_text = String::from(&text[0..n-1]);
-                    self.text_elem.as_text_element().unwrap().set_text(new_text);
-                },
-                key::Shift_L | key::Shift_R | key::Control_L | key::Control_R |
-                key::Meta_L | key::Meta_R | key::Alt_L | key::Alt_R |
-                key::Super_L | key::Super_R | key::Hyper_L | key::Hyper_R |
-                key::Caps_Lock | key::Shift_Lock => {
-                    // Ignore
-                },
-                _ => {
-                    println!("Inserting {:?}", event.key_string());
-                    let new_text = text + event.key_string();
-                    self.text_elem.as_text_element().unwrap().set_text(new_text);
-                }
-            }
-        }
-    }
-}
-
-
 fn main() {
     gtk::init().unwrap_or_else(|_| panic!("Failed to initialize GTK."));
 
@@ -75,28 +33,26 @@ fn main() {
 
     let lspace = Rc::new(LSpaceArea::new());
 
-    // The text that will be edited
-    let text = elem_as_ref(text_element::TextElement::new_in_area(String::from("Hello world"),
-                                                                  text_style, &lspace));
+    // The text that will be edited; the entry handles the keyboard itself while it has the
+    // focus
+    let entry = text_entry_element::TextEntryElement::new("Hello world", text_style);
+    entry.set_placeholder(Some(String::from("Type something")));
+    entry.set_on_change(Some(Rc::new(|text: &str| println!("Text = {:?}", text))));
+    let text = elem_as_ref(entry);
 
     // End of line marker (in blue)
     let end_of_line = elem_as_ref(text_element::TextElement::new_in_area(String::from("}{"),
                                                                          eol_style, &lspace));
 
-    // Editor that will mutate the text element
-    let editor_as_interactor: Rc<TKeyboardInteractor> = LineEditor::new(&text);
-
     // Place the editable text and EOL marker in a row
     let line = elem_as_ref(row::RowElement::new(0.0));
-    line.as_container_sequence().unwrap().set_children(&line, &vec![text, end_of_line]);
+    line.as_container_sequence().unwrap().set_children(&line, &vec![text.clone(), end_of_line]);
 
     let content = elem_as_ref(column::ColumnElement::new(0.0));
     content.as_container_sequence().unwrap().set_children(&content, &vec![line]);
 
     lspace.set_content_element(content);
-
-    // Add the editor as a listener
-    lspace.keyboard().add_interactor(&editor_as_interactor);
+    lspace.set_focus(Some(&text));
 
     // Create the LSpace widget, showing our content
     let lsw = LSpaceWidget::new_with_area(lspace);
@@ -0,0 +1,334 @@
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+
+use elements::element::{ElementRef, queue_redraw, queue_resize};
+use animation::easing::Easing;
+use animation::interpolate::TInterpolate;
+use animation::clock::TClock;
+
+
+/// How an animated element must be updated when the animated value changes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AnimationEffect {
+    /// The value only affects the element's appearance
+    Redraw,
+    /// The value affects the element's layout
+    Resize,
+}
+
+
+pub trait TAnimation {
+    /// Update the animation for time `t` (in seconds) since it started. Returns true once
+    /// the animation has finished.
+    fn update(&self, t: f64) -> bool;
+}
+
+
+/// Animates a value from `from` to `to` over `duration` seconds, passing each intermediate
+/// value to `setter` and queueing a redraw or resize of the target elements
+pub struct Animation<T: TInterpolate> {
+    from: T,
+    to: T,
+    duration: f64,
+    easing: Easing,
+    setter: Box<Fn(&T)>,
+    targets: Vec<(ElementRef, AnimationEffect)>,
+}
+
+impl <T: TInterpolate> Animation<T> {
+    pub fn new(from: T, to: T, duration: f64, setter: Box<Fn(&T)>) -> Animation<T> {
+        Animation{from: from, to: to, duration: duration, easing: Easing::Linear,
+                  setter: setter, targets: vec![]}
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Animation<T> {
+        self.easing = easing;
+        self
+    }
+
+    /// Queue a redraw or resize of `elem` each time the value changes
+    pub fn with_target(mut self, elem: &ElementRef, effect: AnimationEffect) -> Animation<T> {
+        self.targets.push((elem.clone(), effect));
+        self
+    }
+}
+
+impl <T: TInterpolate> TAnimation for Animation<T> {
+    fn update(&self, t: f64) -> bool {
+        let fraction = if self.duration > 0.0 {t / self.duration} else {1.0};
+        let value = self.from.interpolate(&self.to, self.easing.apply(fraction));
+        (self.setter)(&value);
+        for &(ref elem, effect) in self.targets.iter() {
+            match effect {
+                AnimationEffect::Redraw => queue_redraw(&**elem),
+                AnimationEffect::Resize => queue_resize(&**elem),
+            }
+        }
+        fraction >= 1.0
+    }
+}
+
+
+/// Identifies an animation or timer registered with an `Animator`
+pub type AnimationId = usize;
+
+/// Notified when an idle `Animator` gains an animation or timer, so that the host can start
+/// calling `Animator::tick` periodically
+pub trait TAnimatorListener {
+    fn notify_animator_active(&self);
+}
+
+
+struct AnimationEntry {
+    id: AnimationId,
+    start: f64,
+    animation: Rc<TAnimation>,
+}
+
+struct TimerEntry {
+    id: AnimationId,
+    interval: f64,
+    next: f64,
+    callback: Rc<Box<Fn() -> bool>>,
+}
+
+struct AnimatorMut {
+    animations: Vec<AnimationEntry>,
+    timers: Vec<TimerEntry>,
+    next_id: AnimationId,
+    listener: Option<Weak<TAnimatorListener>>,
+}
+
+impl AnimatorMut {
+    fn is_active(&self) -> bool {
+        !self.animations.is_empty() || !self.timers.is_empty()
+    }
+
+    /// The listener to notify when going from idle to active; `None` if already active
+    fn listener_to_notify(&self, was_active: bool) -> Option<Rc<TAnimatorListener>> {
+        if was_active {
+            return None;
+        }
+        match self.listener {
+            Some(ref l) => Weak::upgrade(l),
+            None => None
+        }
+    }
+
+    fn new_id(&mut self) -> AnimationId {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+}
+
+/// Runs animations and timers. Time is read from a clock, so that tests can use a
+/// `ManualClock` and call `tick` themselves.
+pub struct Animator {
+    m: RefCell<AnimatorMut>,
+    clock: Rc<TClock>,
+}
+
+impl Animator {
+    pub fn new(clock: Rc<TClock>) -> Animator {
+        Animator{m: RefCell::new(AnimatorMut{animations: vec![], timers: vec![], next_id: 1,
+                                             listener: None}),
+                 clock: clock}
+    }
+
+    pub fn clock(&self) -> Rc<TClock> {
+        self.clock.clone()
+    }
+
+    /// The listener is held weakly, as it is usually the widget that owns this animator
+    pub fn set_listener(&self, listener: Option<&Rc<TAnimatorListener>>) {
+        self.m.borrow_mut().listener = listener.map(|l| Rc::downgrade(l));
+    }
+
+    /// Start `animation`; its initial value is applied immediately
+    pub fn start<A: TAnimation + 'static>(&self, animation: A) -> AnimationId {
+        let animation = Rc::new(animation);
+        animation.update(0.0);
+        let (id, listener) = {
+            let mut mm = self.m.borrow_mut();
+            let was_active = mm.is_active();
+            let id = mm.new_id();
+            mm.animations.push(AnimationEntry{id: id, start: self.clock.now(),
+                                              animation: animation});
+            (id, mm.listener_to_notify(was_active))
+        };
+        listener.map(|l| l.notify_animator_active());
+        id
+    }
+
+    /// Call `callback` every `interval` seconds, until it returns false or the timer is
+    /// cancelled
+    pub fn add_timer<F: Fn() -> bool + 'static>(&self, interval: f64,
+                                                 callback: F) -> AnimationId {
+        let (id, listener) = {
+            let mut mm = self.m.borrow_mut();
+            let was_active = mm.is_active();
+            let id = mm.new_id();
+            mm.timers.push(TimerEntry{id: id, interval: interval,
+                                      next: self.clock.now() + interval,
+                                      callback: Rc::new(Box::new(callback))});
+            (id, mm.listener_to_notify(was_active))
+        };
+        listener.map(|l| l.notify_animator_active());
+        id
+    }
+
+    /// Stop an animation or timer; an animation is left at its current value
+    pub fn cancel(&self, id: AnimationId) {
+        let mut mm = self.m.borrow_mut();
+        mm.animations.retain(|a| a.id != id);
+        mm.timers.retain(|t| t.id != id);
+    }
+
+    /// True while there are animations or timers running
+    pub fn is_active(&self) -> bool {
+        self.m.borrow().is_active()
+    }
+
+    /// Update running animations and fire due timers. Returns true while there are
+    /// animations or timers still running.
+    pub fn tick(&self) -> bool {
+        let now = self.clock.now();
+        let (animations, timers) = {
+            let mm = self.m.borrow();
+            let animations: Vec<(AnimationId, f64, Rc<TAnimation>)> = mm.animations.iter().map(
+                |a| (a.id, a.start, a.animation.clone())).collect();
+            let timers: Vec<(AnimationId, Rc<Box<Fn() -> bool>>)> = mm.timers.iter().filter(
+                |t| t.next <= now).map(|t| (t.id, t.callback.clone())).collect();
+            (animations, timers)
+        };
+
+        // Animations and timers are run outside the borrow, as they may start or cancel others
+        let finished: Vec<AnimationId> = animations.iter().filter(
+            |&&(_, start, ref a)| a.update(now - start)).map(|&(id, _, _)| id).collect();
+        let fired: Vec<(AnimationId, bool)> = timers.iter().map(
+            |&(id, ref callback)| (id, callback())).collect();
+
+        let mut mm = self.m.borrow_mut();
+        mm.animations.retain(|a| !finished.contains(&a.id));
+        for &(id, keep) in fired.iter() {
+            if keep {
+                for t in mm.timers.iter_mut().filter(|t| t.id == id) {
+                    t.next = now + t.interval;
+                }
+            } else {
+                mm.timers.retain(|t| t.id != id);
+            }
+        }
+        mm.is_active()
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::cell::Cell;
+    use super::*;
+    use animation::clock::{TClock, ManualClock};
+    use animation::easing::Easing;
+
+    fn animator() -> (Rc<ManualClock>, Animator) {
+        let clock = Rc::new(ManualClock::new());
+        let c: Rc<TClock> = clock.clone();
+        (clock, Animator::new(c))
+    }
+
+    #[test]
+    fn test_animation() {
+        let (clock, animator) = animator();
+        let value = Rc::new(Cell::new(-1.0));
+        let v = value.clone();
+        animator.start(Animation::new(0.0, 10.0, 2.0, Box::new(move |x: &f64| v.set(*x))));
+        assert_eq!(value.get(), 0.0);
+        clock.advance(0.5);
+        assert!(animator.tick());
+        assert_eq!(value.get(), 2.5);
+        clock.advance(2.0);
+        assert!(!animator.tick());
+        assert_eq!(value.get(), 10.0);
+    }
+
+    #[test]
+    fn test_easing() {
+        let (clock, animator) = animator();
+        let value = Rc::new(Cell::new(-1.0));
+        let v = value.clone();
+        animator.start(Animation::new(0.0, 1.0, 1.0, Box::new(move |x: &f64| v.set(*x)))
+                           .with_easing(Easing::Step));
+        clock.advance(0.5);
+        animator.tick();
+        assert_eq!(value.get(), 0.0);
+        clock.advance(0.5);
+        animator.tick();
+        assert_eq!(value.get(), 1.0);
+    }
+
+    #[test]
+    fn test_timer() {
+        let (clock, animator) = animator();
+        let count = Rc::new(Cell::new(0));
+        let c = count.clone();
+        animator.add_timer(0.5, move || {c.set(c.get() + 1); c.get() < 3});
+        animator.tick();
+        assert_eq!(count.get(), 0);
+        for _ in 0..10 {
+            clock.advance(0.5);
+            animator.tick();
+        }
+        assert_eq!(count.get(), 3);
+        assert!(!animator.is_active());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let (clock, animator) = animator();
+        let count = Rc::new(Cell::new(0));
+        let c = count.clone();
+        let id = animator.add_timer(0.5, move || {c.set(c.get() + 1); true});
+        clock.advance(0.5);
+        animator.tick();
+        animator.cancel(id);
+        clock.advance(0.5);
+        assert!(!animator.tick());
+        assert_eq!(count.get(), 1);
+    }
+
+    struct CountingListener {
+        count: Cell<i32>,
+    }
+
+    impl TAnimatorListener for CountingListener {
+        fn notify_animator_active(&self) {
+            self.count.set(self.count.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_listener() {
+        let (clock, animator) = animator();
+        let listener = Rc::new(CountingListener{count: Cell::new(0)});
+        let l: Rc<TAnimatorListener> = listener.clone();
+        animator.set_listener(Some(&l));
+        animator.add_timer(0.5, || true);
+        animator.add_timer(0.5, || true);
+        assert_eq!(listener.count.get(), 1);
+
+        // The animator must not keep its listener alive
+        let weak = Rc::downgrade(&listener);
+        drop(l);
+        drop(listener);
+        assert!(weak.upgrade().is_none());
+        clock.advance(0.5);
+        assert!(animator.tick());
+    }
+}
@@ -0,0 +1,51 @@
+extern crate time;
+
+use std::cell::Cell;
+
+
+/// Source of the current time, in seconds
+pub trait TClock {
+    fn now(&self) -> f64;
+}
+
+
+/// Clock that reads the system's monotonic timer
+pub struct SystemClock;
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock
+    }
+}
+
+impl TClock for SystemClock {
+    fn now(&self) -> f64 {
+        time::precise_time_s()
+    }
+}
+
+
+/// Clock that only advances when told to; used for testing animations
+pub struct ManualClock {
+    t: Cell<f64>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock{t: Cell::new(0.0)}
+    }
+
+    pub fn set(&self, t: f64) {
+        self.t.set(t);
+    }
+
+    pub fn advance(&self, dt: f64) {
+        self.t.set(self.t.get() + dt);
+    }
+}
+
+impl TClock for ManualClock {
+    fn now(&self) -> f64 {
+        self.t.get()
+    }
+}
@@ -0,0 +1,58 @@
+/// Easing function; maps the fraction of an animation's duration that has elapsed to the
+/// fraction of the change in value that has been applied
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// Jump to the final value at the end
+    Step,
+}
+
+impl Easing {
+    /// Apply the easing function to `t`; `t` is clamped to the range `0` to `1`
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.max(0.0).min(1.0);
+        match self {
+            &Easing::Linear => t,
+            &Easing::EaseIn => t * t * t,
+            &Easing::EaseOut => {
+                let u = 1.0 - t;
+                1.0 - u * u * u
+            },
+            &Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            &Easing::Step => if t < 1.0 {0.0} else {1.0},
+        }
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_end_points() {
+        for e in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut,
+                  Easing::Step].iter() {
+            assert_eq!(e.apply(0.0), 0.0);
+            assert_eq!(e.apply(1.0), 1.0);
+            assert_eq!(e.apply(-1.0), 0.0);
+            assert_eq!(e.apply(2.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_mid_point() {
+        assert_eq!(Easing::Linear.apply(0.5), 0.5);
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.125);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.875);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!(Easing::Step.apply(0.5), 0.0);
+    }
+}
@@ -0,0 +1,65 @@
+use geom::colour::Colour;
+use geom::point2::Point2;
+use geom::vector2::Vector2;
+
+
+/// Values that can be animated
+pub trait TInterpolate {
+    /// Interpolate between `self` (at `t = 0`) and `b` (at `t = 1`)
+    fn interpolate(&self, b: &Self, t: f64) -> Self;
+}
+
+impl TInterpolate for f64 {
+    fn interpolate(&self, b: &f64, t: f64) -> f64 {
+        self + (b - self) * t
+    }
+}
+
+impl TInterpolate for f32 {
+    fn interpolate(&self, b: &f32, t: f64) -> f32 {
+        self + (b - self) * (t as f32)
+    }
+}
+
+impl TInterpolate for Colour {
+    fn interpolate(&self, b: &Colour, t: f64) -> Colour {
+        self.lerp(b, t as f32)
+    }
+}
+
+impl TInterpolate for Point2 {
+    fn interpolate(&self, b: &Point2, t: f64) -> Point2 {
+        Point2::new(self.x.interpolate(&b.x, t), self.y.interpolate(&b.y, t))
+    }
+}
+
+impl TInterpolate for Vector2 {
+    fn interpolate(&self, b: &Vector2, t: f64) -> Vector2 {
+        Vector2::new(self.x.interpolate(&b.x, t), self.y.interpolate(&b.y, t))
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::colour::Colour;
+    use geom::point2::Point2;
+    use geom::vector2::Vector2;
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(2.0f64.interpolate(&4.0, 0.25), 2.5);
+        assert_eq!(Colour::new(0.0, 0.5, 1.0, 1.0).interpolate(&Colour::new(1.0, 0.5, 0.0, 0.0),
+                                                              0.5),
+                   Colour::new(0.5, 0.5, 0.5, 0.5));
+        assert_eq!(Point2::new(0.0, 10.0).interpolate(&Point2::new(10.0, 0.0), 0.5),
+                   Point2::new(5.0, 5.0));
+        assert_eq!(Vector2::new(0.0, 10.0).interpolate(&Vector2::new(10.0, 0.0), 1.0),
+                   Vector2::new(10.0, 0.0));
+    }
+}
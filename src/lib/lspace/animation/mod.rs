@@ -0,0 +1,4 @@
+pub mod easing;
+pub mod interpolate;
+pub mod clock;
+pub mod animator;
@@ -0,0 +1,390 @@
+use editing::undo::TextChange;
+
+
+/// True for characters that form words, for word-wise caret movement and deletion
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Start of the word before character index `pos` in `chars`; skips any non-word characters
+/// immediately before `pos`, then the word
+pub fn prev_word_boundary(chars: &[char], pos: usize) -> usize {
+    let mut i = pos.min(chars.len());
+    while i > 0 && !is_word_char(chars[i - 1]) {
+        i -= 1;
+    }
+    while i > 0 && is_word_char(chars[i - 1]) {
+        i -= 1;
+    }
+    i
+}
+
+/// End of the word after character index `pos` in `chars`; skips any non-word characters
+/// immediately after `pos`, then the word
+pub fn next_word_boundary(chars: &[char], pos: usize) -> usize {
+    let mut i = pos.min(chars.len());
+    while i < chars.len() && !is_word_char(chars[i]) {
+        i += 1;
+    }
+    while i < chars.len() && is_word_char(chars[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// Byte offset of the character at index `pos` in `text`; the length of `text` if `pos` is at
+/// or beyond its end
+pub fn byte_offset(text: &str, pos: usize) -> usize {
+    match text.char_indices().nth(pos) {
+        Some((offset, _)) => offset,
+        None => text.len()
+    }
+}
+
+/// Index of the entry in `offsets`, which must be in ascending order, that is nearest to `x`;
+/// used to find the caret position under the pointer from the x offset of each position
+pub fn nearest_offset(offsets: &[f64], x: f64) -> usize {
+    let mut best = 0;
+    for (i, o) in offsets.iter().enumerate() {
+        if (o - x).abs() < (offsets[best] - x).abs() {
+            best = i;
+        }
+    }
+    best
+}
+
+
+/// A single line of editable text with a caret and an optional selection.
+///
+/// Positions are character indices, ranging from `0` to the number of characters in the text.
+/// The selection runs between the anchor, where it was started, and the caret.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineEdit {
+    text: String,
+    caret: usize,
+    anchor: Option<usize>,
+    max_length: Option<usize>,
+    /// The replacements made since `record_changes` was called; `None` if they are not being
+    /// recorded
+    changes: Option<Vec<TextChange>>,
+}
+
+impl LineEdit {
+    pub fn new(text: &str) -> LineEdit {
+        let mut e = LineEdit{text: String::new(), caret: 0, anchor: None, max_length: None,
+                             changes: None};
+        e.set_text(text);
+        e
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Number of characters in the text
+    pub fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    pub fn anchor(&self) -> Option<usize> {
+        self.anchor
+    }
+
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Limit the text to `max_length` characters, truncating it if necessary
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.max_length = max_length;
+        match max_length {
+            Some(n) if self.len() > n => {
+                let text = self.text.chars().take(n).collect::<String>();
+                self.set_text(&text);
+            },
+            _ => {}
+        }
+    }
+
+    /// Replace the text, placing the caret at its end and clearing the selection. The text
+    /// is truncated to the maximum length.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = match self.max_length {
+            Some(n) => text.chars().take(n).collect(),
+            None => text.to_string()
+        };
+        self.caret = self.len();
+        self.anchor = None;
+    }
+
+    /// The selected range `(start, end)`, with `start < end`; `None` if nothing is selected
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        match self.anchor {
+            Some(a) if a != self.caret => Some((a.min(self.caret), a.max(self.caret))),
+            _ => None
+        }
+    }
+
+    pub fn selected_text(&self) -> &str {
+        match self.selection() {
+            Some((start, end)) => &self.text[byte_offset(&self.text, start)..
+                                             byte_offset(&self.text, end)],
+            None => ""
+        }
+    }
+
+    /// Move the caret to `pos`. If `extend` is true the selection is extended to `pos`,
+    /// otherwise it is cleared.
+    pub fn set_caret(&mut self, pos: usize, extend: bool) {
+        let pos = pos.min(self.len());
+        if extend {
+            if self.anchor.is_none() {
+                self.anchor = Some(self.caret);
+            }
+        } else {
+            self.anchor = None;
+        }
+        self.caret = pos;
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.caret = self.len();
+    }
+
+    pub fn move_left(&mut self, extend: bool) {
+        match self.selection() {
+            Some((start, _)) if !extend => self.set_caret(start, false),
+            _ => {
+                let pos = if self.caret > 0 {self.caret - 1} else {0};
+                self.set_caret(pos, extend);
+            }
+        }
+    }
+
+    pub fn move_right(&mut self, extend: bool) {
+        match self.selection() {
+            Some((_, end)) if !extend => self.set_caret(end, false),
+            _ => {
+                let pos = self.caret + 1;
+                self.set_caret(pos, extend);
+            }
+        }
+    }
+
+    pub fn move_word_left(&mut self, extend: bool) {
+        let chars: Vec<char> = self.text.chars().collect();
+        let pos = prev_word_boundary(&chars, self.caret);
+        self.set_caret(pos, extend);
+    }
+
+    pub fn move_word_right(&mut self, extend: bool) {
+        let chars: Vec<char> = self.text.chars().collect();
+        let pos = next_word_boundary(&chars, self.caret);
+        self.set_caret(pos, extend);
+    }
+
+    pub fn move_home(&mut self, extend: bool) {
+        self.set_caret(0, extend);
+    }
+
+    pub fn move_end(&mut self, extend: bool) {
+        let pos = self.len();
+        self.set_caret(pos, extend);
+    }
+
+    /// Replace the characters from `start` to `end` with `text`, placing the caret after the
+    /// inserted text. Returns false if nothing changed.
+    pub fn replace(&mut self, start: usize, end: usize, text: &str) -> bool {
+        let len = self.len();
+        let end = end.min(len);
+        let start = start.min(end);
+        // Only insert as much of `text` as the maximum length allows
+        let room = match self.max_length {
+            Some(n) => n.saturating_sub(len - (end - start)),
+            None => usize::max_value()
+        };
+        let inserted: String = text.chars().take(room).collect();
+        if start == end && inserted.is_empty() {
+            return false;
+        }
+        let a = byte_offset(&self.text, start);
+        let b = byte_offset(&self.text, end);
+        match self.changes {
+            Some(ref mut changes) => changes.push(TextChange::new(start, &self.text[a..b],
+                                                                  &inserted)),
+            None => {}
+        }
+        let mut new_text = String::with_capacity(self.text.len() + inserted.len());
+        new_text.push_str(&self.text[..a]);
+        new_text.push_str(&inserted);
+        new_text.push_str(&self.text[b..]);
+        self.text = new_text;
+        self.caret = start + inserted.chars().count();
+        self.anchor = None;
+        true
+    }
+
+    /// Start recording the replacements made to the text, discarding any that have been
+    /// recorded; `set_text` is not recorded
+    pub fn record_changes(&mut self) {
+        self.changes = Some(vec![]);
+    }
+
+    /// Stop recording replacements, returning those made since `record_changes` was called
+    pub fn take_changes(&mut self) -> Vec<TextChange> {
+        self.changes.take().unwrap_or(vec![])
+    }
+
+    /// Insert `text` at the caret, replacing the selection
+    pub fn insert(&mut self, text: &str) -> bool {
+        let (start, end) = self.selection().unwrap_or((self.caret, self.caret));
+        self.replace(start, end, text)
+    }
+
+    pub fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some((start, end)) => self.replace(start, end, ""),
+            None => false
+        }
+    }
+
+    /// Delete the selection, or the character before the caret
+    pub fn delete_backward(&mut self) -> bool {
+        if self.selection().is_some() {
+            return self.delete_selection();
+        }
+        let caret = self.caret;
+        caret > 0 && self.replace(caret - 1, caret, "")
+    }
+
+    /// Delete the selection, or the character after the caret
+    pub fn delete_forward(&mut self) -> bool {
+        if self.selection().is_some() {
+            return self.delete_selection();
+        }
+        let caret = self.caret;
+        self.replace(caret, caret + 1, "")
+    }
+
+    /// Delete the selection, or back to the start of the word before the caret
+    pub fn delete_word_backward(&mut self) -> bool {
+        if self.selection().is_some() {
+            return self.delete_selection();
+        }
+        let chars: Vec<char> = self.text.chars().collect();
+        let caret = self.caret;
+        self.replace(prev_word_boundary(&chars, caret), caret, "")
+    }
+
+    /// Delete the selection, or up to the end of the word after the caret
+    pub fn delete_word_forward(&mut self) -> bool {
+        if self.selection().is_some() {
+            return self.delete_selection();
+        }
+        let chars: Vec<char> = self.text.chars().collect();
+        let caret = self.caret;
+        self.replace(caret, next_word_boundary(&chars, caret), "")
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_delete() {
+        let mut e = LineEdit::new("helo");
+        e.set_caret(3, false);
+        assert!(e.insert("l"));
+        assert_eq!(e.text(), "hello");
+        assert_eq!(e.caret(), 4);
+        assert!(e.delete_forward());
+        assert_eq!(e.text(), "hell");
+        assert!(!e.delete_forward());
+        assert!(e.delete_backward());
+        assert_eq!(e.text(), "hel");
+        e.move_home(false);
+        assert!(!e.delete_backward());
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut e = LineEdit::new("hello world");
+        e.move_home(false);
+        e.move_word_right(true);
+        assert_eq!(e.selection(), Some((0, 5)));
+        assert_eq!(e.selected_text(), "hello");
+        assert!(e.insert("goodbye"));
+        assert_eq!(e.text(), "goodbye world");
+        assert_eq!(e.selection(), None);
+        e.select_all();
+        e.move_left(false);
+        assert_eq!(e.caret(), 0);
+        e.move_end(true);
+        assert!(e.delete_backward());
+        assert_eq!(e.text(), "");
+    }
+
+    #[test]
+    fn test_word_movement() {
+        let mut e = LineEdit::new("let x_1 = foo(bar);");
+        e.move_word_left(false);
+        assert_eq!(e.caret(), 14);
+        e.move_word_left(false);
+        assert_eq!(e.caret(), 10);
+        e.move_word_left(false);
+        assert_eq!(e.caret(), 4);
+        e.move_word_right(false);
+        assert_eq!(e.caret(), 7);
+        assert!(e.delete_word_backward());
+        assert_eq!(e.text(), "let  = foo(bar);");
+        assert!(e.delete_word_forward());
+        assert_eq!(e.text(), "let (bar);");
+    }
+
+    #[test]
+    fn test_max_length() {
+        let mut e = LineEdit::new("abc");
+        e.set_max_length(Some(5));
+        assert!(e.insert("defgh"));
+        assert_eq!(e.text(), "abcde");
+        assert!(!e.insert("x"));
+        e.move_home(true);
+        assert!(e.insert("vwxyz!"));
+        assert_eq!(e.text(), "vwxyz");
+        e.set_max_length(Some(2));
+        assert_eq!(e.text(), "vw");
+    }
+
+    #[test]
+    fn test_nearest_offset() {
+        let offsets = [0.0, 7.0, 14.0, 20.0];
+        assert_eq!(nearest_offset(&offsets, -5.0), 0);
+        assert_eq!(nearest_offset(&offsets, 3.0), 0);
+        assert_eq!(nearest_offset(&offsets, 4.0), 1);
+        assert_eq!(nearest_offset(&offsets, 100.0), 3);
+        assert_eq!(nearest_offset(&[], 1.0), 0);
+    }
+
+    #[test]
+    fn test_unicode() {
+        let mut e = LineEdit::new("naïve");
+        assert_eq!(e.len(), 5);
+        e.set_caret(3, false);
+        assert!(e.delete_backward());
+        assert_eq!(e.text(), "nave");
+        e.set_caret(1, false);
+        e.set_caret(3, true);
+        assert_eq!(e.selected_text(), "av");
+    }
+}
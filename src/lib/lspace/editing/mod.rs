@@ -0,0 +1,3 @@
+pub mod line_edit;
+pub mod text_buffer;
+pub mod undo;
@@ -0,0 +1,505 @@
+use editing::line_edit::{prev_word_boundary, next_word_boundary, byte_offset};
+use editing::undo::TextChange;
+
+
+/// A position in a `TextBuffer`; `column` is a character index within the line
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextPos {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl TextPos {
+    pub fn new(line: usize, column: usize) -> TextPos {
+        TextPos{line: line, column: column}
+    }
+}
+
+
+/// Multi-line text held as a list of lines, with a caret and an optional selection.
+///
+/// The lines do not include line separators; `text` joins them with `'\n'`. The selection
+/// runs between the anchor, where it was started, and the caret. Moving the caret up and
+/// down keeps to the column that it was moved from, where lines are long enough.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextBuffer {
+    lines: Vec<String>,
+    caret: TextPos,
+    anchor: Option<TextPos>,
+    /// Column that vertical movement tries to return to
+    goal_column: Option<usize>,
+    /// The replacements made since `record_changes` was called; `None` if they are not being
+    /// recorded
+    changes: Option<Vec<TextChange>>,
+}
+
+impl TextBuffer {
+    pub fn new(text: &str) -> TextBuffer {
+        let mut b = TextBuffer{lines: vec![String::new()], caret: TextPos::new(0, 0),
+                               anchor: None, goal_column: None, changes: None};
+        b.set_text(text);
+        b
+    }
+
+    /// The text, with lines separated by `'\n'`
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Replace the text, placing the caret at its end and clearing the selection
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = text.split('\n').map(|l| l.to_string()).collect();
+        self.caret = self.end_pos();
+        self.anchor = None;
+        self.goal_column = None;
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Number of lines; there is always at least one
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn line(&self, i: usize) -> &str {
+        &self.lines[i]
+    }
+
+    /// Number of characters in line `i`
+    pub fn line_len(&self, i: usize) -> usize {
+        self.lines[i].chars().count()
+    }
+
+    pub fn end_pos(&self) -> TextPos {
+        let last = self.lines.len() - 1;
+        TextPos::new(last, self.line_len(last))
+    }
+
+    /// `pos`, moved to the nearest valid position
+    pub fn clamp(&self, pos: TextPos) -> TextPos {
+        let line = pos.line.min(self.lines.len() - 1);
+        TextPos::new(line, pos.column.min(self.line_len(line)))
+    }
+
+    /// Character index of `pos` in `text`, counting each line break as one character
+    pub fn pos_to_index(&self, pos: TextPos) -> usize {
+        let pos = self.clamp(pos);
+        (0..pos.line).fold(pos.column, |n, i| n + self.line_len(i) + 1)
+    }
+
+    /// Position of the character index `index` in `text`; the end of the text if `index` is
+    /// beyond it
+    pub fn index_to_pos(&self, index: usize) -> TextPos {
+        let mut remaining = index;
+        for i in 0..self.lines.len() {
+            let len = self.line_len(i);
+            if remaining <= len {
+                return TextPos::new(i, remaining);
+            }
+            remaining -= len + 1;
+        }
+        self.end_pos()
+    }
+
+    pub fn caret(&self) -> TextPos {
+        self.caret
+    }
+
+    pub fn anchor(&self) -> Option<TextPos> {
+        self.anchor
+    }
+
+    /// The selected range `(start, end)`, with `start < end`; `None` if nothing is selected
+    pub fn selection(&self) -> Option<(TextPos, TextPos)> {
+        match self.anchor {
+            Some(a) if a != self.caret => Some((a.min(self.caret), a.max(self.caret))),
+            _ => None
+        }
+    }
+
+    /// The text between `start` and `end`
+    pub fn text_between(&self, start: TextPos, end: TextPos) -> String {
+        let (start, end) = (self.clamp(start), self.clamp(end));
+        if start >= end {
+            return String::new();
+        }
+        let first = &self.lines[start.line];
+        if start.line == end.line {
+            return first[byte_offset(first, start.column)..byte_offset(first, end.column)]
+                .to_string();
+        }
+        let mut text = first[byte_offset(first, start.column)..].to_string();
+        for l in self.lines[start.line + 1..end.line].iter() {
+            text.push('\n');
+            text.push_str(l);
+        }
+        let last = &self.lines[end.line];
+        text.push('\n');
+        text.push_str(&last[..byte_offset(last, end.column)]);
+        text
+    }
+
+    pub fn selected_text(&self) -> String {
+        match self.selection() {
+            Some((start, end)) => self.text_between(start, end),
+            None => String::new()
+        }
+    }
+
+    /// Move the caret to `pos`. If `extend` is true the selection is extended to `pos`,
+    /// otherwise it is cleared.
+    pub fn set_caret(&mut self, pos: TextPos, extend: bool) {
+        self.goal_column = None;
+        self.place_caret(pos, extend);
+    }
+
+    fn place_caret(&mut self, pos: TextPos, extend: bool) {
+        let pos = self.clamp(pos);
+        if extend {
+            if self.anchor.is_none() {
+                self.anchor = Some(self.caret);
+            }
+        } else {
+            self.anchor = None;
+        }
+        self.caret = pos;
+    }
+
+    pub fn select_all(&mut self) {
+        self.goal_column = None;
+        self.anchor = Some(TextPos::new(0, 0));
+        self.caret = self.end_pos();
+    }
+
+    /// Move left by a character, onto the end of the previous line from the start of a line
+    pub fn move_left(&mut self, extend: bool) {
+        let c = self.caret;
+        match self.selection() {
+            Some((start, _)) if !extend => self.set_caret(start, false),
+            _ if c.column > 0 => self.set_caret(TextPos::new(c.line, c.column - 1), extend),
+            _ if c.line > 0 => {
+                let pos = TextPos::new(c.line - 1, self.line_len(c.line - 1));
+                self.set_caret(pos, extend);
+            },
+            _ => self.set_caret(c, extend)
+        }
+    }
+
+    /// Move right by a character, onto the start of the next line from the end of a line
+    pub fn move_right(&mut self, extend: bool) {
+        let c = self.caret;
+        match self.selection() {
+            Some((_, end)) if !extend => self.set_caret(end, false),
+            _ if c.column < self.line_len(c.line) => {
+                self.set_caret(TextPos::new(c.line, c.column + 1), extend);
+            },
+            _ if c.line + 1 < self.lines.len() => {
+                self.set_caret(TextPos::new(c.line + 1, 0), extend);
+            },
+            _ => self.set_caret(c, extend)
+        }
+    }
+
+    /// Move to the start of the previous word, or to the end of the previous line from the
+    /// start of a line
+    pub fn move_word_left(&mut self, extend: bool) {
+        let pos = self.prev_word_pos(self.caret);
+        self.set_caret(pos, extend);
+    }
+
+    /// Move to the end of the next word, or to the start of the next line from the end of a
+    /// line
+    pub fn move_word_right(&mut self, extend: bool) {
+        let pos = self.next_word_pos(self.caret);
+        self.set_caret(pos, extend);
+    }
+
+    fn prev_word_pos(&self, pos: TextPos) -> TextPos {
+        if pos.column == 0 {
+            if pos.line > 0 {TextPos::new(pos.line - 1, self.line_len(pos.line - 1))} else {pos}
+        } else {
+            let chars: Vec<char> = self.lines[pos.line].chars().collect();
+            TextPos::new(pos.line, prev_word_boundary(&chars, pos.column))
+        }
+    }
+
+    fn next_word_pos(&self, pos: TextPos) -> TextPos {
+        if pos.column >= self.line_len(pos.line) {
+            if pos.line + 1 < self.lines.len() {TextPos::new(pos.line + 1, 0)} else {pos}
+        } else {
+            let chars: Vec<char> = self.lines[pos.line].chars().collect();
+            TextPos::new(pos.line, next_word_boundary(&chars, pos.column))
+        }
+    }
+
+    /// Move the caret by `delta` lines, up if negative; moving beyond the first or last line
+    /// moves to its start or end. Used for the arrow keys and Page Up / Page Down.
+    pub fn move_lines(&mut self, delta: isize, extend: bool) {
+        let c = self.caret;
+        let goal = self.goal_column.unwrap_or(c.column);
+        let target = c.line as isize + delta;
+        let pos = if target < 0 {
+            TextPos::new(0, 0)
+        } else if target as usize >= self.lines.len() {
+            self.end_pos()
+        } else {
+            TextPos::new(target as usize, goal)
+        };
+        self.place_caret(pos, extend);
+        self.goal_column = Some(goal);
+    }
+
+    pub fn move_up(&mut self, extend: bool) {
+        self.move_lines(-1, extend);
+    }
+
+    pub fn move_down(&mut self, extend: bool) {
+        self.move_lines(1, extend);
+    }
+
+    /// Move to the start of the caret's line
+    pub fn move_home(&mut self, extend: bool) {
+        let pos = TextPos::new(self.caret.line, 0);
+        self.set_caret(pos, extend);
+    }
+
+    /// Move to the end of the caret's line
+    pub fn move_end(&mut self, extend: bool) {
+        let pos = TextPos::new(self.caret.line, self.line_len(self.caret.line));
+        self.set_caret(pos, extend);
+    }
+
+    pub fn move_doc_start(&mut self, extend: bool) {
+        self.set_caret(TextPos::new(0, 0), extend);
+    }
+
+    pub fn move_doc_end(&mut self, extend: bool) {
+        let pos = self.end_pos();
+        self.set_caret(pos, extend);
+    }
+
+    /// Replace the text from `start` to `end` with `text`, which may contain line breaks,
+    /// placing the caret after the inserted text. Returns false if nothing changed.
+    pub fn replace(&mut self, start: TextPos, end: TextPos, text: &str) -> bool {
+        let end = self.clamp(end);
+        let start = self.clamp(start).min(end);
+        if start == end && text.is_empty() {
+            return false;
+        }
+        if self.changes.is_some() {
+            let change = TextChange::new(self.pos_to_index(start), &self.text_between(start, end),
+                                         text);
+            self.changes.as_mut().unwrap().push(change);
+        }
+        let prefix = {
+            let l = &self.lines[start.line];
+            l[..byte_offset(l, start.column)].to_string()
+        };
+        let suffix = {
+            let l = &self.lines[end.line];
+            l[byte_offset(l, end.column)..].to_string()
+        };
+        let mut new_lines: Vec<String> = text.split('\n').map(|l| l.to_string()).collect();
+        let last = new_lines.len() - 1;
+        let caret = TextPos::new(start.line + last, if last == 0 {
+            start.column + new_lines[0].chars().count()
+        } else {
+            new_lines[last].chars().count()
+        });
+        let first = prefix + &new_lines[0];
+        new_lines[0] = first;
+        new_lines[last].push_str(&suffix);
+
+        let tail = self.lines.split_off(end.line + 1);
+        self.lines.truncate(start.line);
+        self.lines.extend(new_lines.into_iter());
+        self.lines.extend(tail.into_iter());
+
+        self.caret = caret;
+        self.anchor = None;
+        self.goal_column = None;
+        true
+    }
+
+    /// Start recording the replacements made to the text, discarding any that have been
+    /// recorded; `set_text` is not recorded
+    pub fn record_changes(&mut self) {
+        self.changes = Some(vec![]);
+    }
+
+    /// Stop recording replacements, returning those made since `record_changes` was called
+    pub fn take_changes(&mut self) -> Vec<TextChange> {
+        self.changes.take().unwrap_or(vec![])
+    }
+
+    /// Insert `text` at the caret, replacing the selection
+    pub fn insert(&mut self, text: &str) -> bool {
+        let (start, end) = self.selection().unwrap_or((self.caret, self.caret));
+        self.replace(start, end, text)
+    }
+
+    /// Split the line at the caret
+    pub fn insert_newline(&mut self) -> bool {
+        self.insert("\n")
+    }
+
+    pub fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some((start, end)) => self.replace(start, end, ""),
+            None => false
+        }
+    }
+
+    /// Delete the selection, or the character before the caret; at the start of a line, the
+    /// line is joined onto the previous line
+    pub fn delete_backward(&mut self) -> bool {
+        if self.selection().is_some() {
+            return self.delete_selection();
+        }
+        let c = self.caret;
+        if c.column > 0 {
+            self.replace(TextPos::new(c.line, c.column - 1), c, "")
+        } else if c.line > 0 {
+            let prev = TextPos::new(c.line - 1, self.line_len(c.line - 1));
+            self.replace(prev, c, "")
+        } else {
+            false
+        }
+    }
+
+    /// Delete the selection, or the character after the caret; at the end of a line, the next
+    /// line is joined onto it
+    pub fn delete_forward(&mut self) -> bool {
+        if self.selection().is_some() {
+            return self.delete_selection();
+        }
+        let c = self.caret;
+        if c.column < self.line_len(c.line) {
+            self.replace(c, TextPos::new(c.line, c.column + 1), "")
+        } else if c.line + 1 < self.lines.len() {
+            self.replace(c, TextPos::new(c.line + 1, 0), "")
+        } else {
+            false
+        }
+    }
+
+    /// Delete the selection, or back to the start of the word before the caret
+    pub fn delete_word_backward(&mut self) -> bool {
+        if self.selection().is_some() {
+            return self.delete_selection();
+        }
+        let c = self.caret;
+        let start = self.prev_word_pos(c);
+        self.replace(start, c, "")
+    }
+
+    /// Delete the selection, or up to the end of the word after the caret
+    pub fn delete_word_forward(&mut self) -> bool {
+        if self.selection().is_some() {
+            return self.delete_selection();
+        }
+        let c = self.caret;
+        let end = self.next_word_pos(c);
+        self.replace(c, end, "")
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let b = TextBuffer::new("one\ntwo\n");
+        assert_eq!(b.line_count(), 3);
+        assert_eq!(b.line(1), "two");
+        assert_eq!(b.line(2), "");
+        assert_eq!(b.text(), "one\ntwo\n");
+        assert_eq!(b.caret(), TextPos::new(2, 0));
+        assert_eq!(TextBuffer::new("").line_count(), 1);
+    }
+
+    #[test]
+    fn test_split_and_join() {
+        let mut b = TextBuffer::new("hello world");
+        b.set_caret(TextPos::new(0, 5), false);
+        assert!(b.insert_newline());
+        assert_eq!(b.lines(), &["hello".to_string(), " world".to_string()][..]);
+        assert_eq!(b.caret(), TextPos::new(1, 0));
+        assert!(b.delete_backward());
+        assert_eq!(b.text(), "hello world");
+        assert_eq!(b.caret(), TextPos::new(0, 5));
+        b.move_end(false);
+        b.insert("\nfoo\nbar");
+        assert_eq!(b.line_count(), 3);
+        assert_eq!(b.caret(), TextPos::new(2, 3));
+        b.set_caret(TextPos::new(1, 3), false);
+        assert!(b.delete_forward());
+        assert_eq!(b.text(), "hello world\nfoobar");
+        b.move_doc_end(false);
+        assert!(!b.delete_forward());
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut b = TextBuffer::new("abc\ndef\nghi");
+        b.set_caret(TextPos::new(0, 1), false);
+        b.set_caret(TextPos::new(2, 2), true);
+        assert_eq!(b.selected_text(), "bc\ndef\ngh");
+        assert!(b.insert("X"));
+        assert_eq!(b.text(), "aXi");
+        assert_eq!(b.caret(), TextPos::new(0, 2));
+        b.select_all();
+        assert_eq!(b.selected_text(), "aXi");
+    }
+
+    #[test]
+    fn test_vertical_movement() {
+        let mut b = TextBuffer::new("a long line\nab\nanother line");
+        b.set_caret(TextPos::new(0, 7), false);
+        b.move_down(false);
+        assert_eq!(b.caret(), TextPos::new(1, 2));
+        b.move_down(false);
+        assert_eq!(b.caret(), TextPos::new(2, 7));
+        b.move_lines(-10, true);
+        assert_eq!(b.caret(), TextPos::new(0, 0));
+        assert_eq!(b.selection(), Some((TextPos::new(0, 0), TextPos::new(2, 7))));
+        b.move_lines(10, false);
+        assert_eq!(b.caret(), b.end_pos());
+    }
+
+    #[test]
+    fn test_index_conversion() {
+        let b = TextBuffer::new("ab
+
+cde");
+        assert_eq!(b.pos_to_index(TextPos::new(0, 2)), 2);
+        assert_eq!(b.pos_to_index(TextPos::new(1, 0)), 3);
+        assert_eq!(b.pos_to_index(TextPos::new(2, 1)), 5);
+        for i in 0..8 {
+            assert_eq!(b.pos_to_index(b.index_to_pos(i)), i);
+        }
+        assert_eq!(b.index_to_pos(100), b.end_pos());
+    }
+
+    #[test]
+    fn test_word_movement() {
+        let mut b = TextBuffer::new("foo bar\nbaz");
+        b.set_caret(TextPos::new(1, 0), false);
+        b.move_word_left(false);
+        assert_eq!(b.caret(), TextPos::new(0, 7));
+        b.move_word_left(false);
+        assert_eq!(b.caret(), TextPos::new(0, 4));
+        assert!(b.delete_word_forward());
+        assert_eq!(b.text(), "foo \nbaz");
+        assert!(b.delete_word_forward());
+        assert_eq!(b.text(), "foo baz");
+    }
+}
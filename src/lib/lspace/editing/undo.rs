@@ -0,0 +1,508 @@
+use std::rc::Rc;
+
+use editing::line_edit::LineEdit;
+use editing::text_buffer::{TextBuffer, TextPos};
+use input::keyboard::{KeyEvent, KeyEventType, TKeyboardInteractor};
+
+
+/// Key values of Z, for the undo and redo bindings
+const KEY_LOWER_Z: u32 = 0x007a;
+const KEY_UPPER_Z: u32 = 0x005a;
+
+/// Maximum number of undo steps kept by default
+const DEFAULT_LIMIT: usize = 1000;
+
+
+/// Caret and selection anchor, as character indices into the whole text
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CaretState {
+    pub caret: usize,
+    pub anchor: Option<usize>,
+}
+
+impl CaretState {
+    pub fn new(caret: usize, anchor: Option<usize>) -> CaretState {
+        CaretState{caret: caret, anchor: anchor}
+    }
+}
+
+
+/// A replacement made to a text: `old_text` at `start`, a character index into the whole text,
+/// was replaced with `new_text`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChange {
+    pub start: usize,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+impl TextChange {
+    pub fn new(start: usize, old_text: &str, new_text: &str) -> TextChange {
+        TextChange{start: start, old_text: old_text.to_string(), new_text: new_text.to_string()}
+    }
+}
+
+
+/// Text that can be edited through an `UndoHistory`; positions are character indices into the
+/// whole text
+pub trait TUndoable {
+    /// Start recording the replacements made to the text
+    fn undo_record_changes(&mut self);
+    /// Stop recording replacements, returning those that were made
+    fn undo_take_changes(&mut self) -> Vec<TextChange>;
+    fn undo_caret_state(&self) -> CaretState;
+    fn undo_replace(&mut self, start: usize, end: usize, text: &str);
+    fn undo_set_caret_state(&mut self, state: CaretState);
+}
+
+impl TUndoable for LineEdit {
+    fn undo_record_changes(&mut self) {
+        self.record_changes();
+    }
+
+    fn undo_take_changes(&mut self) -> Vec<TextChange> {
+        self.take_changes()
+    }
+
+    fn undo_caret_state(&self) -> CaretState {
+        CaretState::new(self.caret(), self.anchor())
+    }
+
+    fn undo_replace(&mut self, start: usize, end: usize, text: &str) {
+        self.replace(start, end, text);
+    }
+
+    fn undo_set_caret_state(&mut self, state: CaretState) {
+        match state.anchor {
+            Some(a) => {
+                self.set_caret(a, false);
+                self.set_caret(state.caret, true);
+            },
+            None => self.set_caret(state.caret, false)
+        }
+    }
+}
+
+impl TUndoable for TextBuffer {
+    fn undo_record_changes(&mut self) {
+        self.record_changes();
+    }
+
+    fn undo_take_changes(&mut self) -> Vec<TextChange> {
+        self.take_changes()
+    }
+
+    fn undo_caret_state(&self) -> CaretState {
+        CaretState::new(self.pos_to_index(self.caret()),
+                        self.anchor().map(|a| self.pos_to_index(a)))
+    }
+
+    fn undo_replace(&mut self, start: usize, end: usize, text: &str) {
+        let (start, end) = (self.index_to_pos(start), self.index_to_pos(end));
+        self.replace(start, end, text);
+    }
+
+    fn undo_set_caret_state(&mut self, state: CaretState) {
+        let caret: TextPos = self.index_to_pos(state.caret);
+        match state.anchor {
+            Some(a) => {
+                let anchor = self.index_to_pos(a);
+                self.set_caret(anchor, false);
+                self.set_caret(caret, true);
+            },
+            None => self.set_caret(caret, false)
+        }
+    }
+}
+
+
+/// The kind of an edit; consecutive edits of the same kind may be coalesced into one step
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EditKind {
+    /// Typing
+    Insert,
+    /// Deleting with Backspace or Delete
+    Delete,
+    /// Any other change, e.g. replacing the selection or inserting a line break
+    Replace,
+}
+
+
+/// A reversible edit: `old_text` at `start` was replaced with `new_text`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditCommand {
+    pub kind: EditKind,
+    pub start: usize,
+    pub old_text: String,
+    pub new_text: String,
+    pub before: CaretState,
+    pub after: CaretState,
+}
+
+impl EditCommand {
+    fn old_end(&self) -> usize {
+        self.start + self.old_text.chars().count()
+    }
+
+    fn new_end(&self) -> usize {
+        self.start + self.new_text.chars().count()
+    }
+
+    /// Merge `next`, which immediately follows `self`, into `self` if both are typing or both
+    /// are deletion in the same direction. Typing a line break or typing over a selection
+    /// starts a new step.
+    fn coalesce(&mut self, next: &EditCommand) -> bool {
+        if self.kind != next.kind || self.after != next.before {
+            return false;
+        }
+        match self.kind {
+            EditKind::Insert => {
+                if next.old_text.is_empty() && next.start == self.new_end() &&
+                        !next.new_text.contains('\n') {
+                    self.new_text.push_str(&next.new_text);
+                    self.after = next.after;
+                    return true;
+                }
+            },
+            EditKind::Delete => {
+                if self.new_text.is_empty() && next.new_text.is_empty() {
+                    if next.old_end() == self.start {
+                        // Backspace
+                        self.old_text = next.old_text.clone() + &self.old_text;
+                        self.start = next.start;
+                        self.after = next.after;
+                        return true;
+                    } else if next.start == self.start {
+                        // Delete
+                        self.old_text.push_str(&next.old_text);
+                        self.after = next.after;
+                        return true;
+                    }
+                }
+            },
+            EditKind::Replace => {}
+        }
+        false
+    }
+
+    pub fn undo<T: TUndoable>(&self, target: &mut T) {
+        target.undo_replace(self.start, self.new_end(), &self.old_text);
+        target.undo_set_caret_state(self.before);
+    }
+
+    pub fn redo<T: TUndoable>(&self, target: &mut T) {
+        target.undo_replace(self.start, self.old_end(), &self.new_text);
+        target.undo_set_caret_state(self.after);
+    }
+}
+
+
+/// History of the edits made to a text, for undo and redo
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoHistory {
+    undo_stack: Vec<EditCommand>,
+    redo_stack: Vec<EditCommand>,
+    limit: usize,
+    /// False once coalescing has been broken, e.g. by moving the caret
+    can_coalesce: bool,
+}
+
+impl UndoHistory {
+    pub fn new() -> UndoHistory {
+        UndoHistory{undo_stack: vec![], redo_stack: vec![], limit: DEFAULT_LIMIT,
+                    can_coalesce: false}
+    }
+
+    /// Keep at most `limit` undo steps, discarding the oldest
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.can_coalesce = false;
+    }
+
+    /// Record `cmd`, coalescing it with the previous step where possible. Clears the redo
+    /// history.
+    pub fn record(&mut self, cmd: EditCommand) {
+        self.redo_stack.clear();
+        let coalesced = self.can_coalesce && match self.undo_stack.last_mut() {
+            Some(last) => last.coalesce(&cmd),
+            None => false
+        };
+        if !coalesced {
+            self.undo_stack.push(cmd);
+            self.trim();
+        }
+        self.can_coalesce = true;
+    }
+
+    /// Record the edit made by `f` to `target`; `f` returns true if it changed the text.
+    /// Returns the result of `f`. Each replacement that `f` makes is recorded as it is made, so
+    /// the cost does not depend on the length of the text.
+    pub fn record_edit<T: TUndoable, F: FnOnce(&mut T) -> bool>(&mut self, target: &mut T,
+                                                               kind: EditKind, f: F) -> bool {
+        let before = target.undo_caret_state();
+        target.undo_record_changes();
+        let changed = f(target);
+        let changes = target.undo_take_changes();
+        if changed {
+            let after = target.undo_caret_state();
+            let n = changes.len();
+            let mut caret = before;
+            for (i, c) in changes.into_iter().enumerate() {
+                // A replacement leaves the caret after the inserted text
+                let next = if i + 1 == n {after} else {
+                    CaretState::new(c.start + c.new_text.chars().count(), None)
+                };
+                if c.old_text != c.new_text {
+                    self.record(EditCommand{kind: kind, start: c.start, old_text: c.old_text,
+                                            new_text: c.new_text, before: caret, after: next});
+                }
+                caret = next;
+            }
+        }
+        changed
+    }
+
+    /// Start a new undo step with the next edit; called when the caret is moved
+    pub fn break_coalescing(&mut self) {
+        self.can_coalesce = false;
+    }
+
+    /// Undo the most recent step; returns false if there is nothing to undo
+    pub fn undo<T: TUndoable>(&mut self, target: &mut T) -> bool {
+        match self.undo_stack.pop() {
+            Some(cmd) => {
+                cmd.undo(target);
+                self.redo_stack.push(cmd);
+                self.can_coalesce = false;
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Redo the most recently undone step; returns false if there is nothing to redo
+    pub fn redo<T: TUndoable>(&mut self, target: &mut T) -> bool {
+        match self.redo_stack.pop() {
+            Some(cmd) => {
+                cmd.redo(target);
+                self.undo_stack.push(cmd);
+                self.can_coalesce = false;
+                true
+            },
+            None => false
+        }
+    }
+
+    fn trim(&mut self) {
+        if self.undo_stack.len() > self.limit {
+            let excess = self.undo_stack.len() - self.limit;
+            self.undo_stack.drain(..excess);
+        }
+    }
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UndoAction {
+    Undo,
+    Redo,
+}
+
+/// The undo action bound to a key press: Ctrl+Z undoes and Ctrl+Shift+Z redoes
+pub fn undo_action_for_key(event: &KeyEvent) -> Option<UndoAction> {
+    let mods = event.mod_state();
+    if event.event_type() != KeyEventType::Press || !mods.control_key() || mods.alt_key() {
+        return None;
+    }
+    match event.key_val() {
+        KEY_LOWER_Z | KEY_UPPER_Z => {
+            Some(if mods.shift_key() {UndoAction::Redo} else {UndoAction::Undo})
+        },
+        _ => None
+    }
+}
+
+
+/// Keyboard interactor that binds Ctrl+Z and Ctrl+Shift+Z to undo and redo functions, for
+/// applications that keep their own undo history. The editing elements handle these keys
+/// themselves while they have the focus.
+pub struct UndoKeyBinding {
+    undo: Rc<Fn()>,
+    redo: Rc<Fn()>,
+}
+
+impl UndoKeyBinding {
+    pub fn new(undo: Rc<Fn()>, redo: Rc<Fn()>) -> Rc<UndoKeyBinding> {
+        Rc::new(UndoKeyBinding{undo: undo, redo: redo})
+    }
+}
+
+impl TKeyboardInteractor for UndoKeyBinding {
+    fn on_key_event(&self, event: &KeyEvent) {
+        match undo_action_for_key(event) {
+            Some(UndoAction::Undo) => (self.undo)(),
+            Some(UndoAction::Redo) => (self.redo)(),
+            None => {}
+        }
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editing::line_edit::LineEdit;
+    use editing::text_buffer::{TextBuffer, TextPos};
+    use input::inputmodifier::{InputModifierState, KEY_CTRL, KEY_SHIFT};
+    use input::keyboard::KeyEvent;
+
+    fn type_text(history: &mut UndoHistory, e: &mut LineEdit, text: &str) {
+        for c in text.chars() {
+            history.record_edit(e, EditKind::Insert, |e| e.insert(&c.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_record_changes() {
+        let mut e = LineEdit::new("abc");
+        e.replace(0, 1, "x");
+        e.record_changes();
+        e.replace(1, 2, "YZ");
+        assert_eq!(e.take_changes(), vec![TextChange::new(1, "b", "YZ")]);
+        assert_eq!(e.take_changes(), vec![]);
+
+        let mut b = TextBuffer::new("one\ntwo");
+        b.record_changes();
+        b.replace(TextPos::new(0, 2), TextPos::new(1, 1), "");
+        assert_eq!(b.take_changes(), vec![TextChange::new(2, "e\nt", "")]);
+    }
+
+    #[test]
+    fn test_several_replacements() {
+        let mut h = UndoHistory::new();
+        let mut e = LineEdit::new("abc");
+        h.record_edit(&mut e, EditKind::Replace, |e| e.replace(0, 1, "x") && e.replace(2, 3, "z"));
+        assert_eq!(e.text(), "xbz");
+        assert!(h.undo(&mut e));
+        assert_eq!(e.text(), "xbc");
+        assert!(h.undo(&mut e));
+        assert_eq!(e.text(), "abc");
+        assert_eq!(e.caret(), 3);
+    }
+
+    #[test]
+    fn test_typing_is_coalesced() {
+        let mut h = UndoHistory::new();
+        let mut e = LineEdit::new("");
+        type_text(&mut h, &mut e, "hello");
+        e.move_home(false);
+        h.break_coalescing();
+        type_text(&mut h, &mut e, "oh ");
+        assert_eq!(e.text(), "oh hello");
+        assert!(h.undo(&mut e));
+        assert_eq!(e.text(), "hello");
+        assert_eq!(e.caret(), 0);
+        assert!(h.undo(&mut e));
+        assert_eq!(e.text(), "");
+        assert!(!h.undo(&mut e));
+        assert!(h.redo(&mut e));
+        assert_eq!(e.text(), "hello");
+        assert_eq!(e.caret(), 5);
+        assert!(h.redo(&mut e));
+        assert_eq!(e.text(), "oh hello");
+        assert!(!h.redo(&mut e));
+    }
+
+    #[test]
+    fn test_deletion_is_coalesced() {
+        let mut h = UndoHistory::new();
+        let mut e = LineEdit::new("hello world");
+        for _ in 0..3 {
+            h.record_edit(&mut e, EditKind::Delete, |e| e.delete_backward());
+        }
+        assert_eq!(e.text(), "hello wo");
+        e.set_caret(0, false);
+        h.break_coalescing();
+        for _ in 0..2 {
+            h.record_edit(&mut e, EditKind::Delete, |e| e.delete_forward());
+        }
+        assert_eq!(e.text(), "llo wo");
+        h.undo(&mut e);
+        assert_eq!(e.text(), "hello wo");
+        h.undo(&mut e);
+        assert_eq!(e.text(), "hello world");
+        assert_eq!(e.caret(), 11);
+        assert!(!h.can_undo());
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo() {
+        let mut h = UndoHistory::new();
+        let mut e = LineEdit::new("");
+        type_text(&mut h, &mut e, "abc");
+        h.undo(&mut e);
+        assert!(h.can_redo());
+        type_text(&mut h, &mut e, "x");
+        assert!(!h.can_redo());
+    }
+
+    #[test]
+    fn test_selection_restored() {
+        let mut h = UndoHistory::new();
+        let mut b = TextBuffer::new("one\ntwo\nthree");
+        b.set_caret(TextPos::new(0, 1), false);
+        b.set_caret(TextPos::new(2, 2), true);
+        h.record_edit(&mut b, EditKind::Replace, |b| b.insert("X"));
+        assert_eq!(b.text(), "oXree");
+        h.undo(&mut b);
+        assert_eq!(b.text(), "one\ntwo\nthree");
+        assert_eq!(b.selection(), Some((TextPos::new(0, 1), TextPos::new(2, 2))));
+        h.redo(&mut b);
+        assert_eq!(b.text(), "oXree");
+        assert_eq!(b.caret(), TextPos::new(0, 2));
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut h = UndoHistory::new();
+        h.set_limit(2);
+        let mut e = LineEdit::new("");
+        for c in ["a", "b", "c"].iter() {
+            h.break_coalescing();
+            h.record_edit(&mut e, EditKind::Insert, |e| e.insert(c));
+        }
+        assert!(h.undo(&mut e));
+        assert!(h.undo(&mut e));
+        assert!(!h.undo(&mut e));
+        assert_eq!(e.text(), "a");
+    }
+
+    #[test]
+    fn test_undo_keys() {
+        let key = |keys: u16, key_val: u32| KeyEvent::new_press(
+            InputModifierState::from_values(keys), key_val, String::new());
+        assert_eq!(undo_action_for_key(&key(KEY_CTRL, 0x7a)), Some(UndoAction::Undo));
+        assert_eq!(undo_action_for_key(&key(KEY_CTRL | KEY_SHIFT, 0x5a)),
+                   Some(UndoAction::Redo));
+        assert_eq!(undo_action_for_key(&key(0, 0x7a)), None);
+        assert_eq!(undo_action_for_key(&key(KEY_CTRL, 0x61)), None);
+    }
+}
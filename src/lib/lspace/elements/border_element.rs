@@ -1,6 +1,7 @@
 use cairo::Context;
 
 use std::rc::Rc;
+use std::any::Any;
 use std::cell::{RefCell, Ref, RefMut};
 
 use layout::lalloc::LAlloc;
@@ -9,7 +10,7 @@ use geom::bbox2::BBox2;
 use graphics::border::Border;
 use elements::element_ctx::ElementLayoutContext;
 use elements::element_layout::{ElementReq, ElementAlloc};
-use elements::element::{TElement, ElementRef, ElementParentMut, queue_resize};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, queue_resize};
 use elements::container::TContainerElement;
 use elements::bin::{TBinElement, BinComponentMut};
 use elements::container_sequence::{TContainerSequenceElement};
@@ -18,23 +19,40 @@ use elements::root_element::{TRootElement};
 
 struct BorderElementMut {
     parent: ElementParentMut,
+    identity: ElementIdentity,
     req: ElementReq,
     alloc: ElementAlloc,
     bin: BinComponentMut,
+    border: Rc<Border>,
 }
 
 pub struct BorderElement {
     m: RefCell<BorderElementMut>,
-    border: Rc<Border>,
 }
 
 impl BorderElement {
     pub fn new(border: &Rc<Border>) -> BorderElement {
         return BorderElement{m: RefCell::new(BorderElementMut{
-            parent: ElementParentMut::new(),
+            parent: ElementParentMut::new(), identity: ElementIdentity::new(),
             req: ElementReq::new(), alloc: ElementAlloc::new(),
-            bin: BinComponentMut::new()}),
-            border: border.clone()};
+            bin: BinComponentMut::new(),
+            border: border.clone()})};
+    }
+
+    pub fn get_border(&self) -> Rc<Border> {
+        return self.m.borrow().border.clone();
+    }
+
+    pub fn set_border(&self, border: &Rc<Border>) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let changed = *mm.border != **border;
+            mm.border = border.clone();
+            changed
+        };
+        if changed {
+            queue_resize(self);
+        }
     }
 }
 
@@ -56,6 +74,15 @@ impl TElement for BorderElement {
         return None;
     }
 
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "BorderElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
     /// Parent get and set methods
     fn get_parent(&self) -> Option<ElementRef> {
         return self.m.borrow().parent.get().clone();
@@ -65,6 +92,10 @@ impl TElement for BorderElement {
         self.m.borrow_mut().parent.set(p);
     }
 
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
     // Layout structure acquisition
     fn element_req(&self) -> Ref<ElementReq> {
         return Ref::map(self.m.borrow(), |m| &m.req);
@@ -88,14 +119,21 @@ impl TElement for BorderElement {
         return self.m.borrow_mut().req.update_y_req(y_req);
     }
 
+    /// Border insets, for debug drawing
+    fn debug_insets(&self) -> Option<(f64, f64, f64, f64)> {
+        let mm = self.m.borrow();
+        return Some((mm.border.left_margin(), mm.border.right_margin(),
+                     mm.border.top_margin(), mm.border.bottom_margin()));
+    }
+
     /// Paint the element content that is contributed by the element itself
     fn draw_self(&self, cairo_ctx: &Context, visible_region: &BBox2) {
         let mm = self.m.borrow();
         let alloc = &mm.alloc;
-        self.border.draw_background(cairo_ctx,
+        mm.border.draw_background(cairo_ctx,
                                     alloc.x_alloc.pos_in_parent(), alloc.y_alloc.pos_in_parent(),
                                     alloc.x_alloc.actual_size(), alloc.y_alloc.actual_size());
-        self.border.draw(cairo_ctx,
+        mm.border.draw(cairo_ctx,
                          alloc.x_alloc.pos_in_parent(), alloc.y_alloc.pos_in_parent(),
                          alloc.x_alloc.actual_size(), alloc.y_alloc.actual_size());
     }
@@ -136,15 +174,15 @@ impl TContainerElement for BorderElement {
             None => LReq::new_empty(),
             Some(ref ch) => ch.element_req().x_req.clone()
         };
-        child_x_req.apply_border(self.border.left_margin(), self.border.right_margin())
+        child_x_req.apply_border(mm.border.left_margin(), mm.border.right_margin())
     }
 
     fn compute_child_x_allocs(&self) -> Vec<LAlloc> {
         let mm = self.m.borrow();
         match mm.bin.get_child() {
             None => vec![],
-            Some(_) => vec![mm.alloc.x_alloc.apply_border(self.border.left_margin(),
-                                                          self.border.right_margin())]
+            Some(_) => vec![mm.alloc.x_alloc.apply_border(mm.border.left_margin(),
+                                                          mm.border.right_margin())]
         }
     }
 
@@ -154,15 +192,15 @@ impl TContainerElement for BorderElement {
             None => LReq::new_empty(),
             Some(ref ch) => ch.element_req().y_req.clone()
         };
-        child_y_req.apply_border(self.border.top_margin(), self.border.bottom_margin())
+        child_y_req.apply_border(mm.border.top_margin(), mm.border.bottom_margin())
     }
 
     fn compute_child_y_allocs(&self) -> Vec<LAlloc> {
         let mm = self.m.borrow();
         match mm.bin.get_child() {
             None => vec![],
-            Some(_) => vec![mm.alloc.y_alloc.apply_border(self.border.top_margin(),
-                                                          self.border.bottom_margin())]
+            Some(_) => vec![mm.alloc.y_alloc.apply_border(mm.border.top_margin(),
+                                                          mm.border.bottom_margin())]
         }
     }
 }
@@ -174,14 +212,12 @@ impl TBinElement for BorderElement {
     }
 
     fn set_child(&self, self_ref: &ElementRef, child: ElementRef) {
-        let mut mm = self.m.borrow_mut();
-        mm.bin.set_child(self_ref, child);
+        self.m.borrow_mut().bin.set_child(self_ref, child);
         queue_resize(self);
     }
 
     fn clear_child(&self) {
-        let mut mm = self.m.borrow_mut();
-        mm.bin.clear_child();
+        self.m.borrow_mut().bin.clear_child();
         queue_resize(self);
     }
 }
@@ -0,0 +1,354 @@
+use cairo::Context;
+
+use std::any::Any;
+use std::cell::{RefCell, Ref, RefMut};
+
+use layout::lreq::LReq;
+use layout::lalloc::LAlloc;
+use geom::bbox2::BBox2;
+use geom::point2::Point2;
+use geom::fastminmax::{fast_min, fast_max};
+
+use elements::element_ctx::ElementLayoutContext;
+use elements::element_layout::{ElementReq, ElementAlloc};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, elems_are_same,
+                        queue_resize, queue_redraw};
+use elements::container::{TContainerElement, draw_bbox_changed};
+use elements::bin::{TBinElement};
+use elements::container_sequence::{TContainerSequenceElement};
+use elements::root_element::{TRootElement};
+
+
+struct CanvasElementMut {
+    parent: ElementParentMut,
+    identity: ElementIdentity,
+    req: ElementReq,
+    alloc: ElementAlloc,
+    children: Vec<ElementRef>,
+    /// The position of each child, in the co-ordinate space of the canvas
+    positions: Vec<Point2>,
+    /// Set when the children or their positions change; the X requisition must then be
+    /// recomputed even if the requisitions of the children are unchanged
+    x_req_stale: bool,
+}
+
+impl CanvasElementMut {
+    /// The horizontal range `(start, end)` covered by the children and the origin, in the
+    /// co-ordinate space of the positions
+    fn x_extent(&self) -> (f64, f64) {
+        return self.children.iter().zip(self.positions.iter()).fold((0.0, 0.0), |(s, e), (c, p)| {
+            (fast_min(s, p.x), fast_max(e, p.x + c.element_req().x_req.size().size()))
+        });
+    }
+
+    fn y_extent(&self) -> (f64, f64) {
+        return self.children.iter().zip(self.positions.iter()).fold((0.0, 0.0), |(s, e), (c, p)| {
+            (fast_min(s, p.y), fast_max(e, p.y + c.element_req().y_req.size().size()))
+        });
+    }
+
+    /// Where the origin of the positions lies within the canvas; it is moved right and down
+    /// from the top left corner when children have negative positions
+    fn origin(&self) -> Point2 {
+        return Point2::new(-self.x_extent().0, -self.y_extent().0);
+    }
+
+    /// Requisition that reaches the far edge of every child
+    fn x_req(&self) -> LReq {
+        let (start, end) = self.x_extent();
+        return LReq::new_fixed_size(end - start);
+    }
+
+    fn y_req(&self) -> LReq {
+        let (start, end) = self.y_extent();
+        return LReq::new_fixed_size(end - start);
+    }
+
+    fn index_of(&self, elem: &ElementRef) -> Option<usize> {
+        return self.children.iter().position(|c| elems_are_same(c, elem));
+    }
+}
+
+/// Places its children at explicit positions, at their natural sizes. The canvas covers the
+/// union of the bounding boxes of its children, along with the origin of the positions. Children
+/// may have negative positions; the origin is then placed to the right of and below the top left
+/// corner of the canvas, so that every child lies within it and can be hit.
+pub struct CanvasElement {
+    m: RefCell<CanvasElementMut>,
+}
+
+
+impl CanvasElement {
+    pub fn new() -> CanvasElement {
+        return CanvasElement{m: RefCell::new(CanvasElementMut{
+                parent: ElementParentMut::new(), identity: ElementIdentity::new(),
+                req: ElementReq::new(), alloc: ElementAlloc::new(),
+                children: vec![], positions: vec![], x_req_stale: true})};
+    }
+
+    pub fn get_children(&self) -> Ref<Vec<ElementRef>> {
+        return Ref::map(self.m.borrow(), |m| &m.children);
+    }
+
+    /// The position of each child, in child order
+    pub fn get_positions(&self) -> Ref<Vec<Point2>> {
+        return Ref::map(self.m.borrow(), |m| &m.positions);
+    }
+
+    pub fn get_child_position(&self, elem: &ElementRef) -> Option<Point2> {
+        let mm = self.m.borrow();
+        return mm.index_of(elem).map(|i| mm.positions[i]);
+    }
+
+    /// Replace the children; later children are drawn on top of earlier ones
+    pub fn set_children(&self, self_ref: &ElementRef, children: Vec<(ElementRef, Point2)>) {
+        {
+            let mut mm = self.m.borrow_mut();
+            for c in mm.children.iter() {
+                c.set_parent(None);
+            }
+            let (elems, positions): (Vec<ElementRef>, Vec<Point2>) = children.into_iter().unzip();
+            for c in elems.iter() {
+                c.set_parent(Some(self_ref));
+            }
+            mm.children = elems;
+            mm.positions = positions;
+            mm.x_req_stale = true;
+        }
+        queue_resize(self);
+    }
+
+    pub fn add_child(&self, self_ref: &ElementRef, elem: ElementRef, pos: Point2) {
+        {
+            let mut mm = self.m.borrow_mut();
+            elem.set_parent(Some(self_ref));
+            mm.children.push(elem);
+            mm.positions.push(pos);
+            mm.x_req_stale = true;
+        }
+        queue_resize(self);
+    }
+
+    pub fn remove_child(&self, elem: &ElementRef) {
+        let index = self.m.borrow().index_of(elem);
+        match index {
+            Some(i) => {
+                queue_redraw(&**elem);
+                {
+                    let mut mm = self.m.borrow_mut();
+                    mm.children.remove(i);
+                    mm.positions.remove(i);
+                    mm.x_req_stale = true;
+                }
+                elem.set_parent(None);
+                queue_resize(self);
+            },
+            None => {}
+        }
+    }
+
+    /// Move the child `elem` to `pos`. If the canvas still covers its children, the child is
+    /// moved without laying the canvas out again, and only its old and new bounding boxes are
+    /// redrawn.
+    pub fn move_child(&self, elem: &ElementRef, pos: Point2) {
+        let index = self.m.borrow().index_of(elem);
+        let i = match index {
+            Some(i) => i,
+            None => {return;}
+        };
+        if self.m.borrow().positions[i] == pos {
+            return;
+        }
+
+        // Redraw the child at its old position
+        queue_redraw(&**elem);
+        let (req_unchanged, origin) = {
+            let mut mm = self.m.borrow_mut();
+            let old_origin = mm.origin();
+            mm.positions[i] = pos;
+            let origin = mm.origin();
+            (mm.x_req() == mm.req.x_req && mm.y_req() == mm.req.y_req && origin == old_origin,
+             origin)
+        };
+        if req_unchanged {
+            // Only the position of the child changes; its size and contents are unaffected
+            let (x_alloc, y_alloc) = {
+                let child_req = elem.element_req();
+                (LAlloc::new_from_req(&child_req.x_req, origin.x + pos.x),
+                 LAlloc::new_from_req(&child_req.y_req, origin.y + pos.y))
+            };
+            {
+                let mut child_alloc = elem.element_alloc_mut();
+                child_alloc.update_x_alloc(&x_alloc);
+                child_alloc.update_y_alloc(&y_alloc);
+            }
+            draw_bbox_changed(&**elem);
+            queue_redraw(&**elem);
+        } else {
+            self.m.borrow_mut().x_req_stale = true;
+            queue_resize(self);
+        }
+    }
+}
+
+
+impl TElement for CanvasElement {
+    /// Interface acquisition
+    fn as_container(&self) -> Option<&TContainerElement> {
+        return Some(self);
+    }
+
+    fn as_bin(&self) -> Option<&TBinElement> {
+        return None;
+    }
+
+    fn as_container_sequence(&self) -> Option<&TContainerSequenceElement> {
+        return None;
+    }
+
+    fn as_root_element(&self) -> Option<&TRootElement> {
+        return None;
+    }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "CanvasElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    /// Parent get and set methods
+    fn get_parent(&self) -> Option<ElementRef> {
+        return self.m.borrow().parent.get().clone();
+    }
+
+    fn set_parent(&self, p: Option<&ElementRef>) {
+        self.m.borrow_mut().parent.set(p);
+    }
+
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
+    /// Element layout structure acquisition
+    fn element_req(&self) -> Ref<ElementReq> {
+        return Ref::map(self.m.borrow(), |m| &m.req);
+    }
+
+    fn element_alloc(&self) -> Ref<ElementAlloc> {
+        return Ref::map(self.m.borrow(), |m| &m.alloc);
+    }
+
+    fn element_alloc_mut(&self) -> RefMut<ElementAlloc> {
+        return RefMut::map(self.m.borrow_mut(), |m| &mut m.alloc);
+    }
+
+    /// Update element X requisition
+    fn element_update_x_req(&self, x_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_x_req(x_req);
+    }
+
+    /// Update element Y requisition
+    fn element_update_y_req(&self, y_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_y_req(y_req);
+    }
+
+    /// Draw
+    fn draw(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        self.draw_self(cairo_ctx, visible_region);
+        self.draw_children(cairo_ctx, visible_region);
+    }
+
+    /// Update layout
+    fn update_x_req(&self, layout_ctx: &ElementLayoutContext) -> bool {
+        let mut changed = self.container_update_x_req(layout_ctx);
+        let stale = {
+            let mut mm = self.m.borrow_mut();
+            let stale = mm.x_req_stale;
+            mm.x_req_stale = false;
+            stale
+        };
+        if stale {
+            // The children moved, but their requisitions may not have changed
+            let x_req = self.compute_x_req();
+            if self.element_update_x_req(&x_req) {
+                self.element_alloc_mut().x_alloc_dirty();
+                changed = true;
+            }
+        }
+        return changed;
+    }
+
+    fn allocate_x(&self, x_alloc: &LAlloc) -> bool {
+        return self.container_allocate_x(x_alloc);
+    }
+
+    fn update_y_req(&self) -> bool {
+        return self.container_update_y_req();
+    }
+
+    fn allocate_y(&self, y_alloc: &LAlloc) {
+        self.container_allocate_y(y_alloc);
+    }
+}
+
+
+impl TContainerElement for CanvasElement {
+    fn children(&self) -> Ref<[ElementRef]> {
+        return Ref::map(self.m.borrow(), |m| &m.children[..]);
+    }
+
+    fn compute_x_req(&self) -> LReq {
+        return self.m.borrow().x_req();
+    }
+
+    fn compute_child_x_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        let origin_x = -mm.x_extent().0;
+        return mm.children.iter().zip(mm.positions.iter()).map(
+            |(c, p)| LAlloc::new_from_req(&c.element_req().x_req, origin_x + p.x)).collect();
+    }
+
+    fn compute_y_req(&self) -> LReq {
+        return self.m.borrow().y_req();
+    }
+
+    fn compute_child_y_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        let origin_y = -mm.y_extent().0;
+        return mm.children.iter().zip(mm.positions.iter()).map(
+            |(c, p)| LAlloc::new_from_req(&c.element_req().y_req, origin_y + p.y)).collect();
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use elements::element::elem_as_ref;
+    use elements::shape_element::ShapeElement;
+
+    #[test]
+    fn test_negative_positions() {
+        let canvas = elem_as_ref(CanvasElement::new());
+        let shape = || elem_as_ref(ShapeElement::new(10.0, 10.0, &Rc::new(vec![])));
+        let c = canvas.as_any().downcast_ref::<CanvasElement>().unwrap();
+        c.set_children(&canvas, vec![(shape(), Point2::new(-10.0, 5.0)),
+                                     (shape(), Point2::new(20.0, -4.0))]);
+
+        // The canvas covers both children, with the origin moved to keep them within it
+        assert_eq!(c.compute_x_req().size().size(), 40.0);
+        assert_eq!(c.compute_y_req().size().size(), 19.0);
+        let x: Vec<f64> = c.compute_child_x_allocs().iter().map(|a| a.pos_in_parent()).collect();
+        let y: Vec<f64> = c.compute_child_y_allocs().iter().map(|a| a.pos_in_parent()).collect();
+        assert_eq!(x, vec![0.0, 30.0]);
+        assert_eq!(y, vec![9.0, 0.0]);
+    }
+}
@@ -1,5 +1,6 @@
 use cairo::Context;
 
+use std::any::Any;
 use std::cell::{RefCell, Ref, RefMut};
 
 use layout::lreq::LReq;
@@ -9,7 +10,7 @@ use geom::bbox2::BBox2;
 
 use elements::element_ctx::ElementLayoutContext;
 use elements::element_layout::{ElementReq, ElementAlloc};
-use elements::element::{TElement, ElementRef, ElementParentMut, queue_resize};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, queue_resize};
 use elements::container::TContainerElement;
 use elements::bin::{TBinElement};
 use elements::container_sequence::{TContainerSequenceElement, ContainerSequenceComponentMut};
@@ -18,6 +19,7 @@ use elements::root_element::{TRootElement};
 
 struct ColumnElementMut {
     parent: ElementParentMut,
+    identity: ElementIdentity,
     req: ElementReq,
     alloc: ElementAlloc,
     container_seq: ContainerSequenceComponentMut,
@@ -32,10 +34,26 @@ pub struct ColumnElement {
 impl ColumnElement {
     pub fn new(y_spacing: f64) -> ColumnElement {
         return ColumnElement{m: RefCell::new(ColumnElementMut{
-                parent: ElementParentMut::new(),
+                parent: ElementParentMut::new(), identity: ElementIdentity::new(),
                 req: ElementReq::new(), alloc: ElementAlloc::new(),
                 container_seq: ContainerSequenceComponentMut::new(), y_spacing: y_spacing})};
     }
+
+    pub fn get_y_spacing(&self) -> f64 {
+        return self.m.borrow().y_spacing;
+    }
+
+    pub fn set_y_spacing(&self, y_spacing: f64) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let changed = mm.y_spacing != y_spacing;
+            mm.y_spacing = y_spacing;
+            changed
+        };
+        if changed {
+            queue_resize(self);
+        }
+    }
 }
 
 
@@ -57,6 +75,15 @@ impl TElement for ColumnElement {
         return None;
     }
 
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "ColumnElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
     /// Parent get and set methods
     fn get_parent(&self) -> Option<ElementRef> {
         return self.m.borrow().parent.get().clone();
@@ -66,6 +93,10 @@ impl TElement for ColumnElement {
         self.m.borrow_mut().parent.set(p);
     }
 
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
     /// Element layout structure acquisition
     fn element_req(&self) -> Ref<ElementReq> {
         return Ref::map(self.m.borrow(), |m| &m.req);
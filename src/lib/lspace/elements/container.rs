@@ -18,7 +18,7 @@ pub trait TContainerElement : TElement {
     fn draw_children(&self, cairo_ctx: &Context, visible_region: &BBox2) {
         for child in self.children().iter() {
             let a = child.element_alloc();
-            let child_bbox = BBox2::from_allocs(&a.x_alloc, &a.y_alloc);
+            let child_bbox = a.local_bbox_to_parent_space(&child.draw_bbox());
             if child_bbox.intersects(visible_region) {
                 let dx = a.x_alloc.pos_in_parent();
                 let dy = a.y_alloc
@@ -62,6 +62,19 @@ pub trait TContainerElement : TElement {
         }
     }
 
+    /// Recompute the union of the draw bounding boxes of the children, so that ancestors
+    /// do not cull children that paint outside the allocated box of this element
+    fn update_children_draw_bbox(&self) {
+        let bbox = self.children().iter().fold(None, |b: Option<BBox2>, c| {
+            let child_bbox = c.element_alloc().local_bbox_to_parent_space(&c.draw_bbox());
+            Some(match b {
+                Some(b) => b.union(&child_bbox),
+                None => child_bbox
+            })
+        });
+        self.element_alloc_mut().children_draw_bbox = bbox;
+    }
+
     fn compute_x_req(&self) -> LReq;
     fn compute_child_x_allocs(&self) -> Vec<LAlloc>;
     fn compute_y_req(&self) -> LReq;
@@ -98,6 +111,7 @@ pub trait TContainerElement : TElement {
         }
         let x_allocs = self.compute_child_x_allocs();
         let child_y_reqs_dirty = self.allocate_children_x(&x_allocs);
+        self.update_children_draw_bbox();
         if child_y_reqs_dirty {
             let mut elem_alloc = self.element_alloc_mut();
             elem_alloc.y_req_dirty();
@@ -132,6 +146,58 @@ pub trait TContainerElement : TElement {
         if update_needed {
             let y_allocs = self.compute_child_y_allocs();
             self.allocate_children_y(&y_allocs);
+            self.update_children_draw_bbox();
+        }
+    }
+}
+
+
+/// Notify the ancestors of `elem` that its draw bounding box has changed outside of layout,
+/// e.g. because it moved or because it paints a different region
+pub fn draw_bbox_changed(elem: &TElement) {
+    let mut x: Option<ElementRef> = elem.get_parent();
+
+    while !x.is_none() {
+        let e = x.unwrap();
+        match e.as_container() {
+            Some(c) => c.update_children_draw_bbox(),
+            None => {}
         }
+        x = e.get_parent();
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use geom::point2::Point2;
+    use geom::colour::BLACK;
+    use graphics::shadow::Shadow;
+    use elements::element::elem_as_ref;
+    use elements::column::ColumnElement;
+    use elements::shadow_element::ShadowElement;
+
+    #[test]
+    fn test_draw_bbox_includes_descendants() {
+        let shadow = elem_as_ref(ShadowElement::new(
+            &Rc::new(Shadow::new_glow(4.0, 0.0, 0.0, BLACK))));
+        let parent = elem_as_ref(ColumnElement::new(0.0));
+        parent.as_container_sequence().unwrap().set_children(&parent, &vec![shadow.clone()]);
+        let grandparent = elem_as_ref(ColumnElement::new(0.0));
+        grandparent.as_container_sequence().unwrap().set_children(&grandparent,
+                                                                  &vec![parent.clone()]);
+        grandparent.allocate_x(&LAlloc::new(0.0, 100.0, 100.0));
+        grandparent.allocate_y(&LAlloc::new(0.0, 100.0, 100.0));
+
+        // The shadow extends beyond the allocated boxes of both its parent and grandparent
+        assert_eq!(shadow.draw_bbox().lower, Point2::new(-4.0, -4.0));
+        assert_eq!(parent.draw_bbox().lower, Point2::new(-4.0, -4.0));
+        assert_eq!(grandparent.draw_bbox().lower, Point2::new(-4.0, -4.0));
     }
 }
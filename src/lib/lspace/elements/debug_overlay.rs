@@ -0,0 +1,187 @@
+use cairo::Context;
+
+use geom::colour::Colour;
+use elements::element::{TElement, ElementRef, elems_are_same};
+
+
+/// Layout debug overlay; draws the allocated box, the border insets and the reference
+/// point lines of each element over the normal rendering.
+#[derive(Clone)]
+pub struct DebugOverlay {
+    max_depth: Option<usize>,
+    subtree: Option<ElementRef>,
+}
+
+impl DebugOverlay {
+    /// Overlay covering the whole element tree
+    pub fn new() -> DebugOverlay {
+        DebugOverlay{max_depth: None, subtree: None}
+    }
+
+    /// Limit the overlay to elements at most `max_depth` levels below the root of the
+    /// overlaid tree (the root is at depth 0)
+    pub fn with_max_depth(&self, max_depth: usize) -> DebugOverlay {
+        DebugOverlay{max_depth: Some(max_depth), subtree: self.subtree.clone()}
+    }
+
+    /// Limit the overlay to the subtree rooted at `subtree`; depth is then measured from
+    /// `subtree`
+    pub fn with_subtree(&self, subtree: &ElementRef) -> DebugOverlay {
+        DebugOverlay{max_depth: self.max_depth, subtree: Some(subtree.clone())}
+    }
+
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    pub fn subtree(&self) -> Option<&ElementRef> {
+        self.subtree.as_ref()
+    }
+
+    /// Overlay colour used for elements of the given type. Types that are not known to the
+    /// overlay, e.g. those defined by applications, are given a colour derived from their name.
+    pub fn colour_for_element_type(type_name: &str) -> Colour {
+        match type_name {
+            "TextElement" => Colour::new(0.0, 0.6, 0.0, 0.8),
+            "BorderElement" => Colour::new(0.8, 0.4, 0.0, 0.8),
+            "ColumnElement" => Colour::new(0.0, 0.3, 0.9, 0.8),
+            "RowElement" => Colour::new(0.7, 0.0, 0.7, 0.8),
+            "FlowElement" => Colour::new(0.0, 0.6, 0.7, 0.8),
+            "RootElement" => Colour::new(0.4, 0.4, 0.4, 0.8),
+            "EventBindingElement" => Colour::new(0.6, 0.3, 0.3, 0.8),
+            "OpacityElement" => Colour::new(0.6, 0.6, 0.8, 0.8),
+            "ShadowElement" => Colour::new(0.3, 0.3, 0.6, 0.8),
+            "StateBorderElement" => Colour::new(0.9, 0.6, 0.0, 0.8),
+            "ShapeElement" => Colour::new(0.5, 0.7, 0.0, 0.8),
+            "TextEntryElement" => Colour::new(0.8, 0.0, 0.0, 0.8),
+            "TextEditorElement" => Colour::new(0.5, 0.0, 0.2, 0.8),
+            "ExpanderElement" => Colour::new(0.0, 0.4, 0.2, 0.8),
+            "StackElement" => Colour::new(0.3, 0.0, 0.9, 0.8),
+            "CanvasElement" => Colour::new(0.9, 0.0, 0.4, 0.8),
+            "LazyColumnElement" => Colour::new(0.0, 0.5, 0.5, 0.8),
+            _ => {
+                // FNV-1a hash of the name, so that each type keeps the same colour
+                let hash = type_name.bytes().fold(0x811c9dc5u32, |h, b| {
+                    (h ^ (b as u32)).wrapping_mul(0x01000193)
+                });
+                let channel = |shift: u32| 0.1 + ((hash >> shift) & 0xff) as f32 / 255.0 * 0.7;
+                Colour::new(channel(0), channel(8), channel(16), 0.8)
+            }
+        }
+    }
+
+    /// Draw the overlay for the tree rooted at `root`. `cairo_ctx` should be in the
+    /// co-ordinate space of `root`.
+    pub fn draw(&self, root: &ElementRef, cairo_ctx: &Context) {
+        cairo_ctx.save();
+        cairo_ctx.set_line_width(1.0);
+        let depth = match self.subtree {
+            None => Some(0),
+            Some(_) => None,
+        };
+        self.draw_element(root, cairo_ctx, depth);
+        cairo_ctx.restore();
+    }
+
+    /// `depth` is `None` while we are outside the chosen subtree
+    fn draw_element(&self, elem: &ElementRef, cairo_ctx: &Context, depth: Option<usize>) {
+        let depth = match (depth, &self.subtree) {
+            (None, &Some(ref s)) if elems_are_same(elem, s) => Some(0),
+            (d, _) => d,
+        };
+
+        match (depth, self.max_depth) {
+            (Some(d), Some(max_d)) if d > max_d => return,
+            (Some(_), _) => self.draw_element_overlay(&**elem, cairo_ctx),
+            (None, _) => {}
+        }
+
+        let children = match elem.as_container() {
+            Some(c) => c.children().to_vec(),
+            None => vec![]
+        };
+        for child in children.iter() {
+            let (dx, dy) = {
+                let a = child.element_alloc();
+                (a.x_alloc.pos_in_parent(), a.y_alloc.pos_in_parent())
+            };
+            cairo_ctx.save();
+            cairo_ctx.translate(dx, dy);
+            self.draw_element(child, cairo_ctx, depth.map(|d| d + 1));
+            cairo_ctx.restore();
+        }
+    }
+
+    fn draw_element_overlay(&self, elem: &TElement, cairo_ctx: &Context) {
+        let col = DebugOverlay::colour_for_element_type(elem.element_type_name());
+        let (w, h, x_ref, y_ref) = {
+            let a = elem.element_alloc();
+            (a.x_alloc.actual_size(), a.y_alloc.actual_size(),
+             a.x_alloc.ref_point(), a.y_alloc.ref_point())
+        };
+        cairo_ctx.set_source_rgba(col.r as f64, col.g as f64, col.b as f64, col.a as f64);
+
+        // Allocated box
+        cairo_ctx.set_dash(&[], 0.0);
+        cairo_ctx.rectangle(0.5, 0.5, w - 1.0, h - 1.0);
+        cairo_ctx.stroke();
+
+        // Border insets
+        match elem.debug_insets() {
+            Some((l, r, t, b)) => {
+                cairo_ctx.set_dash(&[2.0, 2.0], 0.0);
+                cairo_ctx.rectangle(l + 0.5, t + 0.5, w - l - r - 1.0, h - t - b - 1.0);
+                cairo_ctx.stroke();
+            },
+            None => {}
+        }
+
+        // Reference point lines
+        cairo_ctx.set_dash(&[4.0, 2.0], 0.0);
+        match y_ref {
+            Some(y) => {
+                cairo_ctx.move_to(0.0, y);
+                cairo_ctx.line_to(w, y);
+                cairo_ctx.stroke();
+            },
+            None => {}
+        }
+        match x_ref {
+            Some(x) => {
+                cairo_ctx.move_to(x, 0.0);
+                cairo_ctx.line_to(x, h);
+                cairo_ctx.stroke();
+            },
+            None => {}
+        }
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colour_for_element_type() {
+        let names = ["TextElement", "BorderElement", "ColumnElement", "RowElement",
+                     "FlowElement", "RootElement", "EventBindingElement", "OpacityElement",
+                     "ShadowElement", "StateBorderElement", "ShapeElement", "TextEntryElement",
+                     "TextEditorElement", "ExpanderElement", "StackElement", "CanvasElement",
+                     "LazyColumnElement"];
+        // Every built in type has a colour of its own
+        for (i, a) in names.iter().enumerate() {
+            for b in names[i + 1..].iter() {
+                assert!(DebugOverlay::colour_for_element_type(a) !=
+                        DebugOverlay::colour_for_element_type(b), "{} and {}", a, b);
+            }
+        }
+        // Other types are told apart by their names
+        assert!(DebugOverlay::colour_for_element_type("GraphElement") !=
+                DebugOverlay::colour_for_element_type("PlotElement"));
+    }
+}
@@ -1,12 +1,15 @@
 use cairo::Context;
 
 use std::rc::Rc;
-use std::cell::{Ref, RefMut};
+use std::any::Any;
+use std::cell::{RefCell, Ref, RefMut};
 
 use layout::lreq::LReq;
 use layout::lalloc::LAlloc;
 use geom::bbox2::BBox2;
 
+use input::element_events::EventBindings;
+use input::interactive::TInteractiveElement;
 use elements::element_layout::{ElementReq, ElementAlloc};
 use elements::element_ctx::ElementLayoutContext;
 use elements::text_element::TTextElement;
@@ -22,6 +25,13 @@ pub fn elem_as_ref<T: TElement + 'static>(x: T) -> ElementRef {
     return Rc::new(x);
 }
 
+/// Check if `a` and `b` refer to the same element
+pub fn elems_are_same(a: &ElementRef, b: &ElementRef) -> bool {
+    let aptr: &TElement = &**a;
+    let bptr: &TElement = &**b;
+    return (aptr as *const TElement) == (bptr as *const TElement);
+}
+
 
 pub trait TElement {
     /// Interface acquisition
@@ -34,10 +44,43 @@ pub trait TElement {
     fn as_container_sequence(&self) -> Option<&TContainerSequenceElement>;
     fn as_root_element(&self) -> Option<&TRootElement>;
 
+    /// Elements that react to hover, presses, focus and key events
+    fn as_interactive(&self) -> Option<&TInteractiveElement> {
+        return None;
+    }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str;
+
+    /// Access as `Any`, so that the concrete element type can be recovered by downcasting
+    fn as_any(&self) -> &Any;
+
     /// Parent get and set methods
     fn get_parent(&self) -> Option<ElementRef>;
     fn set_parent(&self, p: Option<&ElementRef>);
 
+    /// Acquire reference to the element key and id
+    fn element_identity(&self) -> Ref<ElementIdentity>;
+
+    /// Key get and set methods; keys identify list children when reconciling presentations
+    fn get_key(&self) -> Option<String> {
+        return self.element_identity().get_key();
+    }
+
+    fn set_key(&self, key: Option<String>) {
+        self.element_identity().set_key(key);
+    }
+
+    /// Id get and set methods; ids allow the application to find elements built from
+    /// presentations, see `ElementContext::find_by_id`
+    fn get_id(&self) -> Option<String> {
+        return self.element_identity().get_id();
+    }
+
+    fn set_id(&self, id: Option<String>) {
+        self.element_identity().set_id(id);
+    }
+
     /// Acquire reference to the element layout requisition
     fn element_req(&self) -> Ref<ElementReq>;
     /// Acquire reference to the element layout allocation
@@ -48,6 +91,24 @@ pub trait TElement {
     /// Update element Y requisition
     fn element_update_y_req(&self, y_req: &LReq) -> bool;
 
+    /// Named events bound to the element; `None` for elements that do not bind events
+    fn event_bindings(&self) -> Option<&EventBindings> {
+        return None;
+    }
+
+    /// Insets between the element's allocated box and its content (left, right, top, bottom),
+    /// for debug drawing; `None` for elements without padding or borders
+    fn debug_insets(&self) -> Option<(f64, f64, f64, f64)> {
+        return None;
+    }
+
+    /// Region painted by the element and its children, in its local co-ordinate space. This is
+    /// its allocated box, unless the element or one of its descendants paints outside it (e.g.
+    /// a drop shadow).
+    fn draw_bbox(&self) -> BBox2 {
+        return self.element_alloc().draw_bbox();
+    }
+
     /// Paint the element content that is contributed by the element itself, as opposed to child
     /// elements.
     fn draw_self(&self, cairo_ctx: &Context, visible_region: &BBox2) {
@@ -111,7 +172,7 @@ pub fn queue_resize(elem: &TElement) {
 pub fn queue_redraw(elem: &TElement) {
     queue_redraw_for_elem_if_root(elem, None);
 
-    let mut bbox = elem.element_alloc().local_bbox();
+    let mut bbox = elem.draw_bbox();
     bbox = elem.element_alloc().local_bbox_to_parent_space(&bbox);
     let mut x: Option<ElementRef> = elem.get_parent();
 
@@ -127,6 +188,43 @@ pub fn queue_redraw(elem: &TElement) {
     }
 }
 
+/// Bounding box of `elem` in the co-ordinate space of the root of its tree
+pub fn elem_root_space_bbox(elem: &TElement) -> BBox2 {
+    let mut bbox = elem.element_alloc().local_bbox();
+    bbox = elem.element_alloc().local_bbox_to_parent_space(&bbox);
+    let mut x: Option<ElementRef> = elem.get_parent();
+
+    while !x.is_none() {
+        let e = x.unwrap();
+        bbox = e.element_alloc().local_bbox_to_parent_space(&bbox);
+        x = e.get_parent();
+    }
+    return bbox;
+}
+
+/// The ancestors of `elem`, starting with the root and ending with the parent of `elem`
+pub fn elem_ancestors(elem: &TElement) -> Vec<ElementRef> {
+    let mut ancestors: Vec<ElementRef> = Vec::new();
+    let mut x: Option<ElementRef> = elem.get_parent();
+
+    while !x.is_none() {
+        let e = x.unwrap();
+        x = e.get_parent();
+        ancestors.push(e);
+    }
+    ancestors.reverse();
+    return ancestors;
+}
+
+/// Check if `elem` is `root` or one of its descendants
+pub fn elem_is_in_tree(elem: &ElementRef, root: &ElementRef) -> bool {
+    return match elem_ancestors(&**elem).first() {
+        Some(top) => elems_are_same(top, root),
+        None => elems_are_same(elem, root)
+    };
+}
+
+
 
 pub struct ElementParentMut {
     parent: Option<ElementRef>
@@ -147,4 +245,34 @@ impl ElementParentMut {
             Some(pp) => Some(pp.clone())
         };
     }
-}
\ No newline at end of file
+}
+
+
+/// The key and id of an element. They are held in cells so that they can be changed through
+/// the shared reference returned by `TElement::element_identity`.
+pub struct ElementIdentity {
+    key: RefCell<Option<String>>,
+    id: RefCell<Option<String>>,
+}
+
+impl ElementIdentity {
+    pub fn new() -> ElementIdentity {
+        return ElementIdentity{key: RefCell::new(None), id: RefCell::new(None)};
+    }
+
+    pub fn get_key(&self) -> Option<String> {
+        return self.key.borrow().clone();
+    }
+
+    pub fn set_key(&self, key: Option<String>) {
+        *self.key.borrow_mut() = key;
+    }
+
+    pub fn get_id(&self) -> Option<String> {
+        return self.id.borrow().clone();
+    }
+
+    pub fn set_id(&self, id: Option<String>) {
+        *self.id.borrow_mut() = id;
+    }
+}
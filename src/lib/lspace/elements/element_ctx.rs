@@ -1,18 +1,20 @@
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use std::cell::RefCell;
 
 use cairo::Context;
 
 use layout::lreq::LReq;
 
+use elements::element::{TElement, ElementRef};
 use elements::element_layout::ElementReq;
 use elements::text_element::{TextReqKey, TextStyleParams};
 
 
 struct ElementContextMut {
     req_table: HashMap<TextReqKey, Rc<ElementReq>>,
+    id_table: HashMap<String, Weak<TElement>>,
 }
 
 impl ElementContextMut {
@@ -45,7 +47,8 @@ pub struct ElementContext {
 
 impl ElementContext {
     pub fn new() -> ElementContext {
-        ElementContext{m: RefCell::new(ElementContextMut{req_table: HashMap::new()}),
+        ElementContext{m: RefCell::new(ElementContextMut{req_table: HashMap::new(),
+                                                         id_table: HashMap::new()}),
                        empty_shared_req: Rc::new(ElementReq::new())}
     }
 
@@ -57,6 +60,35 @@ impl ElementContext {
     pub fn empty_shared_req(&self) -> Rc<ElementReq> {
         return self.empty_shared_req.clone();
     }
+
+    /// Give `elem` the id `id` and record it in the id index, replacing any element
+    /// previously registered under `id`
+    pub fn register_id(&self, id: &str, elem: &ElementRef) {
+        elem.set_id(Some(id.to_string()));
+        self.m.borrow_mut().id_table.insert(id.to_string(), Rc::downgrade(elem));
+    }
+
+    /// Remove id index entries for elements that no longer exist
+    pub fn prune_ids(&self) {
+        let mut mm = self.m.borrow_mut();
+        let dead: Vec<String> = mm.id_table.iter().filter(|&(_, e)| e.upgrade().is_none()).map(
+            |(id, _)| id.clone()).collect();
+        for id in dead.iter() {
+            mm.id_table.remove(id);
+        }
+    }
+
+    /// Find the element registered under `id`. Elements that have been dropped, or whose id
+    /// has since changed, are not found.
+    pub fn find_by_id(&self, id: &str) -> Option<ElementRef> {
+        let mm = self.m.borrow();
+        match mm.id_table.get(id).and_then(|e| e.upgrade()) {
+            Some(e) => {
+                if e.get_id().as_ref().map(|x| x.as_str()) == Some(id) {Some(e)} else {None}
+            },
+            None => None
+        }
+    }
 }
 
 
@@ -78,3 +110,34 @@ impl <'a> ElementLayoutContext<'a> {
         self.cairo_ctx
     }
 }
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::border::Border;
+    use elements::element::elem_as_ref;
+    use elements::border_element::BorderElement;
+
+    #[test]
+    fn test_prune_ids() {
+        let elem_ctx = ElementContext::new();
+        let border = Rc::new(Border::new_filled(0.0, 0.0, 0.0, 0.0, 0.0, None));
+        let a = elem_as_ref(BorderElement::new(&border));
+        let b = elem_as_ref(BorderElement::new(&border));
+        elem_ctx.register_id("a", &a);
+        elem_ctx.register_id("b", &b);
+        drop(b);
+        elem_ctx.prune_ids();
+        assert_eq!(elem_ctx.m.borrow().id_table.len(), 1);
+        assert!(elem_ctx.find_by_id("a").is_some());
+
+        // An element whose id has since changed is not found under its old id
+        a.set_id(Some("c".to_string()));
+        assert!(elem_ctx.find_by_id("a").is_none());
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt::Write;
+
+use rustc_serialize::json::{Json, Object};
+
+use layout::lreq::{LReq, LNatSize, LFlex};
+use layout::lalloc::LAlloc;
+use elements::element::{TElement, ElementRef};
+
+
+/// Describe a layout requisition, e.g. `ref 10+3 flex(shrink 0, stretch 1)`
+pub fn describe_req(req: &LReq) -> String {
+    let size = match req.size() {
+        &LNatSize::Empty => "empty".to_string(),
+        &LNatSize::Size{size} => format!("size {}", size),
+        &LNatSize::Ref{before, after} => format!("ref {}+{}", before, after),
+    };
+    let flex = match req.flex() {
+        &LFlex::Fixed => "fixed".to_string(),
+        &LFlex::Flex{shrink, stretch} => format!("flex(shrink {}, stretch {})", shrink, stretch),
+    };
+    format!("{} {}", size, flex)
+}
+
+/// Describe a layout allocation, e.g. `pos 0 alloc 30 actual 30 ref 10`
+pub fn describe_alloc(alloc: &LAlloc) -> String {
+    let mut s = format!("pos {} alloc {} actual {}", alloc.pos_in_parent(), alloc.alloc_size(),
+                        alloc.actual_size());
+    match alloc.ref_point() {
+        Some(r) => {write!(s, " ref {}", r).unwrap();},
+        None => {}
+    }
+    s
+}
+
+/// Names of the layout dirty flags that are set on `elem`
+pub fn dirty_flag_names(elem: &TElement) -> Vec<&'static str> {
+    let alloc = elem.element_alloc();
+    let mut names = Vec::new();
+    if alloc.is_x_req_update_required() {
+        names.push("x_req");
+    }
+    if alloc.is_y_req_update_required() {
+        names.push("y_req");
+    }
+    if alloc.is_x_alloc_update_required() {
+        names.push("x_alloc");
+    }
+    if alloc.is_y_alloc_update_required() {
+        names.push("y_alloc");
+    }
+    names
+}
+
+fn children_of(elem: &TElement) -> Vec<ElementRef> {
+    match elem.as_container() {
+        Some(c) => c.children().to_vec(),
+        None => vec![]
+    }
+}
+
+fn text_of(elem: &TElement) -> Option<String> {
+    elem.as_text_element().map(|t| t.get_text().clone())
+}
+
+
+fn dump_element(elem: &TElement, depth: usize, out: &mut String) {
+    let indent: String = (0..depth).map(|_| "  ").collect();
+    match text_of(elem) {
+        Some(text) => writeln!(out, "{}{} {:?}", indent, elem.element_type_name(), text),
+        None => writeln!(out, "{}{}", indent, elem.element_type_name()),
+    }.unwrap();
+    {
+        let req = elem.element_req();
+        writeln!(out, "{}    req: x=[{}] y=[{}]", indent, describe_req(&req.x_req),
+                 describe_req(&req.y_req)).unwrap();
+    }
+    {
+        let alloc = elem.element_alloc();
+        writeln!(out, "{}    alloc: x=[{}] y=[{}]", indent, describe_alloc(&alloc.x_alloc),
+                 describe_alloc(&alloc.y_alloc)).unwrap();
+    }
+    let dirty = dirty_flag_names(elem);
+    if dirty.is_empty() {
+        writeln!(out, "{}    dirty: none", indent).unwrap();
+    } else {
+        writeln!(out, "{}    dirty: {}", indent, dirty.join(", ")).unwrap();
+    }
+
+    for child in children_of(elem).iter() {
+        dump_element(&**child, depth + 1, out);
+    }
+}
+
+/// Dump the element tree rooted at `elem` as indented text. Each element is described by
+/// its type, its text content (text elements only), its requisition, its allocation and the
+/// layout dirty flags that are set.
+pub fn dump_tree(elem: &ElementRef) -> String {
+    let mut out = String::new();
+    dump_element(&**elem, 0, &mut out);
+    out
+}
+
+
+fn req_to_json(req: &LReq) -> Json {
+    let mut obj = Object::new();
+    obj.insert("size".to_string(), Json::F64(req.size().size()));
+    match req.size().before_and_after_ref_opt() {
+        Some((before, after)) => {
+            obj.insert("before_ref".to_string(), Json::F64(before));
+            obj.insert("after_ref".to_string(), Json::F64(after));
+        },
+        None => {}
+    }
+    match req.flex() {
+        &LFlex::Fixed => {},
+        &LFlex::Flex{shrink, stretch} => {
+            obj.insert("shrink".to_string(), Json::F64(shrink));
+            obj.insert("stretch".to_string(), Json::F64(stretch as f64));
+        }
+    }
+    Json::Object(obj)
+}
+
+fn alloc_to_json(alloc: &LAlloc) -> Json {
+    let mut obj = Object::new();
+    obj.insert("pos".to_string(), Json::F64(alloc.pos_in_parent()));
+    obj.insert("alloc_size".to_string(), Json::F64(alloc.alloc_size()));
+    obj.insert("actual_size".to_string(), Json::F64(alloc.actual_size()));
+    match alloc.ref_point() {
+        Some(r) => {obj.insert("ref_point".to_string(), Json::F64(r));},
+        None => {}
+    }
+    Json::Object(obj)
+}
+
+fn element_to_json(elem: &TElement) -> Json {
+    let mut obj = Object::new();
+    obj.insert("type".to_string(), Json::String(elem.element_type_name().to_string()));
+    match text_of(elem) {
+        Some(text) => {obj.insert("text".to_string(), Json::String(text));},
+        None => {}
+    }
+    {
+        let req = elem.element_req();
+        let mut req_obj = Object::new();
+        req_obj.insert("x".to_string(), req_to_json(&req.x_req));
+        req_obj.insert("y".to_string(), req_to_json(&req.y_req));
+        obj.insert("req".to_string(), Json::Object(req_obj));
+    }
+    {
+        let alloc = elem.element_alloc();
+        let mut alloc_obj = Object::new();
+        alloc_obj.insert("x".to_string(), alloc_to_json(&alloc.x_alloc));
+        alloc_obj.insert("y".to_string(), alloc_to_json(&alloc.y_alloc));
+        obj.insert("alloc".to_string(), Json::Object(alloc_obj));
+    }
+    obj.insert("dirty".to_string(), Json::Array(dirty_flag_names(elem).iter().map(
+        |x| Json::String(x.to_string())).collect()));
+    let children = children_of(elem);
+    if !children.is_empty() {
+        obj.insert("children".to_string(), Json::Array(children.iter().map(
+            |c| element_to_json(&**c)).collect()));
+    }
+    Json::Object(obj)
+}
+
+/// Dump the element tree rooted at `elem` as JSON; contains the same information as `dump_tree`
+pub fn dump_tree_json(elem: &ElementRef) -> Json {
+    element_to_json(&**elem)
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use graphics::border::Border;
+    use elements::element::elem_as_ref;
+    use elements::column::ColumnElement;
+    use elements::border_element::BorderElement;
+
+    /// An empty border element, given a fixed requisition of `width` by `height`
+    fn block(width: f64, height: f64) -> ElementRef {
+        let border = Rc::new(Border::new_filled(0.0, 0.0, 0.0, 0.0, 0.0, None));
+        let elem = elem_as_ref(BorderElement::new(&border));
+        elem.element_update_x_req(&LReq::new_fixed_size(width));
+        elem.element_update_y_req(&LReq::new_fixed_size(height));
+        elem
+    }
+
+    /// A column of two blocks, laid out at its natural size. The requisitions of the column
+    /// are computed directly, as updating them would replace those given to the blocks, so its
+    /// `x_req` and `y_req` flags are left dirty.
+    fn laid_out_column() -> ElementRef {
+        let column = elem_as_ref(ColumnElement::new(2.0));
+        column.as_container_sequence().unwrap().set_children(
+            &column, &vec![block(10.0, 10.0), block(20.0, 5.0)]);
+
+        let x_req = column.as_container().unwrap().compute_x_req();
+        column.element_update_x_req(&x_req);
+        column.allocate_x(&LAlloc::new_from_req(&x_req, 0.0));
+        let y_req = column.as_container().unwrap().compute_y_req();
+        column.element_update_y_req(&y_req);
+        column.allocate_y(&LAlloc::new_from_req(&y_req, 0.0));
+        column
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(describe_req(&LReq::new_fixed_size(10.0)), "size 10 fixed");
+        assert_eq!(describe_alloc(&LAlloc::new(5.0, 20.0, 10.0)), "pos 5 alloc 20 actual 10");
+    }
+
+    #[test]
+    fn test_dump_tree() {
+        let dump = dump_tree(&laid_out_column());
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines[..4].to_vec(), vec![
+            "ColumnElement",
+            "    req: x=[size 20 fixed] y=[size 17 fixed]",
+            "    alloc: x=[pos 0 alloc 20 actual 20] y=[pos 0 alloc 17 actual 17]",
+            "    dirty: x_req, y_req"]);
+        assert_eq!(lines[4], "  BorderElement");
+        assert_eq!(lines[5], "      req: x=[size 10 fixed] y=[size 10 fixed]");
+        assert_eq!(lines[6], concat!("      alloc: x=[pos 0 alloc 10 actual 10] ",
+                                     "y=[pos 0 alloc 10 actual 10]"));
+        assert_eq!(lines[8], "  BorderElement");
+        assert_eq!(lines[9], "      req: x=[size 20 fixed] y=[size 5 fixed]");
+        assert_eq!(lines[10], concat!("      alloc: x=[pos 0 alloc 20 actual 20] ",
+                                      "y=[pos 12 alloc 5 actual 5]"));
+        assert_eq!(lines.len(), 12);
+    }
+
+    #[test]
+    fn test_dump_tree_json() {
+        let dump = dump_tree_json(&laid_out_column());
+        let f = |path: &[&str]| dump.find_path(path).cloned();
+        assert_eq!(f(&["type"]), Some(Json::String("ColumnElement".to_string())));
+        assert_eq!(f(&["req", "x", "size"]), Some(Json::F64(20.0)));
+        assert_eq!(f(&["req", "y", "size"]), Some(Json::F64(17.0)));
+        assert_eq!(f(&["req", "x", "shrink"]), None);
+        assert_eq!(f(&["alloc", "y", "alloc_size"]), Some(Json::F64(17.0)));
+        assert_eq!(f(&["dirty"]), Some(Json::Array(vec![Json::String("x_req".to_string()),
+                                                       Json::String("y_req".to_string())])));
+
+        let children = dump.find("children").unwrap().as_array().unwrap();
+        assert_eq!(children.len(), 2);
+        let second = &children[1];
+        assert_eq!(second.find("type"), Some(&Json::String("BorderElement".to_string())));
+        assert_eq!(second.find_path(&["req", "y", "size"]), Some(&Json::F64(5.0)));
+        assert_eq!(second.find_path(&["alloc", "y", "pos"]), Some(&Json::F64(12.0)));
+        assert_eq!(second.find_path(&["alloc", "y", "actual_size"]), Some(&Json::F64(5.0)));
+        assert_eq!(second.find("children"), None);
+    }
+}
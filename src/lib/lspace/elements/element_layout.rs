@@ -49,12 +49,15 @@ pub struct ElementAlloc {
     pub x_alloc: LAlloc,
     pub y_alloc: LAlloc,
     pub layout_flags: u8,
+    /// Union of the draw bounding boxes of the children, in local space; `None` for elements
+    /// without children. Maintained by `TContainerElement` as it allocates its children.
+    pub children_draw_bbox: Option<BBox2>,
 }
 
 impl ElementAlloc {
     pub fn new() -> ElementAlloc {
         return ElementAlloc{x_alloc: LAlloc::new_empty(), y_alloc: LAlloc::new_empty(),
-                            layout_flags: LAYOUT_FLAGS_ALL_DIRTY};
+                            layout_flags: LAYOUT_FLAGS_ALL_DIRTY, children_draw_bbox: None};
     }
 
     /// Update element X allocation
@@ -145,6 +148,15 @@ impl ElementAlloc {
                                Vector2::new(self.x_alloc.actual_size(), self.y_alloc.actual_size()))
     }
 
+    /// The allocated box extended by the region painted by the children
+    pub fn draw_bbox(&self) -> BBox2 {
+        let bbox = self.local_bbox();
+        return match self.children_draw_bbox {
+            Some(ref c) => bbox.union(c),
+            None => bbox
+        };
+    }
+
     pub fn local_bbox_to_parent_space(&self, bbox: &BBox2) -> BBox2 {
         bbox.offset(&Vector2::new(self.x_alloc.pos_in_parent(), self.y_alloc.pos_in_parent()))
     }
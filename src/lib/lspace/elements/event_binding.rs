@@ -0,0 +1,189 @@
+use cairo::Context;
+
+use std::any::Any;
+use std::cell::{RefCell, Ref, RefMut};
+
+use layout::lalloc::LAlloc;
+use layout::lreq::LReq;
+use geom::bbox2::BBox2;
+use input::element_events::EventBindings;
+use elements::element_ctx::ElementLayoutContext;
+use elements::element_layout::{ElementReq, ElementAlloc};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, queue_resize};
+use elements::container::TContainerElement;
+use elements::bin::{TBinElement, BinComponentMut};
+use elements::container_sequence::{TContainerSequenceElement};
+use elements::root_element::{TRootElement};
+
+
+struct EventBindingElementMut {
+    parent: ElementParentMut,
+    identity: ElementIdentity,
+    req: ElementReq,
+    alloc: ElementAlloc,
+    bin: BinComponentMut,
+}
+
+/// Bin element that binds named events to its child; it takes the layout of its child and
+/// draws nothing itself
+pub struct EventBindingElement {
+    m: RefCell<EventBindingElementMut>,
+    bindings: EventBindings,
+}
+
+impl EventBindingElement {
+    pub fn new(bindings: EventBindings) -> EventBindingElement {
+        return EventBindingElement{m: RefCell::new(EventBindingElementMut{
+            parent: ElementParentMut::new(), identity: ElementIdentity::new(),
+            req: ElementReq::new(), alloc: ElementAlloc::new(),
+            bin: BinComponentMut::new()}),
+            bindings: bindings};
+    }
+}
+
+impl TElement for EventBindingElement {
+    /// Interface acquisition
+    fn as_container(&self) -> Option<&TContainerElement> {
+        return Some(self);
+    }
+
+    fn as_bin(&self) -> Option<&TBinElement> {
+        return Some(self);
+    }
+
+    fn as_container_sequence(&self) -> Option<&TContainerSequenceElement> {
+        return None
+    }
+
+    fn as_root_element(&self) -> Option<&TRootElement> {
+        return None;
+    }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "EventBindingElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    /// Event bindings
+    fn event_bindings(&self) -> Option<&EventBindings> {
+        return Some(&self.bindings);
+    }
+
+    /// Parent get and set methods
+    fn get_parent(&self) -> Option<ElementRef> {
+        return self.m.borrow().parent.get().clone();
+    }
+
+    fn set_parent(&self, p: Option<&ElementRef>) {
+        self.m.borrow_mut().parent.set(p);
+    }
+
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
+    // Layout structure acquisition
+    fn element_req(&self) -> Ref<ElementReq> {
+        return Ref::map(self.m.borrow(), |m| &m.req);
+    }
+
+    fn element_alloc(&self) -> Ref<ElementAlloc> {
+        return Ref::map(self.m.borrow(), |m| &m.alloc);
+    }
+
+    fn element_alloc_mut(&self) -> RefMut<ElementAlloc> {
+        return RefMut::map(self.m.borrow_mut(), |m| &mut m.alloc);
+    }
+
+    /// Update element X requisition
+    fn element_update_x_req(&self, x_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_x_req(x_req);
+    }
+
+    /// Update element Y requisition
+    fn element_update_y_req(&self, y_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_y_req(y_req);
+    }
+
+    /// Draw
+    fn draw(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        self.draw_children(cairo_ctx, visible_region);
+    }
+
+    // Update layout
+    fn update_x_req(&self, layout_ctx: &ElementLayoutContext) -> bool {
+        self.container_update_x_req(layout_ctx)
+    }
+
+    fn allocate_x(&self, x_alloc: &LAlloc) -> bool {
+        self.container_allocate_x(x_alloc)
+    }
+
+    fn update_y_req(&self) -> bool {
+        self.container_update_y_req()
+    }
+
+    fn allocate_y(&self, y_alloc: &LAlloc) {
+        self.container_allocate_y(y_alloc);
+    }
+}
+
+
+impl TContainerElement for EventBindingElement {
+    fn children(&self) -> Ref<[ElementRef]> {
+        Ref::map(self.m.borrow(), |m| m.bin.children())
+    }
+
+    fn compute_x_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => LReq::new_empty(),
+            Some(ref ch) => ch.element_req().x_req.clone()
+        }
+    }
+
+    fn compute_child_x_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => vec![],
+            Some(_) => vec![mm.alloc.x_alloc.without_position()]
+        }
+    }
+
+    fn compute_y_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => LReq::new_empty(),
+            Some(ref ch) => ch.element_req().y_req.clone()
+        }
+    }
+
+    fn compute_child_y_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => vec![],
+            Some(_) => vec![mm.alloc.y_alloc.without_position()]
+        }
+    }
+}
+
+impl TBinElement for EventBindingElement {
+    fn get_child(&self) -> Option<ElementRef> {
+        let mm = self.m.borrow();
+        mm.bin.get_child()
+    }
+
+    fn set_child(&self, self_ref: &ElementRef, child: ElementRef) {
+        self.m.borrow_mut().bin.set_child(self_ref, child);
+        queue_resize(self);
+    }
+
+    fn clear_child(&self) {
+        self.m.borrow_mut().bin.clear_child();
+        queue_resize(self);
+    }
+}
@@ -0,0 +1,538 @@
+use cairo::Context;
+
+use std::any::Any;
+use std::rc::{Rc, Weak};
+use std::cell::{RefCell, Ref, RefMut};
+
+use layout::lalloc::LAlloc;
+use layout::lreq::LReq;
+use layout::vertical_layout;
+use geom::bbox2::BBox2;
+use geom::colour::Colour;
+use geom::point2::Point2;
+use geom::vector2::Vector2;
+use graphics::shape::Shape;
+use input::interactive::TInteractiveElement;
+use input::pointer_events::PointerEvent;
+use input::keyboard::{KeyEvent, KeyEventType, KEY_SPACE, KEY_RETURN, KEY_KP_ENTER, KEY_LEFT,
+                      KEY_RIGHT};
+use elements::element_ctx::ElementLayoutContext;
+use elements::element_layout::{ElementReq, ElementAlloc};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity,
+                        elem_root_space_bbox, queue_resize, queue_redraw};
+use elements::container::TContainerElement;
+use elements::bin::TBinElement;
+use elements::container_sequence::{TContainerSequenceElement};
+use elements::root_element::{TRootElement};
+
+
+/// Width of the space to the left of the header and body, in which the disclosure triangle
+/// is drawn
+pub const EXPANDER_INDENT: f64 = 16.0;
+/// Size of the disclosure triangle; the header row is at least this tall
+pub const TRIANGLE_SIZE: f64 = 8.0;
+
+fn triangle_colour() -> Colour {
+    Colour::from_u8(0x50, 0x50, 0x50, 0xff)
+}
+
+fn focus_colour() -> Colour {
+    Colour::from_u8(0x35, 0x84, 0xe4, 0xff)
+}
+
+
+struct ExpanderElementMut {
+    parent: ElementParentMut,
+    identity: ElementIdentity,
+    req: ElementReq,
+    alloc: ElementAlloc,
+    self_ref: Option<Weak<TElement>>,
+    header: Option<ElementRef>,
+    body: Option<ElementRef>,
+    /// The visible children: the header, followed by the body while expanded
+    children: Vec<ElementRef>,
+    expanded: bool,
+    /// The expanded state most recently requested by a presentation
+    presented_expanded: bool,
+    /// Set when the visible children change; the X requisition must then be recomputed even
+    /// if the requisitions of the children are unchanged
+    x_req_stale: bool,
+    focused: bool,
+    on_toggle: Option<Rc<Fn(bool)>>,
+}
+
+impl ExpanderElementMut {
+    /// Update the visible children. The body is detached from the tree while collapsed, so that
+    /// the interactive elements within it lose the pointer and the focus.
+    fn update_children(&mut self) {
+        let mut children = vec![];
+        match self.header {
+            Some(ref h) => children.push(h.clone()),
+            None => {}
+        }
+        match self.body {
+            Some(ref b) if self.expanded => {
+                let self_ref = self.self_ref.as_ref().and_then(|r| r.upgrade());
+                b.set_parent(self_ref.as_ref());
+                children.push(b.clone());
+            },
+            Some(ref b) => b.set_parent(None),
+            None => {}
+        }
+        self.children = children;
+        self.x_req_stale = true;
+    }
+
+    /// Y requisitions of the visible children; the header row is tall enough for the triangle
+    fn child_y_reqs(&self) -> Vec<LReq> {
+        self.children.iter().enumerate().map(|(i, c)| {
+            let y_req = c.element_req().y_req.clone();
+            if i == 0 && self.header.is_some() {
+                y_req.max(&LReq::new_fixed_size(TRIANGLE_SIZE))
+            } else {
+                y_req
+            }
+        }).collect()
+    }
+
+    /// Vertical extent of the header row, in local co-ordinates
+    fn header_row(&self) -> (f64, f64) {
+        match self.header {
+            Some(ref h) => {
+                let a = h.element_alloc();
+                let top = a.y_alloc.pos_in_parent();
+                (top, top + a.y_alloc.actual_size().max(TRIANGLE_SIZE))
+            },
+            None => (0.0, TRIANGLE_SIZE)
+        }
+    }
+}
+
+/// Container element with a header and a body. A disclosure triangle beside the header shows
+/// whether the body is expanded; clicking on the header row toggles it, as do Space and Enter
+/// while the element has the focus. Left collapses and Right expands the body. The body is
+/// neither laid out, drawn nor hit while collapsed.
+pub struct ExpanderElement {
+    m: RefCell<ExpanderElementMut>,
+}
+
+impl ExpanderElement {
+    pub fn new(expanded: bool) -> ExpanderElement {
+        return ExpanderElement{m: RefCell::new(ExpanderElementMut{
+            parent: ElementParentMut::new(), identity: ElementIdentity::new(),
+            req: ElementReq::new(), alloc: ElementAlloc::new(),
+            self_ref: None, header: None, body: None, children: vec![], expanded: expanded,
+            presented_expanded: expanded, x_req_stale: true, focused: false,
+            on_toggle: None})};
+    }
+
+    pub fn get_header(&self) -> Option<ElementRef> {
+        return self.m.borrow().header.clone();
+    }
+
+    pub fn set_header(&self, self_ref: &ElementRef, header: ElementRef) {
+        {
+            let mut mm = self.m.borrow_mut();
+            mm.self_ref = Some(Rc::downgrade(self_ref));
+            mm.header.take().map(|h| h.set_parent(None));
+            header.set_parent(Some(self_ref));
+            mm.header = Some(header);
+            mm.update_children();
+        }
+        queue_resize(self);
+    }
+
+    pub fn get_body(&self) -> Option<ElementRef> {
+        return self.m.borrow().body.clone();
+    }
+
+    pub fn set_body(&self, self_ref: &ElementRef, body: ElementRef) {
+        {
+            let mut mm = self.m.borrow_mut();
+            mm.self_ref = Some(Rc::downgrade(self_ref));
+            mm.body.take().map(|b| b.set_parent(None));
+            // The parent of the body is set by `update_children`, if it is visible
+            mm.body = Some(body);
+            mm.update_children();
+        }
+        queue_resize(self);
+    }
+
+    pub fn is_expanded(&self) -> bool {
+        return self.m.borrow().expanded;
+    }
+
+    /// Show or hide the body. The toggle function is not called. A hidden body is detached
+    /// from the tree; `LSpaceArea` then moves the focus off any element within it.
+    pub fn set_expanded(&self, expanded: bool) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let changed = mm.expanded != expanded;
+            if changed {
+                mm.expanded = expanded;
+                mm.update_children();
+            }
+            changed
+        };
+        if changed {
+            queue_resize(self);
+        }
+    }
+
+    /// Apply the expanded state requested by a presentation. The state only changes if it
+    /// differs from that requested by the previous presentation, so that rebuilding preserves
+    /// the user's toggling.
+    pub fn present_expanded(&self, expanded: bool) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let changed = mm.presented_expanded != expanded;
+            mm.presented_expanded = expanded;
+            changed
+        };
+        if changed {
+            self.set_expanded(expanded);
+        }
+    }
+
+    /// Set the function that is called with the new state when the user toggles the body
+    pub fn set_on_toggle(&self, on_toggle: Option<Rc<Fn(bool)>>) {
+        self.m.borrow_mut().on_toggle = on_toggle;
+    }
+
+    /// Toggle the body, as if by the user, calling the toggle function
+    pub fn toggle(&self) {
+        self.user_set_expanded(!self.is_expanded());
+    }
+
+    fn user_set_expanded(&self, expanded: bool) {
+        if expanded != self.is_expanded() {
+            self.set_expanded(expanded);
+            let on_toggle = self.m.borrow().on_toggle.clone();
+            // Called outside the borrow, as the function may update the element
+            on_toggle.map(|f| f(expanded));
+        }
+    }
+
+    /// The disclosure triangle, pointing right when collapsed and down when expanded
+    fn triangle(&self) -> Shape {
+        let mm = self.m.borrow();
+        let (top, bottom) = mm.header_row();
+        let c = Point2::new(EXPANDER_INDENT * 0.5, (top + bottom) * 0.5);
+        let h = TRIANGLE_SIZE * 0.5;
+        let points = if mm.expanded {
+            vec![Point2::new(c.x - h, c.y - h * 0.75), Point2::new(c.x + h, c.y - h * 0.75),
+                 Point2::new(c.x, c.y + h * 0.75)]
+        } else {
+            vec![Point2::new(c.x - h * 0.75, c.y - h), Point2::new(c.x + h * 0.75, c.y),
+                 Point2::new(c.x - h * 0.75, c.y + h)]
+        };
+        Shape::polyline(points, true).filled(triangle_colour())
+    }
+}
+
+impl TElement for ExpanderElement {
+    /// Interface acquisition
+    fn as_container(&self) -> Option<&TContainerElement> {
+        return Some(self);
+    }
+
+    fn as_bin(&self) -> Option<&TBinElement> {
+        return None;
+    }
+
+    fn as_container_sequence(&self) -> Option<&TContainerSequenceElement> {
+        return None
+    }
+
+    fn as_root_element(&self) -> Option<&TRootElement> {
+        return None;
+    }
+
+    fn as_interactive(&self) -> Option<&TInteractiveElement> {
+        return Some(self);
+    }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "ExpanderElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    /// Parent get and set methods
+    fn get_parent(&self) -> Option<ElementRef> {
+        return self.m.borrow().parent.get().clone();
+    }
+
+    fn set_parent(&self, p: Option<&ElementRef>) {
+        self.m.borrow_mut().parent.set(p);
+    }
+
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
+    // Layout structure acquisition
+    fn element_req(&self) -> Ref<ElementReq> {
+        return Ref::map(self.m.borrow(), |m| &m.req);
+    }
+
+    fn element_alloc(&self) -> Ref<ElementAlloc> {
+        return Ref::map(self.m.borrow(), |m| &m.alloc);
+    }
+
+    fn element_alloc_mut(&self) -> RefMut<ElementAlloc> {
+        return RefMut::map(self.m.borrow_mut(), |m| &mut m.alloc);
+    }
+
+    /// Update element X requisition
+    fn element_update_x_req(&self, x_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_x_req(x_req);
+    }
+
+    /// Update element Y requisition
+    fn element_update_y_req(&self, y_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_y_req(y_req);
+    }
+
+    /// Paint the disclosure triangle, outlined while the element has the focus
+    fn draw_self(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        self.triangle().draw(cairo_ctx);
+        if self.m.borrow().focused {
+            let (top, bottom) = self.m.borrow().header_row();
+            let lower = Point2::new(1.0, (top + bottom - EXPANDER_INDENT) * 0.5 + 1.0);
+            let size = Vector2::new(EXPANDER_INDENT - 2.0, EXPANDER_INDENT - 2.0);
+            Shape::rectangle(lower, size, 3.0).stroked(1.0, focus_colour()).draw(cairo_ctx);
+        }
+    }
+
+    /// Draw
+    fn draw(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        self.draw_self(cairo_ctx, visible_region);
+        self.draw_children(cairo_ctx, visible_region);
+    }
+
+    // Update layout
+    fn update_x_req(&self, layout_ctx: &ElementLayoutContext) -> bool {
+        let mut changed = self.container_update_x_req(layout_ctx);
+        let stale = {
+            let mut mm = self.m.borrow_mut();
+            let stale = mm.x_req_stale;
+            mm.x_req_stale = false;
+            stale
+        };
+        if stale {
+            // The visible children changed, but their requisitions may not have
+            let x_req = self.compute_x_req();
+            if self.element_update_x_req(&x_req) {
+                self.element_alloc_mut().x_alloc_dirty();
+                changed = true;
+            }
+        }
+        return changed;
+    }
+
+    fn allocate_x(&self, x_alloc: &LAlloc) -> bool {
+        self.container_allocate_x(x_alloc)
+    }
+
+    fn update_y_req(&self) -> bool {
+        self.container_update_y_req()
+    }
+
+    fn allocate_y(&self, y_alloc: &LAlloc) {
+        self.container_allocate_y(y_alloc);
+    }
+}
+
+
+impl TContainerElement for ExpanderElement {
+    fn children(&self) -> Ref<[ElementRef]> {
+        Ref::map(self.m.borrow(), |m| &m.children[..])
+    }
+
+    fn compute_x_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        let child_reqs: Vec<Ref<ElementReq>> = mm.children.iter().map(
+            |c| c.element_req()).collect();
+        let child_x_reqs: Vec<&LReq> = child_reqs.iter().map(|c| &c.x_req).collect();
+        // An empty requisition cannot be indented, so the indent would be lost without children
+        vertical_layout::requisition_x(&child_x_reqs).max(
+            &LReq::new_fixed_size(0.0)).indent(EXPANDER_INDENT)
+    }
+
+    fn compute_child_x_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        let child_reqs: Vec<Ref<ElementReq>> = mm.children.iter().map(
+            |c| c.element_req()).collect();
+        let child_x_reqs: Vec<&LReq> = child_reqs.iter().map(|c| &c.x_req).collect();
+        vertical_layout::alloc_x(&mm.req.x_req,
+                                 &mm.alloc.x_alloc.without_position().indent(EXPANDER_INDENT),
+                                 &child_x_reqs)
+    }
+
+    fn compute_y_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        let child_y_reqs = mm.child_y_reqs();
+        let refs: Vec<&LReq> = child_y_reqs.iter().collect();
+        vertical_layout::requisition_y(&refs, 0.0, None)
+    }
+
+    fn compute_child_y_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        let child_y_reqs = mm.child_y_reqs();
+        let refs: Vec<&LReq> = child_y_reqs.iter().collect();
+        vertical_layout::alloc_y(&mm.req.y_req, &mm.alloc.y_alloc.without_position(), &refs,
+                                 0.0, None)
+    }
+}
+
+impl TInteractiveElement for ExpanderElement {
+    fn can_focus(&self) -> bool {
+        true
+    }
+
+    /// Presses on the header row, including the triangle, are captured so that the click
+    /// toggles the body; presses on the body are left for the elements beneath
+    fn on_press(&self, event: &PointerEvent) -> bool {
+        let lower = elem_root_space_bbox(self).lower;
+        let (top, bottom) = self.m.borrow().header_row();
+        match event.position() {
+            Some(p) => p.y - lower.y >= top && p.y - lower.y < bottom,
+            None => false
+        }
+    }
+
+    fn on_release(&self, event: &PointerEvent, inside: bool) {
+        if inside {
+            self.toggle();
+        }
+    }
+
+    fn on_focus_change(&self, focused: bool) {
+        self.m.borrow_mut().focused = focused;
+        queue_redraw(self);
+    }
+
+    fn on_key_event(&self, event: &KeyEvent) -> bool {
+        let expanded = match event.key_val() {
+            KEY_SPACE | KEY_RETURN | KEY_KP_ENTER => !self.is_expanded(),
+            KEY_LEFT => false,
+            KEY_RIGHT => true,
+            _ => return false
+        };
+        if event.event_type() == KeyEventType::Press {
+            self.user_set_expanded(expanded);
+        }
+        return true;
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use graphics::border::{Border, StateBorders};
+    use elements::element::{TElement, ElementRef, elem_as_ref, elems_are_same};
+    use elements::container::TContainerElement;
+    use elements::root_element::RootElement;
+    use elements::shape_element::ShapeElement;
+    use elements::state_border_element::StateBorderElement;
+    use input::interactive::ElementInteraction;
+
+    fn shape() -> ElementRef {
+        elem_as_ref(ShapeElement::new(20.0, 10.0, &Rc::new(vec![])))
+    }
+
+    fn expander_with(expanded: bool, body: &ElementRef) -> ElementRef {
+        let elem = elem_as_ref(ExpanderElement::new(expanded));
+        {
+            let x = elem.as_any().downcast_ref::<ExpanderElement>().unwrap();
+            x.set_header(&elem, shape());
+            x.set_body(&elem, body.clone());
+        }
+        elem
+    }
+
+    fn as_expander(elem: &ElementRef) -> &ExpanderElement {
+        elem.as_any().downcast_ref::<ExpanderElement>().unwrap()
+    }
+
+    #[test]
+    fn test_toggle() {
+        let body = shape();
+        let elem = expander_with(false, &body);
+        let x = as_expander(&elem);
+        let toggles = Rc::new(RefCell::new(vec![]));
+        let t = toggles.clone();
+        x.set_on_toggle(Some(Rc::new(move |expanded| t.borrow_mut().push(expanded))));
+
+        // The collapsed body is neither a child nor attached to the expander
+        assert_eq!(x.children().len(), 1);
+        assert!(body.get_parent().is_none());
+
+        x.toggle();
+        assert!(x.is_expanded());
+        assert_eq!(x.children().len(), 2);
+        assert!(elems_are_same(&body.get_parent().unwrap(), &elem));
+
+        x.toggle();
+        assert!(!x.is_expanded());
+        assert_eq!(x.children().len(), 1);
+        assert!(body.get_parent().is_none());
+        assert_eq!(*toggles.borrow(), vec![true, false]);
+
+        // Setting the state directly does not call the toggle function
+        x.set_expanded(true);
+        assert!(x.is_expanded());
+        assert_eq!(toggles.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_present_expanded() {
+        let elem = expander_with(false, &shape());
+        let x = as_expander(&elem);
+
+        // Presenting the same state again keeps the user's toggling
+        x.toggle();
+        x.present_expanded(false);
+        assert!(x.is_expanded());
+
+        // A change in the presented state is applied
+        x.present_expanded(true);
+        assert!(x.is_expanded());
+        x.toggle();
+        assert!(!x.is_expanded());
+        x.present_expanded(true);
+        assert!(!x.is_expanded());
+        x.present_expanded(false);
+        assert!(!x.is_expanded());
+    }
+
+    #[test]
+    fn test_collapse_removes_focus() {
+        let border = Rc::new(Border::new_filled(0.0, 0.0, 0.0, 0.0, 0.0, None));
+        let body = elem_as_ref(StateBorderElement::new(&Rc::new(StateBorders::new(border)), true));
+        let elem = expander_with(true, &body);
+        let root = elem_as_ref(RootElement::new());
+        root.as_bin().unwrap().set_child(&root, elem.clone());
+
+        let interaction = ElementInteraction::new();
+        interaction.set_focus(Some(&body));
+        interaction.remove_detached(&root);
+        assert!(interaction.focused().is_some());
+
+        // Collapsing detaches the body, so the element within it loses the focus
+        as_expander(&elem).toggle();
+        interaction.remove_detached(&root);
+        assert!(interaction.focused().is_none());
+    }
+}
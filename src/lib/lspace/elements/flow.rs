@@ -1,5 +1,6 @@
 use cairo::Context;
 
+use std::any::Any;
 use std::cell::{RefCell, Ref, RefMut};
 
 use layout::lreq::LReq;
@@ -9,7 +10,7 @@ use geom::bbox2::BBox2;
 
 use elements::element_ctx::ElementLayoutContext;
 use elements::element_layout::{ElementReq, ElementAlloc};
-use elements::element::{TElement, ElementRef, ElementParentMut, queue_resize};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, queue_resize};
 use elements::container::TContainerElement;
 use elements::bin::{TBinElement};
 use elements::container_sequence::{TContainerSequenceElement, ContainerSequenceComponentMut};
@@ -18,6 +19,7 @@ use elements::root_element::{TRootElement};
 
 struct FlowElementMut {
     parent: ElementParentMut,
+    identity: ElementIdentity,
     req: ElementReq,
     alloc: ElementAlloc,
     container_seq: ContainerSequenceComponentMut,
@@ -79,13 +81,35 @@ impl FlowElement {
     pub fn new(x_spacing: f64, y_spacing: f64,
                indentation: flow_layout::FlowIndent) -> FlowElement {
         return FlowElement{m: RefCell::new(FlowElementMut{
-                parent: ElementParentMut::new(),
+                parent: ElementParentMut::new(), identity: ElementIdentity::new(),
                 req: ElementReq::new(), alloc: ElementAlloc::new(),
                 container_seq: ContainerSequenceComponentMut::new(),
                 x_spacing: x_spacing, y_spacing: y_spacing,
                 indentation: indentation,
                 lines: Vec::new()})};
     }
+
+    /// Get the `(x_spacing, y_spacing, indentation)` of the flow
+    pub fn get_spacing(&self) -> (f64, f64, flow_layout::FlowIndent) {
+        let mm = self.m.borrow();
+        return (mm.x_spacing, mm.y_spacing, mm.indentation);
+    }
+
+    pub fn set_spacing(&self, x_spacing: f64, y_spacing: f64,
+                       indentation: flow_layout::FlowIndent) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let changed = (mm.x_spacing, mm.y_spacing, mm.indentation) !=
+                          (x_spacing, y_spacing, indentation);
+            mm.x_spacing = x_spacing;
+            mm.y_spacing = y_spacing;
+            mm.indentation = indentation;
+            changed
+        };
+        if changed {
+            queue_resize(self);
+        }
+    }
 }
 
 impl TElement for FlowElement {
@@ -106,6 +130,15 @@ impl TElement for FlowElement {
         return None;
     }
 
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "FlowElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
     /// Parent get and set methods
     fn get_parent(&self) -> Option<ElementRef> {
         return self.m.borrow().parent.get().clone();
@@ -115,6 +148,10 @@ impl TElement for FlowElement {
         self.m.borrow_mut().parent.set(p);
     }
 
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
     // Layout structure acquisition
     fn element_req(&self) -> Ref<ElementReq> {
         return Ref::map(self.m.borrow(), |m| &m.req);
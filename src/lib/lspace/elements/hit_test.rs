@@ -0,0 +1,53 @@
+use geom::point2::Point2;
+use geom::vector2::Vector2;
+use elements::element::ElementRef;
+
+
+/// Find the path of elements under the point `p`, starting at `root` and ending with the
+/// deepest element whose allocated box contains `p`. `p` is in the co-ordinate space of
+/// `root`. Returns an empty path if `p` lies outside `root`, unless `root` is a root element
+/// and `p` lies within one of its floating elements, which may extend beyond it.
+pub fn elem_path_at_point(root: &ElementRef, p: &Point2) -> Vec<ElementRef> {
+    let mut path: Vec<ElementRef> = Vec::new();
+    let inside = root.element_alloc().local_bbox().contains(p);
+    if inside || root.as_root_element().is_some() {
+        let mut elem = root.clone();
+        let mut local_p = *p;
+        loop {
+            path.push(elem.clone());
+            let children = match elem.as_container() {
+                Some(c) => c.children().to_vec(),
+                None => vec![]
+            };
+            // Children that are later in the list are painted on top, so search them first
+            let mut hit: Option<(ElementRef, Point2)> = None;
+            for child in children.iter().rev() {
+                let (offset, bbox) = {
+                    let a = child.element_alloc();
+                    (Vector2::new(a.x_alloc.pos_in_parent(), a.y_alloc.pos_in_parent()),
+                     a.local_bbox_to_parent_space(&a.local_bbox()))
+                };
+                if bbox.contains(&local_p) {
+                    hit = Some((child.clone(), local_p - offset));
+                    break;
+                }
+            }
+            match hit {
+                Some((child, child_p)) => {
+                    elem = child;
+                    local_p = child_p;
+                },
+                None => break
+            }
+        }
+        if !inside && path.len() == 1 {
+            path.clear();
+        }
+    }
+    path
+}
+
+/// Find the deepest element under the point `p`, which is in the co-ordinate space of `root`
+pub fn elem_at_point(root: &ElementRef, p: &Point2) -> Option<ElementRef> {
+    elem_path_at_point(root, p).pop()
+}
@@ -0,0 +1,606 @@
+use cairo::Context;
+
+use std::any::Any;
+use std::mem;
+use std::cmp::min;
+use std::rc::{Rc, Weak};
+use std::cell::{RefCell, Ref, RefMut};
+use std::collections::HashMap;
+
+use layout::lreq::LReq;
+use layout::lalloc::LAlloc;
+use layout::vertical_layout;
+use layout::row_heights::RowHeights;
+use geom::bbox2::BBox2;
+use geom::vector2::Vector2;
+
+use input::interactive::TInteractiveElement;
+use elements::element_ctx::{ElementContext, ElementLayoutContext};
+use elements::element_layout::{ElementReq, ElementAlloc};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, elem_ancestors,
+                        queue_resize};
+use elements::container::TContainerElement;
+use elements::bin::{TBinElement};
+use elements::container_sequence::{TContainerSequenceElement};
+use elements::root_element::{TRootElement};
+
+
+/// Rows beyond each end of the drawn region that are built along with the visible rows, so
+/// that scrolling a short distance does not expose rows that have yet to be built
+const OVERSCAN_ROWS: usize = 8;
+
+/// Rows scrolled by one step of the scroll wheel
+const SCROLL_STEP_ROWS: f64 = 3.0;
+
+
+/// Builds the element for the row at an index. The element of a row that has been scrolled
+/// away may be passed, for the builder to reuse.
+pub type RowBuilder = Rc<Fn(usize, Option<&ElementRef>, &ElementContext) -> ElementRef>;
+
+
+struct LazyColumnElementMut {
+    parent: ElementParentMut,
+    identity: ElementIdentity,
+    req: ElementReq,
+    alloc: ElementAlloc,
+    self_ref: Option<Weak<TElement>>,
+    builder: RowBuilder,
+    /// Identifies what the builder presents; see `set_row_source`
+    row_source: Option<Rc<Any>>,
+    rows: RowHeights,
+    /// The range of rows that have been built; `children` holds their elements
+    built: (usize, usize),
+    children: Vec<ElementRef>,
+    /// Elements of rows that have been scrolled away, for reuse by other rows
+    recycled: Vec<ElementRef>,
+    /// The range of rows to build, from the region that was most recently drawn
+    wanted: (usize, usize),
+    /// Set when the built rows must be built again, e.g. because the builder has changed
+    rows_stale: bool,
+    /// Set when the built rows change; the X requisition must then be recomputed even if the
+    /// requisitions of the children are unchanged
+    x_req_stale: bool,
+    /// The height of the view onto the rows; `None` if the column is as high as its rows
+    view_height: Option<f64>,
+    /// The position, within the rows, of the top of the view
+    scroll_y: f64,
+}
+
+impl LazyColumnElementMut {
+    /// The range of rows to build in order to show the region from `top` to `bottom`
+    fn rows_to_build(&mut self, top: f64, bottom: f64) -> (usize, usize) {
+        let (start, end) = self.rows.rows_in_region(top, bottom);
+        let n = self.rows.len();
+        return (start.saturating_sub(OVERSCAN_ROWS), min(end + OVERSCAN_ROWS, n));
+    }
+
+    fn max_scroll(&mut self) -> f64 {
+        return match self.view_height {
+            Some(h) => (self.rows.total_height() - h).max(0.0),
+            None => 0.0
+        };
+    }
+
+    fn clamp_scroll(&mut self) {
+        let max_scroll = self.max_scroll();
+        self.scroll_y = self.scroll_y.min(max_scroll).max(0.0);
+    }
+
+    /// Clamp the scroll position and request the rows that are in view; they are built
+    /// during the next layout
+    fn update_view(&mut self) {
+        self.clamp_scroll();
+        match self.view_height {
+            Some(h) => {
+                let (top, bottom) = (self.scroll_y, self.scroll_y + h);
+                self.wanted = self.rows_to_build(top, bottom);
+            },
+            None => {}
+        }
+    }
+}
+
+/// A column of `item_count` rows, of which only those that intersect the region being drawn
+/// are built and laid out. Rows that have yet to be laid out are assumed to be the estimated
+/// row height high. Rows that are scrolled into view are built once drawing has finished,
+/// and appear in the following frame.
+///
+/// Given a view height, the column shows that much of its rows and scrolls through them, in
+/// response to the scroll wheel or `set_scroll_y`; rows scrolled into view are then built
+/// during the next layout.
+pub struct LazyColumnElement {
+    m: RefCell<LazyColumnElementMut>,
+}
+
+
+impl LazyColumnElement {
+    pub fn new(item_count: usize, estimated_row_height: f64,
+               builder: RowBuilder) -> Rc<LazyColumnElement> {
+        let column = Rc::new(LazyColumnElement{m: RefCell::new(LazyColumnElementMut{
+            parent: ElementParentMut::new(), identity: ElementIdentity::new(),
+            req: ElementReq::new(), alloc: ElementAlloc::new(),
+            self_ref: None, builder: builder, row_source: None,
+            rows: RowHeights::new(item_count, estimated_row_height), built: (0, 0),
+            children: vec![], recycled: vec![], wanted: (0, 0), rows_stale: false,
+            x_req_stale: true, view_height: None, scroll_y: 0.0})});
+        let self_ref: ElementRef = column.clone();
+        column.m.borrow_mut().self_ref = Some(Rc::downgrade(&self_ref));
+        return column;
+    }
+
+    pub fn get_item_count(&self) -> usize {
+        return self.m.borrow().rows.len();
+    }
+
+    /// Change the number of rows. The rows that have been built are built again, as they may
+    /// now present different items.
+    pub fn set_item_count(&self, item_count: usize) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let changed = mm.rows.len() != item_count;
+            if changed {
+                mm.rows.set_len(item_count);
+                mm.rows_stale = true;
+            }
+            changed
+        };
+        if changed {
+            queue_resize(self);
+        }
+    }
+
+    pub fn get_estimated_row_height(&self) -> f64 {
+        return self.m.borrow().rows.estimated_height();
+    }
+
+    pub fn set_estimated_row_height(&self, estimated_row_height: f64) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let changed = mm.rows.estimated_height() != estimated_row_height;
+            mm.rows.set_estimated_height(estimated_row_height);
+            changed
+        };
+        if changed {
+            queue_resize(self);
+        }
+    }
+
+    /// Replace the row builder; the rows that have been built are built again
+    pub fn set_row_builder(&self, builder: RowBuilder) {
+        {
+            let mut mm = self.m.borrow_mut();
+            mm.builder = builder;
+            mm.rows_stale = true;
+        }
+        queue_resize(self);
+    }
+
+    /// Replace the row builder with one that presents the same rows; the rows that have been
+    /// built are kept, and only rows that are built from now on use the new builder
+    pub fn update_row_builder(&self, builder: RowBuilder) {
+        self.m.borrow_mut().builder = builder;
+    }
+
+    /// A value that identifies what the row builder presents, so that presentations can tell
+    /// if the rows must be built again when they are rebuilt
+    pub fn get_row_source(&self) -> Option<Rc<Any>> {
+        return self.m.borrow().row_source.clone();
+    }
+
+    pub fn set_row_source(&self, row_source: Option<Rc<Any>>) {
+        self.m.borrow_mut().row_source = row_source;
+    }
+
+    /// The range of rows that have been built
+    pub fn get_built_rows(&self) -> (usize, usize) {
+        return self.m.borrow().built;
+    }
+
+    pub fn get_view_height(&self) -> Option<f64> {
+        return self.m.borrow().view_height;
+    }
+
+    /// Show `view_height` of the rows, scrolling through them; if `None`, the column is as
+    /// high as all of its rows
+    pub fn set_view_height(&self, view_height: Option<f64>) {
+        {
+            let mut mm = self.m.borrow_mut();
+            mm.view_height = view_height;
+            mm.update_view();
+        }
+        queue_resize(self);
+    }
+
+    pub fn get_scroll_y(&self) -> f64 {
+        return self.m.borrow().scroll_y;
+    }
+
+    /// Scroll so that the rows at `scroll_y` are at the top of the view
+    pub fn set_scroll_y(&self, scroll_y: f64) {
+        {
+            let mut mm = self.m.borrow_mut();
+            if mm.view_height.is_none() || scroll_y == mm.scroll_y {
+                return;
+            }
+            mm.scroll_y = scroll_y;
+            mm.update_view();
+        }
+        queue_resize(self);
+    }
+
+    /// Note the rows that intersect `region`, which is being drawn and is in the co-ordinate
+    /// space of the rows. If any of them have yet to be built, a resize is queued for once
+    /// drawing has finished, so that they are built.
+    fn request_rows_in_region(&self, region: &BBox2) {
+        let self_ref = {
+            let mut mm = self.m.borrow_mut();
+            let (start, end) = mm.rows.rows_in_region(region.lower.y, region.upper.y);
+            let (built_start, built_end) = mm.built;
+            if start == end || mm.rows_stale || (start >= built_start && end <= built_end) {
+                return;
+            }
+            let wanted = mm.rows_to_build(region.lower.y, region.upper.y);
+            if wanted == mm.wanted {
+                // Already requested
+                return;
+            }
+            mm.wanted = wanted;
+            mm.self_ref.as_ref().and_then(|r| r.upgrade())
+        };
+        match (self_ref, elem_ancestors(self).first()) {
+            (Some(self_ref), Some(root)) => {
+                match root.as_root_element() {
+                    Some(r) => r.root_queue_resize_after_draw(&self_ref),
+                    None => {}
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Build the wanted rows, reusing the elements of rows that are no longer wanted.
+    /// Returns `true` if the children changed.
+    fn build_rows(&self, elem_ctx: &ElementContext) -> bool {
+        let (start, end) = {
+            let mm = self.m.borrow();
+            let n = mm.rows.len();
+            let wanted = (min(mm.wanted.0, n), min(mm.wanted.1, n));
+            if !mm.rows_stale && wanted == mm.built {
+                return false;
+            }
+            wanted
+        };
+
+        // Take the existing rows, as the builder must be called without `m` borrowed
+        let (builder, self_ref, old_start, old_children, mut recycled, stale) = {
+            let mut mm = self.m.borrow_mut();
+            let old_children = mem::replace(&mut mm.children, vec![]);
+            let recycled = mem::replace(&mut mm.recycled, vec![]);
+            (mm.builder.clone(), mm.self_ref.as_ref().and_then(|r| r.upgrade()), mm.built.0,
+             old_children, recycled, mm.rows_stale)
+        };
+        let mut kept: HashMap<usize, ElementRef> = HashMap::new();
+        for (k, e) in old_children.into_iter().enumerate() {
+            e.set_parent(None);
+            let i = old_start + k;
+            if i >= start && i < end {
+                kept.insert(i, e);
+            } else {
+                recycled.push(e);
+            }
+        }
+
+        let mut children: Vec<ElementRef> = Vec::with_capacity(end - start);
+        for i in start..end {
+            let elem = match kept.remove(&i) {
+                Some(e) => if stale {builder(i, Some(&e), elem_ctx)} else {e},
+                None => match recycled.pop() {
+                    Some(e) => builder(i, Some(&e), elem_ctx),
+                    None => builder(i, None, elem_ctx)
+                }
+            };
+            children.push(elem);
+        }
+        // There is no need to keep more elements for reuse than there are visible rows
+        recycled.truncate(children.len());
+
+        match self_ref {
+            Some(ref r) => {
+                for c in children.iter() {
+                    c.set_parent(Some(r));
+                }
+            },
+            None => {}
+        }
+        let mut mm = self.m.borrow_mut();
+        mm.children = children;
+        mm.recycled = recycled;
+        mm.built = (start, end);
+        mm.rows_stale = false;
+        mm.x_req_stale = true;
+        return true;
+    }
+}
+
+
+impl TElement for LazyColumnElement {
+    /// Interface acquisition
+    fn as_container(&self) -> Option<&TContainerElement> {
+        return Some(self);
+    }
+
+    fn as_bin(&self) -> Option<&TBinElement> {
+        return None;
+    }
+
+    fn as_container_sequence(&self) -> Option<&TContainerSequenceElement> {
+        return None;
+    }
+
+    fn as_root_element(&self) -> Option<&TRootElement> {
+        return None;
+    }
+
+    fn as_interactive(&self) -> Option<&TInteractiveElement> {
+        return Some(self);
+    }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "LazyColumnElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    /// Parent get and set methods
+    fn get_parent(&self) -> Option<ElementRef> {
+        return self.m.borrow().parent.get().clone();
+    }
+
+    fn set_parent(&self, p: Option<&ElementRef>) {
+        self.m.borrow_mut().parent.set(p);
+    }
+
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
+    /// Element layout structure acquisition
+    fn element_req(&self) -> Ref<ElementReq> {
+        return Ref::map(self.m.borrow(), |m| &m.req);
+    }
+
+    fn element_alloc(&self) -> Ref<ElementAlloc> {
+        return Ref::map(self.m.borrow(), |m| &m.alloc);
+    }
+
+    fn element_alloc_mut(&self) -> RefMut<ElementAlloc> {
+        return RefMut::map(self.m.borrow_mut(), |m| &mut m.alloc);
+    }
+
+    /// Update element X requisition
+    fn element_update_x_req(&self, x_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_x_req(x_req);
+    }
+
+    /// Update element Y requisition
+    fn element_update_y_req(&self, y_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_y_req(y_req);
+    }
+
+    /// Given a view height, only the view is painted
+    fn draw_bbox(&self) -> BBox2 {
+        let mm = self.m.borrow();
+        return match mm.view_height {
+            Some(_) => mm.alloc.local_bbox(),
+            None => mm.alloc.draw_bbox()
+        };
+    }
+
+    /// Draw, clipped to the view if there is one
+    fn draw(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        let (view_height, scroll_y) = {
+            let mm = self.m.borrow();
+            (mm.view_height, mm.scroll_y)
+        };
+        match view_height {
+            Some(_) => {
+                let bbox = self.element_alloc().local_bbox();
+                cairo_ctx.save();
+                cairo_ctx.rectangle(bbox.lower.x, bbox.lower.y, bbox.size().x, bbox.size().y);
+                cairo_ctx.clip();
+                let visible = match visible_region.intersection(&bbox) {
+                    Some(v) => v,
+                    None => bbox
+                };
+                self.draw_self(cairo_ctx, &visible);
+                self.draw_children(cairo_ctx, &visible);
+                cairo_ctx.restore();
+                self.request_rows_in_region(&visible.offset(&Vector2::new(0.0, scroll_y)));
+            },
+            None => {
+                self.draw_self(cairo_ctx, visible_region);
+                self.draw_children(cairo_ctx, visible_region);
+                self.request_rows_in_region(visible_region);
+            }
+        }
+    }
+
+    /// Update layout
+    fn update_x_req(&self, layout_ctx: &ElementLayoutContext) -> bool {
+        if self.build_rows(layout_ctx.elem_ctx()) {
+            // The new rows must be laid out
+            let mut alloc_mut = self.element_alloc_mut();
+            alloc_mut.x_req_dirty();
+            alloc_mut.y_req_dirty();
+            alloc_mut.x_alloc_dirty();
+            alloc_mut.y_alloc_dirty();
+        }
+        let mut changed = self.container_update_x_req(layout_ctx);
+        let stale = {
+            let mut mm = self.m.borrow_mut();
+            let stale = mm.x_req_stale;
+            mm.x_req_stale = false;
+            stale
+        };
+        if stale {
+            // The built rows changed, but their requisitions may not have
+            let x_req = self.compute_x_req();
+            if self.element_update_x_req(&x_req) {
+                self.element_alloc_mut().x_alloc_dirty();
+                changed = true;
+            }
+        }
+        return changed;
+    }
+
+    fn allocate_x(&self, x_alloc: &LAlloc) -> bool {
+        return self.container_allocate_x(x_alloc);
+    }
+
+    fn update_y_req(&self) -> bool {
+        return self.container_update_y_req();
+    }
+
+    fn allocate_y(&self, y_alloc: &LAlloc) {
+        self.container_allocate_y(y_alloc);
+    }
+}
+
+
+impl TContainerElement for LazyColumnElement {
+    fn children(&self) -> Ref<[ElementRef]> {
+        return Ref::map(self.m.borrow(), |m| &m.children[..]);
+    }
+
+    /// The width of the built rows
+    fn compute_x_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        let child_reqs: Vec<Ref<ElementReq>> = mm.children.iter().map(
+            |c| c.element_req()).collect();
+        let child_x_reqs: Vec<&LReq> = child_reqs.iter().map(|c| &c.x_req).collect();
+        return vertical_layout::requisition_x(&child_x_reqs);
+    }
+
+    fn compute_child_x_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        let child_reqs: Vec<Ref<ElementReq>> = mm.children.iter().map(
+            |c| c.element_req()).collect();
+        let child_x_reqs: Vec<&LReq> = child_reqs.iter().map(|c| &c.x_req).collect();
+        return vertical_layout::alloc_x(&mm.req.x_req, &mm.alloc.x_alloc.without_position(),
+                                        &child_x_reqs);
+    }
+
+    /// The view height if given, otherwise the height of every row; the heights of the built
+    /// rows are measured, replacing their estimates
+    fn compute_y_req(&self) -> LReq {
+        let mut mm = self.m.borrow_mut();
+        let heights: Vec<f64> = mm.children.iter().map(
+            |c| c.element_req().y_req.size().size()).collect();
+        let start = mm.built.0;
+        for (k, h) in heights.into_iter().enumerate() {
+            mm.rows.set_height(start + k, h);
+        }
+        // Measured heights may move the end of the rows closer to the view
+        mm.clamp_scroll();
+        return match mm.view_height {
+            Some(h) => LReq::new_fixed_size(h),
+            None => LReq::new_fixed_size(mm.rows.total_height())
+        };
+    }
+
+    /// The rows are offset by the scroll position
+    fn compute_child_y_allocs(&self) -> Vec<LAlloc> {
+        let mut mm = self.m.borrow_mut();
+        let start = mm.built.0;
+        let n = mm.children.len();
+        let scroll_y = mm.scroll_y;
+        let offsets: Vec<f64> = (0..n).map(|k| mm.rows.offset(start + k) - scroll_y).collect();
+        return mm.children.iter().zip(offsets.iter()).map(
+            |(c, pos)| LAlloc::new_from_req(&c.element_req().y_req, *pos)).collect();
+    }
+}
+
+
+impl TInteractiveElement for LazyColumnElement {
+    /// Scroll the view, if there is one
+    fn on_scroll(&self, scroll_x: f64, scroll_y: f64) -> bool {
+        let (scrollable, pos, step) = {
+            let mut mm = self.m.borrow_mut();
+            let max_scroll = mm.max_scroll();
+            (mm.view_height.is_some() && max_scroll > 0.0, mm.scroll_y,
+             mm.rows.estimated_height() * SCROLL_STEP_ROWS)
+        };
+        if scrollable && scroll_y != 0.0 {
+            self.set_scroll_y(pos + scroll_y * step);
+            return true;
+        }
+        return false;
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use elements::element::elem_as_ref;
+    use elements::shape_element::ShapeElement;
+
+    #[test]
+    fn test_scroll_rebuilds_rows() {
+        // Count the rows that are built afresh, and those that reuse a recycled element
+        let (fresh, reused) = (Rc::new(Cell::new(0)), Rc::new(Cell::new(0)));
+        let (f, r) = (fresh.clone(), reused.clone());
+        let builder: RowBuilder = Rc::new(
+            move |i: usize, recycled: Option<&ElementRef>, elem_ctx: &ElementContext| {
+                match recycled {
+                    Some(e) => {r.set(r.get() + 1); e.clone()},
+                    None => {
+                        f.set(f.get() + 1);
+                        elem_as_ref(ShapeElement::new(100.0, 10.0, &Rc::new(vec![])))
+                    }
+                }
+            });
+        let elem_ctx = ElementContext::new();
+        let column = LazyColumnElement::new(1000, 10.0, builder.clone());
+        column.set_view_height(Some(50.0));
+        assert!(column.build_rows(&elem_ctx));
+        assert_eq!(column.get_built_rows(), (0, 5 + OVERSCAN_ROWS));
+        assert_eq!((fresh.get(), reused.get()), (13, 0));
+
+        // Scrolling far away recycles every row that was built
+        column.set_scroll_y(500.0);
+        assert!(column.build_rows(&elem_ctx));
+        assert_eq!(column.get_built_rows(), (50 - OVERSCAN_ROWS, 55 + OVERSCAN_ROWS));
+        assert_eq!((fresh.get(), reused.get()), (21, 13));
+
+        // Scrolling a short distance keeps the rows that remain in range
+        column.set_scroll_y(520.0);
+        assert!(column.build_rows(&elem_ctx));
+        assert_eq!(column.get_built_rows(), (52 - OVERSCAN_ROWS, 57 + OVERSCAN_ROWS));
+        assert_eq!((fresh.get(), reused.get()), (21, 15));
+        assert!(!column.build_rows(&elem_ctx));
+
+        // A builder that presents the same rows keeps the rows that were built
+        column.update_row_builder(builder.clone());
+        assert!(!column.build_rows(&elem_ctx));
+        column.set_row_builder(builder);
+        assert!(column.build_rows(&elem_ctx));
+        assert_eq!((fresh.get(), reused.get()), (21, 28));
+
+        // The scroll wheel scrolls by a few rows
+        assert!(column.on_scroll(0.0, 1.0));
+        assert_eq!(column.get_scroll_y(), 520.0 + 10.0 * SCROLL_STEP_ROWS);
+
+        // The scroll position is limited to the end of the rows
+        column.set_scroll_y(20000.0);
+        assert_eq!(column.get_scroll_y(), 10000.0 - 50.0);
+    }
+}
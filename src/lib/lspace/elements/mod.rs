@@ -9,4 +9,18 @@ pub mod border_element;
 pub mod flow;
 pub mod column;
 pub mod row;
+pub mod stack;
+pub mod canvas;
+pub mod lazy_column;
 pub mod root_element;
+pub mod element_dump;
+pub mod debug_overlay;
+pub mod hit_test;
+pub mod event_binding;
+pub mod opacity_element;
+pub mod shadow_element;
+pub mod state_border_element;
+pub mod shape_element;
+pub mod text_entry_element;
+pub mod text_editor_element;
+pub mod expander_element;
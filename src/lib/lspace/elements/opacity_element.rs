@@ -0,0 +1,214 @@
+use cairo::Context;
+
+use std::any::Any;
+use std::cell::{RefCell, Ref, RefMut};
+
+use layout::lalloc::LAlloc;
+use layout::lreq::LReq;
+use geom::bbox2::BBox2;
+use elements::element_ctx::ElementLayoutContext;
+use elements::element_layout::{ElementReq, ElementAlloc};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, queue_resize,
+                        queue_redraw};
+use elements::container::TContainerElement;
+use elements::bin::{TBinElement, BinComponentMut};
+use elements::container_sequence::{TContainerSequenceElement};
+use elements::root_element::{TRootElement};
+
+
+fn clamp_opacity(opacity: f64) -> f64 {
+    return opacity.max(0.0).min(1.0);
+}
+
+struct OpacityElementMut {
+    parent: ElementParentMut,
+    identity: ElementIdentity,
+    req: ElementReq,
+    alloc: ElementAlloc,
+    bin: BinComponentMut,
+    opacity: f64,
+}
+
+/// Bin element that draws its child partially transparent, by drawing it to a group that is
+/// painted with an alpha of `opacity`, from `0` (invisible) to `1` (opaque). It takes the layout
+/// of its child, and an invisible child can still be hit. Animate the opacity with an
+/// `Animation<f64>` whose target is the element, to fade it in or out.
+pub struct OpacityElement {
+    m: RefCell<OpacityElementMut>,
+}
+
+impl OpacityElement {
+    pub fn new(opacity: f64) -> OpacityElement {
+        return OpacityElement{m: RefCell::new(OpacityElementMut{
+            parent: ElementParentMut::new(), identity: ElementIdentity::new(),
+            req: ElementReq::new(), alloc: ElementAlloc::new(),
+            bin: BinComponentMut::new(), opacity: clamp_opacity(opacity)})};
+    }
+
+    pub fn get_opacity(&self) -> f64 {
+        return self.m.borrow().opacity;
+    }
+
+    /// Set the opacity, which is limited to the range from `0` to `1`
+    pub fn set_opacity(&self, opacity: f64) {
+        let opacity = clamp_opacity(opacity);
+        if self.m.borrow().opacity != opacity {
+            self.m.borrow_mut().opacity = opacity;
+            queue_redraw(self);
+        }
+    }
+}
+
+impl TElement for OpacityElement {
+    /// Interface acquisition
+    fn as_container(&self) -> Option<&TContainerElement> {
+        return Some(self);
+    }
+
+    fn as_bin(&self) -> Option<&TBinElement> {
+        return Some(self);
+    }
+
+    fn as_container_sequence(&self) -> Option<&TContainerSequenceElement> {
+        return None
+    }
+
+    fn as_root_element(&self) -> Option<&TRootElement> {
+        return None;
+    }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "OpacityElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    /// Parent get and set methods
+    fn get_parent(&self) -> Option<ElementRef> {
+        return self.m.borrow().parent.get().clone();
+    }
+
+    fn set_parent(&self, p: Option<&ElementRef>) {
+        self.m.borrow_mut().parent.set(p);
+    }
+
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
+    // Layout structure acquisition
+    fn element_req(&self) -> Ref<ElementReq> {
+        return Ref::map(self.m.borrow(), |m| &m.req);
+    }
+
+    fn element_alloc(&self) -> Ref<ElementAlloc> {
+        return Ref::map(self.m.borrow(), |m| &m.alloc);
+    }
+
+    fn element_alloc_mut(&self) -> RefMut<ElementAlloc> {
+        return RefMut::map(self.m.borrow_mut(), |m| &mut m.alloc);
+    }
+
+    /// Update element X requisition
+    fn element_update_x_req(&self, x_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_x_req(x_req);
+    }
+
+    /// Update element Y requisition
+    fn element_update_y_req(&self, y_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_y_req(y_req);
+    }
+
+    /// Draw the child to a group, and paint it with the opacity as its alpha. The group is
+    /// skipped when the child is opaque, and nothing is drawn when it is invisible.
+    fn draw(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        let opacity = self.m.borrow().opacity;
+        if opacity <= 0.0 {
+            return;
+        }
+        if opacity >= 1.0 {
+            self.draw_children(cairo_ctx, visible_region);
+            return;
+        }
+        cairo_ctx.push_group();
+        self.draw_children(cairo_ctx, visible_region);
+        cairo_ctx.pop_group_to_source();
+        cairo_ctx.paint_with_alpha(opacity);
+    }
+
+    // Update layout
+    fn update_x_req(&self, layout_ctx: &ElementLayoutContext) -> bool {
+        self.container_update_x_req(layout_ctx)
+    }
+
+    fn allocate_x(&self, x_alloc: &LAlloc) -> bool {
+        self.container_allocate_x(x_alloc)
+    }
+
+    fn update_y_req(&self) -> bool {
+        self.container_update_y_req()
+    }
+
+    fn allocate_y(&self, y_alloc: &LAlloc) {
+        self.container_allocate_y(y_alloc);
+    }
+}
+
+
+impl TContainerElement for OpacityElement {
+    fn children(&self) -> Ref<[ElementRef]> {
+        Ref::map(self.m.borrow(), |m| m.bin.children())
+    }
+
+    fn compute_x_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => LReq::new_empty(),
+            Some(ref ch) => ch.element_req().x_req.clone()
+        }
+    }
+
+    fn compute_child_x_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => vec![],
+            Some(_) => vec![mm.alloc.x_alloc.without_position()]
+        }
+    }
+
+    fn compute_y_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => LReq::new_empty(),
+            Some(ref ch) => ch.element_req().y_req.clone()
+        }
+    }
+
+    fn compute_child_y_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => vec![],
+            Some(_) => vec![mm.alloc.y_alloc.without_position()]
+        }
+    }
+}
+
+impl TBinElement for OpacityElement {
+    fn get_child(&self) -> Option<ElementRef> {
+        let mm = self.m.borrow();
+        mm.bin.get_child()
+    }
+
+    fn set_child(&self, self_ref: &ElementRef, child: ElementRef) {
+        self.m.borrow_mut().bin.set_child(self_ref, child);
+        queue_resize(self);
+    }
+
+    fn clear_child(&self) {
+        self.m.borrow_mut().bin.clear_child();
+        queue_resize(self);
+    }
+}
@@ -1,49 +1,240 @@
 use cairo::Context;
 
 use std::rc::{Rc, Weak};
+use std::any::Any;
 use std::cell::{RefCell, Ref, RefMut};
 
 use layout::lalloc::LAlloc;
 use layout::lreq::LReq;
 use geom::bbox2::BBox2;
+use geom::point2::Point2;
+use geom::vector2::Vector2;
 
 use elements::element_ctx::ElementLayoutContext;
 use elements::element_layout::{ElementReq, ElementAlloc};
-use elements::element::{TElement, ElementRef, queue_resize};
+use elements::element::{TElement, ElementRef, ElementIdentity, elems_are_same, elem_root_space_bbox,
+                        elem_is_in_tree, queue_resize, queue_redraw};
 use elements::container::TContainerElement;
 use elements::bin::{TBinElement, BinComponentMut};
 use elements::container_sequence::{TContainerSequenceElement};
 use lspace_area::TLSpaceListener;
 
 
+/// Where a floating element is placed in the overlay layer of the root. Anchor elements are
+/// held weakly; a floating element is hidden while its anchor element has been dropped or is not
+/// in the tree of the root.
+#[derive(Clone)]
+pub enum OverlayAnchor {
+    /// Below the element's bounding box, aligned with its left edge
+    Below(Weak<TElement>),
+    /// To the right of the element's bounding box, aligned with its top edge
+    RightOf(Weak<TElement>),
+    /// With its top left corner at a point in root space, e.g. the pointer position
+    At(Point2),
+}
+
+impl OverlayAnchor {
+    pub fn below(elem: &ElementRef) -> OverlayAnchor {
+        OverlayAnchor::Below(Rc::downgrade(elem))
+    }
+
+    pub fn right_of(elem: &ElementRef) -> OverlayAnchor {
+        OverlayAnchor::RightOf(Rc::downgrade(elem))
+    }
+}
+
+
 pub trait TRootElement : TBinElement {
     fn root_requisition_x(&self, layout_ctx: &ElementLayoutContext) -> f64;
     fn root_allocate_x(&self, width: f64);
     fn root_requisition_y(&self) -> f64;
 
     fn root_allocate_y(&self, height: f64);
+    /// Set the size of the region in which the tree is shown; floating elements are kept
+    /// within it where possible. Until it is set, they are kept within the content.
+    fn root_set_viewport_size(&self, size: Vector2);
 
     fn root_set_lspace_listener(&self, listener: Option<&Rc<TLSpaceListener>>);
     fn root_queue_redraw(&self, rect: &BBox2);
+
+    /// Queue a resize of `elem` once drawing has finished. Elements that lay themselves out
+    /// according to the region being drawn use this, as elements cannot be resized while the
+    /// tree is being drawn.
+    fn root_queue_resize_after_draw(&self, elem: &ElementRef);
+    /// Resize the elements queued by `root_queue_resize_after_draw`
+    fn root_flush_resizes_after_draw(&self);
+
+    /// Add `elem` to the overlay layer, placed against `anchor`
+    fn root_add_floating(&self, self_ref: &ElementRef, elem: ElementRef, anchor: OverlayAnchor);
+    fn root_set_floating_anchor(&self, elem: &ElementRef, anchor: OverlayAnchor);
+    fn root_remove_floating(&self, elem: &ElementRef);
+    /// The floating elements, from bottom to top, including those that are hidden
+    fn root_floating_elements(&self) -> Vec<ElementRef>;
+}
+
+
+struct FloatingElement {
+    elem: ElementRef,
+    anchor: OverlayAnchor,
+    /// False while the anchor cannot be placed; hidden elements are not laid out, drawn or hit
+    shown: bool,
 }
 
 
 struct RootElementMut {
+    identity: ElementIdentity,
     req: ElementReq,
     alloc: ElementAlloc,
     bin: BinComponentMut,
+    /// Elements in the overlay layer, from bottom to top, with their anchors
+    floating: Vec<FloatingElement>,
+    /// The content child followed by the floating elements that are shown
+    children: Vec<ElementRef>,
     listener: Option<Weak<TLSpaceListener>>,
+    /// Set when the first floating element is added, so that anchors can be checked against
+    /// the tree
+    self_ref: Option<Weak<TElement>>,
+    viewport_size: Option<Vector2>,
+}
+
+impl RootElementMut {
+    fn update_children(&mut self) {
+        let mut children = self.bin.children().to_vec();
+        children.extend(self.floating.iter().filter(|f| f.shown).map(|f| f.elem.clone()));
+        self.children = children;
+    }
+
+    /// The floating elements that are shown
+    fn shown_floating(&self) -> Vec<ElementRef> {
+        self.floating.iter().filter(|f| f.shown).map(|f| f.elem.clone()).collect()
+    }
+
+    /// The root space bounding box of the anchor element `e`; `None` if it has been dropped or
+    /// is not in the tree
+    fn anchor_bbox(&self, e: &Weak<TElement>) -> Option<BBox2> {
+        match (e.upgrade(), self.self_ref.as_ref().and_then(|r| r.upgrade())) {
+            (Some(ref e), Some(ref root)) if elem_is_in_tree(e, root) => {
+                Some(elem_root_space_bbox(&**e))
+            },
+            _ => None
+        }
+    }
+
+    /// The top left corner of a floating element placed against `anchor`; `None` if the
+    /// anchor cannot be placed
+    fn anchor_pos(&self, anchor: &OverlayAnchor) -> Option<Point2> {
+        match anchor {
+            &OverlayAnchor::Below(ref e) => self.anchor_bbox(e).map(
+                |bbox| Point2::new(bbox.lower.x, bbox.upper.y)),
+            &OverlayAnchor::RightOf(ref e) => self.anchor_bbox(e).map(
+                |bbox| Point2::new(bbox.upper.x, bbox.lower.y)),
+            &OverlayAnchor::At(p) => Some(p)
+        }
+    }
+
+    /// The position of a shown floating element; its anchor is checked when it is shown, at
+    /// the start of layout
+    fn floating_pos(&self, f: &FloatingElement) -> Point2 {
+        self.anchor_pos(&f.anchor).unwrap_or(Point2::new(0.0, 0.0))
+    }
+
+    /// X allocations of the shown floating elements at their natural widths; they are kept
+    /// within the width of the viewport where possible
+    fn floating_x_allocs(&self) -> Vec<LAlloc> {
+        let width = self.viewport_size.map_or(self.alloc.x_alloc.alloc_size(), |s| s.x);
+        self.floating.iter().filter(|f| f.shown).map(|f| {
+            let x_req = f.elem.element_req().x_req.clone();
+            let x = self.floating_pos(f).x.min(width - x_req.size().size()).max(0.0);
+            LAlloc::new_from_req(&x_req, x)
+        }).collect()
+    }
+
+    /// Y allocations of the shown floating elements at their natural heights; they are kept
+    /// within the height of the viewport, rather than that of the content, where possible
+    fn floating_y_allocs(&self) -> Vec<LAlloc> {
+        let height = self.viewport_size.map_or(self.alloc.y_alloc.alloc_size(), |s| s.y);
+        self.floating.iter().filter(|f| f.shown).map(|f| {
+            let y_req = f.elem.element_req().y_req.clone();
+            let y = self.floating_pos(f).y.min(height - y_req.size().size()).max(0.0);
+            LAlloc::new_from_req(&y_req, y)
+        }).collect()
+    }
 }
 
+/// Root of an element tree. It holds the content as its single bin child, and an overlay
+/// layer of floating elements, such as tooltips and menus, that are laid out at their natural
+/// size against an anchor after the content has been laid out, kept within the viewport where
+/// possible. Floating elements are drawn above the content and are hit first. A floating element
+/// whose anchor element has been dropped or removed from the tree is hidden until it is given a
+/// new anchor.
 pub struct RootElement {
     m: RefCell<RootElementMut>,
+    /// Kept apart from `m`, which is borrowed while the tree is being drawn
+    resizes_after_draw: RefCell<Vec<ElementRef>>,
 }
 
 impl RootElement {
     pub fn new() -> RootElement {
         return RootElement{m: RefCell::new(RootElementMut{
-            req: ElementReq::new(), alloc: ElementAlloc::new(),
-            bin: BinComponentMut::new(), listener: None})};
+            identity: ElementIdentity::new(), req: ElementReq::new(), alloc: ElementAlloc::new(),
+            bin: BinComponentMut::new(), floating: vec![], children: vec![], listener: None,
+            self_ref: None, viewport_size: None}),
+            resizes_after_draw: RefCell::new(vec![])};
+    }
+
+    /// Show the floating elements whose anchors can be placed, and hide the others. Returns
+    /// true if any were shown or hidden.
+    fn update_floating_shown(&self) -> bool {
+        let shown: Vec<bool> = {
+            let mm = self.m.borrow();
+            mm.floating.iter().map(|f| mm.anchor_pos(&f.anchor).is_some()).collect()
+        };
+        let hidden: Vec<ElementRef> = {
+            let mut mm = self.m.borrow_mut();
+            if mm.floating.iter().zip(shown.iter()).all(|(f, s)| f.shown == *s) {
+                return false;
+            }
+            let hidden: Vec<ElementRef> = mm.floating.iter().zip(shown.iter())
+                .filter(|&(f, s)| f.shown && !*s).map(|(f, _)| f.elem.clone()).collect();
+            for (f, s) in mm.floating.iter_mut().zip(shown.into_iter()) {
+                f.shown = s;
+            }
+            mm.update_children();
+            hidden
+        };
+        for e in hidden.iter() {
+            queue_redraw(&**e);
+        }
+        let mut elem_alloc = self.element_alloc_mut();
+        elem_alloc.x_req_dirty();
+        elem_alloc.x_alloc_dirty();
+        elem_alloc.y_req_dirty();
+        elem_alloc.y_alloc_dirty();
+        return true;
+    }
+
+    /// Place the floating elements against their anchors, now that the content has been
+    /// allocated. Returns true if their Y requisitions need updating.
+    fn allocate_floating_x(&self) -> bool {
+        let (elems, x_allocs) = {
+            let mm = self.m.borrow();
+            (mm.shown_floating(), mm.floating_x_allocs())
+        };
+        let mut child_y_reqs_dirty = false;
+        for (e, a) in elems.iter().zip(x_allocs.iter()) {
+            child_y_reqs_dirty = child_y_reqs_dirty | e.allocate_x(a);
+        }
+        return child_y_reqs_dirty;
+    }
+
+    fn allocate_floating_y(&self) {
+        let (elems, y_allocs) = {
+            let mm = self.m.borrow();
+            (mm.shown_floating(), mm.floating_y_allocs())
+        };
+        for (e, a) in elems.iter().zip(y_allocs.iter()) {
+            e.allocate_y(a);
+        }
     }
 }
 
@@ -65,6 +256,15 @@ impl TElement for RootElement {
         return Some(self);
     }
 
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "RootElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
     /// Parent get and set methods
     fn get_parent(&self) -> Option<ElementRef> {
         return None;
@@ -74,6 +274,18 @@ impl TElement for RootElement {
         panic!("Cannot set parent of root element");
     }
 
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
+    fn set_key(&self, key: Option<String>) {
+        panic!("Cannot set key of root element");
+    }
+
+    fn set_id(&self, id: Option<String>) {
+        panic!("Cannot set id of root element");
+    }
+
     // Layout structure acquisition
     fn element_req(&self) -> Ref<ElementReq> {
         return Ref::map(self.m.borrow(), |m| &m.req);
@@ -105,11 +317,21 @@ impl TElement for RootElement {
 
     // Update layout
     fn update_x_req(&self, layout_ctx: &ElementLayoutContext) -> bool {
+        // Anchors may have been dropped or removed from the tree since the last layout
+        self.update_floating_shown();
         return self.container_update_x_req(layout_ctx);
     }
 
     fn allocate_x(&self, x_alloc: &LAlloc) -> bool {
-        return self.container_allocate_x(x_alloc);
+        let mut child_y_reqs_dirty = self.container_allocate_x(x_alloc);
+        // The content may have moved the anchors, so place the floating elements again
+        if self.allocate_floating_x() {
+            let mut elem_alloc = self.element_alloc_mut();
+            elem_alloc.y_req_dirty();
+            elem_alloc.y_alloc_dirty();
+            child_y_reqs_dirty = true;
+        }
+        return child_y_reqs_dirty;
     }
 
     fn update_y_req(&self) -> bool {
@@ -118,12 +340,13 @@ impl TElement for RootElement {
 
     fn allocate_y(&self, y_alloc: &LAlloc) {
         self.container_allocate_y(y_alloc);
+        self.allocate_floating_y();
     }
 }
 
 impl TContainerElement for RootElement {
     fn children(&self) -> Ref<[ElementRef]> {
-        Ref::map(self.m.borrow(), |m| m.bin.children())
+        Ref::map(self.m.borrow(), |m| &m.children[..])
     }
 
     fn compute_x_req(&self) -> LReq {
@@ -136,10 +359,12 @@ impl TContainerElement for RootElement {
 
     fn compute_child_x_allocs(&self) -> Vec<LAlloc> {
         let mm = self.m.borrow();
-        return match mm.bin.get_child() {
+        let mut allocs = match mm.bin.get_child() {
             None => vec![],
             Some(_) => vec![mm.alloc.x_alloc]
         };
+        allocs.extend(mm.floating_x_allocs());
+        return allocs;
     }
 
     fn compute_y_req(&self) -> LReq {
@@ -152,10 +377,12 @@ impl TContainerElement for RootElement {
 
     fn compute_child_y_allocs(&self) -> Vec<LAlloc> {
         let mm = self.m.borrow();
-        return match mm.bin.get_child() {
+        let mut allocs = match mm.bin.get_child() {
             None => vec![],
             Some(_) => vec![mm.alloc.y_alloc]
         };
+        allocs.extend(mm.floating_y_allocs());
+        return allocs;
     }
 }
 
@@ -168,6 +395,7 @@ impl TBinElement for RootElement {
     fn set_child(&self, self_ref: &ElementRef, child: ElementRef) {
         let mut mm = self.m.borrow_mut();
         mm.bin.set_child(self_ref, child);
+        mm.update_children();
         // We should do this but doing so causes a dynamic mut borrow fail, so
         // don't until this is fixed.
         //queue_resize(self);
@@ -176,6 +404,7 @@ impl TBinElement for RootElement {
     fn clear_child(&self) {
         let mut mm = self.m.borrow_mut();
         mm.bin.clear_child();
+        mm.update_children();
         // We should do this but doing so causes a dynamic mut borrow fail, so
         // don't until this is fixed.
         //queue_resize(self);
@@ -207,6 +436,10 @@ impl TRootElement for RootElement {
         self.allocate_y(&y_alloc);
     }
 
+    fn root_set_viewport_size(&self, size: Vector2) {
+        self.m.borrow_mut().viewport_size = Some(size);
+    }
+
     fn root_set_lspace_listener(&self, listener: Option<&Rc<TLSpaceListener>>) {
         let mut mm = self.m.borrow_mut();
         mm.listener = match listener {
@@ -227,6 +460,180 @@ impl TRootElement for RootElement {
             None => {}
         }
     }
+
+    fn root_queue_resize_after_draw(&self, elem: &ElementRef) {
+        self.resizes_after_draw.borrow_mut().push(elem.clone());
+    }
+
+    fn root_flush_resizes_after_draw(&self) {
+        let elems: Vec<ElementRef> = self.resizes_after_draw.borrow_mut().drain(..).collect();
+        for e in elems.iter() {
+            queue_resize(&**e);
+        }
+    }
+
+    /// The element is shown when the root is next laid out, if its anchor can be placed
+    fn root_add_floating(&self, self_ref: &ElementRef, elem: ElementRef, anchor: OverlayAnchor) {
+        {
+            let mut mm = self.m.borrow_mut();
+            mm.self_ref = Some(Rc::downgrade(self_ref));
+            elem.set_parent(Some(self_ref));
+            mm.floating.push(FloatingElement{elem: elem, anchor: anchor, shown: false});
+        }
+        queue_resize(self);
+    }
+
+    fn root_set_floating_anchor(&self, elem: &ElementRef, anchor: OverlayAnchor) {
+        let found = {
+            let mut mm = self.m.borrow_mut();
+            match mm.floating.iter().position(|f| elems_are_same(&f.elem, elem)) {
+                Some(i) => {
+                    mm.floating[i].anchor = anchor;
+                    true
+                },
+                None => false
+            }
+        };
+        if found {
+            // Redraw the element at its old position; laying it out again redraws the new one
+            queue_redraw(&**elem);
+            queue_resize(self);
+        }
+    }
+
+    fn root_remove_floating(&self, elem: &ElementRef) {
+        let found = self.m.borrow().floating.iter().any(|f| elems_are_same(&f.elem, elem));
+        if found {
+            queue_redraw(&**elem);
+            {
+                let mut mm = self.m.borrow_mut();
+                mm.floating.retain(|f| !elems_are_same(&f.elem, elem));
+                mm.update_children();
+            }
+            elem.set_parent(None);
+            queue_resize(self);
+        }
+    }
+
+    fn root_floating_elements(&self) -> Vec<ElementRef> {
+        return self.m.borrow().floating.iter().map(|f| f.elem.clone()).collect();
+    }
 }
 
 
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elements::element::elem_as_ref;
+    use elements::column::ColumnElement;
+    use elements::shape_element::ShapeElement;
+
+    fn shape() -> ElementRef {
+        elem_as_ref(ShapeElement::new(10.0, 10.0, &Rc::new(vec![])))
+    }
+
+    /// A root whose content is a column of `rows`
+    fn root_with_column(rows: &Vec<ElementRef>) -> ElementRef {
+        let root = elem_as_ref(RootElement::new());
+        let content = elem_as_ref(ColumnElement::new(0.0));
+        content.as_container_sequence().unwrap().set_children(&content, rows);
+        root.as_bin().unwrap().set_child(&root, content.clone());
+        root
+    }
+
+    /// Lay out `root` in a viewport of the given size, without a layout context; the
+    /// requisitions of shape elements are set when they are created
+    fn lay_out(root: &ElementRef, width: f64, height: f64) {
+        let r = root.as_any().downcast_ref::<RootElement>().unwrap();
+        r.root_set_viewport_size(Vector2::new(width, height));
+        r.update_floating_shown();
+        let content = r.get_child().unwrap();
+        content.element_update_x_req(&content.as_container().unwrap().compute_x_req());
+        root.element_update_x_req(&r.compute_x_req());
+        r.root_allocate_x(width);
+        let ry = r.root_requisition_y();
+        r.root_allocate_y(ry);
+    }
+
+    fn position(elem: &ElementRef) -> (f64, f64) {
+        let a = elem.element_alloc();
+        (a.x_alloc.pos_in_parent(), a.y_alloc.pos_in_parent())
+    }
+
+    #[test]
+    fn test_floating_placed_against_anchor() {
+        let (a, b) = (shape(), shape());
+        let root = root_with_column(&vec![a.clone(), b.clone()]);
+        let r = root.as_any().downcast_ref::<RootElement>().unwrap();
+        let (below, right, at) = (shape(), shape(), shape());
+        r.root_add_floating(&root, below.clone(), OverlayAnchor::below(&a));
+        r.root_add_floating(&root, right.clone(), OverlayAnchor::right_of(&b));
+        r.root_add_floating(&root, at.clone(), OverlayAnchor::At(Point2::new(30.0, 40.0)));
+        lay_out(&root, 100.0, 100.0);
+        assert_eq!(position(&below), (0.0, 10.0));
+        assert_eq!(position(&right), (10.0, 10.0));
+        assert_eq!(position(&at), (30.0, 40.0));
+    }
+
+    #[test]
+    fn test_floating_clamped_to_viewport() {
+        let (a, b) = (shape(), shape());
+        let root = root_with_column(&vec![a.clone(), b.clone()]);
+        let r = root.as_any().downcast_ref::<RootElement>().unwrap();
+        let (below, at) = (shape(), shape());
+        r.root_add_floating(&root, below.clone(), OverlayAnchor::below(&b));
+        r.root_add_floating(&root, at.clone(), OverlayAnchor::At(Point2::new(95.0, 60.0)));
+
+        // The content is 10 wide and 20 high; the floats are kept within the viewport,
+        // rather than the content
+        lay_out(&root, 100.0, 50.0);
+        assert_eq!(position(&below), (0.0, 20.0));
+        assert_eq!(position(&at), (90.0, 40.0));
+
+        // A viewport that is shorter than the content moves them up, where possible
+        lay_out(&root, 100.0, 15.0);
+        assert_eq!(position(&below), (0.0, 5.0));
+        lay_out(&root, 100.0, 5.0);
+        assert_eq!(position(&below), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_floating_hidden_without_anchor() {
+        let root = elem_as_ref(RootElement::new());
+        let content = elem_as_ref(ColumnElement::new(0.0));
+        let (a, b) = (shape(), shape());
+        content.as_container_sequence().unwrap().set_children(&content, &vec![a.clone(),
+                                                                             b.clone()]);
+        root.as_bin().unwrap().set_child(&root, content.clone());
+        let r = root.as_any().downcast_ref::<RootElement>().unwrap();
+
+        let (float_a, float_b) = (shape(), shape());
+        r.root_add_floating(&root, float_a.clone(), OverlayAnchor::below(&a));
+        r.root_add_floating(&root, float_b.clone(), OverlayAnchor::right_of(&b));
+        assert!(r.update_floating_shown());
+        assert_eq!(r.children().len(), 3);
+        assert!(!r.update_floating_shown());
+
+        // Removing an anchor from the tree hides its floating element
+        content.as_container_sequence().unwrap().set_children(&content, &vec![b.clone()]);
+        assert!(r.update_floating_shown());
+        assert_eq!(r.children().len(), 2);
+        assert!(elems_are_same(&r.children()[1], &float_b));
+
+        // As does dropping it
+        content.as_container_sequence().unwrap().set_children(&content, &vec![]);
+        drop(b);
+        assert!(r.update_floating_shown());
+        assert_eq!(r.children().len(), 1);
+        assert_eq!(r.root_floating_elements().len(), 2);
+
+        // A new anchor shows it again
+        r.root_set_floating_anchor(&float_a, OverlayAnchor::At(Point2::new(5.0, 5.0)));
+        assert!(r.update_floating_shown());
+        assert_eq!(r.children().len(), 2);
+    }
+}
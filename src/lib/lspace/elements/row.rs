@@ -1,5 +1,6 @@
 use cairo::Context;
 
+use std::any::Any;
 use std::cell::{RefCell, Ref, RefMut};
 
 use layout::lreq::LReq;
@@ -9,7 +10,7 @@ use geom::bbox2::BBox2;
 
 use elements::element_ctx::ElementLayoutContext;
 use elements::element_layout::{ElementReq, ElementAlloc};
-use elements::element::{TElement, ElementRef, ElementParentMut, queue_resize};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, queue_resize};
 use elements::container::TContainerElement;
 use elements::bin::{TBinElement};
 use elements::container_sequence::{TContainerSequenceElement, ContainerSequenceComponentMut};
@@ -18,6 +19,7 @@ use elements::root_element::{TRootElement};
 
 struct RowElementMut {
     parent: ElementParentMut,
+    identity: ElementIdentity,
     req: ElementReq,
     alloc: ElementAlloc,
     container_seq: ContainerSequenceComponentMut,
@@ -32,10 +34,26 @@ pub struct RowElement {
 impl RowElement {
     pub fn new(x_spacing: f64) -> RowElement {
         return RowElement{m: RefCell::new(RowElementMut{
-                parent: ElementParentMut::new(),
+                parent: ElementParentMut::new(), identity: ElementIdentity::new(),
                 req: ElementReq::new(), alloc: ElementAlloc::new(),
                 container_seq: ContainerSequenceComponentMut::new(), x_spacing: x_spacing})};
     }
+
+    pub fn get_x_spacing(&self) -> f64 {
+        return self.m.borrow().x_spacing;
+    }
+
+    pub fn set_x_spacing(&self, x_spacing: f64) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let changed = mm.x_spacing != x_spacing;
+            mm.x_spacing = x_spacing;
+            changed
+        };
+        if changed {
+            queue_resize(self);
+        }
+    }
 }
 
 
@@ -57,6 +75,15 @@ impl TElement for RowElement {
         return None;
     }
 
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "RowElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
     /// Parent get and set methods
     fn get_parent(&self) -> Option<ElementRef> {
         return self.m.borrow().parent.get().clone();
@@ -66,6 +93,10 @@ impl TElement for RowElement {
         self.m.borrow_mut().parent.set(p);
     }
 
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
     // Layout structure acquisition
     fn element_req(&self) -> Ref<ElementReq> {
         return Ref::map(self.m.borrow(), |m| &m.req);
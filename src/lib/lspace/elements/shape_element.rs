@@ -0,0 +1,194 @@
+use cairo::Context;
+
+use std::any::Any;
+use std::rc::Rc;
+use std::cell::{RefCell, Ref, RefMut};
+
+use layout::lalloc::LAlloc;
+use layout::lreq::LReq;
+use geom::bbox2::BBox2;
+use graphics::shape::Shape;
+use elements::element_ctx::ElementLayoutContext;
+use elements::element_layout::{ElementReq, ElementAlloc};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, queue_resize,
+                        queue_redraw};
+use elements::container::{TContainerElement, draw_bbox_changed};
+use elements::bin::TBinElement;
+use elements::container_sequence::{TContainerSequenceElement};
+use elements::root_element::{TRootElement};
+
+
+struct ShapeElementMut {
+    parent: ElementParentMut,
+    identity: ElementIdentity,
+    req: ElementReq,
+    alloc: ElementAlloc,
+    width: f64,
+    height: f64,
+    shapes: Rc<Vec<Shape>>,
+}
+
+/// Leaf element of a fixed size that draws a list of shapes, in its local co-ordinate space
+pub struct ShapeElement {
+    m: RefCell<ShapeElementMut>,
+}
+
+impl ShapeElement {
+    pub fn new(width: f64, height: f64, shapes: &Rc<Vec<Shape>>) -> ShapeElement {
+        return ShapeElement{m: RefCell::new(ShapeElementMut{
+            parent: ElementParentMut::new(), identity: ElementIdentity::new(),
+            req: ElementReq::new_from_reqs(LReq::new_fixed_size(width),
+                                           LReq::new_fixed_size(height)),
+            alloc: ElementAlloc::new(),
+            width: width, height: height, shapes: shapes.clone()})};
+    }
+
+    pub fn get_size(&self) -> (f64, f64) {
+        let mm = self.m.borrow();
+        return (mm.width, mm.height);
+    }
+
+    pub fn set_size(&self, width: f64, height: f64) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let changed = mm.width != width || mm.height != height;
+            mm.width = width;
+            mm.height = height;
+            changed
+        };
+        if changed {
+            queue_resize(self);
+        }
+    }
+
+    pub fn get_shapes(&self) -> Rc<Vec<Shape>> {
+        return self.m.borrow().shapes.clone();
+    }
+
+    pub fn set_shapes(&self, shapes: &Rc<Vec<Shape>>) {
+        if *self.m.borrow().shapes != **shapes {
+            // Shapes may extend outside the element, so redraw both the old and new shapes
+            queue_redraw(self);
+            self.m.borrow_mut().shapes = shapes.clone();
+            draw_bbox_changed(self);
+            queue_redraw(self);
+        }
+    }
+}
+
+impl TElement for ShapeElement {
+    /// Interface acquisition
+    fn as_container(&self) -> Option<&TContainerElement> {
+        return None;
+    }
+
+    fn as_bin(&self) -> Option<&TBinElement> {
+        return None
+    }
+
+    fn as_container_sequence(&self) -> Option<&TContainerSequenceElement> {
+        return None
+    }
+
+    fn as_root_element(&self) -> Option<&TRootElement> {
+        return None;
+    }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "ShapeElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    /// Parent get and set methods
+    fn get_parent(&self) -> Option<ElementRef> {
+        return self.m.borrow().parent.get().clone();
+    }
+
+    fn set_parent(&self, p: Option<&ElementRef>) {
+        self.m.borrow_mut().parent.set(p);
+    }
+
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
+    // Layout structure acquisition
+    fn element_req(&self) -> Ref<ElementReq> {
+        return Ref::map(self.m.borrow(), |m| &m.req);
+    }
+
+    fn element_alloc(&self) -> Ref<ElementAlloc> {
+        return Ref::map(self.m.borrow(), |m| &m.alloc);
+    }
+
+    fn element_alloc_mut(&self) -> RefMut<ElementAlloc> {
+        return RefMut::map(self.m.borrow_mut(), |m| &mut m.alloc);
+    }
+
+    /// Update element X requisition
+    fn element_update_x_req(&self, x_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_x_req(x_req);
+    }
+
+    /// Update element Y requisition
+    fn element_update_y_req(&self, y_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_y_req(y_req);
+    }
+
+    /// The allocated box extended by any shapes that lie outside it
+    fn draw_bbox(&self) -> BBox2 {
+        let mm = self.m.borrow();
+        return mm.shapes.iter().fold(mm.alloc.local_bbox(), |bbox, s| bbox.union(&s.bbox()));
+    }
+
+    /// Paint the shapes
+    fn draw_self(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        let mm = self.m.borrow();
+        for s in mm.shapes.iter() {
+            s.draw(cairo_ctx);
+        }
+    }
+
+    /// Draw
+    fn draw(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        self.draw_self(cairo_ctx, visible_region);
+    }
+
+    // Update layout
+    fn update_x_req(&self, layout_ctx: &ElementLayoutContext) -> bool {
+        let mut mm = self.m.borrow_mut();
+        let x_req = LReq::new_fixed_size(mm.width);
+        let changed = mm.req.update_x_req(&x_req);
+        let updated = mm.alloc.is_x_req_update_required();
+        mm.alloc.x_req_updated();
+        return changed || updated;
+    }
+
+    fn allocate_x(&self, x_alloc: &LAlloc) -> bool {
+        // Nothing to do; no children
+        let mut mm = self.m.borrow_mut();
+        mm.alloc.update_x_alloc(x_alloc);
+        mm.alloc.x_alloc_updated();
+        return false;
+    }
+
+    fn update_y_req(&self) -> bool {
+        let mut mm = self.m.borrow_mut();
+        let y_req = LReq::new_fixed_size(mm.height);
+        let changed = mm.req.update_y_req(&y_req);
+        let updated = mm.alloc.is_y_req_update_required();
+        mm.alloc.y_req_updated();
+        return changed || updated;
+    }
+
+    fn allocate_y(&self, y_alloc: &LAlloc) {
+        // Nothing to do; no children
+        let mut mm = self.m.borrow_mut();
+        mm.alloc.update_y_alloc(y_alloc);
+        mm.alloc.y_alloc_updated();
+    }
+}
@@ -0,0 +1,499 @@
+use cairo::Context;
+
+use std::any::Any;
+use std::rc::Rc;
+use std::cell::{RefCell, Ref, RefMut};
+
+use layout::lalloc::LAlloc;
+use layout::lreq::LReq;
+use geom::bbox2::BBox2;
+use graphics::border::{Border, StateBorders};
+use input::interactive::{TInteractiveElement, InteractionFlags, VisualState};
+use input::pointer_events::PointerEvent;
+use input::keyboard::{KeyEvent, KeyEventType, KEY_SPACE, KEY_RETURN, KEY_KP_ENTER};
+use elements::element_ctx::ElementLayoutContext;
+use elements::element_layout::{ElementReq, ElementAlloc};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, queue_resize,
+                        queue_redraw};
+use elements::container::TContainerElement;
+use elements::bin::{TBinElement, BinComponentMut};
+use elements::container_sequence::{TContainerSequenceElement};
+use elements::root_element::{TRootElement};
+
+
+fn same_margins(a: &Border, b: &Border) -> bool {
+    a.left_margin() == b.left_margin() && a.right_margin() == b.right_margin() &&
+        a.top_margin() == b.top_margin() && a.bottom_margin() == b.bottom_margin()
+}
+
+
+struct StateBorderElementMut {
+    parent: ElementParentMut,
+    identity: ElementIdentity,
+    req: ElementReq,
+    alloc: ElementAlloc,
+    bin: BinComponentMut,
+    borders: Rc<StateBorders>,
+    flags: InteractionFlags,
+    focusable: bool,
+    on_activate: Option<Rc<Fn()>>,
+}
+
+impl StateBorderElementMut {
+    /// The border for the current visual state
+    fn border(&self) -> &Rc<Border> {
+        self.borders.for_state(self.flags.visual_state())
+    }
+}
+
+/// Bin element that surrounds its child with a border chosen by the element's visual state:
+/// normal, hovered, pressed, focused or disabled. Hover and press state is tracked by the
+/// `LSpaceArea` through `TInteractiveElement`. The element can be given an activation
+/// function, making it the basis of clickable controls.
+pub struct StateBorderElement {
+    m: RefCell<StateBorderElementMut>,
+}
+
+impl StateBorderElement {
+    pub fn new(borders: &Rc<StateBorders>, focusable: bool) -> StateBorderElement {
+        return StateBorderElement{m: RefCell::new(StateBorderElementMut{
+            parent: ElementParentMut::new(), identity: ElementIdentity::new(),
+            req: ElementReq::new(), alloc: ElementAlloc::new(),
+            bin: BinComponentMut::new(), borders: borders.clone(),
+            flags: InteractionFlags::new(), focusable: focusable, on_activate: None})};
+    }
+
+    pub fn get_borders(&self) -> Rc<StateBorders> {
+        return self.m.borrow().borders.clone();
+    }
+
+    pub fn set_borders(&self, borders: &Rc<StateBorders>) {
+        self.update(|mm| {mm.borders = borders.clone();});
+    }
+
+    pub fn is_focusable(&self) -> bool {
+        return self.m.borrow().focusable;
+    }
+
+    pub fn set_focusable(&self, focusable: bool) {
+        self.m.borrow_mut().focusable = focusable;
+    }
+
+    /// Set the function that is called when the element is activated, by clicking on it or by
+    /// pressing Space or Enter while it has the focus
+    pub fn set_on_activate(&self, on_activate: Option<Rc<Fn()>>) {
+        self.m.borrow_mut().on_activate = on_activate;
+    }
+
+    /// Call the activation function, unless the element is disabled
+    pub fn activate(&self) {
+        let on_activate = {
+            let mm = self.m.borrow();
+            if mm.flags.disabled {None} else {mm.on_activate.clone()}
+        };
+        // Called outside the borrow, as the function may update the element
+        on_activate.map(|f| f());
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        return self.m.borrow().flags.disabled;
+    }
+
+    /// Disable or enable the element; disabling it releases any press in progress
+    pub fn set_disabled(&self, disabled: bool) {
+        self.update(|mm| {
+            mm.flags.disabled = disabled;
+            if disabled {
+                mm.flags.pressed = false;
+            }
+        });
+    }
+
+    pub fn interaction_flags(&self) -> InteractionFlags {
+        return self.m.borrow().flags;
+    }
+
+    pub fn visual_state(&self) -> VisualState {
+        return self.m.borrow().flags.visual_state();
+    }
+
+    /// Apply `f` to the element state, then queue a resize if the margins of the current
+    /// border changed, or a redraw if only its appearance did
+    fn update<F: FnOnce(&mut StateBorderElementMut)>(&self, f: F) {
+        let (changed, resize) = {
+            let mut mm = self.m.borrow_mut();
+            let old = mm.border().clone();
+            f(&mut *mm);
+            let new = mm.border();
+            (*old != **new, !same_margins(&*old, &**new))
+        };
+        if resize {
+            queue_resize(self);
+        } else if changed {
+            queue_redraw(self);
+        }
+    }
+}
+
+impl TElement for StateBorderElement {
+    /// Interface acquisition
+    fn as_container(&self) -> Option<&TContainerElement> {
+        return Some(self);
+    }
+
+    fn as_bin(&self) -> Option<&TBinElement> {
+        return Some(self);
+    }
+
+    fn as_container_sequence(&self) -> Option<&TContainerSequenceElement> {
+        return None
+    }
+
+    fn as_root_element(&self) -> Option<&TRootElement> {
+        return None;
+    }
+
+    fn as_interactive(&self) -> Option<&TInteractiveElement> {
+        return Some(self);
+    }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "StateBorderElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    /// Parent get and set methods
+    fn get_parent(&self) -> Option<ElementRef> {
+        return self.m.borrow().parent.get().clone();
+    }
+
+    fn set_parent(&self, p: Option<&ElementRef>) {
+        self.m.borrow_mut().parent.set(p);
+    }
+
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
+    // Layout structure acquisition
+    fn element_req(&self) -> Ref<ElementReq> {
+        return Ref::map(self.m.borrow(), |m| &m.req);
+    }
+
+    fn element_alloc(&self) -> Ref<ElementAlloc> {
+        return Ref::map(self.m.borrow(), |m| &m.alloc);
+    }
+
+    fn element_alloc_mut(&self) -> RefMut<ElementAlloc> {
+        return RefMut::map(self.m.borrow_mut(), |m| &mut m.alloc);
+    }
+
+    /// Update element X requisition
+    fn element_update_x_req(&self, x_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_x_req(x_req);
+    }
+
+    /// Update element Y requisition
+    fn element_update_y_req(&self, y_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_y_req(y_req);
+    }
+
+    /// Border insets, for debug drawing
+    fn debug_insets(&self) -> Option<(f64, f64, f64, f64)> {
+        let mm = self.m.borrow();
+        let border = mm.border();
+        return Some((border.left_margin(), border.right_margin(),
+                     border.top_margin(), border.bottom_margin()));
+    }
+
+    /// Paint the border for the current visual state
+    fn draw_self(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        let mm = self.m.borrow();
+        let border = mm.border();
+        let w = mm.alloc.x_alloc.actual_size();
+        let h = mm.alloc.y_alloc.actual_size();
+        border.draw_background(cairo_ctx, 0.0, 0.0, w, h);
+        border.draw(cairo_ctx, 0.0, 0.0, w, h);
+    }
+
+    /// Draw
+    fn draw(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        self.draw_self(cairo_ctx, visible_region);
+        self.draw_children(cairo_ctx, visible_region);
+    }
+
+    // Update layout
+    fn update_x_req(&self, layout_ctx: &ElementLayoutContext) -> bool {
+        self.container_update_x_req(layout_ctx)
+    }
+
+    fn allocate_x(&self, x_alloc: &LAlloc) -> bool {
+        self.container_allocate_x(x_alloc)
+    }
+
+    fn update_y_req(&self) -> bool {
+        self.container_update_y_req()
+    }
+
+    fn allocate_y(&self, y_alloc: &LAlloc) {
+        self.container_allocate_y(y_alloc);
+    }
+}
+
+
+impl TContainerElement for StateBorderElement {
+    fn children(&self) -> Ref<[ElementRef]> {
+        Ref::map(self.m.borrow(), |m| m.bin.children())
+    }
+
+    fn compute_x_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        let child_x_req = match mm.bin.get_child() {
+            None => LReq::new_empty(),
+            Some(ref ch) => ch.element_req().x_req.clone()
+        };
+        child_x_req.apply_border(mm.border().left_margin(), mm.border().right_margin())
+    }
+
+    fn compute_child_x_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => vec![],
+            Some(_) => vec![mm.alloc.x_alloc.apply_border(mm.border().left_margin(),
+                                                          mm.border().right_margin())]
+        }
+    }
+
+    fn compute_y_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        let child_y_req = match mm.bin.get_child() {
+            None => LReq::new_empty(),
+            Some(ref ch) => ch.element_req().y_req.clone()
+        };
+        child_y_req.apply_border(mm.border().top_margin(), mm.border().bottom_margin())
+    }
+
+    fn compute_child_y_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => vec![],
+            Some(_) => vec![mm.alloc.y_alloc.apply_border(mm.border().top_margin(),
+                                                          mm.border().bottom_margin())]
+        }
+    }
+}
+
+impl TInteractiveElement for StateBorderElement {
+    fn is_enabled(&self) -> bool {
+        !self.is_disabled()
+    }
+
+    fn can_focus(&self) -> bool {
+        self.is_focusable()
+    }
+
+    fn on_hover_change(&self, hovered: bool) {
+        self.update(|mm| {mm.flags.hovered = hovered;});
+    }
+
+    fn on_press(&self, event: &PointerEvent) -> bool {
+        self.update(|mm| {mm.flags.pressed = true;});
+        true
+    }
+
+    fn on_release(&self, event: &PointerEvent, inside: bool) {
+        // The press is dropped if the element was disabled in the meantime
+        let was_pressed = self.m.borrow().flags.pressed;
+        self.update(|mm| {mm.flags.pressed = false;});
+        if inside && was_pressed {
+            self.activate();
+        }
+    }
+
+    fn on_focus_change(&self, focused: bool) {
+        self.update(|mm| {mm.flags.focused = focused;});
+    }
+
+    fn on_key_event(&self, event: &KeyEvent) -> bool {
+        let key = event.key_val();
+        if key == KEY_SPACE || key == KEY_RETURN || key == KEY_KP_ENTER {
+            if event.event_type() == KeyEventType::Press {
+                self.activate();
+            }
+            return true;
+        }
+        return false;
+    }
+}
+
+impl TBinElement for StateBorderElement {
+    fn get_child(&self) -> Option<ElementRef> {
+        let mm = self.m.borrow();
+        mm.bin.get_child()
+    }
+
+    fn set_child(&self, self_ref: &ElementRef, child: ElementRef) {
+        self.m.borrow_mut().bin.set_child(self_ref, child);
+        queue_resize(self);
+    }
+
+    fn clear_child(&self) {
+        self.m.borrow_mut().bin.clear_child();
+        queue_resize(self);
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use geom::point2::Point2;
+    use input::inputmodifier::InputModifierState;
+    use input::interactive::ElementInteraction;
+    use input::pointer_events::PointerEventType;
+    use elements::element::elem_as_ref;
+    use elements::column::ColumnElement;
+    use elements::shape_element::ShapeElement;
+
+    fn shape() -> ElementRef {
+        elem_as_ref(ShapeElement::new(10.0, 10.0, &Rc::new(vec![])))
+    }
+
+    /// A column of a state border element, at y = 0 to 12, above a plain element, at y = 12
+    /// to 22. Returns the column, the state border element and the number of activations.
+    fn content(focusable: bool) -> (ElementRef, ElementRef, Rc<Cell<u32>>) {
+        let normal = Rc::new(Border::new_filled(1.0, 1.0, 1.0, 1.0, 0.0, None));
+        let hovered = Rc::new(Border::new_filled(1.0, 1.0, 1.0, 1.0, 1.0, None));
+        let pressed = Rc::new(Border::new_filled(1.0, 1.0, 1.0, 1.0, 2.0, None));
+        let borders = Rc::new(StateBorders::new(normal).with_hovered(hovered)
+            .with_pressed(pressed));
+        let elem = elem_as_ref(StateBorderElement::new(&borders, focusable));
+        elem.as_bin().unwrap().set_child(&elem, shape());
+
+        let activations = Rc::new(Cell::new(0));
+        let counter = activations.clone();
+        state_border(&elem).set_on_activate(Some(Rc::new(move || {
+            counter.set(counter.get() + 1);
+        })));
+
+        let column = elem_as_ref(ColumnElement::new(0.0));
+        column.as_container_sequence().unwrap().set_children(&column,
+                                                             &vec![elem.clone(), shape()]);
+        elem.element_update_x_req(&elem.as_container().unwrap().compute_x_req());
+        column.element_update_x_req(&column.as_container().unwrap().compute_x_req());
+        let x_req = column.element_req().x_req.clone();
+        column.allocate_x(&LAlloc::new_from_req(&x_req, 0.0));
+        column.update_y_req();
+        let y_req = column.element_req().y_req.clone();
+        column.allocate_y(&LAlloc::new_from_req(&y_req, 0.0));
+        (column, elem, activations)
+    }
+
+    fn state_border(elem: &ElementRef) -> &StateBorderElement {
+        elem.as_any().downcast_ref::<StateBorderElement>().unwrap()
+    }
+
+    fn pointer(event_type: PointerEventType, pos: Option<(f64, f64)>) -> PointerEvent {
+        PointerEvent::new(event_type, InputModifierState::new(),
+                          pos.map(|(x, y)| Point2::new(x, y)), 1)
+    }
+
+    #[test]
+    fn test_visual_state() {
+        let (root, elem, _) = content(false);
+        let interaction = ElementInteraction::new();
+        assert_eq!(state_border(&elem).visual_state(), VisualState::Normal);
+
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Motion, Some((5.0, 5.0))));
+        assert_eq!(state_border(&elem).visual_state(), VisualState::Hovered);
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Press, Some((5.0, 5.0))));
+        assert_eq!(state_border(&elem).visual_state(), VisualState::Pressed);
+
+        // Only looks pressed while the pointer is over it
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Motion,
+                                                     Some((5.0, 17.0))));
+        assert_eq!(state_border(&elem).visual_state(), VisualState::Normal);
+        assert!(state_border(&elem).interaction_flags().pressed);
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Motion, Some((5.0, 5.0))));
+        assert_eq!(state_border(&elem).visual_state(), VisualState::Pressed);
+
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Release, Some((5.0, 5.0))));
+        assert_eq!(state_border(&elem).visual_state(), VisualState::Hovered);
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Leave, None));
+        assert_eq!(state_border(&elem).visual_state(), VisualState::Normal);
+    }
+
+    #[test]
+    fn test_activate_on_release_inside() {
+        let (root, _, activations) = content(false);
+        let interaction = ElementInteraction::new();
+
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Press, Some((5.0, 5.0))));
+        assert_eq!(activations.get(), 0);
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Release, Some((6.0, 6.0))));
+        assert_eq!(activations.get(), 1);
+
+        // Releasing outside the element cancels the click
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Press, Some((5.0, 5.0))));
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Release,
+                                                     Some((5.0, 17.0))));
+        assert_eq!(activations.get(), 1);
+
+        // As does pressing outside it
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Press, Some((5.0, 17.0))));
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Release, Some((5.0, 5.0))));
+        assert_eq!(activations.get(), 1);
+    }
+
+    #[test]
+    fn test_activate_by_key() {
+        let (_, elem, activations) = content(true);
+        let interaction = ElementInteraction::new();
+        interaction.set_focus(Some(&elem));
+        assert_eq!(state_border(&elem).visual_state(), VisualState::Focused);
+
+        let space = " ".to_string();
+        assert!(interaction.on_key_event(&KeyEvent::new_press(InputModifierState::new(),
+                                                              KEY_SPACE, space.clone())));
+        assert_eq!(activations.get(), 1);
+        // Releasing the key is handled without activating again
+        assert!(interaction.on_key_event(&KeyEvent::new_release(InputModifierState::new(),
+                                                                KEY_SPACE, space)));
+        assert_eq!(activations.get(), 1);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let (root, elem, activations) = content(true);
+        let interaction = ElementInteraction::new();
+        interaction.set_focus(Some(&elem));
+        state_border(&elem).set_disabled(true);
+        assert_eq!(state_border(&elem).visual_state(), VisualState::Disabled);
+
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Press, Some((5.0, 5.0))));
+        assert!(!state_border(&elem).interaction_flags().pressed);
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Release, Some((5.0, 5.0))));
+        assert!(!interaction.on_key_event(&KeyEvent::new_press(InputModifierState::new(),
+                                                               KEY_SPACE, " ".to_string())));
+        state_border(&elem).activate();
+        assert_eq!(activations.get(), 0);
+
+        // Disabling the element releases a press in progress, so it does not fire once enabled
+        state_border(&elem).set_disabled(false);
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Press, Some((5.0, 5.0))));
+        state_border(&elem).set_disabled(true);
+        assert!(!state_border(&elem).interaction_flags().pressed);
+        state_border(&elem).set_disabled(false);
+        interaction.on_pointer_event(&root, &pointer(PointerEventType::Release, Some((5.0, 5.0))));
+        assert_eq!(activations.get(), 0);
+    }
+}
@@ -0,0 +1,889 @@
+use cairo::Context;
+
+use std::any::Any;
+use std::rc::Rc;
+use std::cell::{RefCell, Ref, RefMut};
+
+use layout::lalloc::LAlloc;
+use layout::lreq::LReq;
+use geom::bbox2::BBox2;
+use editing::line_edit::nearest_offset;
+use editing::text_buffer::{TextBuffer, TextPos};
+use editing::undo::{UndoHistory, EditKind, UndoAction, undo_action_for_key};
+use input::interactive::TInteractiveElement;
+use input::pointer_events::PointerEvent;
+use input::keyboard::{KeyEvent, KeyEventType, KEY_LEFT, KEY_RIGHT, KEY_UP, KEY_DOWN, KEY_HOME,
+                      KEY_END, KEY_PAGE_UP, KEY_PAGE_DOWN, KEY_BACKSPACE, KEY_DELETE,
+                      KEY_RETURN, KEY_KP_ENTER, KEY_LOWER_A, KEY_UPPER_A};
+use elements::element_ctx::ElementLayoutContext;
+use elements::element_layout::{ElementReq, ElementAlloc};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, elem_as_ref,
+                        queue_resize, queue_redraw, elem_root_space_bbox};
+use elements::text_element::{TextElement, TextStyleParams};
+use elements::text_entry_element::{CARET_WIDTH, selection_colour, measure_caret_offsets};
+use elements::column::ColumnElement;
+use elements::container::TContainerElement;
+use elements::bin::{TBinElement, BinComponentMut};
+use elements::container_sequence::{TContainerSequenceElement};
+use elements::root_element::{TRootElement};
+
+
+/// Number of lines moved by Page Up and Page Down when the editor does not scroll
+const DEFAULT_PAGE_LINES: usize = 20;
+
+
+struct TextEditorElementMut {
+    parent: ElementParentMut,
+    identity: ElementIdentity,
+    req: ElementReq,
+    alloc: ElementAlloc,
+    /// Holds the column of lines
+    bin: BinComponentMut,
+    /// A `TextElement` for each line of the buffer
+    lines: Vec<ElementRef>,
+    /// X offset of each caret position in each line; `None` for lines that have not been
+    /// measured since they changed
+    caret_offsets: Vec<Option<Vec<f64>>>,
+    buffer: TextBuffer,
+    history: UndoHistory,
+    style: Rc<TextStyleParams>,
+    line_height: f64,
+    view_height: Option<f64>,
+    min_width: f64,
+    scroll_y: f64,
+    focused: bool,
+    on_change: Option<Rc<Fn(&str)>>,
+}
+
+impl TextEditorElementMut {
+    fn caret_x(&self, pos: TextPos) -> f64 {
+        match self.caret_offsets.get(pos.line) {
+            Some(&Some(ref offsets)) => match offsets.get(pos.column) {
+                Some(x) => *x,
+                None => offsets.last().cloned().unwrap_or(0.0)
+            },
+            _ => 0.0
+        }
+    }
+
+    fn line_y(&self, line: usize) -> f64 {
+        line as f64 * self.line_height - self.scroll_y
+    }
+
+    fn content_height(&self) -> f64 {
+        self.buffer.line_count() as f64 * self.line_height
+    }
+
+    fn page_lines(&self) -> usize {
+        match self.view_height {
+            Some(h) if self.line_height > 0.0 => ((h / self.line_height).floor() as usize).max(1),
+            _ => DEFAULT_PAGE_LINES
+        }
+    }
+
+    /// Scroll so that the caret's line is in view; returns true if the scroll offset changed
+    fn scroll_to_caret(&mut self) -> bool {
+        let old = self.scroll_y;
+        match self.view_height {
+            Some(h) => {
+                let top = self.buffer.caret().line as f64 * self.line_height;
+                let bottom = top + self.line_height;
+                if top < self.scroll_y {
+                    self.scroll_y = top;
+                } else if bottom > self.scroll_y + h {
+                    self.scroll_y = bottom - h;
+                }
+                let max_scroll = (self.content_height() - h).max(0.0);
+                self.scroll_y = self.scroll_y.min(max_scroll).max(0.0);
+            },
+            None => {
+                self.scroll_y = 0.0;
+            }
+        }
+        self.scroll_y != old
+    }
+}
+
+/// Multi-line text editor. The text is held in a `TextBuffer` and displayed as a column of
+/// `TextElement`s, one per line, that is kept in step with the buffer as it is edited. The
+/// editor takes the keyboard focus when pressed, showing a caret. Given a view height, the
+/// editor shows that much of the text, scrolling to keep the caret in view.
+pub struct TextEditorElement {
+    m: RefCell<TextEditorElementMut>,
+}
+
+impl TextEditorElement {
+    pub fn new(text: &str, style: Rc<TextStyleParams>) -> Rc<TextEditorElement> {
+        let buffer = TextBuffer::new(text);
+        let lines: Vec<ElementRef> = buffer.lines().iter().map(
+            |l| elem_as_ref(TextElement::new_unshared(l.clone(), style.clone()))).collect();
+        let column = elem_as_ref(ColumnElement::new(0.0));
+        column.as_container_sequence().unwrap().set_children(&column, &lines);
+        let n = lines.len();
+        let editor = Rc::new(TextEditorElement{m: RefCell::new(TextEditorElementMut{
+            parent: ElementParentMut::new(), identity: ElementIdentity::new(),
+            req: ElementReq::new(), alloc: ElementAlloc::new(),
+            bin: BinComponentMut::new(), lines: lines, caret_offsets: vec![None; n],
+            buffer: buffer, history: UndoHistory::new(), style: style, line_height: 0.0,
+            view_height: None, min_width: 0.0, scroll_y: 0.0, focused: false,
+            on_change: None})});
+        let self_ref: ElementRef = editor.clone();
+        editor.m.borrow_mut().bin.set_child(&self_ref, column);
+        editor
+    }
+
+    pub fn get_text(&self) -> String {
+        return self.m.borrow().buffer.text();
+    }
+
+    /// Replace the text, placing the caret at its end and clearing the undo history. The
+    /// change function is not called.
+    pub fn set_text(&self, text: &str) {
+        if self.m.borrow().buffer.text() != text {
+            {
+                let mut mm = self.m.borrow_mut();
+                mm.buffer.set_text(text);
+                mm.history.clear();
+            }
+            self.sync_lines();
+            self.m.borrow_mut().scroll_to_caret();
+            queue_resize(self);
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        return self.m.borrow().buffer.line_count();
+    }
+
+    pub fn get_style(&self) -> Rc<TextStyleParams> {
+        return self.m.borrow().style.clone();
+    }
+
+    pub fn set_style(&self, style: Rc<TextStyleParams>) {
+        let lines = {
+            let mut mm = self.m.borrow_mut();
+            mm.style = style.clone();
+            for o in mm.caret_offsets.iter_mut() {
+                *o = None;
+            }
+            mm.lines.clone()
+        };
+        for l in lines.iter() {
+            l.as_text_element().unwrap().set_style(style.clone());
+        }
+        queue_resize(self);
+    }
+
+    pub fn get_view_height(&self) -> Option<f64> {
+        return self.m.borrow().view_height;
+    }
+
+    /// Show `view_height` of the text, scrolling to keep the caret in view; if `None`, the
+    /// editor grows to fit all of the text
+    pub fn set_view_height(&self, view_height: Option<f64>) {
+        {
+            let mut mm = self.m.borrow_mut();
+            mm.view_height = view_height;
+            mm.scroll_to_caret();
+        }
+        queue_resize(self);
+    }
+
+    pub fn get_min_width(&self) -> f64 {
+        return self.m.borrow().min_width;
+    }
+
+    pub fn set_min_width(&self, min_width: f64) {
+        self.m.borrow_mut().min_width = min_width;
+        queue_resize(self);
+    }
+
+    pub fn get_scroll_y(&self) -> f64 {
+        return self.m.borrow().scroll_y;
+    }
+
+    /// Scroll so that the text at `scroll_y` is at the top of the view
+    pub fn set_scroll_y(&self, scroll_y: f64) {
+        {
+            let mut mm = self.m.borrow_mut();
+            let max_scroll = match mm.view_height {
+                Some(h) => (mm.content_height() - h).max(0.0),
+                None => 0.0
+            };
+            mm.scroll_y = scroll_y.min(max_scroll).max(0.0);
+        }
+        queue_resize(self);
+    }
+
+    /// Set the function that is called with the new text whenever the user changes it
+    pub fn set_on_change(&self, on_change: Option<Rc<Fn(&str)>>) {
+        self.m.borrow_mut().on_change = on_change;
+    }
+
+    pub fn caret(&self) -> TextPos {
+        return self.m.borrow().buffer.caret();
+    }
+
+    pub fn selection(&self) -> Option<(TextPos, TextPos)> {
+        return self.m.borrow().buffer.selection();
+    }
+
+    pub fn set_caret(&self, pos: TextPos, extend: bool) {
+        self.edit(|b| b.set_caret(pos, extend));
+    }
+
+    pub fn select_all(&self) {
+        self.edit(|b| b.select_all());
+    }
+
+    /// Undo the most recent edit; returns false if there is nothing to undo
+    pub fn undo(&self) -> bool {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let mm = &mut *mm;
+            mm.history.undo(&mut mm.buffer)
+        };
+        self.edited(changed, changed);
+        return changed;
+    }
+
+    /// Redo the most recently undone edit; returns false if there is nothing to redo
+    pub fn redo(&self) -> bool {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let mm = &mut *mm;
+            mm.history.redo(&mut mm.buffer)
+        };
+        self.edited(changed, changed);
+        return changed;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        return self.m.borrow().history.can_undo();
+    }
+
+    pub fn can_redo(&self) -> bool {
+        return self.m.borrow().history.can_redo();
+    }
+
+    /// Apply `f`, which moves the caret or changes the selection, to the buffer. A change
+    /// starts a new undo step.
+    fn edit<F: FnOnce(&mut TextBuffer)>(&self, f: F) {
+        let caret_changed = {
+            let mut mm = self.m.borrow_mut();
+            let old = (mm.buffer.caret(), mm.buffer.selection());
+            f(&mut mm.buffer);
+            let caret_changed = old != (mm.buffer.caret(), mm.buffer.selection());
+            if caret_changed {
+                mm.history.break_coalescing();
+            }
+            caret_changed
+        };
+        self.edited(false, caret_changed);
+    }
+
+    /// Apply `f`, which edits the text and returns true if it changed, to the buffer,
+    /// recording the change in the undo history
+    fn edit_text<F: FnOnce(&mut TextBuffer) -> bool>(&self, kind: EditKind, f: F) {
+        let text_changed = {
+            let mut mm = self.m.borrow_mut();
+            let mm = &mut *mm;
+            mm.history.record_edit(&mut mm.buffer, kind, f)
+        };
+        self.edited(text_changed, text_changed);
+    }
+
+    /// After an edit, scroll the caret into view. A change to the text updates the lines,
+    /// queues a resize and calls the change function, otherwise a change to the caret or
+    /// selection queues a redraw.
+    fn edited(&self, text_changed: bool, caret_changed: bool) {
+        if text_changed {
+            self.sync_lines();
+        }
+        let scrolled = self.m.borrow_mut().scroll_to_caret();
+        if text_changed || scrolled {
+            queue_resize(self);
+        } else if caret_changed {
+            queue_redraw(self);
+        }
+        if text_changed {
+            let (on_change, text) = {
+                let mm = self.m.borrow();
+                (mm.on_change.clone(), mm.buffer.text())
+            };
+            // Called outside the borrow, as the function may update the element
+            on_change.map(|f| f(&text));
+        }
+    }
+
+    /// Bring the line elements into step with the buffer. Lines at the start and end that
+    /// are unchanged are kept; the others are updated, added or removed.
+    fn sync_lines(&self) {
+        let (updates, children) = {
+            let mut mm = self.m.borrow_mut();
+            let mm = &mut *mm;
+            let texts: Vec<String> = mm.buffer.lines().to_vec();
+            let (old_n, new_n) = (mm.lines.len(), texts.len());
+            let same = |e: &ElementRef, t: &String| *e.as_text_element().unwrap().get_text() == *t;
+
+            let mut prefix = 0;
+            while prefix < old_n && prefix < new_n && same(&mm.lines[prefix], &texts[prefix]) {
+                prefix += 1;
+            }
+            let mut suffix = 0;
+            while suffix < old_n - prefix && suffix < new_n - prefix &&
+                    same(&mm.lines[old_n - 1 - suffix], &texts[new_n - 1 - suffix]) {
+                suffix += 1;
+            }
+
+            // Lines in the changed range that exist before and after the edit are updated
+            let common = (old_n - prefix - suffix).min(new_n - prefix - suffix);
+            let mut updates: Vec<(ElementRef, String)> = vec![];
+            for i in prefix..prefix + common {
+                updates.push((mm.lines[i].clone(), texts[i].clone()));
+                mm.caret_offsets[i] = None;
+            }
+
+            // The rest are added or removed
+            let (start, old_end, new_end) = (prefix + common, old_n - suffix, new_n - suffix);
+            let children = if start < old_end || start < new_end {
+                let style = mm.style.clone();
+                let added: Vec<ElementRef> = texts[start..new_end].iter().map(
+                    |t| elem_as_ref(TextElement::new_unshared(t.clone(), style.clone()))).collect();
+                let tail = mm.lines.split_off(old_end);
+                mm.lines.truncate(start);
+                mm.lines.extend(added.into_iter());
+                mm.lines.extend(tail.into_iter());
+
+                let tail = mm.caret_offsets.split_off(old_end);
+                mm.caret_offsets.truncate(start);
+                mm.caret_offsets.extend((start..new_end).map(|_| None));
+                mm.caret_offsets.extend(tail.into_iter());
+                Some((mm.bin.get_child().unwrap(), mm.lines.clone()))
+            } else {
+                None
+            };
+            (updates, children)
+        };
+        // Update the elements outside the borrow, as they queue a resize of their ancestors
+        for (e, text) in updates.into_iter() {
+            e.as_text_element().unwrap().set_text(text);
+        }
+        match children {
+            Some((column, lines)) => {
+                column.as_container_sequence().unwrap().set_children(&column, &lines);
+            },
+            None => {}
+        }
+    }
+
+    /// Text position nearest to the position of a pointer event
+    fn text_pos_at(&self, event: &PointerEvent) -> Option<TextPos> {
+        let lower = elem_root_space_bbox(self).lower;
+        let mm = self.m.borrow();
+        event.position().map(|p| {
+            let y = p.y - lower.y + mm.scroll_y;
+            let last = mm.buffer.line_count() - 1;
+            let line = if mm.line_height > 0.0 && y > 0.0 {
+                ((y / mm.line_height).floor() as usize).min(last)
+            } else {
+                0
+            };
+            let column = match mm.caret_offsets[line] {
+                Some(ref offsets) => nearest_offset(offsets, p.x - lower.x),
+                None => 0
+            };
+            TextPos::new(line, column)
+        })
+    }
+
+    fn draw_caret(&self, cairo_ctx: &Context) {
+        let mm = self.m.borrow();
+        if mm.focused {
+            let caret = mm.buffer.caret();
+            let c = mm.style.colour;
+            cairo_ctx.set_source_rgba(c.r as f64, c.g as f64, c.b as f64, c.a as f64);
+            cairo_ctx.rectangle(mm.caret_x(caret), mm.line_y(caret.line), CARET_WIDTH,
+                                mm.line_height);
+            cairo_ctx.fill();
+            // Please see comments in Border::draw() concerning correct 'source' handling.
+            cairo_ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+        }
+    }
+}
+
+impl TElement for TextEditorElement {
+    /// Interface acquisition
+    fn as_container(&self) -> Option<&TContainerElement> {
+        return Some(self);
+    }
+
+    fn as_bin(&self) -> Option<&TBinElement> {
+        return None
+    }
+
+    fn as_container_sequence(&self) -> Option<&TContainerSequenceElement> {
+        return None
+    }
+
+    fn as_root_element(&self) -> Option<&TRootElement> {
+        return None;
+    }
+
+    fn as_interactive(&self) -> Option<&TInteractiveElement> {
+        return Some(self);
+    }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "TextEditorElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    /// Parent get and set methods
+    fn get_parent(&self) -> Option<ElementRef> {
+        return self.m.borrow().parent.get().clone();
+    }
+
+    fn set_parent(&self, p: Option<&ElementRef>) {
+        self.m.borrow_mut().parent.set(p);
+    }
+
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
+    // Layout structure acquisition
+    fn element_req(&self) -> Ref<ElementReq> {
+        return Ref::map(self.m.borrow(), |m| &m.req);
+    }
+
+    fn element_alloc(&self) -> Ref<ElementAlloc> {
+        return Ref::map(self.m.borrow(), |m| &m.alloc);
+    }
+
+    fn element_alloc_mut(&self) -> RefMut<ElementAlloc> {
+        return RefMut::map(self.m.borrow_mut(), |m| &mut m.alloc);
+    }
+
+    /// Update element X requisition
+    fn element_update_x_req(&self, x_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_x_req(x_req);
+    }
+
+    /// Update element Y requisition
+    fn element_update_y_req(&self, y_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_y_req(y_req);
+    }
+
+    /// Paint the selection, underneath the lines
+    fn draw_self(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        let mm = self.m.borrow();
+        match mm.buffer.selection() {
+            Some((start, end)) => {
+                let c = selection_colour();
+                cairo_ctx.set_source_rgba(c.r as f64, c.g as f64, c.b as f64, c.a as f64);
+                for line in start.line..end.line + 1 {
+                    let x0 = if line == start.line {mm.caret_x(start)} else {0.0};
+                    // Selected line breaks are shown as a short extension of the line
+                    let x1 = if line == end.line {
+                        mm.caret_x(end)
+                    } else {
+                        mm.caret_x(TextPos::new(line, mm.buffer.line_len(line))) +
+                            mm.line_height * 0.25
+                    };
+                    cairo_ctx.rectangle(x0, mm.line_y(line), x1 - x0, mm.line_height);
+                }
+                cairo_ctx.fill();
+                cairo_ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+            },
+            None => {}
+        }
+    }
+
+    /// Draw the selection, lines and caret, clipped to the view
+    fn draw(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        let bbox = self.element_alloc().local_bbox();
+        cairo_ctx.save();
+        cairo_ctx.rectangle(bbox.lower.x, bbox.lower.y, bbox.size().x, bbox.size().y);
+        cairo_ctx.clip();
+        let visible = match visible_region.intersection(&bbox) {
+            Some(v) => v,
+            None => bbox
+        };
+        self.draw_self(cairo_ctx, &visible);
+        self.draw_children(cairo_ctx, &visible);
+        self.draw_caret(cairo_ctx);
+        cairo_ctx.restore();
+    }
+
+    // Update layout
+    fn update_x_req(&self, layout_ctx: &ElementLayoutContext) -> bool {
+        {
+            // Measure the line height and the caret offsets of lines that have changed
+            let mut mm = self.m.borrow_mut();
+            let mm = &mut *mm;
+            let cairo_ctx = layout_ctx.cairo_ctx();
+            mm.style.apply(cairo_ctx);
+            let font_extents = cairo_ctx.font_extents();
+            mm.line_height = font_extents.ascent + font_extents.descent;
+            for (line, offsets) in mm.buffer.lines().iter().zip(mm.caret_offsets.iter_mut()) {
+                if offsets.is_none() {
+                    *offsets = Some(measure_caret_offsets(cairo_ctx, line));
+                }
+            }
+        }
+        self.container_update_x_req(layout_ctx)
+    }
+
+    fn allocate_x(&self, x_alloc: &LAlloc) -> bool {
+        self.container_allocate_x(x_alloc)
+    }
+
+    fn update_y_req(&self) -> bool {
+        self.container_update_y_req()
+    }
+
+    fn allocate_y(&self, y_alloc: &LAlloc) {
+        self.container_allocate_y(y_alloc);
+    }
+}
+
+impl TContainerElement for TextEditorElement {
+    fn children(&self) -> Ref<[ElementRef]> {
+        Ref::map(self.m.borrow(), |m| m.bin.children())
+    }
+
+    /// Wide enough for the longest line and the caret
+    fn compute_x_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        let width = match mm.bin.get_child() {
+            None => 0.0,
+            Some(ref ch) => ch.element_req().x_req.size().size()
+        };
+        LReq::new_fixed_size(width.max(mm.min_width) + CARET_WIDTH)
+    }
+
+    fn compute_child_x_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => vec![],
+            Some(ref ch) => vec![LAlloc::new_from_req(&ch.element_req().x_req, 0.0)]
+        }
+    }
+
+    /// The view height if given, otherwise the height of the text
+    fn compute_y_req(&self) -> LReq {
+        let mm = self.m.borrow();
+        match mm.view_height {
+            Some(h) => LReq::new_fixed_size(h),
+            None => {
+                let height = match mm.bin.get_child() {
+                    None => 0.0,
+                    Some(ref ch) => ch.element_req().y_req.size().size()
+                };
+                LReq::new_fixed_size(height.max(mm.line_height))
+            }
+        }
+    }
+
+    /// The column of lines is given its natural height, offset by the scroll position
+    fn compute_child_y_allocs(&self) -> Vec<LAlloc> {
+        let mm = self.m.borrow();
+        match mm.bin.get_child() {
+            None => vec![],
+            Some(ref ch) => {
+                let height = ch.element_req().y_req.size().size();
+                vec![LAlloc::new(-mm.scroll_y, height, height)]
+            }
+        }
+    }
+}
+
+impl TInteractiveElement for TextEditorElement {
+    fn can_focus(&self) -> bool {
+        true
+    }
+
+    /// Place the caret at the pointer; shift-click extends the selection
+    fn on_press(&self, event: &PointerEvent) -> bool {
+        match self.text_pos_at(event) {
+            Some(pos) => {
+                let extend = event.mod_state().shift_key();
+                self.edit(|b| b.set_caret(pos, extend));
+            },
+            None => {}
+        }
+        true
+    }
+
+    /// Dragging selects text
+    fn on_pointer_motion(&self, event: &PointerEvent) {
+        match self.text_pos_at(event) {
+            Some(pos) => self.edit(|b| b.set_caret(pos, true)),
+            None => {}
+        }
+    }
+
+    fn on_focus_change(&self, focused: bool) {
+        self.m.borrow_mut().focused = focused;
+        queue_redraw(self);
+    }
+
+    fn on_key_event(&self, event: &KeyEvent) -> bool {
+        if event.event_type() != KeyEventType::Press {
+            return false;
+        }
+        let mods = event.mod_state();
+        let (ctrl, shift) = (mods.control_key(), mods.shift_key());
+        let page = self.m.borrow().page_lines() as isize;
+        match undo_action_for_key(event) {
+            Some(UndoAction::Undo) => {
+                self.undo();
+                return true;
+            },
+            Some(UndoAction::Redo) => {
+                self.redo();
+                return true;
+            },
+            None => {}
+        }
+        match event.key_val() {
+            KEY_LEFT => self.edit(|b| {
+                if ctrl {b.move_word_left(shift)} else {b.move_left(shift)}
+            }),
+            KEY_RIGHT => self.edit(|b| {
+                if ctrl {b.move_word_right(shift)} else {b.move_right(shift)}
+            }),
+            KEY_UP => self.edit(|b| b.move_up(shift)),
+            KEY_DOWN => self.edit(|b| b.move_down(shift)),
+            KEY_PAGE_UP => self.edit(|b| b.move_lines(-page, shift)),
+            KEY_PAGE_DOWN => self.edit(|b| b.move_lines(page, shift)),
+            KEY_HOME => self.edit(|b| {
+                if ctrl {b.move_doc_start(shift)} else {b.move_home(shift)}
+            }),
+            KEY_END => self.edit(|b| {
+                if ctrl {b.move_doc_end(shift)} else {b.move_end(shift)}
+            }),
+            KEY_BACKSPACE => self.edit_text(EditKind::Delete, |b| {
+                if ctrl {b.delete_word_backward()} else {b.delete_backward()}
+            }),
+            KEY_DELETE => self.edit_text(EditKind::Delete, |b| {
+                if ctrl {b.delete_word_forward()} else {b.delete_forward()}
+            }),
+            KEY_RETURN | KEY_KP_ENTER => self.edit_text(EditKind::Insert, |b| b.insert_newline()),
+            KEY_LOWER_A | KEY_UPPER_A if ctrl => self.select_all(),
+            _ => {
+                // Insert printable characters; other keys, e.g. Tab and Escape, are left for
+                // the keyboard interactors
+                let s = event.key_string().clone();
+                if ctrl || mods.alt_key() || s.is_empty() || s.chars().any(|c| c.is_control()) {
+                    return false;
+                }
+                self.edit_text(EditKind::Insert, |b| b.insert(&s));
+            }
+        }
+        return true;
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elements::element::elems_are_same;
+    use input::inputmodifier::{InputModifierState, KEY_CTRL};
+
+    fn editor(text: &str) -> Rc<TextEditorElement> {
+        let e = TextEditorElement::new(text, Rc::new(TextStyleParams::default()));
+        // Mark every line as measured, so that lines that are re-measured can be told apart
+        for o in e.m.borrow_mut().caret_offsets.iter_mut() {
+            *o = Some(vec![0.0]);
+        }
+        e
+    }
+
+    fn press(e: &TextEditorElement, mods: u16, key_val: u32) -> bool {
+        e.on_key_event(&KeyEvent::new_press(InputModifierState::from_values(mods), key_val,
+                                            String::new()))
+    }
+
+    fn lines(e: &TextEditorElement) -> Vec<ElementRef> {
+        e.m.borrow().lines.clone()
+    }
+
+    /// Check that the line elements, their caret offsets and the children of the column are in
+    /// step with the buffer
+    fn assert_in_sync(e: &TextEditorElement) {
+        let mm = e.m.borrow();
+        let texts = mm.buffer.lines();
+        assert_eq!(mm.lines.len(), texts.len());
+        assert_eq!(mm.caret_offsets.len(), texts.len());
+        for (l, t) in mm.lines.iter().zip(texts.iter()) {
+            assert_eq!(*l.as_text_element().unwrap().get_text(), *t);
+        }
+        let column = mm.bin.get_child().unwrap();
+        let children = column.as_container().unwrap().children().to_vec();
+        assert_eq!(children.len(), mm.lines.len());
+        assert!(children.iter().zip(mm.lines.iter()).all(|(c, l)| elems_are_same(c, l)));
+    }
+
+    fn measured(e: &TextEditorElement) -> Vec<bool> {
+        e.m.borrow().caret_offsets.iter().map(|o| o.is_some()).collect()
+    }
+
+    #[test]
+    fn test_split_line() {
+        let e = editor("abc\ndef");
+        let before = lines(&e);
+        e.set_caret(TextPos::new(0, 1), false);
+        assert!(press(&e, 0, KEY_RETURN));
+        assert_eq!(e.get_text(), "a\nbc\ndef");
+        assert_in_sync(&e);
+
+        // The split line is updated in place and the line after it is kept; only the new line
+        // is added
+        let after = lines(&e);
+        assert!(elems_are_same(&after[0], &before[0]));
+        assert!(!elems_are_same(&after[1], &before[0]) && !elems_are_same(&after[1], &before[1]));
+        assert!(elems_are_same(&after[2], &before[1]));
+        assert_eq!(measured(&e), vec![false, false, true]);
+
+        // Splitting at the end of the last line
+        e.set_caret(TextPos::new(2, 3), false);
+        assert!(press(&e, 0, KEY_KP_ENTER));
+        assert_eq!(e.get_text(), "a\nbc\ndef\n");
+        assert_in_sync(&e);
+        assert!(elems_are_same(&lines(&e)[2], &before[1]));
+    }
+
+    #[test]
+    fn test_join_lines() {
+        let e = editor("ab\ncd\nef");
+        let before = lines(&e);
+        e.set_caret(TextPos::new(1, 0), false);
+        assert!(press(&e, 0, KEY_BACKSPACE));
+        assert_eq!(e.get_text(), "abcd\nef");
+        assert_eq!(e.caret(), TextPos::new(0, 2));
+        assert_in_sync(&e);
+        let after = lines(&e);
+        assert!(elems_are_same(&after[0], &before[0]));
+        assert!(elems_are_same(&after[1], &before[2]));
+        assert_eq!(measured(&e), vec![false, true]);
+
+        // Delete joins the next line
+        e.set_caret(TextPos::new(0, 4), false);
+        assert!(press(&e, 0, KEY_DELETE));
+        assert_eq!(e.get_text(), "abcdef");
+        assert_in_sync(&e);
+
+        // Undo splits them again
+        assert!(e.undo());
+        assert!(e.undo());
+        assert_eq!(e.get_text(), "ab\ncd\nef");
+        assert_in_sync(&e);
+    }
+
+    #[test]
+    fn test_delete_selection_across_lines() {
+        let e = editor("one\ntwo\nthree\nfour");
+        e.set_caret(TextPos::new(0, 1), false);
+        e.set_caret(TextPos::new(2, 2), true);
+        assert!(press(&e, 0, KEY_BACKSPACE));
+        assert_eq!(e.get_text(), "oree\nfour");
+        assert_in_sync(&e);
+
+        e.set_text("x");
+        assert_in_sync(&e);
+        e.select_all();
+        assert!(press(&e, 0, KEY_DELETE));
+        assert_eq!(e.get_text(), "");
+        assert_eq!(e.line_count(), 1);
+        assert_in_sync(&e);
+    }
+
+    /// An editor of ten lines, each 10 high, with a view 35 high, so that a page is 3 lines
+    fn paged_editor() -> Rc<TextEditorElement> {
+        let e = editor("0\n1\n2\n3\n4\n5\n6\n7\n8\n9");
+        // As measured by layout
+        e.m.borrow_mut().line_height = 10.0;
+        e.set_view_height(Some(35.0));
+        e.set_caret(TextPos::new(0, 0), false);
+        e
+    }
+
+    #[test]
+    fn test_page_down_up() {
+        let e = paged_editor();
+        assert_eq!(e.get_scroll_y(), 0.0);
+
+        assert!(press(&e, 0, KEY_PAGE_DOWN));
+        assert_eq!(e.caret(), TextPos::new(3, 0));
+        // The caret's line is scrolled up to the bottom of the view
+        assert_eq!(e.get_scroll_y(), 5.0);
+        assert!(press(&e, 0, KEY_PAGE_DOWN));
+        assert_eq!(e.caret(), TextPos::new(6, 0));
+        assert_eq!(e.get_scroll_y(), 35.0);
+        assert!(press(&e, 0, KEY_PAGE_DOWN));
+        assert!(press(&e, 0, KEY_PAGE_DOWN));
+        assert_eq!(e.caret(), TextPos::new(9, 1));
+        assert_eq!(e.get_scroll_y(), 65.0);
+
+        // Lines above the view are scrolled down to its top
+        assert!(press(&e, 0, KEY_PAGE_UP));
+        assert_eq!(e.caret().line, 6);
+        assert_eq!(e.get_scroll_y(), 60.0);
+        // A caret that is in view does not scroll
+        assert!(press(&e, 0, KEY_DOWN));
+        assert_eq!(e.get_scroll_y(), 60.0);
+        assert!(press(&e, 0, KEY_PAGE_UP));
+        assert!(press(&e, 0, KEY_PAGE_UP));
+        assert!(press(&e, 0, KEY_PAGE_UP));
+        assert_eq!(e.caret(), TextPos::new(0, 0));
+        assert_eq!(e.get_scroll_y(), 0.0);
+
+        assert!(press(&e, KEY_CTRL, KEY_END));
+        assert_eq!(e.get_scroll_y(), 65.0);
+        assert!(press(&e, KEY_CTRL, KEY_HOME));
+        assert_eq!(e.get_scroll_y(), 0.0);
+    }
+
+    #[test]
+    fn test_scroll_to_caret_after_edit() {
+        let e = paged_editor();
+        e.set_scroll_y(65.0);
+        // Editing at the caret brings it back into view
+        assert!(press(&e, 0, KEY_RETURN));
+        assert_eq!(e.caret(), TextPos::new(1, 0));
+        assert_eq!(e.get_scroll_y(), 10.0);
+
+        // Removing lines limits the scroll to the new height of the text
+        e.set_caret(TextPos::new(10, 1), false);
+        assert_eq!(e.get_scroll_y(), 75.0);
+        e.set_caret(TextPos::new(1, 0), true);
+        assert!(press(&e, 0, KEY_BACKSPACE));
+        assert_eq!(e.line_count(), 2);
+        assert_in_sync(&e);
+        assert_eq!(e.get_scroll_y(), 0.0);
+    }
+
+    #[test]
+    fn test_page_without_view_height() {
+        let e = editor("0\n1\n2\n3\n4\n5\n6\n7\n8\n9");
+        e.m.borrow_mut().line_height = 10.0;
+        e.set_caret(TextPos::new(0, 0), false);
+        // Pages are `DEFAULT_PAGE_LINES` long, and the editor never scrolls
+        assert!(press(&e, 0, KEY_PAGE_DOWN));
+        assert_eq!(e.caret(), TextPos::new(9, 1));
+        assert_eq!(e.get_scroll_y(), 0.0);
+        e.set_scroll_y(30.0);
+        assert_eq!(e.get_scroll_y(), 0.0);
+    }
+}
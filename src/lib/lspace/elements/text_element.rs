@@ -1,4 +1,5 @@
 use std::rc::Rc;
+use std::any::Any;
 use std::cell::{RefCell, Ref, RefMut};
 use std::string::String;
 use std::mem::transmute;
@@ -12,7 +13,7 @@ use geom::bbox2::BBox2;
 use geom::colour::{Colour, BLACK};
 use elements::element_layout::{ElementReq, ElementAlloc};
 use elements::element_ctx::{ElementContext, ElementLayoutContext};
-use elements::element::{TElement, ElementRef, ElementParentMut, queue_resize};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, queue_resize};
 use elements::container::{TContainerElement};
 use elements::bin::{TBinElement};
 use elements::container_sequence::{TContainerSequenceElement};
@@ -23,6 +24,8 @@ use lspace_area::LSpaceArea;
 pub trait TTextElement : TElement {
     fn get_text(&self) -> Ref<String>;
     fn set_text(&self, text: String);
+    fn get_style(&self) -> Rc<TextStyleParams>;
+    fn set_style(&self, style: Rc<TextStyleParams>);
 }
 
 
@@ -112,26 +115,40 @@ pub type TextReqKey = (String, TextWeight, TextSlant, i64, String);
 
 struct TextElementMut {
     parent: ElementParentMut,
+    identity: ElementIdentity,
     req: Rc<ElementReq>,
     alloc: ElementAlloc,
     text: String,
+    style: Rc<TextStyleParams>,
     req_up_to_date: bool
 }
 
 pub struct TextElement {
-    style: Rc<TextStyleParams>,
     m: RefCell<TextElementMut>,
 }
 
 impl TextElement {
     pub fn new(text: String, style: Rc<TextStyleParams>,
                elem_ctx: &ElementContext) -> TextElement {
-        return TextElement{style: style,
-                           m: RefCell::new(TextElementMut{
-                                parent: ElementParentMut::new(),
+        return TextElement{m: RefCell::new(TextElementMut{
+                                parent: ElementParentMut::new(), identity: ElementIdentity::new(),
                                 req: elem_ctx.empty_shared_req(),
                                 alloc: ElementAlloc::new(),
                                 text: text,
+                                style: style,
+                                req_up_to_date: false}),
+                           };
+    }
+
+    /// Text element created outside of an element context, e.g. by an editor as lines are
+    /// added; its requisition is computed when it is first laid out
+    pub fn new_unshared(text: String, style: Rc<TextStyleParams>) -> TextElement {
+        return TextElement{m: RefCell::new(TextElementMut{
+                                parent: ElementParentMut::new(), identity: ElementIdentity::new(),
+                                req: Rc::new(ElementReq::new()),
+                                alloc: ElementAlloc::new(),
+                                text: text,
+                                style: style,
                                 req_up_to_date: false}),
                            };
     }
@@ -163,6 +180,15 @@ impl TElement for TextElement {
     fn as_root_element(&self) -> Option<&TRootElement> {
         return None;
     }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "TextElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
     
     /// Parent get and set methods
     fn get_parent(&self) -> Option<ElementRef> {
@@ -173,6 +199,10 @@ impl TElement for TextElement {
         self.m.borrow_mut().parent.set(p);
     }
 
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
     // Element structure acquisition
     fn element_req(&self) -> Ref<ElementReq> {
         return Ref::map(self.m.borrow(), |m| &(*m.req));
@@ -204,7 +234,7 @@ impl TElement for TextElement {
             Some(ref_point) => ref_point
         };
         cairo_ctx.move_to(0.0, y);
-        self.style.apply(cairo_ctx);
+        mm.style.apply(cairo_ctx);
         cairo_ctx.show_text(mm.text.as_str());
     }
 
@@ -219,7 +249,7 @@ impl TElement for TextElement {
 
         if !mm.req_up_to_date {
             let elem_ctx = layout_ctx.elem_ctx();
-            let req = elem_ctx.text_shared_req(self.style.clone(), mm.text.clone(),
+            let req = elem_ctx.text_shared_req(mm.style.clone(), mm.text.clone(),
                                                layout_ctx.cairo_ctx());
             mm.req = req;
             mm.req_up_to_date = true;
@@ -261,6 +291,10 @@ impl TTextElement for TextElement {
         return Ref::map(mm, |m| &m.text);
     }
 
+    fn get_style(&self) -> Rc<TextStyleParams> {
+        return self.m.borrow().style.clone();
+    }
+
     fn set_text(&self, text: String) {
         {
             let mut mm = self.m.borrow_mut();
@@ -269,4 +303,13 @@ impl TTextElement for TextElement {
         }
         queue_resize(self);
     }
+
+    fn set_style(&self, style: Rc<TextStyleParams>) {
+        {
+            let mut mm = self.m.borrow_mut();
+            mm.style = style;
+            mm.req_up_to_date = false;
+        }
+        queue_resize(self);
+    }
 }
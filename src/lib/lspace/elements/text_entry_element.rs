@@ -0,0 +1,658 @@
+use cairo::Context;
+
+use std::any::Any;
+use std::rc::Rc;
+use std::cell::{RefCell, Ref, RefMut};
+
+use layout::lalloc::LAlloc;
+use layout::lreq::LReq;
+use geom::bbox2::BBox2;
+use geom::colour::Colour;
+use editing::line_edit::{LineEdit, nearest_offset};
+use editing::undo::{UndoHistory, EditKind, UndoAction, undo_action_for_key};
+use input::interactive::TInteractiveElement;
+use input::pointer_events::PointerEvent;
+use input::keyboard::{KeyEvent, KeyEventType, KEY_LEFT, KEY_RIGHT, KEY_HOME, KEY_END,
+                      KEY_BACKSPACE, KEY_DELETE, KEY_LOWER_A, KEY_UPPER_A};
+use elements::element_ctx::ElementLayoutContext;
+use elements::element_layout::{ElementReq, ElementAlloc};
+use elements::element::{TElement, ElementRef, ElementParentMut, ElementIdentity, queue_resize,
+                        queue_redraw, elem_root_space_bbox};
+use elements::text_element::TextStyleParams;
+use elements::container::TContainerElement;
+use elements::bin::TBinElement;
+use elements::container_sequence::{TContainerSequenceElement};
+use elements::root_element::{TRootElement};
+
+
+pub const CARET_WIDTH: f64 = 1.0;
+
+/// Background colour of selected text
+pub fn selection_colour() -> Colour {
+    Colour::from_u8(0xb4, 0xd5, 0xfe, 0xff)
+}
+
+/// X offset of each caret position in `text`, from the start of the text to its end, in the
+/// font that is selected in `cairo_ctx`
+pub fn measure_caret_offsets(cairo_ctx: &Context, text: &str) -> Vec<f64> {
+    let mut offsets = vec![0.0];
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        offsets.push(cairo_ctx.text_extents(&text[..end]).x_advance);
+    }
+    offsets
+}
+
+/// The default placeholder style; `style` with its colour faded
+pub fn default_placeholder_style(style: &TextStyleParams) -> TextStyleParams {
+    let mut placeholder = style.clone();
+    placeholder.colour.a = placeholder.colour.a * 0.45;
+    placeholder
+}
+
+
+struct TextEntryElementMut {
+    parent: ElementParentMut,
+    identity: ElementIdentity,
+    req: ElementReq,
+    alloc: ElementAlloc,
+    edit: LineEdit,
+    history: UndoHistory,
+    style: Rc<TextStyleParams>,
+    placeholder: Option<String>,
+    placeholder_style: Rc<TextStyleParams>,
+    min_width: f64,
+    /// X offset of each caret position, measured when the requisition is updated
+    caret_offsets: Vec<f64>,
+    focused: bool,
+    on_change: Option<Rc<Fn(&str)>>,
+}
+
+impl TextEntryElementMut {
+    fn caret_x(&self, pos: usize) -> f64 {
+        match self.caret_offsets.get(pos) {
+            Some(x) => *x,
+            None => self.caret_offsets.last().cloned().unwrap_or(0.0)
+        }
+    }
+}
+
+/// Leaf element displaying a single line of editable text. It takes the keyboard focus when
+/// pressed, showing a caret; the pointer and the keyboard move the caret and select text.
+/// A placeholder is shown in its own style while the text is empty.
+pub struct TextEntryElement {
+    m: RefCell<TextEntryElementMut>,
+}
+
+impl TextEntryElement {
+    pub fn new(text: &str, style: Rc<TextStyleParams>) -> TextEntryElement {
+        let placeholder_style = Rc::new(default_placeholder_style(&style));
+        return TextEntryElement{m: RefCell::new(TextEntryElementMut{
+            parent: ElementParentMut::new(), identity: ElementIdentity::new(),
+            req: ElementReq::new(), alloc: ElementAlloc::new(),
+            edit: LineEdit::new(text), history: UndoHistory::new(), style: style,
+            placeholder: None, placeholder_style: placeholder_style, min_width: 0.0,
+            caret_offsets: vec![], focused: false, on_change: None})};
+    }
+
+    pub fn get_text(&self) -> String {
+        return self.m.borrow().edit.text().to_string();
+    }
+
+    /// Replace the text, placing the caret at its end and clearing the undo history. The
+    /// change function is not called.
+    pub fn set_text(&self, text: &str) {
+        if self.m.borrow().edit.text() != text {
+            {
+                let mut mm = self.m.borrow_mut();
+                mm.edit.set_text(text);
+                mm.history.clear();
+            }
+            queue_resize(self);
+        }
+    }
+
+    pub fn get_style(&self) -> Rc<TextStyleParams> {
+        return self.m.borrow().style.clone();
+    }
+
+    pub fn set_style(&self, style: Rc<TextStyleParams>) {
+        self.m.borrow_mut().style = style;
+        queue_resize(self);
+    }
+
+    pub fn get_placeholder(&self) -> Option<String> {
+        return self.m.borrow().placeholder.clone();
+    }
+
+    /// Set the text shown while the entry is empty
+    pub fn set_placeholder(&self, placeholder: Option<String>) {
+        self.m.borrow_mut().placeholder = placeholder;
+        queue_resize(self);
+    }
+
+    pub fn get_placeholder_style(&self) -> Rc<TextStyleParams> {
+        return self.m.borrow().placeholder_style.clone();
+    }
+
+    pub fn set_placeholder_style(&self, style: Rc<TextStyleParams>) {
+        self.m.borrow_mut().placeholder_style = style;
+        queue_resize(self);
+    }
+
+    pub fn get_min_width(&self) -> f64 {
+        return self.m.borrow().min_width;
+    }
+
+    pub fn set_min_width(&self, min_width: f64) {
+        self.m.borrow_mut().min_width = min_width;
+        queue_resize(self);
+    }
+
+    pub fn get_max_length(&self) -> Option<usize> {
+        return self.m.borrow().edit.max_length();
+    }
+
+    /// Limit the text to `max_length` characters; longer text is truncated, clearing the undo
+    /// history
+    pub fn set_max_length(&self, max_length: Option<usize>) {
+        {
+            let mut mm = self.m.borrow_mut();
+            let len = mm.edit.len();
+            mm.edit.set_max_length(max_length);
+            if mm.edit.len() != len {
+                mm.history.clear();
+            }
+        }
+        queue_resize(self);
+    }
+
+    /// Set the function that is called with the new text whenever the user changes it
+    pub fn set_on_change(&self, on_change: Option<Rc<Fn(&str)>>) {
+        self.m.borrow_mut().on_change = on_change;
+    }
+
+    pub fn caret(&self) -> usize {
+        return self.m.borrow().edit.caret();
+    }
+
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        return self.m.borrow().edit.selection();
+    }
+
+    pub fn set_caret(&self, pos: usize, extend: bool) {
+        self.edit(|e| e.set_caret(pos, extend));
+    }
+
+    pub fn select_all(&self) {
+        self.edit(|e| e.select_all());
+    }
+
+    /// Undo the most recent edit; returns false if there is nothing to undo
+    pub fn undo(&self) -> bool {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let mm = &mut *mm;
+            mm.history.undo(&mut mm.edit)
+        };
+        self.text_changed(changed);
+        return changed;
+    }
+
+    /// Redo the most recently undone edit; returns false if there is nothing to redo
+    pub fn redo(&self) -> bool {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let mm = &mut *mm;
+            mm.history.redo(&mut mm.edit)
+        };
+        self.text_changed(changed);
+        return changed;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        return self.m.borrow().history.can_undo();
+    }
+
+    pub fn can_redo(&self) -> bool {
+        return self.m.borrow().history.can_redo();
+    }
+
+    /// Apply `f`, which moves the caret or changes the selection, to the edit state. A change
+    /// queues a redraw and starts a new undo step.
+    fn edit<F: FnOnce(&mut LineEdit)>(&self, f: F) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let old = (mm.edit.caret(), mm.edit.selection());
+            f(&mut mm.edit);
+            let changed = old != (mm.edit.caret(), mm.edit.selection());
+            if changed {
+                mm.history.break_coalescing();
+            }
+            changed
+        };
+        if changed {
+            queue_redraw(self);
+        }
+    }
+
+    /// Apply `f`, which edits the text and returns true if it changed, to the edit state,
+    /// recording the change in the undo history
+    fn edit_text<F: FnOnce(&mut LineEdit) -> bool>(&self, kind: EditKind, f: F) {
+        let changed = {
+            let mut mm = self.m.borrow_mut();
+            let mm = &mut *mm;
+            mm.history.record_edit(&mut mm.edit, kind, f)
+        };
+        self.text_changed(changed);
+    }
+
+    /// After a change to the text, queue a resize and call the change function
+    fn text_changed(&self, changed: bool) {
+        if changed {
+            queue_resize(self);
+            let (on_change, text) = {
+                let mm = self.m.borrow();
+                (mm.on_change.clone(), mm.edit.text().to_string())
+            };
+            // Called outside the borrow, as the function may update the element
+            on_change.map(|f| f(&text));
+        }
+    }
+
+    /// Caret position nearest to the position of a pointer event
+    fn caret_pos_at(&self, event: &PointerEvent) -> Option<usize> {
+        let lower = elem_root_space_bbox(self).lower;
+        let mm = self.m.borrow();
+        event.position().map(|p| nearest_offset(&mm.caret_offsets, p.x - lower.x))
+    }
+}
+
+impl TElement for TextEntryElement {
+    /// Interface acquisition
+    fn as_container(&self) -> Option<&TContainerElement> {
+        return None;
+    }
+
+    fn as_bin(&self) -> Option<&TBinElement> {
+        return None
+    }
+
+    fn as_container_sequence(&self) -> Option<&TContainerSequenceElement> {
+        return None
+    }
+
+    fn as_root_element(&self) -> Option<&TRootElement> {
+        return None;
+    }
+
+    fn as_interactive(&self) -> Option<&TInteractiveElement> {
+        return Some(self);
+    }
+
+    /// Element type name, for debugging and diagnostics
+    fn element_type_name(&self) -> &'static str {
+        "TextEntryElement"
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    /// Parent get and set methods
+    fn get_parent(&self) -> Option<ElementRef> {
+        return self.m.borrow().parent.get().clone();
+    }
+
+    fn set_parent(&self, p: Option<&ElementRef>) {
+        self.m.borrow_mut().parent.set(p);
+    }
+
+    fn element_identity(&self) -> Ref<ElementIdentity> {
+        return Ref::map(self.m.borrow(), |m| &m.identity);
+    }
+
+    // Layout structure acquisition
+    fn element_req(&self) -> Ref<ElementReq> {
+        return Ref::map(self.m.borrow(), |m| &m.req);
+    }
+
+    fn element_alloc(&self) -> Ref<ElementAlloc> {
+        return Ref::map(self.m.borrow(), |m| &m.alloc);
+    }
+
+    fn element_alloc_mut(&self) -> RefMut<ElementAlloc> {
+        return RefMut::map(self.m.borrow_mut(), |m| &mut m.alloc);
+    }
+
+    /// Update element X requisition
+    fn element_update_x_req(&self, x_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_x_req(x_req);
+    }
+
+    /// Update element Y requisition
+    fn element_update_y_req(&self, y_req: &LReq) -> bool {
+        return self.m.borrow_mut().req.update_y_req(y_req);
+    }
+
+    /// Paint the selection, the text or the placeholder, and the caret if focused
+    fn draw_self(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        let mm = self.m.borrow();
+        let bbox = mm.alloc.local_bbox();
+        let y = match mm.alloc.y_alloc.ref_point() {
+            None => 0.0,
+            Some(ref_point) => ref_point
+        };
+
+        match mm.edit.selection() {
+            Some((start, end)) => {
+                let (x0, x1) = (mm.caret_x(start), mm.caret_x(end));
+                let c = selection_colour();
+                cairo_ctx.set_source_rgba(c.r as f64, c.g as f64, c.b as f64, c.a as f64);
+                cairo_ctx.rectangle(x0, bbox.lower.y, x1 - x0, bbox.size().y);
+                cairo_ctx.fill();
+            },
+            None => {}
+        }
+
+        if mm.edit.len() > 0 {
+            cairo_ctx.move_to(0.0, y);
+            mm.style.apply(cairo_ctx);
+            cairo_ctx.show_text(mm.edit.text());
+        } else {
+            match mm.placeholder {
+                Some(ref p) => {
+                    cairo_ctx.move_to(0.0, y);
+                    mm.placeholder_style.apply(cairo_ctx);
+                    cairo_ctx.show_text(p.as_str());
+                },
+                None => {}
+            }
+        }
+
+        if mm.focused {
+            let c = mm.style.colour;
+            cairo_ctx.set_source_rgba(c.r as f64, c.g as f64, c.b as f64, c.a as f64);
+            cairo_ctx.rectangle(mm.caret_x(mm.edit.caret()), bbox.lower.y, CARET_WIDTH,
+                                bbox.size().y);
+            cairo_ctx.fill();
+        }
+        // Please see comments in Border::draw() concerning correct 'source' handling.
+        cairo_ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+    }
+
+    fn draw(&self, cairo_ctx: &Context, visible_region: &BBox2) {
+        self.draw_self(cairo_ctx, visible_region);
+    }
+
+    // Update layout
+    fn update_x_req(&self, layout_ctx: &ElementLayoutContext) -> bool {
+        let mut mm = self.m.borrow_mut();
+        let cairo_ctx = layout_ctx.cairo_ctx();
+
+        // Measure the placeholder first, so that the caret offsets are in the text style
+        let placeholder_width = match mm.placeholder {
+            Some(ref p) => {
+                mm.placeholder_style.apply(cairo_ctx);
+                cairo_ctx.text_extents(p.as_str()).x_advance
+            },
+            None => 0.0
+        };
+        mm.style.apply(cairo_ctx);
+        let font_extents = cairo_ctx.font_extents();
+        let offsets = measure_caret_offsets(cairo_ctx, mm.edit.text());
+        let text_width = *offsets.last().unwrap();
+        mm.caret_offsets = offsets;
+
+        let width = mm.min_width.max(text_width).max(placeholder_width) + CARET_WIDTH;
+        let x_req = LReq::new_fixed_size(width);
+        let y_req = LReq::new_fixed_ref(font_extents.ascent, font_extents.descent);
+        mm.req = ElementReq::new_from_reqs(x_req, y_req);
+
+        let updated = mm.alloc.is_x_req_update_required();
+        mm.alloc.x_req_updated();
+        return updated;
+    }
+
+    fn allocate_x(&self, x_alloc: &LAlloc) -> bool {
+        // Nothing to do; no children
+        let mut mm = self.m.borrow_mut();
+        mm.alloc.update_x_alloc(x_alloc);
+        mm.alloc.x_alloc_updated();
+        return false;
+    }
+
+    fn update_y_req(&self) -> bool {
+        // Nothing to do; the Y requisition was computed along with the X requisition
+        let mut mm = self.m.borrow_mut();
+        let updated = mm.alloc.is_y_req_update_required();
+        mm.alloc.y_req_updated();
+        return updated;
+    }
+
+    fn allocate_y(&self, y_alloc: &LAlloc) {
+        // Nothing to do; no children
+        let mut mm = self.m.borrow_mut();
+        mm.alloc.update_y_alloc(y_alloc);
+        mm.alloc.y_alloc_updated();
+    }
+}
+
+impl TInteractiveElement for TextEntryElement {
+    fn can_focus(&self) -> bool {
+        true
+    }
+
+    /// Place the caret at the pointer; shift-click extends the selection
+    fn on_press(&self, event: &PointerEvent) -> bool {
+        match self.caret_pos_at(event) {
+            Some(pos) => {
+                let extend = event.mod_state().shift_key();
+                self.edit(|e| e.set_caret(pos, extend));
+            },
+            None => {}
+        }
+        true
+    }
+
+    /// Dragging selects text
+    fn on_pointer_motion(&self, event: &PointerEvent) {
+        match self.caret_pos_at(event) {
+            Some(pos) => self.edit(|e| e.set_caret(pos, true)),
+            None => {}
+        }
+    }
+
+    fn on_focus_change(&self, focused: bool) {
+        self.m.borrow_mut().focused = focused;
+        queue_redraw(self);
+    }
+
+    fn on_key_event(&self, event: &KeyEvent) -> bool {
+        if event.event_type() != KeyEventType::Press {
+            return false;
+        }
+        let mods = event.mod_state();
+        let (ctrl, shift) = (mods.control_key(), mods.shift_key());
+        match undo_action_for_key(event) {
+            Some(UndoAction::Undo) => {
+                self.undo();
+                return true;
+            },
+            Some(UndoAction::Redo) => {
+                self.redo();
+                return true;
+            },
+            None => {}
+        }
+        match event.key_val() {
+            KEY_LEFT => self.edit(|e| {
+                if ctrl {e.move_word_left(shift)} else {e.move_left(shift)}
+            }),
+            KEY_RIGHT => self.edit(|e| {
+                if ctrl {e.move_word_right(shift)} else {e.move_right(shift)}
+            }),
+            KEY_HOME => self.edit(|e| e.move_home(shift)),
+            KEY_END => self.edit(|e| e.move_end(shift)),
+            KEY_BACKSPACE => self.edit_text(EditKind::Delete, |e| {
+                if ctrl {e.delete_word_backward()} else {e.delete_backward()}
+            }),
+            KEY_DELETE => self.edit_text(EditKind::Delete, |e| {
+                if ctrl {e.delete_word_forward()} else {e.delete_forward()}
+            }),
+            KEY_LOWER_A | KEY_UPPER_A if ctrl => self.select_all(),
+            _ => {
+                // Insert printable characters; other keys, e.g. Tab, Return and Escape, are
+                // left for the keyboard interactors
+                let s = event.key_string().clone();
+                if ctrl || mods.alt_key() || s.is_empty() || s.chars().any(|c| c.is_control()) {
+                    return false;
+                }
+                self.edit_text(EditKind::Insert, |e| e.insert(&s));
+            }
+        }
+        return true;
+    }
+}
+
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::inputmodifier::{InputModifierState, KEY_CTRL, KEY_SHIFT};
+    use input::keyboard::KEY_TAB;
+
+    /// An entry containing `text`, and the texts passed to its change function
+    fn entry(text: &str) -> (TextEntryElement, Rc<RefCell<Vec<String>>>) {
+        let e = TextEntryElement::new(text, Rc::new(TextStyleParams::default()));
+        let changes = Rc::new(RefCell::new(vec![]));
+        let c = changes.clone();
+        e.set_on_change(Some(Rc::new(move |text: &str| c.borrow_mut().push(text.to_string()))));
+        (e, changes)
+    }
+
+    fn press(e: &TextEntryElement, mods: u16, key_val: u32, key_string: &str) -> bool {
+        e.on_key_event(&KeyEvent::new_press(InputModifierState::from_values(mods), key_val,
+                                            key_string.to_string()))
+    }
+
+    fn type_text(e: &TextEntryElement, text: &str) {
+        for c in text.chars() {
+            assert!(press(e, 0, c as u32, &c.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let (e, changes) = entry("");
+        type_text(&e, "abc");
+        assert_eq!(e.get_text(), "abc");
+        assert_eq!(e.caret(), 3);
+        assert_eq!(*changes.borrow(), vec!["a", "ab", "abc"]);
+
+        // Inserting replaces the selection
+        e.set_caret(1, false);
+        e.set_caret(2, true);
+        type_text(&e, "X");
+        assert_eq!(e.get_text(), "aXc");
+        assert_eq!(e.caret(), 2);
+        assert_eq!(e.selection(), None);
+
+        // Control characters and keys with Ctrl are left for the keyboard interactors
+        assert!(!press(&e, 0, KEY_TAB, "\t"));
+        assert!(!press(&e, KEY_CTRL, 'b' as u32, "b"));
+        assert_eq!(e.get_text(), "aXc");
+        assert_eq!(changes.borrow().len(), 4);
+    }
+
+    #[test]
+    fn test_insert_max_length() {
+        let (e, changes) = entry("ab");
+        e.set_max_length(Some(3));
+        type_text(&e, "cd");
+        assert_eq!(e.get_text(), "abc");
+        assert_eq!(e.caret(), 3);
+        // The key that did not fit did not change the text
+        assert_eq!(*changes.borrow(), vec!["abc"]);
+
+        // Text replacing the selection is truncated to fit
+        e.select_all();
+        assert!(press(&e, 0, 'w' as u32, "wxyz"));
+        assert_eq!(e.get_text(), "wxy");
+        assert_eq!(*changes.borrow(), vec!["abc", "wxy"]);
+    }
+
+    #[test]
+    fn test_ctrl_backspace() {
+        let (e, changes) = entry("hello big world");
+        assert!(press(&e, KEY_CTRL, KEY_BACKSPACE, ""));
+        assert_eq!(e.get_text(), "hello big ");
+        // Spaces before the caret are deleted along with the word before them
+        assert!(press(&e, KEY_CTRL, KEY_BACKSPACE, ""));
+        assert_eq!(e.get_text(), "hello ");
+        assert!(press(&e, 0, KEY_BACKSPACE, ""));
+        assert_eq!(e.get_text(), "hello");
+
+        // Deletes the selection rather than the word
+        e.set_caret(1, false);
+        e.set_caret(3, true);
+        assert!(press(&e, KEY_CTRL, KEY_BACKSPACE, ""));
+        assert_eq!(e.get_text(), "hlo");
+        assert_eq!(e.caret(), 1);
+
+        e.set_caret(0, false);
+        assert!(press(&e, KEY_CTRL, KEY_BACKSPACE, ""));
+        assert_eq!(e.get_text(), "hlo");
+        assert_eq!(*changes.borrow(), vec!["hello big ", "hello ", "hello", "hlo"]);
+    }
+
+    #[test]
+    fn test_ctrl_a() {
+        let (e, changes) = entry("some text");
+        e.set_caret(2, false);
+        assert!(press(&e, KEY_CTRL, KEY_LOWER_A, "a"));
+        assert_eq!(e.selection(), Some((0, 9)));
+        assert_eq!(e.get_text(), "some text");
+        e.set_caret(2, false);
+        assert!(press(&e, KEY_CTRL | KEY_SHIFT, KEY_UPPER_A, "A"));
+        assert_eq!(e.selection(), Some((0, 9)));
+        assert!(changes.borrow().is_empty());
+
+        assert!(press(&e, 0, KEY_BACKSPACE, ""));
+        assert_eq!(e.get_text(), "");
+        assert_eq!(*changes.borrow(), vec![""]);
+    }
+
+    #[test]
+    fn test_on_change_once_per_change() {
+        let (e, changes) = entry("abc");
+        // Moving the caret and selecting do not change the text
+        assert!(press(&e, 0, KEY_HOME, ""));
+        assert!(press(&e, KEY_SHIFT, KEY_RIGHT, ""));
+        assert!(press(&e, 0, KEY_END, ""));
+        assert!(press(&e, 0, KEY_DELETE, ""));
+        assert!(changes.borrow().is_empty());
+
+        type_text(&e, "d");
+        assert!(press(&e, 0, KEY_LEFT, ""));
+        assert!(press(&e, 0, KEY_DELETE, ""));
+        assert_eq!(*changes.borrow(), vec!["abcd", "abc"]);
+
+        // Undo and redo each report one change
+        assert!(press(&e, KEY_CTRL, 'z' as u32, "z"));
+        assert_eq!(e.get_text(), "abcd");
+        assert!(press(&e, KEY_CTRL | KEY_SHIFT, 'z' as u32, "Z"));
+        assert_eq!(e.get_text(), "abc");
+        assert_eq!(*changes.borrow(), vec!["abcd", "abc", "abcd", "abc"]);
+
+        // Key releases are ignored
+        assert!(!e.on_key_event(&KeyEvent::new_release(InputModifierState::new(), 'e' as u32,
+                                                       "e".to_string())));
+        assert_eq!(changes.borrow().len(), 4);
+    }
+}
@@ -66,6 +66,14 @@ impl BBox2 {
             return None;
         }
     }
+
+    /// The smallest box that contains both `self` and `r`
+    pub fn union(&self, r: &BBox2) -> BBox2 {
+        return BBox2::new(Point2::new(fast_min(self.lower.x, r.lower.x),
+                                      fast_min(self.lower.y, r.lower.y)),
+                          Point2::new(fast_max(self.upper.x, r.upper.x),
+                                      fast_max(self.upper.y, r.upper.y)));
+    }
 }
 
 //
@@ -177,4 +185,13 @@ mod tests {
         assert_eq!(BBox2::new(Point2::new(1.0, 2.0), Point2::new(10.0, 20.0)).intersection(
                         &BBox2::new(Point2::new(1.0, 22.0), Point2::new(10.0, 40.0))), None);
     }
+    #[test]
+    fn test_union() {
+        assert_eq!(BBox2::new(Point2::new(1.0, 2.0), Point2::new(10.0, 20.0)).union(
+                        &BBox2::new(Point2::new(3.0, 4.0), Point2::new(5.0, 6.0))),
+                BBox2::new(Point2::new(1.0, 2.0), Point2::new(10.0, 20.0)));
+        assert_eq!(BBox2::new(Point2::new(1.0, 2.0), Point2::new(10.0, 20.0)).union(
+                        &BBox2::new(Point2::new(-9.0, 12.0), Point2::new(5.0, 30.0))),
+                BBox2::new(Point2::new(-9.0, 2.0), Point2::new(10.0, 30.0)));
+    }
 }
@@ -18,8 +18,219 @@ impl Colour {
     pub fn new(r: f32, g: f32, b: f32, a: f32) -> Colour {
         return Colour{r: r, g: g, b: b, a: a};
     }
+
+    /// Colour from 8 bit components
+    pub fn from_u8(r: u8, g: u8, b: u8, a: u8) -> Colour {
+        return Colour{r: r as f32 / 255.0, g: g as f32 / 255.0, b: b as f32 / 255.0,
+                      a: a as f32 / 255.0};
+    }
+
+    /// Parse a colour in `#rrggbb`, `#rrggbbaa`, `#rgb` or `#rgba` form
+    pub fn from_hex(s: &str) -> Option<Colour> {
+        if !s.starts_with('#') {
+            return None;
+        }
+        let digits: Vec<u8> = match s[1..].chars().map(|c| c.to_digit(16).map(|d| d as u8))
+                                          .collect::<Option<Vec<u8>>>() {
+            Some(d) => d,
+            None => return None
+        };
+        let components: Vec<u8> = match digits.len() {
+            3 | 4 => digits.iter().map(|d| d * 17).collect(),
+            6 | 8 => digits.chunks(2).map(|p| p[0] * 16 + p[1]).collect(),
+            _ => return None
+        };
+        let a = if components.len() == 4 {components[3]} else {255};
+        return Some(Colour::from_u8(components[0], components[1], components[2], a));
+    }
+
+    /// Look up a CSS named colour; names are case insensitive
+    pub fn from_name(name: &str) -> Option<Colour> {
+        let name = name.to_lowercase();
+        if name == "transparent" {
+            return Some(TRANSPARENT);
+        }
+        return CSS_NAMED_COLOURS.iter().find(|&&(n, _)| n == name).map(|&(_, rgb)| {
+            Colour::from_u8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+        });
+    }
+
+    /// Parse a colour in hex form (see `from_hex`) or a CSS colour name
+    pub fn parse(s: &str) -> Option<Colour> {
+        let s = s.trim();
+        if s.starts_with('#') {
+            return Colour::from_hex(s);
+        } else {
+            return Colour::from_name(s);
+        }
+    }
+
+    /// Format as `#rrggbb`, or `#rrggbbaa` if the colour is not opaque
+    pub fn to_hex(&self) -> String {
+        let byte = |x: f32| (x.max(0.0).min(1.0) * 255.0).round() as u8;
+        if byte(self.a) == 255 {
+            return format!("#{:02x}{:02x}{:02x}", byte(self.r), byte(self.g), byte(self.b));
+        } else {
+            return format!("#{:02x}{:02x}{:02x}{:02x}", byte(self.r), byte(self.g), byte(self.b),
+                           byte(self.a));
+        }
+    }
+
+    /// Colour from hue (in degrees), saturation and lightness (in the range `0` to `1`)
+    pub fn from_hsl(h: f32, s: f32, l: f32, a: f32) -> Colour {
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = l - c * 0.5;
+        return Colour{r: r + m, g: g + m, b: b + m, a: a};
+    }
+
+    /// Hue (in degrees), saturation and lightness
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue_max_min();
+        let l = (max + min) * 0.5;
+        let d = max - min;
+        let s = if d == 0.0 {0.0} else {d / (1.0 - (2.0 * l - 1.0).abs())};
+        return (h, s, l);
+    }
+
+    /// Colour from hue (in degrees), saturation and value (in the range `0` to `1`)
+    pub fn from_hsv(h: f32, s: f32, v: f32, a: f32) -> Colour {
+        let c = v * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = v - c;
+        return Colour{r: r + m, g: g + m, b: b + m, a: a};
+    }
+
+    /// Hue (in degrees), saturation and value
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (h, max, min) = self.hue_max_min();
+        let s = if max == 0.0 {0.0} else {(max - min) / max};
+        return (h, s, max);
+    }
+
+    /// Hue in degrees, and the largest and smallest of the RGB components
+    fn hue_max_min(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let d = max - min;
+        let h = if d == 0.0 {
+            0.0
+        } else if max == self.r {
+            60.0 * ((self.g - self.b) / d)
+        } else if max == self.g {
+            60.0 * ((self.b - self.r) / d + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / d + 4.0)
+        };
+        return (if h < 0.0 {h + 360.0} else {h}, max, min);
+    }
+
+    /// Linear interpolation between `self` (at `t = 0`) and `b` (at `t = 1`)
+    pub fn lerp(&self, b: &Colour, t: f32) -> Colour {
+        return self + (b - self) * t;
+    }
+
+    /// Composite `self` over `below`, using its alpha
+    pub fn over(&self, below: &Colour) -> Colour {
+        let a = self.a + below.a * (1.0 - self.a);
+        if a == 0.0 {
+            return TRANSPARENT;
+        }
+        let blend = |x: f32, y: f32| (x * self.a + y * below.a * (1.0 - self.a)) / a;
+        return Colour{r: blend(self.r, below.r), g: blend(self.g, below.g),
+                      b: blend(self.b, below.b), a: a};
+    }
+
+    /// Increase the HSL lightness by `amount`
+    pub fn lighten(&self, amount: f32) -> Colour {
+        let (h, s, l) = self.to_hsl();
+        return Colour::from_hsl(h, s, (l + amount).max(0.0).min(1.0), self.a);
+    }
+
+    /// Decrease the HSL lightness by `amount`
+    pub fn darken(&self, amount: f32) -> Colour {
+        return self.lighten(-amount);
+    }
+
+    /// Relative luminance, as defined by WCAG 2.0
+    pub fn relative_luminance(&self) -> f32 {
+        let linear = |x: f32| if x <= 0.03928 {x / 12.92} else {((x + 0.055) / 1.055).powf(2.4)};
+        return 0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b);
+    }
+
+    /// Contrast ratio between `self` and `other`, as defined by WCAG 2.0; ranges from 1 to 21
+    pub fn contrast_ratio(&self, other: &Colour) -> f32 {
+        let l1 = self.relative_luminance();
+        let l2 = other.relative_luminance();
+        return (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05);
+    }
 }
 
+/// RGB components for hue `h` (in degrees) and chroma `c`, before adding the lightness offset
+fn hue_to_rgb(h: f32, c: f32) -> (f32, f32, f32) {
+    let h = ((h % 360.0) + 360.0) % 360.0 / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    return match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+}
+
+
+/// CSS named colours, as `0xrrggbb`
+const CSS_NAMED_COLOURS: &'static [(&'static str, u32)] = &[
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4), ("azure", 0xf0ffff), ("beige", 0xf5f5dc), ("bisque", 0xffe4c4),
+    ("black", 0x000000), ("blanchedalmond", 0xffebcd), ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a), ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00), ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed), ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c), ("cyan", 0x00ffff), ("darkblue", 0x00008b), ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b), ("darkgray", 0xa9a9a9), ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9), ("darkkhaki", 0xbdb76b), ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f), ("darkorange", 0xff8c00), ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000), ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b), ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1), ("darkviolet", 0x9400d3), ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff), ("dimgray", 0x696969), ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff), ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22), ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff), ("gold", 0xffd700), ("goldenrod", 0xdaa520), ("gray", 0x808080),
+    ("green", 0x008000), ("greenyellow", 0xadff2f), ("grey", 0x808080), ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c), ("indigo", 0x4b0082), ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa), ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080), ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3), ("lightgreen", 0x90ee90), ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1), ("lightsalmon", 0xffa07a), ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de), ("lightyellow", 0xffffe0), ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32), ("linen", 0xfaf0e6), ("magenta", 0xff00ff), ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa), ("mediumblue", 0x0000cd), ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371), ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585), ("midnightblue", 0x191970), ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1), ("moccasin", 0xffe4b5), ("navajowhite", 0xffdead),
+    ("navy", 0x000080), ("oldlace", 0xfdf5e6), ("olive", 0x808000), ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500), ("orangered", 0xff4500), ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093), ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f), ("pink", 0xffc0cb), ("plum", 0xdda0dd), ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080), ("rebeccapurple", 0x663399), ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f), ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072), ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee), ("sienna", 0xa0522d), ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd), ("slategray", 0x708090), ("slategrey", 0x708090),
+    ("snow", 0xfffafa), ("springgreen", 0x00ff7f), ("steelblue", 0x4682b4), ("tan", 0xd2b48c),
+    ("teal", 0x008080), ("thistle", 0xd8bfd8), ("tomato", 0xff6347), ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee), ("wheat", 0xf5deb3), ("white", 0xffffff), ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
+
 // ADDITION
 
 impl Add<Colour> for Colour {
@@ -213,6 +424,68 @@ mod tests {
                    Colour::new(0.05, 0.4, 0.75, 0.125));
     }
 
+    fn assert_close(a: Colour, b: Colour) {
+        let d = a - b;
+        assert!(d.r.abs() < 1.0e-3 && d.g.abs() < 1.0e-3 && d.b.abs() < 1.0e-3 &&
+                d.a.abs() < 1.0e-3, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Colour::from_hex("#ff8000"), Some(Colour::from_u8(255, 128, 0, 255)));
+        assert_eq!(Colour::from_hex("#FF800080"), Some(Colour::from_u8(255, 128, 0, 128)));
+        assert_eq!(Colour::from_hex("#f80"), Some(Colour::from_u8(255, 136, 0, 255)));
+        assert_eq!(Colour::from_hex("#ff800"), None);
+        assert_eq!(Colour::from_hex("#gg0000"), None);
+        assert_eq!(Colour::from_hex("ff0000"), None);
+        assert_eq!(Colour::parse("CornflowerBlue"), Some(Colour::from_u8(100, 149, 237, 255)));
+        assert_eq!(Colour::parse(" #000000 "), Some(BLACK));
+        assert_eq!(Colour::parse("transparent"), Some(TRANSPARENT));
+        assert_eq!(Colour::parse("blurple"), None);
+    }
+
+    #[test]
+    fn test_to_hex() {
+        assert_eq!(Colour::from_u8(255, 128, 0, 255).to_hex(), "#ff8000");
+        assert_eq!(Colour::from_u8(255, 128, 0, 128).to_hex(), "#ff800080");
+    }
+
+    #[test]
+    fn test_hsl_hsv() {
+        let orange = Colour::from_u8(255, 128, 0, 255);
+        let (h, s, l) = orange.to_hsl();
+        assert_close(Colour::from_hsl(h, s, l, 1.0), orange);
+        let (h, s, v) = orange.to_hsv();
+        assert_close(Colour::from_hsv(h, s, v, 1.0), orange);
+        assert_close(Colour::from_hsl(120.0, 1.0, 0.5, 1.0), Colour::new(0.0, 1.0, 0.0, 1.0));
+        assert_close(Colour::from_hsv(240.0, 1.0, 1.0, 1.0), Colour::new(0.0, 0.0, 1.0, 1.0));
+        assert_eq!(Colour::new(0.5, 0.5, 0.5, 1.0).to_hsl(), (0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn test_lerp_over() {
+        assert_eq!(BLACK.lerp(&WHITE, 0.5), Colour::new(0.5, 0.5, 0.5, 1.0));
+        assert_eq!(Colour::new(1.0, 0.0, 0.0, 0.5).over(&WHITE), Colour::new(1.0, 0.5, 0.5, 1.0));
+        assert_eq!(WHITE.over(&BLACK), WHITE);
+        assert_eq!(TRANSPARENT.over(&TRANSPARENT), TRANSPARENT);
+    }
+
+    #[test]
+    fn test_lighten_darken() {
+        assert_close(BLACK.lighten(0.5), Colour::new(0.5, 0.5, 0.5, 1.0));
+        assert_close(WHITE.darken(0.25), Colour::new(0.75, 0.75, 0.75, 1.0));
+        assert_close(WHITE.lighten(0.5), WHITE);
+    }
+
+    #[test]
+    fn test_contrast() {
+        assert_eq!(BLACK.relative_luminance(), 0.0);
+        assert!((WHITE.relative_luminance() - 1.0).abs() < 1.0e-5);
+        assert!((BLACK.contrast_ratio(&WHITE) - 21.0).abs() < 1.0e-4);
+        assert!((WHITE.contrast_ratio(&BLACK) - 21.0).abs() < 1.0e-4);
+        assert_eq!(WHITE.contrast_ratio(&WHITE), 1.0);
+    }
+
     #[test]
     fn test_neg() {
         assert_eq!(-Colour::new(0.1, 0.2, 0.3, 0.4), Colour::new(-0.1, -0.2, -0.3, -0.4));
@@ -1,20 +1,112 @@
 use std;
 use std::cell::Ref;
+use std::rc::Rc;
 
-use cairo::Context;
+use cairo::{Context, LineCap};
 
 use geom::fastminmax::{fast_min, fast_max};
 use geom::colour::Colour;
+use graphics::paint::Paint;
+use input::interactive::VisualState;
 
 
+/// Stroke style of a border side
 #[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StrokeStyle {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+/// One side of a `SidedBorder`; a side with zero thickness is not drawn
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BorderSide {
+    pub thickness: f64,
+    pub colour: Colour,
+    pub style: StrokeStyle,
+}
+
+impl BorderSide {
+    pub fn new(thickness: f64, colour: Colour, style: StrokeStyle) -> BorderSide {
+        BorderSide{thickness: thickness, colour: colour, style: style}
+    }
+
+    pub fn solid(thickness: f64, colour: Colour) -> BorderSide {
+        BorderSide::new(thickness, colour, StrokeStyle::Solid)
+    }
+
+    /// A side that is not drawn
+    pub fn none() -> BorderSide {
+        BorderSide::new(0.0, Colour::new(0.0, 0.0, 0.0, 0.0), StrokeStyle::Solid)
+    }
+
+    /// Set up `cairo_ctx` to stroke this side
+    fn apply(&self, cairo_ctx: &Context) {
+        let t = self.thickness;
+        cairo_ctx.set_line_width(t);
+        cairo_ctx.set_source_rgba(self.colour.r as f64, self.colour.g as f64,
+                                  self.colour.b as f64, self.colour.a as f64);
+        match self.style {
+            StrokeStyle::Solid => {
+                cairo_ctx.set_dash(&[], 0.0);
+                cairo_ctx.set_line_cap(LineCap::Butt);
+            },
+            StrokeStyle::Dashed => {
+                cairo_ctx.set_dash(&[t * 3.0, t * 2.0], 0.0);
+                cairo_ctx.set_line_cap(LineCap::Butt);
+            },
+            StrokeStyle::Dotted => {
+                // Zero length dashes with round caps draw dots
+                cairo_ctx.set_dash(&[0.0, t * 2.0], 0.0);
+                cairo_ctx.set_line_cap(LineCap::Round);
+            }
+        }
+    }
+}
+
+/// Corner radii of a `SidedBorder`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CornerRadii {
+    pub top_left: f64,
+    pub top_right: f64,
+    pub bottom_right: f64,
+    pub bottom_left: f64,
+}
+
+impl CornerRadii {
+    pub fn new(top_left: f64, top_right: f64, bottom_right: f64,
+               bottom_left: f64) -> CornerRadii {
+        CornerRadii{top_left: top_left, top_right: top_right, bottom_right: bottom_right,
+                    bottom_left: bottom_left}
+    }
+
+    pub fn uniform(r: f64) -> CornerRadii {
+        CornerRadii::new(r, r, r, r)
+    }
+
+    /// Radii limited so that the arcs of a `w` by `h` box do not overlap
+    fn clamped(&self, w: f64, h: f64) -> CornerRadii {
+        let limit = fast_max(fast_min(w * 0.5, h * 0.5), 0.0);
+        CornerRadii::new(fast_min(self.top_left, limit), fast_min(self.top_right, limit),
+                         fast_min(self.bottom_right, limit), fast_min(self.bottom_left, limit))
+    }
+}
+
+
+/// A border drawn around an element.
+///
+/// Backgrounds are `Paint`s, so they may be gradients or images as well as solid colours.
+/// This replaced the `background_colour: Option<Colour>` field of each variant with
+/// `background: Option<Paint>`, and as `Paint` is not `Copy`, neither is `Border` any more;
+/// use `background_colour` to get a solid background colour as before.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Border {
     SolidBorder{
         thickness: f64,
         inset: f64,
         rounding: f64,
         border_colour: Colour,
-        background_colour: Option<Colour>
+        background: Option<Paint>
     },
     FilledBorder{
         left_margin: f64,
@@ -22,7 +114,18 @@ pub enum Border {
         top_margin: f64,
         bottom_margin: f64,
         rounding: f64,
-        background_colour: Option<Colour>
+        background: Option<Paint>
+    },
+    /// Border with independent sides and corner radii; the margin on each side is the
+    /// thickness of that side plus `inset`
+    SidedBorder{
+        left: BorderSide,
+        right: BorderSide,
+        top: BorderSide,
+        bottom: BorderSide,
+        inset: f64,
+        radii: CornerRadii,
+        background: Option<Paint>
     }
 }
 
@@ -32,7 +135,7 @@ impl Border {
                      border_colour: Colour, background_colour: Option<Colour>) -> Border {
         Border::SolidBorder{thickness: thickness, inset: inset, rounding: rounding,
             border_colour: border_colour,
-            background_colour: background_colour}
+            background: background_colour.map(|c| Paint::Solid(c))}
     }
 
     pub fn new_filled(left_margin: f64, right_margin: f64,
@@ -40,7 +143,41 @@ impl Border {
                       background_colour: Option<Colour>) -> Border {
         Border::FilledBorder{left_margin: left_margin, right_margin: right_margin,
             top_margin: top_margin, bottom_margin: bottom_margin, rounding: rounding,
-            background_colour: background_colour}
+            background: background_colour.map(|c| Paint::Solid(c))}
+    }
+
+    pub fn new_sided(left: BorderSide, right: BorderSide, top: BorderSide, bottom: BorderSide,
+                     inset: f64, radii: CornerRadii, background: Option<Paint>) -> Border {
+        Border::SidedBorder{left: left, right: right, top: top, bottom: bottom, inset: inset,
+            radii: radii, background: background}
+    }
+
+    /// Copy of this border with its background replaced by `background`
+    pub fn with_background(&self, background: Option<Paint>) -> Border {
+        let mut b = self.clone();
+        match b {
+            Border::SolidBorder{background: ref mut bg, ..} => {*bg = background;},
+            Border::FilledBorder{background: ref mut bg, ..} => {*bg = background;},
+            Border::SidedBorder{background: ref mut bg, ..} => {*bg = background;},
+        }
+        b
+    }
+
+    pub fn background(&self) -> Option<&Paint> {
+        match self {
+            &Border::SolidBorder{ref background, ..} => background.as_ref(),
+            &Border::FilledBorder{ref background, ..} => background.as_ref(),
+            &Border::SidedBorder{ref background, ..} => background.as_ref(),
+        }
+    }
+
+    /// Colour of the background if it is solid; `None` if there is no background or it is a
+    /// gradient or image
+    pub fn background_colour(&self) -> Option<Colour> {
+        match self.background() {
+            Some(&Paint::Solid(c)) => Some(c),
+            _ => None
+        }
     }
 
 
@@ -48,6 +185,7 @@ impl Border {
         match self {
             &Border::SolidBorder{thickness: t, inset: i, ..} => t + i,
             &Border::FilledBorder{left_margin: m, ..} => m,
+            &Border::SidedBorder{left: ref s, inset: i, ..} => s.thickness + i,
         }
     }
 
@@ -55,6 +193,7 @@ impl Border {
         match self {
             &Border::SolidBorder{thickness: t, inset: i, ..} => t + i,
             &Border::FilledBorder{right_margin: m, ..} => m,
+            &Border::SidedBorder{right: ref s, inset: i, ..} => s.thickness + i,
         }
     }
 
@@ -62,6 +201,7 @@ impl Border {
         match self {
             &Border::SolidBorder{thickness: t, inset: i, ..} => t + i,
             &Border::FilledBorder{top_margin: m, ..} => m,
+            &Border::SidedBorder{top: ref s, inset: i, ..} => s.thickness + i,
         }
     }
 
@@ -70,6 +210,7 @@ impl Border {
         match self {
             &Border::SolidBorder{thickness: t, inset: i, ..} => t + i,
             &Border::FilledBorder{bottom_margin: m, ..} => m,
+            &Border::SidedBorder{bottom: ref s, inset: i, ..} => s.thickness + i,
         }
     }
 
@@ -80,6 +221,9 @@ impl Border {
             },
             &Border::FilledBorder{rounding: r, ..} => {
                 Border::border_path(cairo_ctx, x, y, w, h, r);
+            },
+            &Border::SidedBorder{ref left, ref right, ref top, ref bottom, ref radii, ..} => {
+                Border::sided_path(cairo_ctx, x, y, w, h, left, right, top, bottom, radii);
             }
         };
     }
@@ -109,29 +253,52 @@ impl Border {
                 cairo_ctx.set_line_width(prev_width);
                 cairo_ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
             },
+            &Border::SidedBorder{ref left, ref right, ref top, ref bottom, ref radii, ..} => {
+                // Please see comments above concerning correct 'source' handling.
+                let prev_width = cairo_ctx.get_line_width();
+                Border::draw_sides(cairo_ctx, x, y, w, h, left, right, top, bottom, radii);
+                cairo_ctx.set_line_width(prev_width);
+                cairo_ctx.set_dash(&[], 0.0);
+                cairo_ctx.set_line_cap(LineCap::Butt);
+                cairo_ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+            },
             _ => {}
         };
     }
 
     pub fn draw_background(&self, cairo_ctx: &Context, x: f64, y: f64, w: f64, h: f64) {
         match self {
-            &Border::SolidBorder{thickness: t, rounding: r, background_colour: Some(col), ..} => {
+            &Border::SolidBorder{thickness: t, rounding: r, background: Some(ref paint), ..} => {
                 // Please see comments in draw() method concerning correct 'source' handling.
 
-                cairo_ctx.new_path();
-                Border::border_path(cairo_ctx, x + t * 0.5, y + t * 0.5, w - t, h - t, r);
-                cairo_ctx.set_source_rgba(col.r as f64, col.g as f64, col.b as f64, col.a as f64);
-                cairo_ctx.fill();
+                if paint.set_source(cairo_ctx, x, y, w, h) {
+                    cairo_ctx.new_path();
+                    Border::border_path(cairo_ctx, x + t * 0.5, y + t * 0.5, w - t, h - t, r);
+                    cairo_ctx.fill();
+                }
 
                 cairo_ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
             },
-            &Border::FilledBorder{rounding: r, background_colour: Some(col), ..} => {
+            &Border::FilledBorder{rounding: r, background: Some(ref paint), ..} => {
                 // Please see comments in draw() method concerning correct 'source' handling.
 
-                cairo_ctx.new_path();
-                Border::border_path(cairo_ctx, x, y, w, h, r);
-                cairo_ctx.set_source_rgba(col.r as f64, col.g as f64, col.b as f64, col.a as f64);
-                cairo_ctx.fill();
+                if paint.set_source(cairo_ctx, x, y, w, h) {
+                    cairo_ctx.new_path();
+                    Border::border_path(cairo_ctx, x, y, w, h, r);
+                    cairo_ctx.fill();
+                }
+
+                cairo_ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+            },
+            &Border::SidedBorder{ref left, ref right, ref top, ref bottom, ref radii,
+                                 background: Some(ref paint), ..} => {
+                // Please see comments in draw() method concerning correct 'source' handling.
+
+                if paint.set_source(cairo_ctx, x, y, w, h) {
+                    cairo_ctx.new_path();
+                    Border::sided_path(cairo_ctx, x, y, w, h, left, right, top, bottom, radii);
+                    cairo_ctx.fill();
+                }
 
                 cairo_ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
             },
@@ -140,6 +307,96 @@ impl Border {
     }
 
 
+    /// The path running through the centre of each side of a sided border
+    fn sided_path(cairo_ctx: &Context, x: f64, y: f64, w: f64, h: f64,
+                  left: &BorderSide, right: &BorderSide, top: &BorderSide, bottom: &BorderSide,
+                  radii: &CornerRadii) {
+        let x0 = x + left.thickness * 0.5;
+        let y0 = y + top.thickness * 0.5;
+        let x1 = x + w - right.thickness * 0.5;
+        let y1 = y + h - bottom.thickness * 0.5;
+        Border::rounded_rect_path(cairo_ctx, x0, y0, x1 - x0, y1 - y0, radii);
+    }
+
+    /// Stroke the sides of a sided border. If all sides are the same, the outline is stroked
+    /// in one piece so that dash patterns run continuously around the corners. Otherwise each
+    /// side is stroked separately, taking half of each adjoining corner arc.
+    fn draw_sides(cairo_ctx: &Context, x: f64, y: f64, w: f64, h: f64,
+                  left: &BorderSide, right: &BorderSide, top: &BorderSide, bottom: &BorderSide,
+                  radii: &CornerRadii) {
+        if left == right && left == top && left == bottom {
+            if left.thickness > 0.0 {
+                cairo_ctx.new_path();
+                Border::sided_path(cairo_ctx, x, y, w, h, left, right, top, bottom, radii);
+                left.apply(cairo_ctx);
+                cairo_ctx.stroke();
+            }
+            return;
+        }
+
+        let pi = std::f64::consts::PI;
+        let x0 = x + left.thickness * 0.5;
+        let y0 = y + top.thickness * 0.5;
+        let x1 = x + w - right.thickness * 0.5;
+        let y1 = y + h - bottom.thickness * 0.5;
+        let r = radii.clamped(x1 - x0, y1 - y0);
+
+        if top.thickness > 0.0 {
+            cairo_ctx.new_path();
+            cairo_ctx.arc(x0 + r.top_left, y0 + r.top_left, r.top_left, pi * 1.25, pi * 1.5);
+            cairo_ctx.line_to(x1 - r.top_right, y0);
+            cairo_ctx.arc(x1 - r.top_right, y0 + r.top_right, r.top_right, pi * 1.5, pi * 1.75);
+            top.apply(cairo_ctx);
+            cairo_ctx.stroke();
+        }
+        if right.thickness > 0.0 {
+            cairo_ctx.new_path();
+            cairo_ctx.arc(x1 - r.top_right, y0 + r.top_right, r.top_right, pi * 1.75, pi * 2.0);
+            cairo_ctx.line_to(x1, y1 - r.bottom_right);
+            cairo_ctx.arc(x1 - r.bottom_right, y1 - r.bottom_right, r.bottom_right,
+                          0.0, pi * 0.25);
+            right.apply(cairo_ctx);
+            cairo_ctx.stroke();
+        }
+        if bottom.thickness > 0.0 {
+            cairo_ctx.new_path();
+            cairo_ctx.arc(x1 - r.bottom_right, y1 - r.bottom_right, r.bottom_right,
+                          pi * 0.25, pi * 0.5);
+            cairo_ctx.line_to(x0 + r.bottom_left, y1);
+            cairo_ctx.arc(x0 + r.bottom_left, y1 - r.bottom_left, r.bottom_left,
+                          pi * 0.5, pi * 0.75);
+            bottom.apply(cairo_ctx);
+            cairo_ctx.stroke();
+        }
+        if left.thickness > 0.0 {
+            cairo_ctx.new_path();
+            cairo_ctx.arc(x0 + r.bottom_left, y1 - r.bottom_left, r.bottom_left,
+                          pi * 0.75, pi);
+            cairo_ctx.line_to(x0, y0 + r.top_left);
+            cairo_ctx.arc(x0 + r.top_left, y0 + r.top_left, r.top_left, pi, pi * 1.25);
+            left.apply(cairo_ctx);
+            cairo_ctx.stroke();
+        }
+    }
+
+    /// Rectangle path with independently rounded corners, clockwise from the top left
+    pub fn rounded_rect_path(cairo_ctx: &Context, x: f64, y: f64, w: f64, h: f64,
+                             radii: &CornerRadii) {
+        let pi = std::f64::consts::PI;
+        let r = radii.clamped(w, h);
+        cairo_ctx.move_to(x + r.top_left, y);
+        cairo_ctx.line_to(x + w - r.top_right, y);
+        cairo_ctx.arc(x + w - r.top_right, y + r.top_right, r.top_right, pi * 1.5, pi * 2.0);
+        cairo_ctx.line_to(x + w, y + h - r.bottom_right);
+        cairo_ctx.arc(x + w - r.bottom_right, y + h - r.bottom_right, r.bottom_right,
+                      0.0, pi * 0.5);
+        cairo_ctx.line_to(x + r.bottom_left, y + h);
+        cairo_ctx.arc(x + r.bottom_left, y + h - r.bottom_left, r.bottom_left, pi * 0.5, pi);
+        cairo_ctx.line_to(x, y + r.top_left);
+        cairo_ctx.arc(x + r.top_left, y + r.top_left, r.top_left, pi, pi * 1.5);
+        cairo_ctx.close_path();
+    }
+
     fn border_path(cairo_ctx: &Context, x: f64, y: f64, w: f64, h: f64,
                    rounding: f64) {
         if rounding == 0.0 {
@@ -185,4 +442,131 @@ impl Border {
             cairo_ctx.close_path();
         }
     }
-}
\ No newline at end of file
+}
+
+
+/// Borders to use for each visual state of an interactive element. States without a border
+/// of their own use the `normal` border.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateBorders {
+    pub normal: Rc<Border>,
+    pub hovered: Option<Rc<Border>>,
+    pub pressed: Option<Rc<Border>>,
+    pub focused: Option<Rc<Border>>,
+    pub disabled: Option<Rc<Border>>,
+}
+
+impl StateBorders {
+    pub fn new(normal: Rc<Border>) -> StateBorders {
+        StateBorders{normal: normal, hovered: None, pressed: None, focused: None,
+            disabled: None}
+    }
+
+    pub fn with_hovered(mut self, border: Rc<Border>) -> StateBorders {
+        self.hovered = Some(border);
+        self
+    }
+
+    pub fn with_pressed(mut self, border: Rc<Border>) -> StateBorders {
+        self.pressed = Some(border);
+        self
+    }
+
+    pub fn with_focused(mut self, border: Rc<Border>) -> StateBorders {
+        self.focused = Some(border);
+        self
+    }
+
+    pub fn with_disabled(mut self, border: Rc<Border>) -> StateBorders {
+        self.disabled = Some(border);
+        self
+    }
+
+    /// The border to draw in `state`
+    pub fn for_state(&self, state: VisualState) -> &Rc<Border> {
+        let b = match state {
+            VisualState::Normal => None,
+            VisualState::Hovered => self.hovered.as_ref(),
+            VisualState::Pressed => self.pressed.as_ref(),
+            VisualState::Focused => self.focused.as_ref(),
+            VisualState::Disabled => self.disabled.as_ref(),
+        };
+        b.unwrap_or(&self.normal)
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::point2::Point2;
+    use graphics::paint::ColourStop;
+
+    #[test]
+    fn test_background_colour() {
+        let c = Colour::new(1.0, 0.5, 0.0, 1.0);
+        assert_eq!(Border::new_filled(1.0, 1.0, 1.0, 1.0, 0.0, Some(c)).background_colour(),
+                   Some(c));
+        assert_eq!(Border::new_filled(1.0, 1.0, 1.0, 1.0, 0.0, None).background_colour(), None);
+
+        let gradient = Paint::LinearGradient{start: Point2::new(0.0, 0.0),
+            end: Point2::new(1.0, 0.0),
+            stops: vec![ColourStop::new(0.0, c), ColourStop::new(1.0, c)]};
+        let b = Border::new_solid(1.0, 0.0, 0.0, c, None).with_background(Some(gradient.clone()));
+        assert_eq!(b.background(), Some(&gradient));
+        assert_eq!(b.background_colour(), None);
+    }
+
+    fn sided(left: BorderSide, right: BorderSide, top: BorderSide, bottom: BorderSide,
+             inset: f64) -> Border {
+        Border::new_sided(left, right, top, bottom, inset, CornerRadii::uniform(0.0), None)
+    }
+
+    #[test]
+    fn test_sided_margins() {
+        let c = Colour::new(0.0, 0.0, 0.0, 1.0);
+        let b = sided(BorderSide::solid(1.0, c), BorderSide::solid(2.0, c),
+                      BorderSide::new(3.0, c, StrokeStyle::Dashed),
+                      BorderSide::new(4.0, c, StrokeStyle::Dotted), 0.5);
+        assert_eq!(b.left_margin(), 1.5);
+        assert_eq!(b.right_margin(), 2.5);
+        assert_eq!(b.top_margin(), 3.5);
+        assert_eq!(b.bottom_margin(), 4.5);
+
+        let b = sided(BorderSide::solid(1.0, c), BorderSide::solid(2.0, c),
+                      BorderSide::solid(3.0, c), BorderSide::solid(4.0, c), 0.0);
+        assert_eq!(b.left_margin(), 1.0);
+        assert_eq!(b.right_margin(), 2.0);
+        assert_eq!(b.top_margin(), 3.0);
+        assert_eq!(b.bottom_margin(), 4.0);
+    }
+
+    #[test]
+    fn test_sided_margins_with_no_side() {
+        // A side that is not drawn leaves only the inset
+        let c = Colour::new(0.0, 0.0, 0.0, 1.0);
+        let b = sided(BorderSide::none(), BorderSide::solid(2.0, c), BorderSide::solid(3.0, c),
+                      BorderSide::none(), 1.0);
+        assert_eq!(b.left_margin(), 1.0);
+        assert_eq!(b.right_margin(), 3.0);
+        assert_eq!(b.top_margin(), 4.0);
+        assert_eq!(b.bottom_margin(), 1.0);
+
+        let b = sided(BorderSide::none(), BorderSide::none(), BorderSide::none(),
+                      BorderSide::none(), 0.0);
+        assert_eq!(b.left_margin(), 0.0);
+        assert_eq!(b.right_margin(), 0.0);
+        assert_eq!(b.top_margin(), 0.0);
+        assert_eq!(b.bottom_margin(), 0.0);
+    }
+
+    #[test]
+    fn test_corner_radii_clamped() {
+        let r = CornerRadii::new(2.0, 10.0, 30.0, 0.0).clamped(40.0, 20.0);
+        assert_eq!(r, CornerRadii::new(2.0, 10.0, 10.0, 0.0));
+    }
+}
@@ -1 +1,4 @@
 pub mod border;
+pub mod paint;
+pub mod shadow;
+pub mod shape;
@@ -0,0 +1,227 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+
+use cairo::{Context, Gradient, LinearGradient, RadialGradient, Pattern, SurfacePattern,
+            ImageSurface, Matrix, Extend};
+
+use geom::colour::Colour;
+use geom::point2::Point2;
+
+
+/// A colour at a position along a gradient; `offset` ranges from `0` to `1`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ColourStop {
+    pub offset: f64,
+    pub colour: Colour,
+}
+
+impl ColourStop {
+    pub fn new(offset: f64, colour: Colour) -> ColourStop {
+        ColourStop{offset: offset, colour: colour}
+    }
+}
+
+
+/// How an area is filled.
+///
+/// Gradient geometry is given relative to the box being painted: `(0, 0)` is its top left
+/// corner and `(1, 1)` its bottom right, and the radius of a radial gradient is a fraction
+/// of the larger of the box's width and height.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Paint {
+    Solid(Colour),
+    LinearGradient{
+        start: Point2,
+        end: Point2,
+        stops: Vec<ColourStop>,
+    },
+    RadialGradient{
+        centre: Point2,
+        radius: f64,
+        stops: Vec<ColourStop>,
+    },
+    /// Image loaded from a PNG file, repeated to fill the box
+    ImagePattern{
+        path: String,
+    },
+}
+
+thread_local!(static IMAGE_CACHE: RefCell<HashMap<String, Option<Rc<ImageSurface>>>> =
+              RefCell::new(HashMap::new()));
+
+/// Load the PNG image at `path`, caching the result
+fn load_image(path: &str) -> Option<Rc<ImageSurface>> {
+    IMAGE_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if !cache.contains_key(path) {
+            let image = File::open(path).ok().and_then(
+                |mut f| ImageSurface::create_from_png(&mut f).ok()).map(|s| Rc::new(s));
+            cache.insert(path.to_string(), image);
+        }
+        cache[path].clone()
+    })
+}
+
+fn add_stops<G: Gradient>(gradient: &G, stops: &Vec<ColourStop>) {
+    for s in stops.iter() {
+        gradient.add_color_stop_rgba(s.offset, s.colour.r as f64, s.colour.g as f64,
+                                     s.colour.b as f64, s.colour.a as f64);
+    }
+}
+
+impl Paint {
+    /// Map a point in box-relative co-ordinates to the box at `(x, y)` of size `(w, h)`
+    pub fn box_point(p: &Point2, x: f64, y: f64, w: f64, h: f64) -> Point2 {
+        Point2::new(x + p.x * w, y + p.y * h)
+    }
+
+    /// Set the source of `cairo_ctx` to paint the box at `(x, y)` of size `(w, h)`. Returns
+    /// false, leaving the source unchanged, if the paint cannot be used (e.g. its image could
+    /// not be loaded).
+    pub fn set_source(&self, cairo_ctx: &Context, x: f64, y: f64, w: f64, h: f64) -> bool {
+        match self {
+            &Paint::Solid(col) => {
+                cairo_ctx.set_source_rgba(col.r as f64, col.g as f64, col.b as f64, col.a as f64);
+                true
+            },
+            &Paint::LinearGradient{ref start, ref end, ref stops} => {
+                let s = Paint::box_point(start, x, y, w, h);
+                let e = Paint::box_point(end, x, y, w, h);
+                let gradient = LinearGradient::new(s.x, s.y, e.x, e.y);
+                add_stops(&gradient, stops);
+                cairo_ctx.set_source(&gradient);
+                true
+            },
+            &Paint::RadialGradient{ref centre, radius, ref stops} => {
+                let c = Paint::box_point(centre, x, y, w, h);
+                let r = radius * w.max(h);
+                let gradient = RadialGradient::new(c.x, c.y, 0.0, c.x, c.y, r);
+                add_stops(&gradient, stops);
+                cairo_ctx.set_source(&gradient);
+                true
+            },
+            &Paint::ImagePattern{ref path} => {
+                match load_image(path) {
+                    Some(image) => {
+                        let pattern = SurfacePattern::create(&*image);
+                        pattern.set_extend(Extend::Repeat);
+                        // Anchor the pattern at the top left corner of the box
+                        pattern.set_matrix(Matrix::new(1.0, 0.0, 0.0, 1.0, -x, -y));
+                        cairo_ctx.set_source(&pattern);
+                        true
+                    },
+                    None => false
+                }
+            }
+        }
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cairo::Format;
+    use geom::point2::Point2;
+
+    const RED: Colour = Colour{r: 1.0, g: 0.0, b: 0.0, a: 1.0};
+    const BLUE: Colour = Colour{r: 0.0, g: 0.0, b: 1.0, a: 1.0};
+    const GREEN: Colour = Colour{r: 0.0, g: 1.0, b: 0.0, a: 1.0};
+
+    /// Fill a `w` by `h` image with green, then with `paint`; returns the result of
+    /// `set_source` and the pixels as rows of `(r, g, b, a)`
+    fn render(paint: &Paint, w: i32, h: i32) -> (bool, Vec<Vec<(u8, u8, u8, u8)>>) {
+        let mut surface = ImageSurface::create(Format::ARgb32, w, h).unwrap();
+        let used = {
+            let cairo_ctx = Context::new(&surface);
+            Paint::Solid(GREEN).set_source(&cairo_ctx, 0.0, 0.0, w as f64, h as f64);
+            let used = paint.set_source(&cairo_ctx, 0.0, 0.0, w as f64, h as f64);
+            cairo_ctx.rectangle(0.0, 0.0, w as f64, h as f64);
+            cairo_ctx.fill();
+            used
+        };
+        surface.flush();
+        let stride = surface.get_stride() as usize;
+        let data = surface.get_data().unwrap();
+        // ARGB32 pixels are native endian words; on little endian machines: B, G, R, A
+        let rows = (0..h as usize).map(|y| {
+            (0..w as usize).map(|x| {
+                let i = y * stride + x * 4;
+                (data[i + 2], data[i + 1], data[i], data[i + 3])
+            }).collect()
+        }).collect();
+        (used, rows)
+    }
+
+    #[test]
+    fn test_box_point() {
+        assert_eq!(Paint::box_point(&Point2::new(0.0, 0.0), 10.0, 20.0, 100.0, 50.0),
+                   Point2::new(10.0, 20.0));
+        assert_eq!(Paint::box_point(&Point2::new(0.5, 1.0), 10.0, 20.0, 100.0, 50.0),
+                   Point2::new(60.0, 70.0));
+    }
+
+    #[test]
+    fn test_linear_gradient_stops() {
+        let paint = Paint::LinearGradient{start: Point2::new(0.0, 0.0), end: Point2::new(1.0, 0.0),
+            stops: vec![ColourStop::new(0.0, RED), ColourStop::new(1.0, BLUE)]};
+        let (used, rows) = render(&paint, 100, 1);
+        assert!(used);
+        let row = &rows[0];
+        // Red at the start, blue at the end and a blend of the two in between
+        assert!(row[0].0 > 250 && row[0].2 < 5);
+        assert!(row[99].0 < 5 && row[99].2 > 250);
+        assert!(row[50].0 > 100 && row[50].0 < 155);
+        assert!(row[50].2 > 100 && row[50].2 < 155);
+        assert!(row.iter().all(|p| p.1 == 0 && p.3 == 255));
+    }
+
+    #[test]
+    fn test_gradient_stops_at_same_offset() {
+        // Two stops at the same offset give a hard edge; the colour of the first and last
+        // stops extends to the ends of the gradient
+        let paint = Paint::LinearGradient{start: Point2::new(0.0, 0.0), end: Point2::new(1.0, 0.0),
+            stops: vec![ColourStop::new(0.5, RED), ColourStop::new(0.5, BLUE)]};
+        let (used, rows) = render(&paint, 100, 1);
+        assert!(used);
+        let row = &rows[0];
+        assert_eq!(row[0], (255, 0, 0, 255));
+        assert_eq!(row[48], (255, 0, 0, 255));
+        assert_eq!(row[51], (0, 0, 255, 255));
+        assert_eq!(row[99], (0, 0, 255, 255));
+    }
+
+    #[test]
+    fn test_radial_gradient_stops() {
+        // The radius is relative to the larger of the box's dimensions
+        let paint = Paint::RadialGradient{centre: Point2::new(0.5, 0.5), radius: 0.25,
+            stops: vec![ColourStop::new(0.0, RED), ColourStop::new(1.0, BLUE)]};
+        let (used, rows) = render(&paint, 80, 40);
+        assert!(used);
+        let centre = rows[20][40];
+        assert!(centre.0 > 240 && centre.2 < 15);
+        // 20 pixels from the centre is past the end of the gradient
+        assert_eq!(rows[20][62], (0, 0, 255, 255));
+        assert_eq!(rows[20][0], (0, 0, 255, 255));
+    }
+
+    #[test]
+    fn test_image_load_failure() {
+        let paint = Paint::ImagePattern{path: "no/such/image.png".to_string()};
+        assert!(load_image("no/such/image.png").is_none());
+        // The paint cannot be used, so the source is left as it was
+        let (used, rows) = render(&paint, 4, 4);
+        assert!(!used);
+        assert!(rows.iter().all(|row| row.iter().all(|p| *p == (0, 255, 0, 255))));
+        // The failure is cached
+        IMAGE_CACHE.with(|cache| {
+            assert_eq!(cache.borrow().get("no/such/image.png").map(|i| i.is_none()), Some(true));
+        });
+    }
+}
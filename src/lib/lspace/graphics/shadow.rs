@@ -0,0 +1,87 @@
+use cairo::Context;
+
+use geom::colour::Colour;
+use geom::vector2::Vector2;
+use graphics::border::{Border, CornerRadii};
+
+
+/// Maximum number of layers used to approximate the blur
+const MAX_BLUR_STEPS: usize = 16;
+
+
+/// Drop shadow cast by a rounded box. `spread` grows the shadow beyond the box before it is
+/// blurred over a distance of `blur`. A shadow with a zero offset acts as an outer glow.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Shadow {
+    pub offset: Vector2,
+    pub blur: f64,
+    pub spread: f64,
+    pub rounding: f64,
+    pub colour: Colour,
+}
+
+impl Shadow {
+    pub fn new(offset: Vector2, blur: f64, spread: f64, rounding: f64,
+               colour: Colour) -> Shadow {
+        Shadow{offset: offset, blur: blur, spread: spread, rounding: rounding, colour: colour}
+    }
+
+    /// Outer glow; a shadow with no offset
+    pub fn new_glow(blur: f64, spread: f64, rounding: f64, colour: Colour) -> Shadow {
+        Shadow::new(Vector2::zero(), blur, spread, rounding, colour)
+    }
+
+    /// Distance by which the shadow extends beyond each side of the box that casts it, in the
+    /// order left, right, top, bottom; never negative
+    pub fn extents(&self) -> (f64, f64, f64, f64) {
+        let e = (self.spread + self.blur).max(0.0);
+        ((e - self.offset.x).max(0.0), (e + self.offset.x).max(0.0),
+         (e - self.offset.y).max(0.0), (e + self.offset.y).max(0.0))
+    }
+
+    /// Draw the shadow cast by the box at `(x, y)` of size `(w, h)`. The blur is approximated
+    /// by stacking translucent layers that shrink towards the solid core of the shadow.
+    pub fn draw(&self, cairo_ctx: &Context, x: f64, y: f64, w: f64, h: f64) {
+        let steps = if self.blur > 0.0 {(self.blur.ceil() as usize).min(MAX_BLUR_STEPS)} else {1};
+        let alpha = self.colour.a as f64 / steps as f64;
+        let sx = x + self.offset.x;
+        let sy = y + self.offset.y;
+        cairo_ctx.set_source_rgba(self.colour.r as f64, self.colour.g as f64,
+                                  self.colour.b as f64, alpha);
+        for i in 0..steps {
+            // Outermost layer first; the layers overlap, so the alpha builds up towards the core
+            let grow = self.spread + self.blur * (1.0 - (i as f64 + 0.5) / steps as f64);
+            let lw = w + grow * 2.0;
+            let lh = h + grow * 2.0;
+            if lw > 0.0 && lh > 0.0 {
+                cairo_ctx.new_path();
+                Border::rounded_rect_path(cairo_ctx, sx - grow, sy - grow, lw, lh,
+                                          &CornerRadii::uniform((self.rounding + grow).max(0.0)));
+                cairo_ctx.fill();
+            }
+        }
+        // Please see comments in Border::draw() concerning correct 'source' handling.
+        cairo_ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::colour::BLACK;
+    use geom::vector2::Vector2;
+
+    #[test]
+    fn test_extents() {
+        assert_eq!(Shadow::new(Vector2::new(2.0, 3.0), 4.0, 1.0, 0.0, BLACK).extents(),
+                   (3.0, 7.0, 2.0, 8.0));
+        assert_eq!(Shadow::new(Vector2::new(10.0, 0.0), 2.0, 0.0, 0.0, BLACK).extents(),
+                   (0.0, 12.0, 2.0, 2.0));
+        assert_eq!(Shadow::new_glow(3.0, 1.0, 0.0, BLACK).extents(), (4.0, 4.0, 4.0, 4.0));
+    }
+}
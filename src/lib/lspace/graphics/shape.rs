@@ -0,0 +1,187 @@
+use std::f64::consts::PI;
+
+use cairo::Context;
+
+use geom::colour::Colour;
+use geom::point2::Point2;
+use geom::vector2::Vector2;
+use geom::bbox2::BBox2;
+use graphics::border::{Border, CornerRadii};
+
+
+/// Outline drawn around a shape
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stroke {
+    pub width: f64,
+    pub colour: Colour,
+}
+
+impl Stroke {
+    pub fn new(width: f64, colour: Colour) -> Stroke {
+        Stroke{width: width, colour: colour}
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShapeGeometry {
+    Rectangle{
+        lower: Point2,
+        size: Vector2,
+        rounding: f64,
+    },
+    Ellipse{
+        centre: Point2,
+        radii: Vector2,
+    },
+    /// Lines joining `points` in order; a closed polyline is a polygon
+    Polyline{
+        points: Vec<Point2>,
+        closed: bool,
+    },
+}
+
+
+/// A simple shape, filled and/or stroked
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shape {
+    pub geometry: ShapeGeometry,
+    pub fill: Option<Colour>,
+    pub stroke: Option<Stroke>,
+}
+
+impl Shape {
+    pub fn new(geometry: ShapeGeometry) -> Shape {
+        Shape{geometry: geometry, fill: None, stroke: None}
+    }
+
+    pub fn rectangle(lower: Point2, size: Vector2, rounding: f64) -> Shape {
+        Shape::new(ShapeGeometry::Rectangle{lower: lower, size: size, rounding: rounding})
+    }
+
+    pub fn ellipse(centre: Point2, radii: Vector2) -> Shape {
+        Shape::new(ShapeGeometry::Ellipse{centre: centre, radii: radii})
+    }
+
+    pub fn circle(centre: Point2, radius: f64) -> Shape {
+        Shape::ellipse(centre, Vector2::new(radius, radius))
+    }
+
+    pub fn polyline(points: Vec<Point2>, closed: bool) -> Shape {
+        Shape::new(ShapeGeometry::Polyline{points: points, closed: closed})
+    }
+
+    pub fn filled(mut self, colour: Colour) -> Shape {
+        self.fill = Some(colour);
+        self
+    }
+
+    pub fn stroked(mut self, width: f64, colour: Colour) -> Shape {
+        self.stroke = Some(Stroke::new(width, colour));
+        self
+    }
+
+    /// Bounding box of the region painted by the shape, including its stroke
+    pub fn bbox(&self) -> BBox2 {
+        let (lower, upper) = match self.geometry {
+            ShapeGeometry::Rectangle{lower, size, ..} => (lower, lower + size),
+            ShapeGeometry::Ellipse{centre, radii} => (centre - radii, centre + radii),
+            ShapeGeometry::Polyline{ref points, ..} => {
+                if points.is_empty() {
+                    return BBox2::new(Point2::origin(), Point2::origin());
+                }
+                let mut lower = points[0];
+                let mut upper = points[0];
+                for p in points.iter() {
+                    lower = Point2::new(lower.x.min(p.x), lower.y.min(p.y));
+                    upper = Point2::new(upper.x.max(p.x), upper.y.max(p.y));
+                }
+                (lower, upper)
+            }
+        };
+        let hw = self.stroke.map_or(0.0, |s| s.width * 0.5);
+        BBox2::new(Point2::new(lower.x - hw, lower.y - hw), Point2::new(upper.x + hw, upper.y + hw))
+    }
+
+    fn path(&self, cairo_ctx: &Context) {
+        cairo_ctx.new_path();
+        match self.geometry {
+            ShapeGeometry::Rectangle{lower, size, rounding} => {
+                Border::rounded_rect_path(cairo_ctx, lower.x, lower.y, size.x, size.y,
+                                          &CornerRadii::uniform(rounding));
+            },
+            ShapeGeometry::Ellipse{centre, radii} => {
+                if radii.x > 0.0 && radii.y > 0.0 {
+                    // Scale a unit circle; the path is retained in device space after restore
+                    cairo_ctx.save();
+                    cairo_ctx.translate(centre.x, centre.y);
+                    cairo_ctx.scale(radii.x, radii.y);
+                    cairo_ctx.arc(0.0, 0.0, 1.0, 0.0, PI * 2.0);
+                    cairo_ctx.restore();
+                    cairo_ctx.close_path();
+                }
+            },
+            ShapeGeometry::Polyline{ref points, closed} => {
+                for (i, p) in points.iter().enumerate() {
+                    if i == 0 {
+                        cairo_ctx.move_to(p.x, p.y);
+                    } else {
+                        cairo_ctx.line_to(p.x, p.y);
+                    }
+                }
+                if closed && !points.is_empty() {
+                    cairo_ctx.close_path();
+                }
+            }
+        }
+    }
+
+    pub fn draw(&self, cairo_ctx: &Context) {
+        self.path(cairo_ctx);
+        match self.fill {
+            Some(c) => {
+                cairo_ctx.set_source_rgba(c.r as f64, c.g as f64, c.b as f64, c.a as f64);
+                cairo_ctx.fill_preserve();
+            },
+            None => {}
+        }
+        match self.stroke {
+            Some(s) => {
+                cairo_ctx.set_source_rgba(s.colour.r as f64, s.colour.g as f64,
+                                          s.colour.b as f64, s.colour.a as f64);
+                cairo_ctx.set_line_width(s.width);
+                cairo_ctx.stroke();
+            },
+            None => {
+                cairo_ctx.new_path();
+            }
+        }
+        // Please see comments in Border::draw() concerning correct 'source' handling.
+        cairo_ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::colour::BLACK;
+    use geom::point2::Point2;
+    use geom::vector2::Vector2;
+    use geom::bbox2::BBox2;
+
+    #[test]
+    fn test_bbox() {
+        assert_eq!(Shape::rectangle(Point2::new(1.0, 2.0), Vector2::new(10.0, 5.0), 2.0).bbox(),
+                   BBox2::new(Point2::new(1.0, 2.0), Point2::new(11.0, 7.0)));
+        assert_eq!(Shape::circle(Point2::new(5.0, 5.0), 3.0).stroked(2.0, BLACK).bbox(),
+                   BBox2::new(Point2::new(1.0, 1.0), Point2::new(9.0, 9.0)));
+        assert_eq!(Shape::polyline(vec![Point2::new(3.0, 1.0), Point2::new(0.0, 4.0),
+                                        Point2::new(6.0, 2.0)], false).bbox(),
+                   BBox2::new(Point2::new(0.0, 1.0), Point2::new(6.0, 4.0)));
+    }
+}
@@ -0,0 +1,423 @@
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+
+use geom::point2::Point2;
+use input::inputmodifier::InputModifierState;
+use input::keyboard::{KeyEvent, KeyEventType, TKeyboardInteractor};
+use input::pointer_events::{PointerEvent, PointerEventType, TPointerInteractor};
+use elements::element::{ElementRef, elems_are_same, elem_root_space_bbox, elem_is_in_tree};
+use elements::hit_test::elem_path_at_point;
+use lspace_area::LSpaceArea;
+
+
+/// Named events bound to an element. The names are reported to the application, along with
+/// the element's id, when the corresponding interaction takes place over the element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventBindings {
+    pub on_click: Option<String>,
+    pub on_hover: Option<String>,
+    pub on_key: Option<String>,
+}
+
+impl EventBindings {
+    pub fn new() -> EventBindings {
+        EventBindings{on_click: None, on_hover: None, on_key: None}
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.on_click.is_none() && self.on_hover.is_none() && self.on_key.is_none()
+    }
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ElementEventType {
+    Click,
+    Enter,
+    Leave,
+    KeyPress,
+}
+
+impl ElementEventType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            &ElementEventType::Click => "click",
+            &ElementEventType::Enter => "enter",
+            &ElementEventType::Leave => "leave",
+            &ElementEventType::KeyPress => "key",
+        }
+    }
+}
+
+/// An interaction with an element that has event bindings
+#[derive(Debug, Clone)]
+pub struct ElementEvent {
+    pub event_type: ElementEventType,
+    /// The bound event name
+    pub name: String,
+    /// The id of the element; see `TElement::get_id`
+    pub id: Option<String>,
+    pub mod_state: InputModifierState,
+    /// Pointer position in the co-ordinate space of the element; `None` if unknown
+    pub position: Option<Point2>,
+    /// Button clicked; 0 for other events
+    pub button: u32,
+    /// Key pressed; 0 and empty for other events
+    pub key_val: u32,
+    pub key_string: String,
+}
+
+
+pub trait TElementEventListener {
+    fn on_element_event(&self, event: &ElementEvent);
+}
+
+
+struct ElementEventDispatcherMut {
+    /// Elements with event bindings that are under the pointer, outermost first
+    hovered: Vec<ElementRef>,
+    pointer_pos: Option<Point2>,
+}
+
+/// Dispatches pointer and keyboard interactions in an `LSpaceArea` to the elements that have
+/// event bindings, reporting them to a listener
+pub struct ElementEventDispatcher {
+    m: RefCell<ElementEventDispatcherMut>,
+    area: Weak<LSpaceArea>,
+    listener: Rc<TElementEventListener>,
+}
+
+impl ElementEventDispatcher {
+    /// Create a dispatcher and register it with the pointer and keyboard of `area`
+    pub fn attach(area: &Rc<LSpaceArea>,
+                  listener: &Rc<TElementEventListener>) -> Rc<ElementEventDispatcher> {
+        let dispatcher = Rc::new(ElementEventDispatcher{
+            m: RefCell::new(ElementEventDispatcherMut{hovered: vec![], pointer_pos: None}),
+            area: Rc::downgrade(area),
+            listener: listener.clone()});
+        let pointer_interactor: Rc<TPointerInteractor> = dispatcher.clone();
+        area.pointer_events().add_interactor(&pointer_interactor);
+        let key_interactor: Rc<TKeyboardInteractor> = dispatcher.clone();
+        area.keyboard().add_interactor(&key_interactor);
+        dispatcher
+    }
+
+    /// Unregister the dispatcher from its area
+    pub fn detach(dispatcher: &Rc<ElementEventDispatcher>) {
+        match dispatcher.area.upgrade() {
+            Some(area) => {
+                let pointer_interactor: Rc<TPointerInteractor> = dispatcher.clone();
+                area.pointer_events().remove_interactor(&pointer_interactor);
+                let key_interactor: Rc<TKeyboardInteractor> = dispatcher.clone();
+                area.keyboard().remove_interactor(&key_interactor);
+            },
+            None => {}
+        }
+    }
+
+    /// Elements with event bindings under `pos`, outermost first
+    fn bound_elems_at(&self, pos: &Point2) -> Vec<ElementRef> {
+        match self.area.upgrade() {
+            Some(area) => elem_path_at_point(&area.root_element(), pos).into_iter().filter(
+                |e| e.event_bindings().is_some()).collect(),
+            None => vec![]
+        }
+    }
+
+    fn make_event(&self, elem: &ElementRef, event_type: ElementEventType, name: &String,
+                  mod_state: InputModifierState, pos: Option<Point2>) -> ElementEvent {
+        let local_pos = pos.map(|p| {
+            let lower = elem_root_space_bbox(&**elem).lower;
+            Point2::origin() + (p - lower)
+        });
+        ElementEvent{event_type: event_type, name: name.clone(),
+                     id: elem.get_id(),
+                     mod_state: mod_state, position: local_pos, button: 0,
+                     key_val: 0, key_string: String::new()}
+    }
+
+    /// Forget the hovered elements that are no longer in the tree, e.g. after the content has
+    /// been replaced. They are not sent leave events, so that an element that is rebuilt under
+    /// the pointer is not reported as left.
+    fn remove_detached(&self) {
+        match self.area.upgrade() {
+            Some(area) => {
+                let root = area.root_element();
+                self.m.borrow_mut().hovered.retain(|e| elem_is_in_tree(e, &root));
+            },
+            None => {}
+        }
+    }
+
+    /// Enter and leave events resulting from the pointer moving to `pos`
+    fn update_hover(&self, mod_state: InputModifierState,
+                    pos: Option<Point2>) -> Vec<ElementEvent> {
+        self.remove_detached();
+        let new_hovered = match pos {
+            Some(ref p) => self.bound_elems_at(p),
+            None => vec![]
+        };
+        let old_hovered = {
+            let mut mm = self.m.borrow_mut();
+            mm.pointer_pos = pos;
+            let old = mm.hovered.clone();
+            mm.hovered = new_hovered.clone();
+            old
+        };
+
+        let mut events = vec![];
+        for e in old_hovered.iter().rev() {
+            if !new_hovered.iter().any(|x| elems_are_same(x, e)) {
+                match e.event_bindings().and_then(|b| b.on_hover.clone()) {
+                    Some(name) => events.push(self.make_event(e, ElementEventType::Leave,
+                                                              &name, mod_state, pos)),
+                    None => {}
+                }
+            }
+        }
+        for e in new_hovered.iter() {
+            if !old_hovered.iter().any(|x| elems_are_same(x, e)) {
+                match e.event_bindings().and_then(|b| b.on_hover.clone()) {
+                    Some(name) => events.push(self.make_event(e, ElementEventType::Enter,
+                                                              &name, mod_state, pos)),
+                    None => {}
+                }
+            }
+        }
+        events
+    }
+
+    fn emit(&self, events: Vec<ElementEvent>) {
+        for event in events.iter() {
+            self.listener.on_element_event(event);
+        }
+    }
+}
+
+impl TPointerInteractor for ElementEventDispatcher {
+    fn on_pointer_event(&self, event: &PointerEvent) {
+        let events = match event.event_type() {
+            PointerEventType::Motion | PointerEventType::Enter | PointerEventType::Leave =>
+                self.update_hover(event.mod_state(), event.position()),
+            PointerEventType::Press => {
+                let mut events = self.update_hover(event.mod_state(), event.position());
+                // The innermost element with a click binding receives the click
+                let target = self.m.borrow().hovered.iter().rev().filter_map(
+                    |e| e.event_bindings().and_then(|b| b.on_click.clone()).map(
+                        |name| (e.clone(), name))).next();
+                match target {
+                    Some((e, name)) => {
+                        let mut ev = self.make_event(&e, ElementEventType::Click, &name,
+                                                     event.mod_state(), event.position());
+                        ev.button = event.button();
+                        events.push(ev);
+                    },
+                    None => {}
+                }
+                events
+            },
+            PointerEventType::Release => vec![]
+        };
+        self.emit(events);
+    }
+}
+
+impl TKeyboardInteractor for ElementEventDispatcher {
+    fn on_key_event(&self, event: &KeyEvent) {
+        if event.event_type() != KeyEventType::Press {
+            return;
+        }
+        // The elements under the pointer may have changed since it last moved, e.g. if the
+        // content has been replaced, so hit test again
+        let pos = self.m.borrow().pointer_pos;
+        let mut events = self.update_hover(event.mod_state(), pos);
+        // The innermost element under the pointer with a key binding receives the key press
+        let target = self.m.borrow().hovered.iter().rev().filter_map(
+            |e| e.event_bindings().and_then(|b| b.on_key.clone()).map(
+                |name| (e.clone(), name))).next();
+        match target {
+            Some((e, name)) => {
+                let mut ev = self.make_event(&e, ElementEventType::KeyPress, &name,
+                                             event.mod_state(), pos);
+                ev.key_val = event.key_val();
+                ev.key_string = event.key_string().clone();
+                events.push(ev);
+            },
+            None => {}
+        }
+        self.emit(events);
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use layout::lalloc::LAlloc;
+    use layout::lreq::LReq;
+    use graphics::border::Border;
+    use elements::element::{TElement, elem_as_ref};
+    use elements::column::ColumnElement;
+    use elements::border_element::BorderElement;
+    use elements::event_binding::EventBindingElement;
+
+    struct Recorder {
+        events: RefCell<Vec<(ElementEventType, String)>>,
+    }
+
+    impl TElementEventListener for Recorder {
+        fn on_element_event(&self, event: &ElementEvent) {
+            self.events.borrow_mut().push((event.event_type, event.name.clone()));
+        }
+    }
+
+    impl Recorder {
+        fn take(&self) -> Vec<(ElementEventType, String)> {
+            self.events.borrow_mut().drain(..).collect()
+        }
+    }
+
+    fn bound(child: ElementRef, prefix: &str, on_key: bool) -> ElementRef {
+        let bindings = EventBindings{on_click: Some(format!("{}_click", prefix)),
+                                     on_hover: Some(prefix.to_string()),
+                                     on_key: if on_key {Some(format!("{}_key", prefix))}
+                                             else {None}};
+        let elem = elem_as_ref(EventBindingElement::new(bindings));
+        elem.as_bin().unwrap().set_child(&elem, child);
+        elem
+    }
+
+    /// An empty border element, given a fixed requisition of 10 by 10
+    fn block() -> ElementRef {
+        let border = Rc::new(Border::new_filled(0.0, 0.0, 0.0, 0.0, 0.0, None));
+        let elem = elem_as_ref(BorderElement::new(&border));
+        elem.element_update_x_req(&LReq::new_fixed_size(10.0));
+        elem.element_update_y_req(&LReq::new_fixed_size(10.0));
+        elem
+    }
+
+    fn update_x_reqs(elem: &ElementRef) {
+        match elem.as_container() {
+            Some(c) => {
+                let children = c.children().to_vec();
+                for child in children.iter() {
+                    update_x_reqs(child);
+                }
+                elem.element_update_x_req(&c.compute_x_req());
+            },
+            None => {}
+        }
+    }
+
+    fn update_y_reqs(elem: &ElementRef) {
+        match elem.as_container() {
+            Some(c) => {
+                let children = c.children().to_vec();
+                for child in children.iter() {
+                    update_y_reqs(child);
+                }
+                elem.element_update_y_req(&c.compute_y_req());
+            },
+            None => {}
+        }
+    }
+
+    /// An element bound to `outer` containing a column of an element bound to `inner`, at
+    /// y = 0 to 10, above an unbound element, at y = 10 to 20; both are 10 wide
+    fn content() -> ElementRef {
+        let column = elem_as_ref(ColumnElement::new(0.0));
+        column.as_container_sequence().unwrap().set_children(
+            &column, &vec![bound(block(), "inner", false), block()]);
+        let outer = bound(column, "outer", true);
+        update_x_reqs(&outer);
+        let x_req = outer.element_req().x_req.clone();
+        outer.allocate_x(&LAlloc::new_from_req(&x_req, 0.0));
+        update_y_reqs(&outer);
+        let y_req = outer.element_req().y_req.clone();
+        outer.allocate_y(&LAlloc::new_from_req(&y_req, 0.0));
+        outer
+    }
+
+    fn dispatcher() -> (Rc<LSpaceArea>, Rc<Recorder>, Rc<ElementEventDispatcher>) {
+        let area = Rc::new(LSpaceArea::new());
+        area.set_content_element(content());
+        let recorder = Rc::new(Recorder{events: RefCell::new(vec![])});
+        let listener: Rc<TElementEventListener> = recorder.clone();
+        let dispatcher = ElementEventDispatcher::attach(&area, &listener);
+        (area, recorder, dispatcher)
+    }
+
+    fn pointer(d: &ElementEventDispatcher, event_type: PointerEventType, pos: Option<Point2>) {
+        d.on_pointer_event(&PointerEvent::new(event_type, InputModifierState::new(), pos, 1));
+    }
+
+    fn key(d: &ElementEventDispatcher) {
+        d.on_key_event(&KeyEvent::new_press(InputModifierState::new(), 'a' as u32,
+                                            "a".to_string()));
+    }
+
+    fn event(event_type: ElementEventType, name: &str) -> (ElementEventType, String) {
+        (event_type, name.to_string())
+    }
+
+    #[test]
+    fn test_enter_leave_order() {
+        let (_area, recorder, d) = dispatcher();
+        // Entered outermost first
+        pointer(&d, PointerEventType::Motion, Some(Point2::new(5.0, 5.0)));
+        assert_eq!(recorder.take(), vec![event(ElementEventType::Enter, "outer"),
+                                         event(ElementEventType::Enter, "inner")]);
+        pointer(&d, PointerEventType::Motion, Some(Point2::new(5.0, 15.0)));
+        assert_eq!(recorder.take(), vec![event(ElementEventType::Leave, "inner")]);
+        pointer(&d, PointerEventType::Motion, Some(Point2::new(6.0, 15.0)));
+        assert_eq!(recorder.take(), vec![]);
+
+        // Left innermost first
+        pointer(&d, PointerEventType::Motion, Some(Point2::new(5.0, 5.0)));
+        recorder.take();
+        pointer(&d, PointerEventType::Leave, None);
+        assert_eq!(recorder.take(), vec![event(ElementEventType::Leave, "inner"),
+                                         event(ElementEventType::Leave, "outer")]);
+    }
+
+    #[test]
+    fn test_click_target() {
+        let (_area, recorder, d) = dispatcher();
+        // The innermost element with a click binding receives the click
+        pointer(&d, PointerEventType::Press, Some(Point2::new(5.0, 5.0)));
+        assert_eq!(recorder.take().pop(), Some(event(ElementEventType::Click, "inner_click")));
+        pointer(&d, PointerEventType::Release, Some(Point2::new(5.0, 5.0)));
+        assert_eq!(recorder.take(), vec![]);
+        pointer(&d, PointerEventType::Press, Some(Point2::new(5.0, 15.0)));
+        assert_eq!(recorder.take().pop(), Some(event(ElementEventType::Click, "outer_click")));
+        pointer(&d, PointerEventType::Press, Some(Point2::new(50.0, 50.0)));
+        assert_eq!(recorder.take(), vec![event(ElementEventType::Leave, "outer")]);
+    }
+
+    #[test]
+    fn test_key_target() {
+        let (area, recorder, d) = dispatcher();
+        // Nothing is under the pointer
+        key(&d);
+        assert_eq!(recorder.take(), vec![]);
+
+        // The inner element has no key binding, so the outer element receives the key press
+        pointer(&d, PointerEventType::Motion, Some(Point2::new(5.0, 5.0)));
+        recorder.take();
+        key(&d);
+        assert_eq!(recorder.take(), vec![event(ElementEventType::KeyPress, "outer_key")]);
+
+        // Once the content is replaced, the key press goes to the new element under the
+        // pointer; the detached elements are not reported as left
+        area.set_content_element(content());
+        key(&d);
+        assert_eq!(recorder.take(), vec![event(ElementEventType::Enter, "outer"),
+                                         event(ElementEventType::Enter, "inner"),
+                                         event(ElementEventType::KeyPress, "outer_key")]);
+    }
+}
@@ -0,0 +1,322 @@
+use std::cell::RefCell;
+
+use geom::point2::Point2;
+use input::keyboard::KeyEvent;
+use input::pointer_events::{PointerEvent, PointerEventType};
+use elements::element::{ElementRef, elems_are_same, elem_is_in_tree};
+use elements::hit_test::elem_path_at_point;
+
+
+/// The visual state of an interactive element, as shown to the user
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum VisualState {
+    Normal,
+    Hovered,
+    Pressed,
+    Focused,
+    Disabled,
+}
+
+/// Interaction state of an element, from which its `VisualState` is derived
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InteractionFlags {
+    pub hovered: bool,
+    pub pressed: bool,
+    pub focused: bool,
+    pub disabled: bool,
+}
+
+impl InteractionFlags {
+    pub fn new() -> InteractionFlags {
+        InteractionFlags{hovered: false, pressed: false, focused: false, disabled: false}
+    }
+
+    /// The state to show. Disabled takes precedence over everything else; an element only
+    /// looks pressed while the pointer is over it, and hovering takes precedence over focus.
+    pub fn visual_state(&self) -> VisualState {
+        if self.disabled {
+            VisualState::Disabled
+        } else if self.pressed && self.hovered {
+            VisualState::Pressed
+        } else if self.hovered {
+            VisualState::Hovered
+        } else if self.focused {
+            VisualState::Focused
+        } else {
+            VisualState::Normal
+        }
+    }
+}
+
+
+/// Elements that react to the pointer and keyboard; acquired through
+/// `TElement::as_interactive`
+pub trait TInteractiveElement {
+    /// Disabled elements do not receive presses, releases, key events or the focus
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    /// True if the element accepts the keyboard focus when pressed
+    fn can_focus(&self) -> bool {
+        false
+    }
+
+    /// The pointer has entered (`hovered` is true) or left the element
+    fn on_hover_change(&self, hovered: bool) {
+    }
+
+    /// A button was pressed over the element. Return true to capture the pointer, so that
+    /// the element receives the matching release.
+    fn on_press(&self, event: &PointerEvent) -> bool {
+        false
+    }
+
+    /// The pointer moved while the element has captured it, e.g. to drag a selection
+    fn on_pointer_motion(&self, event: &PointerEvent) {
+    }
+
+    /// The button that was pressed over the element was released; `inside` is true if the
+    /// pointer is still over the element, in which case the press and release form a click.
+    fn on_release(&self, event: &PointerEvent, inside: bool) {
+    }
+
+    /// The element gained (`focused` is true) or lost the keyboard focus
+    fn on_focus_change(&self, focused: bool) {
+    }
+
+    /// A key event while the element has the focus. Return true if the event was handled.
+    fn on_key_event(&self, event: &KeyEvent) -> bool {
+        false
+    }
+
+    /// The scroll wheel moved by `scroll_x` and `scroll_y` steps while the pointer is over the
+    /// element. Return true if the element scrolled.
+    fn on_scroll(&self, scroll_x: f64, scroll_y: f64) -> bool {
+        false
+    }
+}
+
+fn is_enabled(elem: &ElementRef) -> bool {
+    elem.as_interactive().map_or(false, |i| i.is_enabled())
+}
+
+fn contains(elems: &Vec<ElementRef>, elem: &ElementRef) -> bool {
+    elems.iter().any(|x| elems_are_same(x, elem))
+}
+
+
+struct ElementInteractionMut {
+    /// Interactive elements under the pointer, outermost first
+    hovered: Vec<ElementRef>,
+    /// Element that captured the pointer when a button was pressed
+    pressed: Option<ElementRef>,
+    focused: Option<ElementRef>,
+}
+
+/// Tracks the interactive elements of an `LSpaceArea` that are hovered, pressed and focused,
+/// notifying them as their state changes
+pub struct ElementInteraction {
+    m: RefCell<ElementInteractionMut>,
+}
+
+impl ElementInteraction {
+    pub fn new() -> ElementInteraction {
+        ElementInteraction{m: RefCell::new(ElementInteractionMut{
+            hovered: vec![], pressed: None, focused: None})}
+    }
+
+    pub fn focused(&self) -> Option<ElementRef> {
+        self.m.borrow().focused.clone()
+    }
+
+    /// Move the keyboard focus to `elem`, or clear it by passing `None`
+    pub fn set_focus(&self, elem: Option<&ElementRef>) {
+        let old = {
+            let mut mm = self.m.borrow_mut();
+            let unchanged = match (&mm.focused, elem) {
+                (&Some(ref a), Some(b)) => elems_are_same(a, b),
+                (&None, None) => true,
+                _ => false
+            };
+            if unchanged {
+                return;
+            }
+            let old = mm.focused.take();
+            mm.focused = elem.map(|e| e.clone());
+            old
+        };
+        // Notify outside the borrow, as the elements may query the focus
+        match old {
+            Some(ref e) => e.as_interactive().map(|i| i.on_focus_change(false)),
+            None => None
+        };
+        match elem {
+            Some(e) => e.as_interactive().map(|i| i.on_focus_change(true)),
+            None => None
+        };
+    }
+
+    /// Forget the hovered, pressed and focused elements that are no longer in the tree rooted
+    /// at `root`, e.g. after the content has been replaced. Hovered and focused elements are
+    /// notified that they have lost the pointer and the focus.
+    pub fn remove_detached(&self, root: &ElementRef) {
+        let (unhovered, unfocused) = {
+            let mut mm = self.m.borrow_mut();
+            let (hovered, unhovered): (Vec<ElementRef>, Vec<ElementRef>) =
+                mm.hovered.drain(..).partition(|e| elem_is_in_tree(e, root));
+            mm.hovered = hovered;
+            if mm.pressed.as_ref().map_or(false, |e| !elem_is_in_tree(e, root)) {
+                mm.pressed = None;
+            }
+            let unfocused = if mm.focused.as_ref().map_or(false, |e| !elem_is_in_tree(e, root)) {
+                mm.focused.take()
+            } else {
+                None
+            };
+            (unhovered, unfocused)
+        };
+        // Notify outside the borrow, as the elements may query the focus
+        for e in unhovered.iter().rev() {
+            e.as_interactive().map(|i| i.on_hover_change(false));
+        }
+        match unfocused {
+            Some(ref e) => e.as_interactive().map(|i| i.on_focus_change(false)),
+            None => None
+        };
+    }
+
+    /// Update the hovered elements for the pointer moving to `pos`, which is in the
+    /// co-ordinate space of `root`
+    fn update_hover(&self, root: &ElementRef, pos: Option<Point2>) {
+        let new_hovered: Vec<ElementRef> = match pos {
+            Some(ref p) => elem_path_at_point(root, p).into_iter().filter(
+                |e| e.as_interactive().is_some()).collect(),
+            None => vec![]
+        };
+        let old_hovered = {
+            let mut mm = self.m.borrow_mut();
+            let old = mm.hovered.clone();
+            mm.hovered = new_hovered.clone();
+            old
+        };
+        for e in old_hovered.iter().rev() {
+            if !contains(&new_hovered, e) {
+                e.as_interactive().map(|i| i.on_hover_change(false));
+            }
+        }
+        for e in new_hovered.iter() {
+            if !contains(&old_hovered, e) {
+                e.as_interactive().map(|i| i.on_hover_change(true));
+            }
+        }
+    }
+
+    /// Handle a pointer event whose position is in the co-ordinate space of `root`
+    pub fn on_pointer_event(&self, root: &ElementRef, event: &PointerEvent) {
+        self.update_hover(root, event.position());
+        match event.event_type() {
+            PointerEventType::Press => {
+                let hovered = self.m.borrow().hovered.clone();
+                // The innermost enabled element that accepts the press captures the pointer
+                let pressed = hovered.iter().rev().filter(|e| is_enabled(e)).filter(
+                    |e| e.as_interactive().unwrap().on_press(event)).next().cloned();
+                if pressed.is_some() {
+                    self.m.borrow_mut().pressed = pressed;
+                }
+                // Pressing moves the focus to the innermost focusable element, or clears it
+                let focus = hovered.iter().rev().filter(
+                    |e| is_enabled(e) && e.as_interactive().unwrap().can_focus()).next().cloned();
+                self.set_focus(focus.as_ref());
+            },
+            PointerEventType::Release => {
+                let pressed = self.m.borrow_mut().pressed.take();
+                match pressed {
+                    Some(ref e) => {
+                        let inside = contains(&self.m.borrow().hovered, e);
+                        e.as_interactive().unwrap().on_release(event, inside);
+                    },
+                    None => {}
+                }
+            },
+            PointerEventType::Motion => {
+                let pressed = self.m.borrow().pressed.clone();
+                match pressed {
+                    Some(ref e) => e.as_interactive().unwrap().on_pointer_motion(event),
+                    None => {}
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Pass a scroll to the innermost enabled element under `pos` that scrolls, where `pos` is
+    /// in the co-ordinate space of `root`. Returns true if an element scrolled.
+    pub fn on_scroll(&self, root: &ElementRef, pos: Point2, scroll_x: f64,
+                     scroll_y: f64) -> bool {
+        self.update_hover(root, Some(pos));
+        let hovered = self.m.borrow().hovered.clone();
+        hovered.iter().rev().filter(|e| is_enabled(e)).any(
+            |e| e.as_interactive().unwrap().on_scroll(scroll_x, scroll_y))
+    }
+
+    /// Pass a key event to the focused element. Returns true if it was handled.
+    pub fn on_key_event(&self, event: &KeyEvent) -> bool {
+        let focused = self.focused();
+        match focused {
+            Some(ref e) if is_enabled(e) => e.as_interactive().unwrap().on_key_event(event),
+            _ => false
+        }
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use graphics::border::{Border, StateBorders};
+    use elements::element::elem_as_ref;
+    use elements::root_element::RootElement;
+    use elements::state_border_element::StateBorderElement;
+
+    #[test]
+    fn test_visual_state() {
+        let mut f = InteractionFlags::new();
+        assert_eq!(f.visual_state(), VisualState::Normal);
+        f.focused = true;
+        assert_eq!(f.visual_state(), VisualState::Focused);
+        f.hovered = true;
+        assert_eq!(f.visual_state(), VisualState::Hovered);
+        f.pressed = true;
+        assert_eq!(f.visual_state(), VisualState::Pressed);
+        f.hovered = false;
+        assert_eq!(f.visual_state(), VisualState::Focused);
+        f.disabled = true;
+        assert_eq!(f.visual_state(), VisualState::Disabled);
+    }
+
+    #[test]
+    fn test_remove_detached() {
+        let border = Rc::new(Border::new_filled(0.0, 0.0, 0.0, 0.0, 0.0, None));
+        let elem = elem_as_ref(StateBorderElement::new(&Rc::new(StateBorders::new(border)), true));
+        let root = elem_as_ref(RootElement::new());
+        root.as_bin().unwrap().set_child(&root, elem.clone());
+
+        let interaction = ElementInteraction::new();
+        interaction.set_focus(Some(&elem));
+        interaction.remove_detached(&root);
+        assert!(interaction.focused().is_some());
+
+        // Once the element is removed from the tree, it loses the focus
+        root.as_bin().unwrap().clear_child();
+        interaction.remove_detached(&root);
+        assert!(interaction.focused().is_none());
+        let flags = elem.as_any().downcast_ref::<StateBorderElement>().unwrap().interaction_flags();
+        assert!(!flags.focused);
+    }
+}
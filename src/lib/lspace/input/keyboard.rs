@@ -4,6 +4,25 @@ use std::rc::Rc;
 use input::inputmodifier::InputModifierState;
 
 
+/// Key values (GDK key symbols) of keys that the library handles itself
+pub const KEY_SPACE: u32 = 0x0020;
+pub const KEY_RETURN: u32 = 0xff0d;
+pub const KEY_KP_ENTER: u32 = 0xff8d;
+pub const KEY_BACKSPACE: u32 = 0xff08;
+pub const KEY_TAB: u32 = 0xff09;
+pub const KEY_ESCAPE: u32 = 0xff1b;
+pub const KEY_HOME: u32 = 0xff50;
+pub const KEY_LEFT: u32 = 0xff51;
+pub const KEY_UP: u32 = 0xff52;
+pub const KEY_RIGHT: u32 = 0xff53;
+pub const KEY_DOWN: u32 = 0xff54;
+pub const KEY_PAGE_UP: u32 = 0xff55;
+pub const KEY_PAGE_DOWN: u32 = 0xff56;
+pub const KEY_END: u32 = 0xff57;
+pub const KEY_DELETE: u32 = 0xffff;
+pub const KEY_LOWER_A: u32 = 0x0061;
+pub const KEY_UPPER_A: u32 = 0x0041;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum KeyEventType {
     Press,
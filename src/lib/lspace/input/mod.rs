@@ -1,3 +1,6 @@
 pub mod inputmodifier;
 pub mod pointer;
 pub mod keyboard;
+pub mod pointer_events;
+pub mod element_events;
+pub mod interactive;
@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use geom::point2::Point2;
+use input::inputmodifier::InputModifierState;
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PointerEventType {
+    Press,
+    Release,
+    Motion,
+    Enter,
+    Leave,
+}
+
+#[derive(Debug)]
+pub struct PointerEvent {
+    mod_state: InputModifierState,
+    position: Option<Point2>,
+    button: u32,
+    event_type: PointerEventType
+}
+
+impl PointerEvent {
+    pub fn new(event_type: PointerEventType, mod_state: InputModifierState,
+               position: Option<Point2>, button: u32) -> PointerEvent {
+        PointerEvent{mod_state: mod_state, position: position, button: button,
+            event_type: event_type}
+    }
+
+    pub fn mod_state(&self) -> InputModifierState {
+        self.mod_state
+    }
+
+    /// Pointer position in the co-ordinate space of the root element; `None` when the pointer
+    /// has left the area
+    pub fn position(&self) -> Option<Point2> {
+        self.position
+    }
+
+    /// Button pressed or released; 0 for motion, enter and leave events
+    pub fn button(&self) -> u32 {
+        self.button
+    }
+
+    pub fn event_type(&self) -> PointerEventType {
+        self.event_type
+    }
+}
+
+
+pub trait TPointerInteractor {
+    fn on_pointer_event(&self, event: &PointerEvent);
+}
+
+fn are_same(a: &Rc<TPointerInteractor>, b: &Rc<TPointerInteractor>) -> bool {
+    let aptr: &TPointerInteractor = &**a;
+    let bptr: &TPointerInteractor = &**b;
+    return (aptr as *const TPointerInteractor) == (bptr as *const TPointerInteractor);
+}
+
+
+struct PointerEventsMut {
+    interactors: Vec<Rc<TPointerInteractor>>
+}
+
+impl PointerEventsMut {
+    fn new() -> PointerEventsMut {
+        PointerEventsMut{interactors: Vec::new()}
+    }
+
+    fn add_interactor(&mut self, interactor: &Rc<TPointerInteractor>) {
+        self.interactors.push(interactor.clone());
+    }
+
+    fn remove_interactor(&mut self, interactor: &Rc<TPointerInteractor>) {
+        let mut index_to_remove: Option<usize> = None;
+        for ndx_item in self.interactors.iter().enumerate() {
+            if are_same(&ndx_item.1, interactor) {
+                index_to_remove = Some(ndx_item.0);
+                break;
+            }
+        }
+        match index_to_remove {
+            Some(i) => {self.interactors.remove(i);},
+            None => {}
+        }
+    }
+}
+
+
+/// Dispatches pointer events to registered interactors
+pub struct PointerEvents {
+    m: RefCell<PointerEventsMut>
+}
+
+impl PointerEvents {
+    pub fn new() -> PointerEvents {
+        return PointerEvents{
+            m: RefCell::new(PointerEventsMut::new())
+        };
+    }
+
+    pub fn dispatch(&self, event: &PointerEvent) {
+        // Take a copy of the interactor list so that interactors may add or remove
+        // interactors in response to an event
+        let interactors = self.m.borrow().interactors.clone();
+        for interactor in interactors.iter() {
+            interactor.on_pointer_event(event);
+        }
+    }
+
+    pub fn add_interactor(&self, interactor: &Rc<TPointerInteractor>) {
+        self.m.borrow_mut().add_interactor(interactor);
+    }
+
+    pub fn remove_interactor(&self, interactor: &Rc<TPointerInteractor>) {
+        self.m.borrow_mut().remove_interactor(interactor);
+    }
+}
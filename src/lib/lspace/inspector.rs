@@ -0,0 +1,410 @@
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+
+use cairo::Context;
+use gtk;
+use gtk::traits::*;
+use gtk::signal::Inhibit;
+
+use input::pointer_events::{PointerEvent, PointerEventType, TPointerInteractor};
+use elements::element::{ElementRef, elems_are_same, elem_root_space_bbox, elem_ancestors,
+                        elem_is_in_tree};
+use elements::element_dump::{describe_req, describe_alloc};
+use elements::hit_test::elem_at_point;
+use elements::text_element::{TextWeight, TextSlant};
+use pres::pres::Pres;
+use pres::primitive::{Text, Column};
+use pres::style::{StyleValues, ApplyStyleSheet};
+use lspace_area::{LSpaceArea, TOverlayPainter};
+use lspace_widget::LSpaceWidget;
+
+
+fn heading(text: &str) -> Pres {
+    ApplyStyleSheet::new(Text::new_inherited(String::from(text)),
+                         StyleValues{text_weight: Some(TextWeight::Bold), ..StyleValues::new()})
+}
+
+fn line(text: String) -> Pres {
+    Text::new_inherited(text)
+}
+
+/// Build the inspector panel content describing `elem`
+fn panel_pres(elem: Option<&ElementRef>) -> Pres {
+    let mut lines: Vec<Pres> = Vec::new();
+    match elem {
+        None => {
+            lines.push(line(String::from("Click an element to inspect it")));
+        },
+        Some(e) => {
+            lines.push(heading("Element"));
+            lines.push(line(String::from(e.element_type_name())));
+
+            lines.push(heading("Ancestry"));
+            let ancestors = elem_ancestors(&**e);
+            for (i, a) in ancestors.iter().enumerate() {
+                let indent: String = (0..i).map(|_| "  ").collect();
+                lines.push(line(format!("{}{}", indent, a.element_type_name())));
+            }
+            let indent: String = (0..ancestors.len()).map(|_| "  ").collect();
+            lines.push(line(format!("{}{} (selected)", indent, e.element_type_name())));
+
+            lines.push(heading("Layout"));
+            {
+                let req = e.element_req();
+                lines.push(line(format!("x req: {}", describe_req(&req.x_req))));
+                lines.push(line(format!("y req: {}", describe_req(&req.y_req))));
+            }
+            {
+                let alloc = e.element_alloc();
+                lines.push(line(format!("x alloc: {}", describe_alloc(&alloc.x_alloc))));
+                lines.push(line(format!("y alloc: {}", describe_alloc(&alloc.y_alloc))));
+            }
+
+            match e.as_text_element() {
+                Some(t) => {
+                    let style = t.get_style();
+                    lines.push(heading("Text"));
+                    lines.push(line(format!("text: {:?}", *t.get_text())));
+                    lines.push(line(format!("font: {}, {}, {}, {}", style.font_family,
+                        match style.weight {TextWeight::Normal => "normal",
+                                            TextWeight::Bold => "bold"},
+                        match style.slant {TextSlant::Normal => "normal",
+                                           TextSlant::Italic => "italic"},
+                        style.size)));
+                    lines.push(line(format!("colour: rgba({}, {}, {}, {})", style.colour.r,
+                                            style.colour.g, style.colour.b, style.colour.a)));
+                },
+                None => {}
+            }
+        }
+    }
+    ApplyStyleSheet::new(Column::new(lines),
+                         StyleValues{text_size: Some(12.0), column_y_spacing: Some(2.0),
+                                     ..StyleValues::new()})
+}
+
+
+struct InspectorMut {
+    hovered: Option<ElementRef>,
+    selected: Option<ElementRef>,
+}
+
+/// Which of the hovered and selected elements changed
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct InspectorChange {
+    hovered: bool,
+    selected: bool,
+}
+
+impl InspectorChange {
+    fn none() -> InspectorChange {
+        InspectorChange{hovered: false, selected: false}
+    }
+
+    fn or(&self, other: InspectorChange) -> InspectorChange {
+        InspectorChange{hovered: self.hovered || other.hovered,
+                        selected: self.selected || other.selected}
+    }
+}
+
+fn same_elem(a: &Option<ElementRef>, b: &Option<ElementRef>) -> bool {
+    match (a, b) {
+        (&None, &None) => true,
+        (&Some(ref a), &Some(ref b)) => elems_are_same(a, b),
+        _ => false
+    }
+}
+
+impl InspectorMut {
+    fn new() -> InspectorMut {
+        InspectorMut{hovered: None, selected: None}
+    }
+
+    fn set_hovered(&mut self, elem: Option<ElementRef>) -> InspectorChange {
+        let changed = !same_elem(&self.hovered, &elem);
+        self.hovered = elem;
+        InspectorChange{hovered: changed, selected: false}
+    }
+
+    fn set_selected(&mut self, elem: Option<ElementRef>) -> InspectorChange {
+        let changed = !same_elem(&self.selected, &elem);
+        self.selected = elem;
+        InspectorChange{hovered: false, selected: changed}
+    }
+
+    /// Forget the hovered and selected elements if they are no longer in the tree rooted at
+    /// `root`, e.g. because the content has been replaced
+    fn remove_detached(&mut self, root: &ElementRef) -> InspectorChange {
+        let in_tree = |e: &Option<ElementRef>| e.as_ref().map_or(true,
+                                                                 |e| elem_is_in_tree(e, root));
+        let hovered = if in_tree(&self.hovered) {InspectorChange::none()}
+                      else {self.set_hovered(None)};
+        let selected = if in_tree(&self.selected) {InspectorChange::none()}
+                       else {self.set_selected(None)};
+        hovered.or(selected)
+    }
+
+    /// Hover over and select elements in the tree rooted at `root` in response to a pointer
+    /// event, whose position is in the co-ordinate space of `root`
+    fn on_pointer_event(&mut self, root: &ElementRef, event: &PointerEvent) -> InspectorChange {
+        let change = self.remove_detached(root);
+        let elem_under_pointer = || event.position().and_then(|p| elem_at_point(root, &p));
+        change.or(match event.event_type() {
+            PointerEventType::Motion | PointerEventType::Enter => {
+                self.set_hovered(elem_under_pointer())
+            },
+            PointerEventType::Leave => self.set_hovered(None),
+            PointerEventType::Press if event.button() == 1 => {
+                self.set_selected(elem_under_pointer())
+            },
+            _ => InspectorChange::none()
+        })
+    }
+}
+
+/// Element inspector; highlights the element under the pointer in the target widget and
+/// describes the element selected by clicking in a separate panel window. The selection is
+/// dropped when the selected element is removed from the tree.
+pub struct Inspector {
+    m: RefCell<InspectorMut>,
+    target: Weak<LSpaceArea>,
+    panel_area: Rc<LSpaceArea>,
+    panel: Rc<LSpaceWidget>,
+    window: gtk::Window,
+}
+
+impl Inspector {
+    /// Attach an inspector to `widget`, opening the inspector panel window. Returns `None` if
+    /// the window could not be created.
+    pub fn attach(widget: &Rc<LSpaceWidget>) -> Option<Rc<Inspector>> {
+        let window = match gtk::Window::new(gtk::WindowType::Toplevel) {
+            Some(w) => w,
+            None => {return None;}
+        };
+        let target = widget.area();
+
+        let panel_area = Rc::new(LSpaceArea::new());
+        panel_area.set_content_pres(panel_pres(None));
+        let panel = LSpaceWidget::new_with_area(panel_area.clone());
+
+        window.set_title("L-Space inspector");
+        window.add(&*panel.gtk_widget());
+        window.set_default_size(400, 600);
+
+        let inspector = Rc::new(Inspector{
+            m: RefCell::new(InspectorMut::new()),
+            target: Rc::downgrade(&target),
+            panel_area: panel_area,
+            panel: panel,
+            window: window,
+        });
+
+        {
+            let weak_inspector = Rc::downgrade(&inspector);
+            inspector.window.connect_delete_event(move |_, _| {
+                match weak_inspector.upgrade() {
+                    Some(i) => Inspector::detach(&i),
+                    None => {}
+                }
+                Inhibit(true)
+            });
+        }
+
+        let interactor: Rc<TPointerInteractor> = inspector.clone();
+        target.pointer_events().add_interactor(&interactor);
+        let painter: Rc<TOverlayPainter> = inspector.clone();
+        target.add_overlay_painter(&painter);
+
+        inspector.window.show_all();
+        Some(inspector)
+    }
+
+    /// Detach the inspector from its target and close the panel window
+    pub fn detach(inspector: &Rc<Inspector>) {
+        match inspector.target.upgrade() {
+            Some(target) => {
+                let interactor: Rc<TPointerInteractor> = inspector.clone();
+                target.pointer_events().remove_interactor(&interactor);
+                let painter: Rc<TOverlayPainter> = inspector.clone();
+                target.remove_overlay_painter(&painter);
+            },
+            None => {}
+        }
+        inspector.window.hide();
+    }
+
+    /// The currently selected element
+    pub fn selected(&self) -> Option<ElementRef> {
+        self.m.borrow().selected.clone()
+    }
+
+    /// Select `elem` and describe it in the panel
+    pub fn select(&self, elem: Option<ElementRef>) {
+        let change = self.m.borrow_mut().set_selected(elem);
+        self.apply_change(change, true);
+    }
+
+    /// Update the panel if the selection changed, and redraw the target if requested
+    fn apply_change(&self, change: InspectorChange, redraw_target: bool) {
+        if change.selected {
+            let selected = self.selected();
+            self.panel_area.update_content_pres(panel_pres(selected.as_ref()));
+            self.panel_area.queue_redraw();
+        }
+        if redraw_target && (change.hovered || change.selected) {
+            match self.target.upgrade() {
+                Some(target) => target.queue_redraw(),
+                None => {}
+            }
+        }
+    }
+}
+
+impl TPointerInteractor for Inspector {
+    fn on_pointer_event(&self, event: &PointerEvent) {
+        let root = match self.target.upgrade() {
+            Some(target) => target.root_element(),
+            None => {return;}
+        };
+        let change = self.m.borrow_mut().on_pointer_event(&root, event);
+        self.apply_change(change, true);
+    }
+}
+
+impl TOverlayPainter for Inspector {
+    fn paint_overlay(&self, cairo_ctx: &Context) {
+        // The content may have been replaced since the last pointer event; the target is
+        // being drawn, so it need not be redrawn
+        match self.target.upgrade() {
+            Some(target) => {
+                let change = self.m.borrow_mut().remove_detached(&target.root_element());
+                self.apply_change(change, false);
+            },
+            None => {}
+        }
+        let mm = self.m.borrow();
+        match mm.hovered {
+            Some(ref h) => {
+                let bbox = elem_root_space_bbox(&**h);
+                let size = bbox.size();
+                cairo_ctx.rectangle(bbox.lower.x, bbox.lower.y, size.x, size.y);
+                cairo_ctx.set_source_rgba(0.2, 0.5, 1.0, 0.25);
+                cairo_ctx.fill();
+            },
+            None => {}
+        }
+        match mm.selected {
+            Some(ref s) => {
+                let bbox = elem_root_space_bbox(&**s);
+                let size = bbox.size();
+                cairo_ctx.rectangle(bbox.lower.x + 1.0, bbox.lower.y + 1.0,
+                                    size.x - 2.0, size.y - 2.0);
+                cairo_ctx.set_source_rgba(0.9, 0.1, 0.1, 0.9);
+                cairo_ctx.set_line_width(2.0);
+                cairo_ctx.stroke();
+            },
+            None => {}
+        }
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::point2::Point2;
+    use layout::lalloc::LAlloc;
+    use input::inputmodifier::InputModifierState;
+    use layout::lreq::LReq;
+    use graphics::border::Border;
+    use elements::element::{TElement, elem_as_ref};
+    use elements::root_element::RootElement;
+    use elements::column::ColumnElement;
+    use elements::border_element::BorderElement;
+
+    /// An empty border element, given a fixed requisition of 10 by 10
+    fn block() -> ElementRef {
+        let border = Rc::new(Border::new_filled(0.0, 0.0, 0.0, 0.0, 0.0, None));
+        let elem = elem_as_ref(BorderElement::new(&border));
+        elem.element_update_x_req(&LReq::new_fixed_size(10.0));
+        elem.element_update_y_req(&LReq::new_fixed_size(10.0));
+        elem
+    }
+
+    /// A laid out column of `a`, at y = 0 to 10, above `b`
+    fn column_of(a: &ElementRef, b: &ElementRef) -> ElementRef {
+        let column = elem_as_ref(ColumnElement::new(0.0));
+        column.as_container_sequence().unwrap().set_children(&column, &vec![a.clone(), b.clone()]);
+        let x_req = column.as_container().unwrap().compute_x_req();
+        column.element_update_x_req(&x_req);
+        column.allocate_x(&LAlloc::new_from_req(&x_req, 0.0));
+        let y_req = column.as_container().unwrap().compute_y_req();
+        column.element_update_y_req(&y_req);
+        column.allocate_y(&LAlloc::new_from_req(&y_req, 0.0));
+        column
+    }
+
+    fn root_with(a: &ElementRef, b: &ElementRef) -> ElementRef {
+        let root = elem_as_ref(RootElement::new());
+        root.as_bin().unwrap().set_child(&root, column_of(a, b));
+        root
+    }
+
+    fn event(event_type: PointerEventType, pos: Option<Point2>, button: u32) -> PointerEvent {
+        PointerEvent::new(event_type, InputModifierState::new(), pos, button)
+    }
+
+    fn is(elem: &Option<ElementRef>, expected: &ElementRef) -> bool {
+        elem.as_ref().map_or(false, |e| elems_are_same(e, expected))
+    }
+
+    #[test]
+    fn test_hover_and_select() {
+        let (a, b) = (block(), block());
+        let root = root_with(&a, &b);
+        let mut m = InspectorMut::new();
+
+        // The innermost element under the pointer is hovered
+        let change = m.on_pointer_event(&root, &event(PointerEventType::Motion,
+                                                      Some(Point2::new(5.0, 5.0)), 0));
+        assert_eq!(change, InspectorChange{hovered: true, selected: false});
+        assert!(is(&m.hovered, &a));
+        let change = m.on_pointer_event(&root, &event(PointerEventType::Motion,
+                                                      Some(Point2::new(6.0, 6.0)), 0));
+        assert_eq!(change, InspectorChange::none());
+
+        // Only the first button selects
+        let change = m.on_pointer_event(&root, &event(PointerEventType::Press,
+                                                      Some(Point2::new(5.0, 15.0)), 3));
+        assert_eq!(change, InspectorChange::none());
+        let change = m.on_pointer_event(&root, &event(PointerEventType::Press,
+                                                      Some(Point2::new(5.0, 15.0)), 1));
+        assert_eq!(change, InspectorChange{hovered: false, selected: true});
+        assert!(is(&m.selected, &b));
+
+        // Leaving the target clears the hovered element, but not the selection
+        m.on_pointer_event(&root, &event(PointerEventType::Leave, None, 0));
+        assert!(m.hovered.is_none());
+        assert!(is(&m.selected, &b));
+    }
+
+    #[test]
+    fn test_selection_dropped_when_detached() {
+        let (a, b) = (block(), block());
+        let root = root_with(&a, &b);
+        let mut m = InspectorMut::new();
+        m.set_hovered(Some(a.clone()));
+        m.set_selected(Some(b.clone()));
+        assert_eq!(m.remove_detached(&root), InspectorChange::none());
+
+        // Replacing the content drops the hovered and selected elements
+        root.as_bin().unwrap().set_child(&root, column_of(&block(), &block()));
+        assert_eq!(m.remove_detached(&root), InspectorChange{hovered: true, selected: true});
+        assert!(m.hovered.is_none());
+        assert!(m.selected.is_none());
+    }
+}
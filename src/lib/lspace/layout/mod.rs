@@ -4,3 +4,5 @@ pub mod lbox;
 pub mod horizontal_layout;
 pub mod vertical_layout;
 pub mod flow_layout;
+pub mod stack_layout;
+pub mod row_heights;
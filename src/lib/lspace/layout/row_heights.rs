@@ -0,0 +1,156 @@
+use std::cmp::{min, max};
+
+
+/// Heights and positions of the rows of a list in which only some rows have been laid out.
+/// Rows that have yet to be measured are given an estimated height.
+pub struct RowHeights {
+    estimated_height: f64,
+    /// The measured height of each row; `None` for rows that have not been measured
+    heights: Vec<Option<f64>>,
+    /// The position of the top of each row, followed by the total height; `None` when a height
+    /// has changed since they were last computed
+    offsets: Option<Vec<f64>>,
+}
+
+impl RowHeights {
+    pub fn new(count: usize, estimated_height: f64) -> RowHeights {
+        return RowHeights{estimated_height: estimated_height, heights: vec![None; count],
+                          offsets: None};
+    }
+
+    pub fn len(&self) -> usize {
+        return self.heights.len();
+    }
+
+    /// Change the number of rows; new rows are given the estimated height
+    pub fn set_len(&mut self, count: usize) {
+        if count != self.heights.len() {
+            self.heights.resize(count, None);
+            self.offsets = None;
+        }
+    }
+
+    pub fn estimated_height(&self) -> f64 {
+        return self.estimated_height;
+    }
+
+    /// Change the estimated height; rows that have been measured keep their heights
+    pub fn set_estimated_height(&mut self, estimated_height: f64) {
+        if estimated_height != self.estimated_height {
+            self.estimated_height = estimated_height;
+            self.offsets = None;
+        }
+    }
+
+    pub fn height(&self, row: usize) -> f64 {
+        return self.heights[row].unwrap_or(self.estimated_height);
+    }
+
+    /// Record the measured height of `row`; returns `true` if its height changed
+    pub fn set_height(&mut self, row: usize, height: f64) -> bool {
+        let changed = self.height(row) != height;
+        self.heights[row] = Some(height);
+        if changed {
+            self.offsets = None;
+        }
+        return changed;
+    }
+
+    fn update_offsets(&mut self) {
+        if self.offsets.is_none() {
+            let mut offsets = Vec::with_capacity(self.heights.len() + 1);
+            let mut pos = 0.0;
+            for h in self.heights.iter() {
+                offsets.push(pos);
+                pos = pos + h.unwrap_or(self.estimated_height);
+            }
+            offsets.push(pos);
+            self.offsets = Some(offsets);
+        }
+    }
+
+    /// The position of the top of `row`; passing the number of rows gives the total height
+    pub fn offset(&mut self, row: usize) -> f64 {
+        self.update_offsets();
+        return self.offsets.as_ref().unwrap()[row];
+    }
+
+    pub fn total_height(&mut self) -> f64 {
+        let n = self.heights.len();
+        return self.offset(n);
+    }
+
+    /// The range `(start, end)` of rows that intersect the region from `top` to `bottom`
+    pub fn rows_in_region(&mut self, top: f64, bottom: f64) -> (usize, usize) {
+        self.update_offsets();
+        let offsets = self.offsets.as_ref().unwrap();
+        let n = self.heights.len();
+        // The first row whose bottom lies below `top`
+        let start = match offsets[1..].binary_search_by(|o| o.partial_cmp(&top).unwrap()) {
+            Ok(i) => i + 1,
+            Err(i) => i,
+        };
+        // The first row whose top lies at or below `bottom`
+        let end = match offsets[..n].binary_search_by(|o| o.partial_cmp(&bottom).unwrap()) {
+            Ok(i) => i,
+            Err(i) => i,
+        };
+        return (min(start, n), min(max(end, start), n));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offsets() {
+        let mut rows = RowHeights::new(4, 10.0);
+        assert_eq!(rows.offset(2), 20.0);
+        assert_eq!(rows.total_height(), 40.0);
+
+        assert!(rows.set_height(1, 25.0));
+        assert!(!rows.set_height(1, 25.0));
+        assert_eq!(rows.offset(1), 10.0);
+        assert_eq!(rows.offset(2), 35.0);
+        assert_eq!(rows.total_height(), 55.0);
+
+        rows.set_len(6);
+        assert_eq!(rows.height(5), 10.0);
+        assert_eq!(rows.total_height(), 75.0);
+        rows.set_len(2);
+        assert_eq!(rows.total_height(), 35.0);
+    }
+
+    #[test]
+    fn test_estimated_height() {
+        let mut rows = RowHeights::new(3, 10.0);
+        rows.set_height(0, 12.0);
+        rows.set_estimated_height(20.0);
+        assert_eq!(rows.height(0), 12.0);
+        assert_eq!(rows.height(1), 20.0);
+        assert_eq!(rows.total_height(), 52.0);
+    }
+
+    #[test]
+    fn test_rows_in_region() {
+        let mut rows = RowHeights::new(10, 10.0);
+        assert_eq!(rows.rows_in_region(0.0, 25.0), (0, 3));
+        assert_eq!(rows.rows_in_region(10.0, 30.0), (1, 3));
+        assert_eq!(rows.rows_in_region(15.0, 35.0), (1, 4));
+        assert_eq!(rows.rows_in_region(-50.0, -10.0), (0, 0));
+        assert_eq!(rows.rows_in_region(95.0, 200.0), (9, 10));
+        assert_eq!(rows.rows_in_region(150.0, 200.0), (10, 10));
+
+        rows.set_height(0, 40.0);
+        assert_eq!(rows.rows_in_region(30.0, 45.0), (0, 2));
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut rows = RowHeights::new(0, 10.0);
+        assert_eq!(rows.total_height(), 0.0);
+        assert_eq!(rows.rows_in_region(0.0, 100.0), (0, 0));
+    }
+}
@@ -0,0 +1,133 @@
+use layout::lreq::{LReq};
+use layout::lalloc::{LAlloc};
+
+
+/// Placement of a stacked child within the space allocated to the stack, along one axis
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Align {
+    Start,
+    Centre,
+    End,
+    /// Allocate the child all of the available space
+    Fill,
+}
+
+
+/// Placement of a stacked child along both axes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Alignment {
+    pub x: Align,
+    pub y: Align,
+}
+
+impl Alignment {
+    pub fn new(x: Align, y: Align) -> Alignment {
+        return Alignment{x: x, y: y};
+    }
+
+    pub fn fill() -> Alignment {
+        return Alignment{x: Align::Fill, y: Align::Fill};
+    }
+}
+
+
+/// Allocate a child space within a region, placing it according to `align`
+pub fn alloc_aligned(child_req: &LReq, region_pos: f64, region_size: f64,
+                     region_ref: Option<f64>, align: Align) -> LAlloc {
+    let free_space = (region_size - child_req.size().size()).max(0.0);
+    let offset = match align {
+        Align::Start => 0.0,
+        Align::Centre => free_space * 0.5,
+        Align::End => free_space,
+        Align::Fill => {
+            let actual_size = region_size.max(child_req.min_size());
+            return match child_req.size().before_ref_opt() {
+                Some(req_ref) => LAlloc::new_ref(region_pos, region_size, actual_size,
+                                                 region_ref.unwrap_or(req_ref)),
+                None => LAlloc::new(region_pos, region_size, actual_size),
+            };
+        }
+    };
+    return LAlloc::new_from_req_in_avail_size(child_req, region_pos + offset,
+                                              region_size - offset);
+}
+
+
+pub fn requisition_x(child_x_reqs: &[&LReq]) -> LReq {
+    return LReq::perpendicular_acc(child_x_reqs);
+}
+
+/// Allocate every child the whole of `box_x_alloc`; a child without an entry in `aligns` fills it
+pub fn alloc_x(box_x_alloc: &LAlloc, child_reqs: &[&LReq], aligns: &[Alignment]) -> Vec<LAlloc> {
+    return child_reqs.iter().enumerate().map(|(i, r)| {
+        let align = aligns.get(i).map(|a| a.x).unwrap_or(Align::Fill);
+        alloc_aligned(r, box_x_alloc.pos_in_parent(), box_x_alloc.alloc_size(),
+                      box_x_alloc.ref_point(), align)
+    }).collect();
+}
+
+pub fn requisition_y(child_y_reqs: &[&LReq]) -> LReq {
+    return LReq::perpendicular_acc(child_y_reqs);
+}
+
+/// Allocate every child the whole of `box_y_alloc`; a child without an entry in `aligns` fills it
+pub fn alloc_y(box_y_alloc: &LAlloc, child_reqs: &[&LReq], aligns: &[Alignment]) -> Vec<LAlloc> {
+    return child_reqs.iter().enumerate().map(|(i, r)| {
+        let align = aligns.get(i).map(|a| a.y).unwrap_or(Align::Fill);
+        alloc_aligned(r, box_y_alloc.pos_in_parent(), box_y_alloc.alloc_size(),
+                      box_y_alloc.ref_point(), align)
+    }).collect();
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use layout::lreq::{LReq};
+    use layout::lalloc::{LAlloc};
+
+
+    #[test]
+    fn test_stack_requisition() {
+        let ch0 = LReq::new_fixed_size(10.0);
+        let ch1 = LReq::new_fixed_size(30.0);
+        let ch2 = LReq::new_fixed_size(20.0);
+
+        assert_eq!(requisition_x(&[&ch0, &ch1, &ch2]), LReq::new_fixed_size(30.0));
+        assert_eq!(requisition_y(&[&ch0, &ch2]), LReq::new_fixed_size(20.0));
+    }
+
+    #[test]
+    fn test_stack_alloc_aligned() {
+        let ch0 = LReq::new_fixed_size(10.0);
+        let ch1 = LReq::new_fixed_size(10.0);
+        let ch2 = LReq::new_fixed_size(10.0);
+        let ch3 = LReq::new_fixed_size(10.0);
+        let ch4 = LReq::new_fixed_size(10.0);
+        let box_alloc = LAlloc::new(100.0, 30.0, 30.0);
+
+        let aligns = vec![Alignment::new(Align::Start, Align::Start),
+                          Alignment::new(Align::Centre, Align::Centre),
+                          Alignment::new(Align::End, Align::End),
+                          Alignment::fill()];
+        let x_allocs = alloc_x(&box_alloc, &[&ch0, &ch1, &ch2, &ch3, &ch4], &aligns);
+
+        assert_eq!(x_allocs[0], LAlloc::new(100.0, 10.0, 10.0));
+        assert_eq!(x_allocs[1], LAlloc::new(110.0, 10.0, 10.0));
+        assert_eq!(x_allocs[2], LAlloc::new(120.0, 10.0, 10.0));
+        assert_eq!(x_allocs[3], LAlloc::new(100.0, 30.0, 30.0));
+        // No alignment given; fill
+        assert_eq!(x_allocs[4], LAlloc::new(100.0, 30.0, 30.0));
+    }
+
+    #[test]
+    fn test_stack_alloc_insufficient_space() {
+        let ch0 = LReq::new_fixed_size(40.0);
+        let box_alloc = LAlloc::new(0.0, 30.0, 30.0);
+
+        let aligns = vec![Alignment::new(Align::Centre, Align::End)];
+        assert_eq!(alloc_x(&box_alloc, &[&ch0], &aligns)[0], LAlloc::new(0.0, 30.0, 40.0));
+        assert_eq!(alloc_y(&box_alloc, &[&ch0], &aligns)[0], LAlloc::new(0.0, 30.0, 40.0));
+    }
+}
@@ -11,18 +11,35 @@ use geom::vector2::Vector2;
 use geom::point2::Point2;
 use geom::bbox2::BBox2;
 use input::inputmodifier::InputModifierState;
-use input::keyboard::Keyboard;
+use input::keyboard::{Keyboard, KeyEvent};
 use input::pointer::{Pointer, PointerPosition};
+use input::pointer_events::{PointerEvents, PointerEvent, PointerEventType};
+use input::interactive::ElementInteraction;
 use elements::element_ctx::{ElementContext, ElementLayoutContext};
-use elements::element::{ElementRef, elem_as_ref};
-use elements::{root_element};
+use elements::element::{ElementRef, elem_as_ref, elems_are_same, elem_is_in_tree, queue_redraw};
+use elements::{root_element, element_dump};
+use elements::root_element::OverlayAnchor;
+use elements::debug_overlay::DebugOverlay;
 use pres::pres::{Pres, TPres, PresBuildCtx};
+use animation::clock::{TClock, SystemClock};
+use animation::animator::Animator;
 
 
 pub trait TLSpaceListener {
     fn notify_queue_redraw(&self, rect: &BBox2);
 }
 
+/// Paints over the content of an `LSpaceArea`, in the co-ordinate space of the root element
+pub trait TOverlayPainter {
+    fn paint_overlay(&self, cairo_ctx: &Context);
+}
+
+fn overlay_painters_are_same(a: &Rc<TOverlayPainter>, b: &Rc<TOverlayPainter>) -> bool {
+    let aptr: &TOverlayPainter = &**a;
+    let bptr: &TOverlayPainter = &**b;
+    return (aptr as *const TOverlayPainter) == (bptr as *const TOverlayPainter);
+}
+
 pub struct LSpaceAreaMut {
     width: i32,
     height: i32,
@@ -35,6 +52,8 @@ pub struct LSpaceAreaMut {
     root_element: ElementRef,
 
     layout_required: bool,
+
+    debug_overlay: Option<DebugOverlay>,
 }
 
 impl LSpaceAreaMut {
@@ -46,7 +65,8 @@ impl LSpaceAreaMut {
             input_pointer: Pointer::new(),
             elem_ctx: ElementContext::new(),
             root_element: root_elem,
-            layout_required: true};
+            layout_required: true,
+            debug_overlay: None};
     }
 
     pub fn set_content_element(&mut self, content: ElementRef) {
@@ -59,6 +79,29 @@ impl LSpaceAreaMut {
         self.root_element.as_bin().unwrap().set_child(&self.root_element, child);
     }
 
+    pub fn update_content_pres(&mut self, p: Pres) {
+        let pres_ctx = PresBuildCtx::new(&self.elem_ctx);
+        let root = self.root_element.as_bin().unwrap();
+        let existing = root.get_child();
+        let child = match existing {
+            Some(ref e) => p.rebuild(e, &pres_ctx),
+            None => p.build(&pres_ctx)
+        };
+        let same = match existing {
+            Some(ref e) => elems_are_same(e, &child),
+            None => false
+        };
+        if !same {
+            root.set_child(&self.root_element, child);
+        }
+        queue_redraw(&*self.root_element);
+    }
+
+    pub fn set_debug_overlay(&mut self, overlay: Option<DebugOverlay>) {
+        self.debug_overlay = overlay;
+        queue_redraw(&*self.root_element);
+    }
+
     pub fn on_realize(&mut self) {
     }
 
@@ -120,6 +163,7 @@ impl LSpaceAreaMut {
         if self.layout_required || true {
             let e = self.root_element.as_root_element().unwrap();
             let layout_ctx = ElementLayoutContext::new(&self.elem_ctx, cairo_ctx);
+            e.root_set_viewport_size(Vector2::new(self.width as f64, self.height as f64));
             let rx = e.root_requisition_x(&layout_ctx);
             e.root_allocate_x(self.width as f64);
             let ry = e.root_requisition_y();
@@ -133,7 +177,12 @@ impl LSpaceAreaMut {
         let t1 = time::precise_time_ns();
         e.draw(cairo_ctx, &BBox2::from_lower_size(Point2::origin(),
                 Vector2::new(self.width as f64, self.height as f64)));
+        e.root_flush_resizes_after_draw();
         let t2 = time::precise_time_ns();
+        match self.debug_overlay {
+            Some(ref overlay) => overlay.draw(&self.root_element, cairo_ctx),
+            None => {}
+        }
     }
 }
 
@@ -141,12 +190,25 @@ impl LSpaceAreaMut {
 pub struct LSpaceArea {
     m: RefCell<LSpaceAreaMut>,
     input_keyboard: Keyboard,
+    input_pointer_events: PointerEvents,
+    overlay_painters: RefCell<Vec<Rc<TOverlayPainter>>>,
+    animator: Animator,
+    interaction: ElementInteraction,
 }
 
 impl LSpaceArea {
     pub fn new() -> LSpaceArea {
+        LSpaceArea::new_with_clock(Rc::new(SystemClock::new()))
+    }
+
+    /// Create an area whose animations are timed by `clock`
+    pub fn new_with_clock(clock: Rc<TClock>) -> LSpaceArea {
         LSpaceArea{m: RefCell::new(LSpaceAreaMut::new()),
             input_keyboard: Keyboard::new(),
+            input_pointer_events: PointerEvents::new(),
+            overlay_painters: RefCell::new(Vec::new()),
+            animator: Animator::new(clock),
+            interaction: ElementInteraction::new(),
         }
     }
 
@@ -159,14 +221,119 @@ impl LSpaceArea {
         &self.input_keyboard
     }
 
+    pub fn pointer_events(&self) -> &PointerEvents {
+        &self.input_pointer_events
+    }
+
+    /// Animations and timers; `LSpaceWidget` ticks the animator while it is active
+    pub fn animator(&self) -> &Animator {
+        &self.animator
+    }
+
+    /// The interactive element that has the keyboard focus
+    pub fn focused_element(&self) -> Option<ElementRef> {
+        self.interaction.focused()
+    }
+
+    /// Give the keyboard focus to `elem`, or clear it by passing `None`; see
+    /// `TInteractiveElement`
+    pub fn set_focus(&self, elem: Option<&ElementRef>) {
+        self.interaction.set_focus(elem);
+    }
+
+    pub fn root_element(&self) -> ElementRef {
+        self.m.borrow().root_element.clone()
+    }
+
+    /// Dump the element tree as indented text; see `element_dump::dump_tree`
+    pub fn dump_element_tree(&self) -> String {
+        element_dump::dump_tree(&self.root_element())
+    }
+
     pub fn set_content_element(&self, content: ElementRef) {
-        let mut mm = self.m.borrow_mut();
-        mm.set_content_element(content)
+        self.m.borrow_mut().set_content_element(content);
+        self.interaction.remove_detached(&self.root_element());
     }
 
     pub fn set_content_pres(&self, p: Pres) {
-        let mut mm = self.m.borrow_mut();
-        mm.set_content_pres(p)
+        {
+            let mut mm = self.m.borrow_mut();
+            mm.set_content_pres(p);
+            mm.elem_ctx.prune_ids();
+        }
+        self.interaction.remove_detached(&self.root_element());
+    }
+
+    /// Replace the content with the presentation `p`, reconciling it against the existing
+    /// element tree so that unchanged elements, and their layout, are reused
+    pub fn update_content_pres(&self, p: Pres) {
+        {
+            let mut mm = self.m.borrow_mut();
+            mm.update_content_pres(p);
+            mm.elem_ctx.prune_ids();
+        }
+        self.interaction.remove_detached(&self.root_element());
+    }
+
+    /// Find the element in the content tree whose id is `id`; see `pres::element_id::WithId`
+    pub fn find_by_id(&self, id: &str) -> Option<ElementRef> {
+        let root = self.root_element();
+        let found = self.m.borrow().elem_ctx.find_by_id(id);
+        found.and_then(|e| if elem_is_in_tree(&e, &root) {Some(e)} else {None})
+    }
+
+
+    /// Enable the layout debug overlay, or disable it by passing `None`
+    pub fn set_debug_overlay(&self, overlay: Option<DebugOverlay>) {
+        self.m.borrow_mut().set_debug_overlay(overlay);
+    }
+
+    pub fn debug_overlay(&self) -> Option<DebugOverlay> {
+        self.m.borrow().debug_overlay.clone()
+    }
+
+
+    /// Show `elem` in the overlay layer above the content, placed against `anchor`; see
+    /// `RootElement`
+    pub fn add_floating(&self, elem: ElementRef, anchor: OverlayAnchor) {
+        let root = self.root_element();
+        root.as_root_element().unwrap().root_add_floating(&root, elem, anchor);
+    }
+
+    /// Move the floating element `elem` to a new anchor
+    pub fn set_floating_anchor(&self, elem: &ElementRef, anchor: OverlayAnchor) {
+        let root = self.root_element();
+        root.as_root_element().unwrap().root_set_floating_anchor(elem, anchor);
+    }
+
+    pub fn remove_floating(&self, elem: &ElementRef) {
+        let root = self.root_element();
+        root.as_root_element().unwrap().root_remove_floating(elem);
+        self.interaction.remove_detached(&root);
+    }
+
+    /// The floating elements, from bottom to top
+    pub fn floating_elements(&self) -> Vec<ElementRef> {
+        let root = self.root_element();
+        let elems = root.as_root_element().unwrap().root_floating_elements();
+        elems
+    }
+
+
+    pub fn add_overlay_painter(&self, painter: &Rc<TOverlayPainter>) {
+        self.overlay_painters.borrow_mut().push(painter.clone());
+        self.queue_redraw();
+    }
+
+    pub fn remove_overlay_painter(&self, painter: &Rc<TOverlayPainter>) {
+        self.overlay_painters.borrow_mut().retain(|p| !overlay_painters_are_same(p, painter));
+        self.queue_redraw();
+    }
+
+    /// Queue a redraw of the whole area
+    pub fn queue_redraw(&self) {
+        let root = self.root_element();
+        queue_redraw(&*root);
     }
 
 
@@ -175,6 +342,16 @@ impl LSpaceArea {
     }
 
 
+    /// Update the hover and press state of interactive elements, then pass the event to the
+    /// pointer interactors
+    fn dispatch_pointer_event(&self, event: &PointerEvent) {
+        self.interaction.on_pointer_event(&self.root_element(), event);
+        // The event may have removed elements from the tree, e.g. by collapsing an expander
+        self.interaction.remove_detached(&self.root_element());
+        self.input_pointer_events.dispatch(event);
+    }
+
+
     pub fn on_realize(&self) {
         self.m.borrow_mut().on_realize();
     }
@@ -189,41 +366,70 @@ impl LSpaceArea {
 
     pub fn on_button_press(&self, mod_state: InputModifierState, pos: Point2, button: u32) {
         self.m.borrow_mut().on_button_press(mod_state, pos, button);
+        self.dispatch_pointer_event(&PointerEvent::new(PointerEventType::Press, mod_state,
+                                                       Some(pos), button));
     }
 
     pub fn on_button_release(&self, mod_state: InputModifierState, pos: Point2, button: u32) {
         self.m.borrow_mut().on_button_release(mod_state, pos, button);
+        self.dispatch_pointer_event(&PointerEvent::new(PointerEventType::Release, mod_state,
+                                                       Some(pos), button));
     }
 
     pub fn on_enter(&self, mod_state: InputModifierState, pos: Point2) {
         self.m.borrow_mut().on_enter(mod_state, pos);
+        self.dispatch_pointer_event(&PointerEvent::new(PointerEventType::Enter, mod_state,
+                                                       Some(pos), 0));
     }
 
     pub fn on_leave(&self, mod_state: InputModifierState, pos: Point2) {
         self.m.borrow_mut().on_leave(mod_state, pos);
+        self.dispatch_pointer_event(&PointerEvent::new(PointerEventType::Leave, mod_state,
+                                                       None, 0));
     }
 
     pub fn on_motion(&self, mod_state: InputModifierState, pos: Point2) {
         self.m.borrow_mut().on_motion(mod_state, pos);
+        self.dispatch_pointer_event(&PointerEvent::new(PointerEventType::Motion, mod_state,
+                                                       Some(pos), 0));
     }
 
     pub fn on_scroll(&self, mod_state: InputModifierState, pos: Point2,
                      scroll_x: f64, scroll_y: f64) {
         self.m.borrow_mut().on_scroll(mod_state, pos, scroll_x, scroll_y);
+        self.interaction.on_scroll(&self.root_element(), pos, scroll_x, scroll_y);
     }
 
     pub fn on_key_press(&self, mod_state: InputModifierState, key_val: u32, key_string: String) {
         self.m.borrow_mut().on_key_press(mod_state, key_val, key_string.clone());
-        self.input_keyboard.on_key_press(mod_state, key_val, key_string);
+        // Key events handled by the focused element are not passed to the keyboard interactors
+        let event = KeyEvent::new_press(mod_state, key_val, key_string.clone());
+        let handled = self.interaction.on_key_event(&event);
+        self.interaction.remove_detached(&self.root_element());
+        if !handled {
+            self.input_keyboard.on_key_press(mod_state, key_val, key_string);
+        }
     }
 
     pub fn on_key_release(&self, mod_state: InputModifierState, key_val: u32, key_string: String) {
         self.m.borrow_mut().on_key_release(mod_state, key_val, key_string.clone());
-        self.input_keyboard.on_key_release(mod_state, key_val, key_string);
+        let event = KeyEvent::new_release(mod_state, key_val, key_string.clone());
+        let handled = self.interaction.on_key_event(&event);
+        self.interaction.remove_detached(&self.root_element());
+        if !handled {
+            self.input_keyboard.on_key_release(mod_state, key_val, key_string);
+        }
     }
 
     pub fn on_draw(&self, cairo_ctx: &Context) {
         self.m.borrow_mut().on_draw(cairo_ctx);
+        // Overlay painters are invoked outside the borrow so that they may access the area
+        let painters = self.overlay_painters.borrow().clone();
+        for painter in painters.iter() {
+            cairo_ctx.save();
+            painter.paint_overlay(cairo_ctx);
+            cairo_ctx.restore();
+        }
     }
 }
 
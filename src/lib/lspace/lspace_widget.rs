@@ -2,8 +2,8 @@
 
 extern crate time;
 
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::cell::{Cell, RefCell};
 use std::ffi::CStr;
 use libc::c_char;
 
@@ -12,6 +12,7 @@ use gdk::enums::modifier_type;
 use gtk;
 use gtk::traits::*;
 use gtk::signal::Inhibit;
+use glib;
 
 use input::inputmodifier::{self, InputModifierState};
 
@@ -19,6 +20,11 @@ use geom::point2::Point2;
 use geom::bbox2::BBox2;
 use pres::pres::Pres;
 use lspace_area::{LSpaceArea, TLSpaceListener};
+use animation::animator::TAnimatorListener;
+
+
+/// Interval between animation frames, in milliseconds
+const ANIMATION_FRAME_INTERVAL: u32 = 16;
 
 
 fn gdk_modifier_to_input_mod_state(gdk_state: gdk_ffi::GdkModifierType) -> InputModifierState {
@@ -47,7 +53,9 @@ fn gdk_modifier_to_input_mod_state(gdk_state: gdk_ffi::GdkModifierType) -> Input
 
 struct LSpaceWidgetMut {
     drawing_area: Rc<gtk::DrawingArea>,
-    area: Rc<LSpaceArea>
+    area: Rc<LSpaceArea>,
+    // Set while a GLib timeout is ticking the animator
+    ticking: Rc<Cell<bool>>,
 }
 
 impl LSpaceWidgetMut {
@@ -56,7 +64,7 @@ impl LSpaceWidgetMut {
         drawing_area.set_can_focus(true);
 
         let instance = LSpaceWidgetMut{drawing_area: drawing_area.clone(),
-            area: area.clone()
+            area: area.clone(), ticking: Rc::new(Cell::new(false))
         };
 
         {
@@ -170,7 +178,8 @@ impl LSpaceWidgetMut {
             });
         }
 
-        LSpaceWidgetMut{drawing_area: drawing_area.clone(), area: area.clone()}
+        LSpaceWidgetMut{drawing_area: drawing_area.clone(), area: area.clone(),
+                        ticking: Rc::new(Cell::new(false))}
     }
 
     pub fn new(content: Pres) -> LSpaceWidgetMut {
@@ -192,21 +201,34 @@ pub struct LSpaceWidget {
 impl LSpaceWidget {
     pub fn new_with_area(area: Rc<LSpaceArea>) -> Rc<LSpaceWidget> {
         let inst = Rc::new(LSpaceWidget{m: RefCell::new(LSpaceWidgetMut::new_with_area(area))});
-        let listener: Rc<TLSpaceListener> = inst.clone();
-        inst.m.borrow().area.set_lspace_listener(Some(&listener));
+        LSpaceWidget::attach_listeners(&inst);
         inst
     }
 
     pub fn new(content: Pres) -> Rc<LSpaceWidget> {
         let inst = Rc::new(LSpaceWidget{m: RefCell::new(LSpaceWidgetMut::new(content))});
-        let listener: Rc<TLSpaceListener> = inst.clone();
-        inst.m.borrow().area.set_lspace_listener(Some(&listener));
+        LSpaceWidget::attach_listeners(&inst);
         inst
     }
 
+    fn attach_listeners(inst: &Rc<LSpaceWidget>) {
+        let area = inst.area();
+        let listener: Rc<TLSpaceListener> = inst.clone();
+        area.set_lspace_listener(Some(&listener));
+        let animator_listener: Rc<TAnimatorListener> = inst.clone();
+        area.animator().set_listener(Some(&animator_listener));
+        if area.animator().is_active() {
+            inst.notify_animator_active();
+        }
+    }
+
     pub fn gtk_widget(&self) -> Rc<gtk::DrawingArea> {
         return self.m.borrow().gtk_widget();
     }
+
+    pub fn area(&self) -> Rc<LSpaceArea> {
+        return self.m.borrow().area.clone();
+    }
 }
 
 impl TLSpaceListener for LSpaceWidget {
@@ -218,3 +240,24 @@ impl TLSpaceListener for LSpaceWidget {
     }
 }
 
+
+impl TAnimatorListener for LSpaceWidget {
+    fn notify_animator_active(&self) {
+        // Tick the animator from a GLib timeout until it has nothing left to run; if a
+        // timeout is already running, it will pick up the new work
+        let ticking = self.m.borrow().ticking.clone();
+        if ticking.get() {
+            return;
+        }
+        ticking.set(true);
+        let area: Weak<LSpaceArea> = Rc::downgrade(&self.area());
+        glib::timeout_add(ANIMATION_FRAME_INTERVAL, move || {
+            let active = match area.upgrade() {
+                Some(a) => a.animator().tick(),
+                None => false
+            };
+            ticking.set(active);
+            glib::Continue(active)
+        });
+    }
+}
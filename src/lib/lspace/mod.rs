@@ -8,13 +8,18 @@ extern crate gdk;
 extern crate gtk;
 extern crate libc;
 extern crate glib;
+extern crate rustc_serialize;
 
 pub mod geom;
 pub mod graphics;
 pub mod layout;
 pub mod input;
 pub mod elements;
+pub mod editing;
 pub mod pres;
+pub mod animation;
+pub mod widgets;
 
 pub mod lspace_area;
 pub mod lspace_widget;
+pub mod inspector;
@@ -0,0 +1,118 @@
+use elements::element::ElementRef;
+use pres::pres::{Pres, TPres, PresBuildCtx};
+use pres::visitor::TPresVisitor;
+
+
+/// Gives the element built from `child` an id, so that the application can find it later
+/// using `ElementContext::find_by_id` or `LSpaceArea::find_by_id`
+pub struct WithId {
+    child: Pres,
+    id: String,
+}
+
+impl WithId {
+    pub fn new(child: Pres, id: String) -> Pres {
+        Box::new(WithId{child: child, id: id})
+    }
+
+    pub fn child(&self) -> &Pres {
+        &self.child
+    }
+
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+}
+
+impl TPres for WithId {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let elem = self.child.build(pres_ctx);
+        pres_ctx.elem_ctx.register_id(&self.id, &elem);
+        elem
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let elem = self.child.rebuild(existing, pres_ctx);
+        pres_ctx.elem_ctx.register_id(&self.id, &elem);
+        elem
+    }
+
+    fn key(&self) -> Option<&str> {
+        self.child.key()
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_with_id(self);
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use elements::element::{TElement, elems_are_same};
+    use elements::element_ctx::ElementContext;
+    use elements::text_element::TTextElement;
+    use pres::primitive::{Text, Column};
+    use lspace_area::LSpaceArea;
+
+    fn text(t: &str) -> Pres {
+        Text::new_inherited(t.to_string())
+    }
+
+    #[test]
+    fn test_find_by_id() {
+        let elem_ctx = ElementContext::new();
+        let pres_ctx = PresBuildCtx::new(&elem_ctx);
+        let elem = WithId::new(text("a"), "x".to_string()).build(&pres_ctx);
+        assert_eq!(elem.get_id(), Some("x".to_string()));
+        assert!(elems_are_same(&elem_ctx.find_by_id("x").unwrap(), &elem));
+        assert!(elem_ctx.find_by_id("y").is_none());
+
+        // A dropped element is not found, and its entry is pruned
+        drop(elem);
+        assert!(elem_ctx.find_by_id("x").is_none());
+        elem_ctx.prune_ids();
+        assert!(elem_ctx.find_by_id("x").is_none());
+    }
+
+    #[test]
+    fn test_id_cleared_on_rebuild() {
+        let elem_ctx = ElementContext::new();
+        let pres_ctx = PresBuildCtx::new(&elem_ctx);
+        let elem = WithId::new(text("a"), "x".to_string()).build(&pres_ctx);
+
+        // Rebuilding without the id clears it, even though the element is reused
+        let rebuilt = text("a").rebuild(&elem, &pres_ctx);
+        assert!(elems_are_same(&rebuilt, &elem));
+        assert_eq!(rebuilt.get_id(), None);
+        assert!(elem_ctx.find_by_id("x").is_none());
+
+        // Moving the id to another element
+        let other = WithId::new(text("b"), "x".to_string()).build(&pres_ctx);
+        let rebuilt = WithId::new(text("a"), "y".to_string()).rebuild(&elem, &pres_ctx);
+        assert!(elems_are_same(&elem_ctx.find_by_id("x").unwrap(), &other));
+        assert!(elems_are_same(&elem_ctx.find_by_id("y").unwrap(), &rebuilt));
+    }
+
+    #[test]
+    fn test_area_find_by_id() {
+        let area = LSpaceArea::new();
+        area.set_content_pres(Column::new(vec![WithId::new(text("a"), "x".to_string()),
+                                               text("b")]));
+        let elem = area.find_by_id("x").unwrap();
+        assert_eq!(*elem.as_text_element().unwrap().get_text(), "a");
+
+        // An element that is still alive, but is no longer in the tree, is not found
+        area.set_content_pres(text("c"));
+        assert!(area.element_context().find_by_id("x").is_some());
+        assert!(area.find_by_id("x").is_none());
+        drop(elem);
+        assert!(area.element_context().find_by_id("x").is_none());
+    }
+}
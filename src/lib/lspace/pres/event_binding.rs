@@ -0,0 +1,62 @@
+use elements::element::{ElementRef, elem_as_ref, elems_are_same};
+use elements::event_binding::EventBindingElement;
+use elements::bin::{TBinElement};
+use input::element_events::EventBindings;
+use pres::pres::{Pres, TPres, PresBuildCtx, reuse_element};
+use pres::visitor::TPresVisitor;
+
+
+/// Binds named events to the presentation of `child`
+pub struct EventBinding {
+    child: Pres,
+    bindings: EventBindings,
+}
+
+impl EventBinding {
+    pub fn new(child: Pres, bindings: EventBindings) -> Pres {
+        Box::new(EventBinding{child: child, bindings: bindings})
+    }
+
+    pub fn child(&self) -> &Pres {
+        &self.child
+    }
+
+    pub fn bindings(&self) -> &EventBindings {
+        &self.bindings
+    }
+}
+
+impl TPres for EventBinding {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let child = self.child.build(pres_ctx);
+        let elem = elem_as_ref(EventBindingElement::new(self.bindings.clone()));
+        elem.as_bin().unwrap().set_child(&elem, child);
+        elem
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        // Bindings are fixed when the element is created, so reuse it only if they match
+        match existing.as_any().downcast_ref::<EventBindingElement>() {
+            Some(b) if existing.event_bindings() == Some(&self.bindings) => {
+                let old_child = b.get_child();
+                let child = match old_child {
+                    Some(ref c) => self.child.rebuild(c, pres_ctx),
+                    None => self.child.build(pres_ctx)
+                };
+                let same = match old_child {
+                    Some(ref c) => elems_are_same(c, &child),
+                    None => false
+                };
+                if !same {
+                    b.set_child(existing, child);
+                }
+                reuse_element(existing)
+            },
+            _ => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_event_binding(self);
+    }
+}
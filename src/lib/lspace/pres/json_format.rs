@@ -0,0 +1,1799 @@
+//! The JSON presentation format
+//!
+//! Presentations are described by a tree of JSON objects. Every presentation node is an object
+//! with a `__type__` field that identifies the node type:
+//!
+//! - `Text`: `{"__type__": "Text", "text": <string>}`; uses the text style from the style sheet
+//! - `Column`: `{"__type__": "Column", "children": [<node>...]}`
+//! - `Row`: `{"__type__": "Row", "children": [<node>...]}`
+//! - `Flow`: `{"__type__": "Flow", "children": [<node>...]}`
+//! - `Stack`: `{"__type__": "Stack", "children": [<node>...]}`; children layered in the same
+//!   space, from back to front. The optional `alignments` field is an array that holds an
+//!   alignment for each child: an object with optional `x` and `y` fields whose values are
+//!   `"start"`, `"centre"`, `"end"` or `"fill"`. Children without an alignment fill the stack.
+//! - `Canvas`: `{"__type__": "Canvas", "children": [<node>...], "positions": [<point>...]}`;
+//!   children placed at their natural sizes, with their top left corners at the matching
+//!   positions. Positions are objects with numeric `x` and `y` fields, in the co-ordinate space
+//!   of the canvas. Every child must have a position. Positions may be negative; the canvas then
+//!   grows up and to the left to contain the child.
+//! - `LazyColumn`: `{"__type__": "LazyColumn", "rows": [<node>...], "estimated_row_height":
+//!   <number>}`; a column that only builds the rows that are drawn. Rows are assumed to be
+//!   `estimated_row_height` high until they have been laid out. The optional numeric
+//!   `view_height` field shows that much of the column, scrolling through its rows. Rows are
+//!   only decoded when they are built, so an invalid row is not reported by `decode`; the error
+//!   is shown in place of the row.
+//! - `Border`: `{"__type__": "Border", "child": <node>, "border_type": ..., ...}`; the remaining
+//!   fields are those of a border (see below)
+//! - `Shadow`: `{"__type__": "Shadow", "child": <node>, "offset_x": <number>, "offset_y":
+//!   <number>, "blur": <number>, "spread": <number>, "rounding": <number>, "colour": <colour>}`;
+//!   draws a drop shadow beneath the child without affecting layout
+//! - `StateBorder`: `{"__type__": "StateBorder", "child": <node>, "borders": {"normal": <border>,
+//!   ...}}`; a border that changes as the pointer hovers over or presses the node. `borders` may
+//!   also hold `hovered`, `pressed`, `focused` and `disabled` borders; states without a border
+//!   use `normal`. The optional boolean `focusable` field allows the node to take the keyboard
+//!   focus when pressed, and `disabled` shows it in its disabled state.
+//! - `Shape`: `{"__type__": "Shape", "width": <number>, "height": <number>, "shapes":
+//!   [<shape>...]}`; draws shapes in a box of the given size (see below)
+//! - `Expander`: `{"__type__": "Expander", "header": <node>, "body": <node>}`; a header above a
+//!   body that the user can show and hide. The optional boolean `expanded` field shows the
+//!   body; it is hidden by default.
+//! - `ApplyStyleSheet`: `{"__type__": "ApplyStyleSheet", "child": <node>, ...}`; the remaining
+//!   fields are style values (see below)
+//! - `ApplyStyleClass`: `{"__type__": "ApplyStyleClass", "class": <string>, "child": <node>}`
+//! - `DefineStyleClasses`: `{"__type__": "DefineStyleClasses", "classes": {<name>: <style
+//!   values>...}, "child": <node>}`
+//!
+//! Any node may have a `key` field (string); keys identify list children when a presentation is
+//! reconciled against the elements built from a previous version of it. Any node may also have a
+//! `style` field holding style values, which are applied as if by an enclosing `ApplyStyleSheet`
+//! node, and an `id` field (string) that identifies the node to streamed document updates (see
+//! `json_stream`). The element built from a node with an `id` can be found with
+//! `LSpaceArea::find_by_id`.
+//!
+//! Any node may bind named events with `on_click`, `on_hover` and `on_key` fields (strings). The
+//! names are reported to the application, along with the node's `id`, when the user clicks on the
+//! node, moves the pointer into or out of it, or presses a key while the pointer is over it.
+//!
+//! Borders have a `border_type` field whose value is either:
+//!
+//! - `"solid"`, with numeric `thickness`, `inset` and `rounding` fields, a `colour` field and an
+//!   optional `background` field
+//! - `"filled"`, with numeric `left_margin`, `right_margin`, `top_margin`, `bottom_margin` and
+//!   `rounding` fields and an optional `background` field
+//! - `"sided"`, with optional `left`, `right`, `top` and `bottom` sides, a numeric `inset`, an
+//!   optional `radii` field and an optional `background` field. Each side is an object with a
+//!   numeric `thickness`, a `colour` and an optional `style` (`"solid"`, `"dashed"` or
+//!   `"dotted"`); missing sides are not drawn. `radii` is either a number, used for every
+//!   corner, or an object with numeric `top_left`, `top_right`, `bottom_right` and
+//!   `bottom_left` fields.
+//!
+//! A border `background` is a paint: either a colour, or an object with a `paint_type` field
+//! whose value is:
+//!
+//! - `"solid"`, with a `colour` field
+//! - `"linear_gradient"`, with `start` and `end` points and a `stops` array
+//! - `"radial_gradient"`, with a `centre` point, a numeric `radius` and a `stops` array
+//! - `"image"`, with a `path` field naming a PNG file that is repeated to fill the background
+//!
+//! Points are objects with numeric `x` and `y` fields, relative to the box being painted, so
+//! that `{"x": 0, "y": 0}` is its top left corner and `{"x": 1, "y": 1}` its bottom right. The
+//! radius of a radial gradient is a fraction of the larger of the box's width and height.
+//! Gradient stops are objects with a numeric `offset` (from `0` to `1`) and a `colour`. For
+//! compatibility, a `background_colour` field may be given in place of `background`.
+//!
+//! Shapes have a `shape_type` field whose value is either:
+//!
+//! - `"rectangle"`, with a `lower` point and numeric `width`, `height` and `rounding` fields
+//! - `"ellipse"`, with a `centre` point and numeric `radius_x` and `radius_y` fields
+//! - `"polyline"`, with a `points` array and a boolean `closed` field
+//!
+//! and optional `fill` (a colour) and `stroke` fields. A stroke is an object with a numeric
+//! `width` and a `colour`. Shape co-ordinates are in pixels, relative to the top left corner of
+//! the `Shape` node.
+//!
+//! Style values are all optional: `text_font_family` (string), `text_weight` (`"normal"` or
+//! `"bold"`), `text_slant` (`"normal"` or `"italic"`), `text_size` (number), `text_colour`,
+//! `column_y_spacing`, `row_x_spacing`, `flow_x_spacing`, `flow_y_spacing` (numbers) and
+//! `flow_indentation`.
+//!
+//! Colours are objects with numeric `r`, `g`, `b` and `a` fields, in the range `0` to `1`, or
+//! strings holding either a hex colour (`"#rrggbb"`, `"#rrggbbaa"`) or a CSS colour name.
+//!
+//! Flow indentation is an object with an `indent_type` field whose value is `"no_indent"`,
+//! `"first"` or `"except_first"`; the latter two require a numeric `indent` field.
+//!
+//! Decoding errors identify the offending node by its path from the root, e.g.
+//! `children[3].thickness`.
+//!
+//! `encode` converts a presentation back to this format, such that decoding the result yields an
+//! equivalent presentation. Text styles and container spacing that were specified explicitly,
+//! rather than taken from the style sheet, are encoded as an `ApplyStyleSheet` node wrapping the
+//! node concerned.
+
+use std::fmt;
+use std::error::Error;
+use std::rc::Rc;
+
+use rustc_serialize::json::{self, Json, Object};
+
+use geom::colour::Colour;
+use graphics::border::{Border, BorderSide, StrokeStyle, CornerRadii, StateBorders};
+use graphics::paint::{Paint, ColourStop};
+use graphics::shadow::Shadow;
+use graphics::shape::{Shape, ShapeGeometry, Stroke};
+use geom::vector2::Vector2;
+use geom::point2::Point2;
+use layout::flow_layout::FlowIndent;
+use layout::stack_layout::{Align, Alignment};
+use elements::text_element::{TextStyleParams, TextWeight, TextSlant};
+use pres::pres::Pres;
+use pres::primitive;
+use pres::style::{StyleValues, ApplyStyleSheet, ApplyStyleClass, DefineStyleClasses};
+use pres::visitor::TPresVisitor;
+use pres::reconcile::Keyed;
+use pres::event_binding::EventBinding;
+use pres::element_id::WithId;
+use input::element_events::EventBindings;
+
+
+/// The location of a JSON value, relative to the root of the document
+#[derive(Debug, Copy, Clone)]
+pub enum JsonPath<'a> {
+    Root,
+    Field(&'a JsonPath<'a>, &'a str),
+    Index(&'a JsonPath<'a>, usize),
+}
+
+impl <'a> JsonPath<'a> {
+    pub fn field(&'a self, name: &'a str) -> JsonPath<'a> {
+        JsonPath::Field(self, name)
+    }
+
+    pub fn index(&'a self, i: usize) -> JsonPath<'a> {
+        JsonPath::Index(self, i)
+    }
+
+    fn write_to(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &JsonPath::Root => Ok(()),
+            &JsonPath::Field(&JsonPath::Root, name) => write!(f, "{}", name),
+            &JsonPath::Field(parent, name) => {
+                try!(parent.write_to(f));
+                write!(f, ".{}", name)
+            },
+            &JsonPath::Index(parent, i) => {
+                try!(parent.write_to(f));
+                write!(f, "[{}]", i)
+            }
+        }
+    }
+}
+
+impl <'a> fmt::Display for JsonPath<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &JsonPath::Root => write!(f, "<root>"),
+            _ => self.write_to(f)
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonPresErrorKind {
+    /// The document is not valid JSON
+    Syntax(String),
+    /// A required field is not present
+    MissingField,
+    /// A value has the wrong JSON type; holds a description of the expected type
+    WrongType(&'static str),
+    /// A string value is not one of those permitted; holds a description of what was expected
+    /// and the value that was found
+    UnknownValue(&'static str, String),
+    /// Encoding only: the presentation does not expose its structure, so it cannot be encoded
+    Unencodable,
+    /// Document updates only: no node has the given id
+    UnknownId(String),
+}
+
+
+/// Error raised when decoding the JSON presentation format
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPresError {
+    /// The path of the offending value, e.g. `children[3].thickness`; empty for the root
+    pub path: String,
+    pub kind: JsonPresErrorKind,
+}
+
+impl JsonPresError {
+    pub fn new(path: &JsonPath, kind: JsonPresErrorKind) -> JsonPresError {
+        let p = match path {
+            &JsonPath::Root => String::new(),
+            _ => format!("{}", path)
+        };
+        JsonPresError{path: p, kind: kind}
+    }
+}
+
+impl fmt::Display for JsonPresError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() {"<root>"} else {self.path.as_str()};
+        match self.kind {
+            JsonPresErrorKind::Syntax(ref msg) => write!(f, "invalid JSON: {}", msg),
+            JsonPresErrorKind::MissingField => write!(f, "{}: missing field", path),
+            JsonPresErrorKind::WrongType(expected) => write!(f, "{}: expected {}", path, expected),
+            JsonPresErrorKind::UnknownValue(expected, ref value) =>
+                write!(f, "{}: unknown {} '{}'", path, expected, value),
+            JsonPresErrorKind::Unencodable => write!(f, "{}: cannot encode presentation", path),
+            JsonPresErrorKind::UnknownId(ref id) => write!(f, "{}: no node with id '{}'", path, id),
+        }
+    }
+}
+
+impl Error for JsonPresError {
+    fn description(&self) -> &str {
+        match self.kind {
+            JsonPresErrorKind::Syntax(..) => "invalid JSON",
+            JsonPresErrorKind::MissingField => "missing field",
+            JsonPresErrorKind::WrongType(..) => "value has wrong type",
+            JsonPresErrorKind::UnknownValue(..) => "unknown value",
+            JsonPresErrorKind::Unencodable => "cannot encode presentation",
+            JsonPresErrorKind::UnknownId(..) => "unknown node id",
+        }
+    }
+}
+
+pub type JsonPresResult<T> = Result<T, JsonPresError>;
+
+
+//
+// Value access helpers; these report errors at `path`
+//
+
+pub fn as_object<'j>(j: &'j Json, path: &JsonPath) -> JsonPresResult<&'j Object> {
+    j.as_object().ok_or_else(|| JsonPresError::new(path, JsonPresErrorKind::WrongType("object")))
+}
+
+pub fn as_array<'j>(j: &'j Json, path: &JsonPath) -> JsonPresResult<&'j Vec<Json>> {
+    j.as_array().ok_or_else(|| JsonPresError::new(path, JsonPresErrorKind::WrongType("array")))
+}
+
+pub fn as_str<'j>(j: &'j Json, path: &JsonPath) -> JsonPresResult<&'j str> {
+    j.as_string().ok_or_else(|| JsonPresError::new(path, JsonPresErrorKind::WrongType("string")))
+}
+
+pub fn as_f64(j: &Json, path: &JsonPath) -> JsonPresResult<f64> {
+    j.as_f64().ok_or_else(|| JsonPresError::new(path, JsonPresErrorKind::WrongType("number")))
+}
+
+pub fn as_bool(j: &Json, path: &JsonPath) -> JsonPresResult<bool> {
+    j.as_boolean().ok_or_else(|| JsonPresError::new(path,
+                                                     JsonPresErrorKind::WrongType("boolean")))
+}
+
+pub fn field<'j>(obj: &'j Object, name: &str, path: &JsonPath) -> JsonPresResult<&'j Json> {
+    obj.get(name).ok_or_else(|| JsonPresError::new(&path.field(name),
+                                                   JsonPresErrorKind::MissingField))
+}
+
+pub fn str_field<'j>(obj: &'j Object, name: &str, path: &JsonPath) -> JsonPresResult<&'j str> {
+    as_str(try!(field(obj, name, path)), &path.field(name))
+}
+
+pub fn f64_field(obj: &Object, name: &str, path: &JsonPath) -> JsonPresResult<f64> {
+    as_f64(try!(field(obj, name, path)), &path.field(name))
+}
+
+/// Decode an optional field; fields whose value is `null` are treated as absent
+pub fn optional_field<T, F>(obj: &Object, name: &str, path: &JsonPath,
+                        decode: F) -> JsonPresResult<Option<T>>
+        where F: Fn(&Json, &JsonPath) -> JsonPresResult<T> {
+    match obj.get(name) {
+        None => Ok(None),
+        Some(&Json::Null) => Ok(None),
+        Some(j) => decode(j, &path.field(name)).map(|x| Some(x))
+    }
+}
+
+
+//
+// Decoders for value types
+//
+
+/// Decode JSON representation of `TextWeight`
+pub fn decode_text_weight(j: &Json, path: &JsonPath) -> JsonPresResult<TextWeight> {
+    match try!(as_str(j, path)) {
+        "normal" => Ok(TextWeight::Normal),
+        "bold" => Ok(TextWeight::Bold),
+        x => Err(JsonPresError::new(path, JsonPresErrorKind::UnknownValue("text weight",
+                                                                          x.to_string())))
+    }
+}
+
+/// Decode JSON representation of `TextSlant`
+pub fn decode_text_slant(j: &Json, path: &JsonPath) -> JsonPresResult<TextSlant> {
+    match try!(as_str(j, path)) {
+        "normal" => Ok(TextSlant::Normal),
+        "italic" => Ok(TextSlant::Italic),
+        x => Err(JsonPresError::new(path, JsonPresErrorKind::UnknownValue("text slant",
+                                                                          x.to_string())))
+    }
+}
+
+/// Decode JSON representation of `Colour`
+pub fn decode_colour(j: &Json, path: &JsonPath) -> JsonPresResult<Colour> {
+    match j {
+        &Json::String(ref s) => return Colour::parse(s).ok_or_else(
+            || JsonPresError::new(path, JsonPresErrorKind::UnknownValue("colour", s.clone()))),
+        _ => {}
+    }
+    let obj = try!(as_object(j, path));
+    Ok(Colour::new(try!(f64_field(obj, "r", path)) as f32, try!(f64_field(obj, "g", path)) as f32,
+                   try!(f64_field(obj, "b", path)) as f32, try!(f64_field(obj, "a", path)) as f32))
+}
+
+/// Decode JSON representation of `FlowIndent`
+pub fn decode_flow_indent(j: &Json, path: &JsonPath) -> JsonPresResult<FlowIndent> {
+    let obj = try!(as_object(j, path));
+    match try!(str_field(obj, "indent_type", path)) {
+        "no_indent" => Ok(FlowIndent::NoIndent),
+        "first" => Ok(FlowIndent::First{indent: try!(f64_field(obj, "indent", path))}),
+        "except_first" => Ok(FlowIndent::ExceptFirst{indent: try!(f64_field(obj, "indent", path))}),
+        x => Err(JsonPresError::new(&path.field("indent_type"),
+                                    JsonPresErrorKind::UnknownValue("indent type", x.to_string())))
+    }
+}
+
+/// Decode JSON representation of `Point2`
+pub fn decode_point(j: &Json, path: &JsonPath) -> JsonPresResult<Point2> {
+    let obj = try!(as_object(j, path));
+    Ok(Point2::new(try!(f64_field(obj, "x", path)), try!(f64_field(obj, "y", path))))
+}
+
+/// Decode JSON representation of `ColourStop`
+pub fn decode_colour_stop(j: &Json, path: &JsonPath) -> JsonPresResult<ColourStop> {
+    let obj = try!(as_object(j, path));
+    Ok(ColourStop::new(try!(f64_field(obj, "offset", path)),
+                       try!(decode_colour(try!(field(obj, "colour", path)),
+                                          &path.field("colour")))))
+}
+
+fn decode_colour_stops(obj: &Object, path: &JsonPath) -> JsonPresResult<Vec<ColourStop>> {
+    let stops_path = path.field("stops");
+    let items = try!(as_array(try!(field(obj, "stops", path)), &stops_path));
+    let mut stops = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        stops.push(try!(decode_colour_stop(item, &stops_path.index(i))));
+    }
+    Ok(stops)
+}
+
+/// Decode JSON representation of `Paint`; a colour is decoded as a solid paint
+pub fn decode_paint(j: &Json, path: &JsonPath) -> JsonPresResult<Paint> {
+    let obj = match j {
+        &Json::Object(ref obj) if obj.contains_key("paint_type") => obj,
+        _ => return decode_colour(j, path).map(|c| Paint::Solid(c))
+    };
+    let point_field = |name: &str| field(obj, name, path).and_then(
+        |p| decode_point(p, &path.field(name)));
+    match try!(str_field(obj, "paint_type", path)) {
+        "solid" => Ok(Paint::Solid(try!(decode_colour(try!(field(obj, "colour", path)),
+                                                      &path.field("colour"))))),
+        "linear_gradient" => Ok(Paint::LinearGradient{
+            start: try!(point_field("start")),
+            end: try!(point_field("end")),
+            stops: try!(decode_colour_stops(obj, path))}),
+        "radial_gradient" => Ok(Paint::RadialGradient{
+            centre: try!(point_field("centre")),
+            radius: try!(f64_field(obj, "radius", path)),
+            stops: try!(decode_colour_stops(obj, path))}),
+        "image" => Ok(Paint::ImagePattern{path: try!(str_field(obj, "path", path)).to_string()}),
+        x => Err(JsonPresError::new(&path.field("paint_type"),
+                                    JsonPresErrorKind::UnknownValue("paint type", x.to_string())))
+    }
+}
+
+fn decode_background(obj: &Object, path: &JsonPath) -> JsonPresResult<Option<Paint>> {
+    match try!(optional_field(obj, "background", path, decode_paint)) {
+        Some(p) => Ok(Some(p)),
+        None => optional_field(obj, "background_colour", path, decode_colour).map(
+            |c| c.map(|c| Paint::Solid(c)))
+    }
+}
+
+/// Decode JSON representation of `Shadow`
+pub fn decode_shadow(j: &Json, path: &JsonPath) -> JsonPresResult<Shadow> {
+    let obj = try!(as_object(j, path));
+    Ok(Shadow::new(
+        Vector2::new(try!(f64_field(obj, "offset_x", path)),
+                     try!(f64_field(obj, "offset_y", path))),
+        try!(f64_field(obj, "blur", path)),
+        try!(f64_field(obj, "spread", path)),
+        try!(f64_field(obj, "rounding", path)),
+        try!(decode_colour(try!(field(obj, "colour", path)), &path.field("colour")))))
+}
+
+/// Decode JSON representation of `Stroke`
+pub fn decode_stroke(j: &Json, path: &JsonPath) -> JsonPresResult<Stroke> {
+    let obj = try!(as_object(j, path));
+    Ok(Stroke::new(try!(f64_field(obj, "width", path)),
+                   try!(decode_colour(try!(field(obj, "colour", path)), &path.field("colour")))))
+}
+
+/// Decode JSON representation of `Shape`
+pub fn decode_shape(j: &Json, path: &JsonPath) -> JsonPresResult<Shape> {
+    let obj = try!(as_object(j, path));
+    let geometry = match try!(str_field(obj, "shape_type", path)) {
+        "rectangle" => ShapeGeometry::Rectangle{
+            lower: try!(decode_point(try!(field(obj, "lower", path)), &path.field("lower"))),
+            size: Vector2::new(try!(f64_field(obj, "width", path)),
+                               try!(f64_field(obj, "height", path))),
+            rounding: try!(f64_field(obj, "rounding", path)),
+        },
+        "ellipse" => ShapeGeometry::Ellipse{
+            centre: try!(decode_point(try!(field(obj, "centre", path)), &path.field("centre"))),
+            radii: Vector2::new(try!(f64_field(obj, "radius_x", path)),
+                                try!(f64_field(obj, "radius_y", path))),
+        },
+        "polyline" => {
+            let points_path = path.field("points");
+            let points_json = try!(as_array(try!(field(obj, "points", path)), &points_path));
+            let mut points = Vec::with_capacity(points_json.len());
+            for (i, p) in points_json.iter().enumerate() {
+                points.push(try!(decode_point(p, &points_path.index(i))));
+            }
+            ShapeGeometry::Polyline{
+                points: points,
+                closed: try!(as_bool(try!(field(obj, "closed", path)), &path.field("closed"))),
+            }
+        },
+        x => return Err(JsonPresError::new(&path.field("shape_type"),
+                                           JsonPresErrorKind::UnknownValue("shape type",
+                                                                           x.to_string())))
+    };
+    Ok(Shape{geometry: geometry,
+             fill: try!(optional_field(obj, "fill", path, decode_colour)),
+             stroke: try!(optional_field(obj, "stroke", path, decode_stroke))})
+}
+
+/// Decode JSON representation of `StrokeStyle`
+pub fn decode_stroke_style(j: &Json, path: &JsonPath) -> JsonPresResult<StrokeStyle> {
+    match try!(as_str(j, path)) {
+        "solid" => Ok(StrokeStyle::Solid),
+        "dashed" => Ok(StrokeStyle::Dashed),
+        "dotted" => Ok(StrokeStyle::Dotted),
+        x => Err(JsonPresError::new(path, JsonPresErrorKind::UnknownValue("stroke style",
+                                                                           x.to_string())))
+    }
+}
+
+/// Decode JSON representation of `BorderSide`
+pub fn decode_border_side(j: &Json, path: &JsonPath) -> JsonPresResult<BorderSide> {
+    let obj = try!(as_object(j, path));
+    Ok(BorderSide::new(
+        try!(f64_field(obj, "thickness", path)),
+        try!(decode_colour(try!(field(obj, "colour", path)), &path.field("colour"))),
+        try!(optional_field(obj, "style", path, decode_stroke_style)).unwrap_or(
+            StrokeStyle::Solid)))
+}
+
+/// Decode JSON representation of `CornerRadii`
+pub fn decode_corner_radii(j: &Json, path: &JsonPath) -> JsonPresResult<CornerRadii> {
+    match j {
+        &Json::Object(ref obj) => Ok(CornerRadii::new(
+            try!(f64_field(obj, "top_left", path)), try!(f64_field(obj, "top_right", path)),
+            try!(f64_field(obj, "bottom_right", path)),
+            try!(f64_field(obj, "bottom_left", path)))),
+        _ => as_f64(j, path).map(|r| CornerRadii::uniform(r))
+    }
+}
+
+/// Decode JSON representation of `Border`
+pub fn decode_border(j: &Json, path: &JsonPath) -> JsonPresResult<Border> {
+    let obj = try!(as_object(j, path));
+    let border = match try!(str_field(obj, "border_type", path)) {
+        "solid" => Border::new_solid(
+            try!(f64_field(obj, "thickness", path)),
+            try!(f64_field(obj, "inset", path)),
+            try!(f64_field(obj, "rounding", path)),
+            try!(decode_colour(try!(field(obj, "colour", path)), &path.field("colour"))),
+            None
+        ),
+        "filled" => Border::new_filled(
+            try!(f64_field(obj, "left_margin", path)),
+            try!(f64_field(obj, "right_margin", path)),
+            try!(f64_field(obj, "top_margin", path)),
+            try!(f64_field(obj, "bottom_margin", path)),
+            try!(f64_field(obj, "rounding", path)),
+            None
+        ),
+        "sided" => {
+            let side = |name: &str| optional_field(obj, name, path, decode_border_side).map(
+                |s| s.unwrap_or(BorderSide::none()));
+            Border::new_sided(
+                try!(side("left")), try!(side("right")), try!(side("top")), try!(side("bottom")),
+                try!(f64_field(obj, "inset", path)),
+                try!(optional_field(obj, "radii", path, decode_corner_radii)).unwrap_or(
+                    CornerRadii::uniform(0.0)),
+                None
+            )
+        },
+        x => return Err(JsonPresError::new(&path.field("border_type"),
+                                           JsonPresErrorKind::UnknownValue("border type",
+                                                                           x.to_string())))
+    };
+    Ok(border.with_background(try!(decode_background(obj, path))))
+}
+
+/// Decode JSON representation of `StateBorders`
+pub fn decode_state_borders(j: &Json, path: &JsonPath) -> JsonPresResult<StateBorders> {
+    let obj = try!(as_object(j, path));
+    let state = |name: &str| optional_field(obj, name, path, decode_border).map(
+        |b| b.map(|b| Rc::new(b)));
+    Ok(StateBorders{
+        normal: Rc::new(try!(decode_border(try!(field(obj, "normal", path)),
+                                           &path.field("normal")))),
+        hovered: try!(state("hovered")),
+        pressed: try!(state("pressed")),
+        focused: try!(state("focused")),
+        disabled: try!(state("disabled")),
+    })
+}
+
+/// Decode style values from the fields of a JSON object; absent fields are `None`
+pub fn decode_style_values(j: &Json, path: &JsonPath) -> JsonPresResult<StyleValues> {
+    let obj = try!(as_object(j, path));
+    Ok(StyleValues{
+        text_font_family: try!(optional_field(obj, "text_font_family", path,
+                                              |x, p| as_str(x, p).map(|s| s.to_string()))),
+        text_weight: try!(optional_field(obj, "text_weight", path, decode_text_weight)),
+        text_slant: try!(optional_field(obj, "text_slant", path, decode_text_slant)),
+        text_size: try!(optional_field(obj, "text_size", path, as_f64)),
+        text_colour: try!(optional_field(obj, "text_colour", path, decode_colour)),
+
+        column_y_spacing: try!(optional_field(obj, "column_y_spacing", path, as_f64)),
+
+        row_x_spacing: try!(optional_field(obj, "row_x_spacing", path, as_f64)),
+
+        flow_x_spacing: try!(optional_field(obj, "flow_x_spacing", path, as_f64)),
+        flow_y_spacing: try!(optional_field(obj, "flow_y_spacing", path, as_f64)),
+        flow_indentation: try!(optional_field(obj, "flow_indentation", path,
+                                              decode_flow_indent)),
+    })
+}
+
+
+/// Decode JSON representation of `Align`
+pub fn decode_align(j: &Json, path: &JsonPath) -> JsonPresResult<Align> {
+    match try!(as_str(j, path)) {
+        "start" => Ok(Align::Start),
+        "centre" => Ok(Align::Centre),
+        "end" => Ok(Align::End),
+        "fill" => Ok(Align::Fill),
+        x => Err(JsonPresError::new(path, JsonPresErrorKind::UnknownValue("alignment",
+                                                                          x.to_string())))
+    }
+}
+
+/// Decode JSON representation of `Alignment`; missing axes fill
+pub fn decode_alignment(j: &Json, path: &JsonPath) -> JsonPresResult<Alignment> {
+    let obj = try!(as_object(j, path));
+    Ok(Alignment::new(try!(optional_field(obj, "x", path, decode_align)).unwrap_or(Align::Fill),
+                      try!(optional_field(obj, "y", path, decode_align)).unwrap_or(Align::Fill)))
+}
+
+//
+// Presentation decoder
+//
+
+fn decode_children(obj: &Object, path: &JsonPath) -> JsonPresResult<Vec<Pres>> {
+    let children_path = path.field("children");
+    let children = try!(as_array(try!(field(obj, "children", path)), &children_path));
+    let mut pres = Vec::with_capacity(children.len());
+    for (i, child) in children.iter().enumerate() {
+        pres.push(try!(decode_at(child, &children_path.index(i))));
+    }
+    Ok(pres)
+}
+
+fn decode_child(obj: &Object, path: &JsonPath) -> JsonPresResult<Pres> {
+    decode_at(try!(field(obj, "child", path)), &path.field("child"))
+}
+
+/// Hashes the encoded text of JSON values
+struct JsonHasher {
+    hash: u64,
+}
+
+impl fmt::Write for JsonHasher {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // FNV-1a
+        for b in s.bytes() {
+            self.hash = (self.hash ^ (b as u64)).wrapping_mul(0x100000001b3);
+        }
+        Ok(())
+    }
+}
+
+/// A hash of the values in `items`; equal arrays have the same hash
+fn hash_json_array(items: &Vec<Json>) -> u64 {
+    let mut hasher = JsonHasher{hash: 0xcbf29ce484222325};
+    for item in items.iter() {
+        fmt::Write::write_fmt(&mut hasher, format_args!("{},", item)).unwrap();
+    }
+    hasher.hash
+}
+
+/// Decode the row `row` of a lazy column whose rows are at `rows_path`; errors are presented
+/// in place of the row
+fn decode_lazy_row(row: &Json, rows_path: &str, index: usize) -> Pres {
+    match decode_at(row, &JsonPath::Root) {
+        Ok(p) => p,
+        Err(e) => {
+            let row_path = format!("{}[{}]", rows_path, index);
+            let path = if e.path.is_empty() {
+                row_path
+            } else {
+                format!("{}.{}", row_path, e.path)
+            };
+            let e = JsonPresError{path: path, kind: e.kind};
+            primitive::Text::new_inherited(format!("{}", e))
+        }
+    }
+}
+
+/// Decode the presentation node `j` that is located at `path`
+fn decode_node(j: &Json, obj: &Object, path: &JsonPath) -> JsonPresResult<Pres> {
+    match try!(str_field(obj, "__type__", path)) {
+        "Text" => Ok(primitive::Text::new_inherited(
+                try!(str_field(obj, "text", path)).to_string())),
+        "Column" => Ok(primitive::Column::new(try!(decode_children(obj, path)))),
+        "Row" => Ok(primitive::Row::new(try!(decode_children(obj, path)))),
+        "Flow" => Ok(primitive::Flow::new(try!(decode_children(obj, path)))),
+        "Stack" => {
+            let children = try!(decode_children(obj, path));
+            let aligns_path = path.field("alignments");
+            let no_aligns = vec![];
+            let aligns_json = match obj.get("alignments") {
+                None | Some(&Json::Null) => &no_aligns,
+                Some(j) => try!(as_array(j, &aligns_path))
+            };
+            let mut aligned = Vec::with_capacity(children.len());
+            for (i, child) in children.into_iter().enumerate() {
+                let align = match aligns_json.get(i) {
+                    Some(a) => try!(decode_alignment(a, &aligns_path.index(i))),
+                    None => Alignment::fill()
+                };
+                aligned.push((child, align));
+            }
+            Ok(primitive::Stack::new_aligned(aligned))
+        },
+        "Canvas" => {
+            let children = try!(decode_children(obj, path));
+            let positions_path = path.field("positions");
+            let positions = try!(as_array(try!(field(obj, "positions", path)), &positions_path));
+            let mut placed = Vec::with_capacity(children.len());
+            for (i, child) in children.into_iter().enumerate() {
+                let pos_path = positions_path.index(i);
+                let pos = match positions.get(i) {
+                    Some(p) => try!(decode_point(p, &pos_path)),
+                    None => {
+                        return Err(JsonPresError::new(&pos_path, JsonPresErrorKind::MissingField));
+                    }
+                };
+                placed.push((child, pos));
+            }
+            Ok(primitive::Canvas::new(placed))
+        },
+        "LazyColumn" => {
+            let rows_path = path.field("rows");
+            let rows = try!(as_array(try!(field(obj, "rows", path)), &rows_path));
+            let estimated_row_height = try!(f64_field(obj, "estimated_row_height", path));
+            let view_height = try!(optional_field(obj, "view_height", path, as_f64));
+            // Rows are only decoded when they are built, so an invalid row is reported in its
+            // place. The hash identifies the rows, so that decoding the same rows again keeps
+            // the rows that the column has built.
+            let rows_id = hash_json_array(rows);
+            let item_count = rows.len();
+            let rows = Rc::new(rows.clone());
+            let rows_path = format!("{}", rows_path);
+            let factory: Rc<Fn(usize) -> Pres> = Rc::new(move |i| {
+                decode_lazy_row(&rows[i], &rows_path, i)
+            });
+            Ok(primitive::LazyColumn::new_with_rows_id(item_count, factory,
+                                                       estimated_row_height, view_height,
+                                                       rows_id))
+        },
+        "Border" => {
+            let child = try!(decode_child(obj, path));
+            let border = try!(decode_border(j, path));
+            Ok(primitive::Border::new(child, Rc::new(border)))
+        },
+        "Shadow" => {
+            let child = try!(decode_child(obj, path));
+            let shadow = try!(decode_shadow(j, path));
+            Ok(primitive::Shadow::new(child, Rc::new(shadow)))
+        },
+        "StateBorder" => {
+            let child = try!(decode_child(obj, path));
+            let borders = try!(decode_state_borders(try!(field(obj, "borders", path)),
+                                                    &path.field("borders")));
+            let focusable = try!(optional_field(obj, "focusable", path, as_bool));
+            let disabled = try!(optional_field(obj, "disabled", path, as_bool));
+            Ok(primitive::StateBorder::new_full(child, Rc::new(borders),
+                                                focusable.unwrap_or(false),
+                                                disabled.unwrap_or(false)))
+        },
+        "Shape" => {
+            let shapes_path = path.field("shapes");
+            let shapes_json = try!(as_array(try!(field(obj, "shapes", path)), &shapes_path));
+            let mut shapes = Vec::with_capacity(shapes_json.len());
+            for (i, s) in shapes_json.iter().enumerate() {
+                shapes.push(try!(decode_shape(s, &shapes_path.index(i))));
+            }
+            Ok(primitive::Shape::new(try!(f64_field(obj, "width", path)),
+                                     try!(f64_field(obj, "height", path)), Rc::new(shapes)))
+        },
+        "Expander" => {
+            let header = try!(decode_at(try!(field(obj, "header", path)), &path.field("header")));
+            let body = try!(decode_at(try!(field(obj, "body", path)), &path.field("body")));
+            let expanded = try!(optional_field(obj, "expanded", path, as_bool));
+            Ok(primitive::Expander::new(header, body, expanded.unwrap_or(false)))
+        },
+        "ApplyStyleSheet" => {
+            let child = try!(decode_child(obj, path));
+            Ok(ApplyStyleSheet::new(child, try!(decode_style_values(j, path))))
+        },
+        "ApplyStyleClass" => {
+            let child = try!(decode_child(obj, path));
+            Ok(ApplyStyleClass::new(child, try!(str_field(obj, "class", path)).to_string()))
+        },
+        "DefineStyleClasses" => {
+            let child = try!(decode_child(obj, path));
+            let classes_path = path.field("classes");
+            let classes_obj = try!(as_object(try!(field(obj, "classes", path)), &classes_path));
+            let mut classes = Vec::with_capacity(classes_obj.len());
+            for (name, values) in classes_obj.iter() {
+                classes.push((name.clone(),
+                              try!(decode_style_values(values, &classes_path.field(name)))));
+            }
+            Ok(DefineStyleClasses::new(child, classes))
+        },
+        x => Err(JsonPresError::new(&path.field("__type__"),
+                                    JsonPresErrorKind::UnknownValue("node type", x.to_string())))
+    }
+}
+
+/// Decode the event binding fields of a node
+fn decode_event_bindings(obj: &Object, path: &JsonPath) -> JsonPresResult<EventBindings> {
+    let string_field = |name| optional_field(obj, name, path,
+                                             |x, p| as_str(x, p).map(|s| s.to_string()));
+    Ok(EventBindings{on_click: try!(string_field("on_click")),
+                     on_hover: try!(string_field("on_hover")),
+                     on_key: try!(string_field("on_key"))})
+}
+
+/// Decode the presentation node `j` that is located at `path`
+pub fn decode_at(j: &Json, path: &JsonPath) -> JsonPresResult<Pres> {
+    let obj = try!(as_object(j, path));
+    let mut node = try!(decode_node(j, obj, path));
+    match try!(optional_field(obj, "style", path, decode_style_values)) {
+        Some(values) => {node = ApplyStyleSheet::new(node, values);},
+        None => {}
+    }
+    let bindings = try!(decode_event_bindings(obj, path));
+    if !bindings.is_empty() {
+        node = EventBinding::new(node, bindings);
+    }
+    // The id is given to the outermost element, so that events reported by the event binding
+    // element carry it
+    match try!(optional_field(obj, "id", path, |i, p| as_str(i, p).map(|x| x.to_string()))) {
+        Some(id) => {node = WithId::new(node, id);},
+        None => {}
+    }
+    let key = try!(optional_field(obj, "key", path, |k, p| as_str(k, p).map(|x| x.to_string())));
+    Ok(match key {
+        Some(k) => Keyed::new(node, k),
+        None => node
+    })
+}
+
+/// Decode a presentation from its JSON representation
+pub fn decode(j: &Json) -> JsonPresResult<Pres> {
+    decode_at(j, &JsonPath::Root)
+}
+
+/// Parse and decode a presentation from a JSON string
+pub fn decode_str(s: &str) -> JsonPresResult<Pres> {
+    match Json::from_str(s) {
+        Ok(j) => decode(&j),
+        Err(e) => Err(JsonPresError{path: String::new(),
+                                    kind: JsonPresErrorKind::Syntax(format!("{:?}", e))})
+    }
+}
+
+//
+// Encoders for value types
+//
+
+fn object_with_type(type_name: &str) -> Object {
+    let mut obj = Object::new();
+    obj.insert("__type__".to_string(), Json::String(type_name.to_string()));
+    obj
+}
+
+fn insert_f64(obj: &mut Object, name: &str, x: f64) {
+    obj.insert(name.to_string(), Json::F64(x));
+}
+
+/// Encode `TextWeight` as JSON
+pub fn encode_text_weight(x: TextWeight) -> Json {
+    Json::String(match x {
+        TextWeight::Normal => "normal",
+        TextWeight::Bold => "bold",
+    }.to_string())
+}
+
+/// Encode `TextSlant` as JSON
+pub fn encode_text_slant(x: TextSlant) -> Json {
+    Json::String(match x {
+        TextSlant::Normal => "normal",
+        TextSlant::Italic => "italic",
+    }.to_string())
+}
+
+/// Encode `Colour` as JSON
+pub fn encode_colour(c: &Colour) -> Json {
+    let mut obj = Object::new();
+    insert_f64(&mut obj, "r", c.r as f64);
+    insert_f64(&mut obj, "g", c.g as f64);
+    insert_f64(&mut obj, "b", c.b as f64);
+    insert_f64(&mut obj, "a", c.a as f64);
+    Json::Object(obj)
+}
+
+/// Encode `Align` as JSON
+pub fn encode_align(x: Align) -> Json {
+    Json::String(match x {
+        Align::Start => "start",
+        Align::Centre => "centre",
+        Align::End => "end",
+        Align::Fill => "fill",
+    }.to_string())
+}
+
+/// Encode `Alignment` as JSON
+pub fn encode_alignment(x: &Alignment) -> Json {
+    let mut obj = Object::new();
+    obj.insert("x".to_string(), encode_align(x.x));
+    obj.insert("y".to_string(), encode_align(x.y));
+    Json::Object(obj)
+}
+
+/// Encode `FlowIndent` as JSON
+pub fn encode_flow_indent(x: &FlowIndent) -> Json {
+    let mut obj = Object::new();
+    let (indent_type, indent) = match x {
+        &FlowIndent::NoIndent => ("no_indent", None),
+        &FlowIndent::First{indent} => ("first", Some(indent)),
+        &FlowIndent::ExceptFirst{indent} => ("except_first", Some(indent)),
+    };
+    obj.insert("indent_type".to_string(), Json::String(indent_type.to_string()));
+    match indent {
+        Some(i) => insert_f64(&mut obj, "indent", i),
+        None => {}
+    }
+    Json::Object(obj)
+}
+
+/// Encode `Point2` as JSON
+pub fn encode_point(p: &Point2) -> Json {
+    let mut obj = Object::new();
+    insert_f64(&mut obj, "x", p.x);
+    insert_f64(&mut obj, "y", p.y);
+    Json::Object(obj)
+}
+
+fn encode_colour_stops(stops: &Vec<ColourStop>) -> Json {
+    Json::Array(stops.iter().map(|s| {
+        let mut obj = Object::new();
+        insert_f64(&mut obj, "offset", s.offset);
+        obj.insert("colour".to_string(), encode_colour(&s.colour));
+        Json::Object(obj)
+    }).collect())
+}
+
+/// Encode `Paint` as JSON
+pub fn encode_paint(paint: &Paint) -> Json {
+    let mut obj = Object::new();
+    match paint {
+        &Paint::Solid(ref c) => {
+            obj.insert("paint_type".to_string(), Json::String("solid".to_string()));
+            obj.insert("colour".to_string(), encode_colour(c));
+        },
+        &Paint::LinearGradient{ref start, ref end, ref stops} => {
+            obj.insert("paint_type".to_string(), Json::String("linear_gradient".to_string()));
+            obj.insert("start".to_string(), encode_point(start));
+            obj.insert("end".to_string(), encode_point(end));
+            obj.insert("stops".to_string(), encode_colour_stops(stops));
+        },
+        &Paint::RadialGradient{ref centre, radius, ref stops} => {
+            obj.insert("paint_type".to_string(), Json::String("radial_gradient".to_string()));
+            obj.insert("centre".to_string(), encode_point(centre));
+            insert_f64(&mut obj, "radius", radius);
+            obj.insert("stops".to_string(), encode_colour_stops(stops));
+        },
+        &Paint::ImagePattern{ref path} => {
+            obj.insert("paint_type".to_string(), Json::String("image".to_string()));
+            obj.insert("path".to_string(), Json::String(path.clone()));
+        }
+    }
+    Json::Object(obj)
+}
+
+fn encode_background(background: &Option<Paint>, obj: &mut Object) {
+    match background {
+        // Solid backgrounds use the simpler `background_colour` form
+        &Some(Paint::Solid(ref c)) => {
+            obj.insert("background_colour".to_string(), encode_colour(c));
+        },
+        &Some(ref p) => {
+            obj.insert("background".to_string(), encode_paint(p));
+        },
+        &None => {}
+    }
+}
+
+/// Encode `BorderSide` as JSON
+pub fn encode_border_side(side: &BorderSide) -> Json {
+    let mut obj = Object::new();
+    insert_f64(&mut obj, "thickness", side.thickness);
+    obj.insert("colour".to_string(), encode_colour(&side.colour));
+    let style = match side.style {
+        StrokeStyle::Solid => "solid",
+        StrokeStyle::Dashed => "dashed",
+        StrokeStyle::Dotted => "dotted",
+    };
+    obj.insert("style".to_string(), Json::String(style.to_string()));
+    Json::Object(obj)
+}
+
+/// Encode `CornerRadii` as JSON; equal radii are encoded as a single number
+pub fn encode_corner_radii(radii: &CornerRadii) -> Json {
+    if *radii == CornerRadii::uniform(radii.top_left) {
+        return Json::F64(radii.top_left);
+    }
+    let mut obj = Object::new();
+    insert_f64(&mut obj, "top_left", radii.top_left);
+    insert_f64(&mut obj, "top_right", radii.top_right);
+    insert_f64(&mut obj, "bottom_right", radii.bottom_right);
+    insert_f64(&mut obj, "bottom_left", radii.bottom_left);
+    Json::Object(obj)
+}
+
+/// Add the fields that describe `border` to `obj`
+pub fn encode_border_fields(border: &Border, obj: &mut Object) {
+    match border {
+        &Border::SolidBorder{thickness, inset, rounding, border_colour, ref background} => {
+            obj.insert("border_type".to_string(), Json::String("solid".to_string()));
+            insert_f64(obj, "thickness", thickness);
+            insert_f64(obj, "inset", inset);
+            insert_f64(obj, "rounding", rounding);
+            obj.insert("colour".to_string(), encode_colour(&border_colour));
+            encode_background(background, obj);
+        },
+        &Border::FilledBorder{left_margin, right_margin, top_margin, bottom_margin, rounding,
+                              ref background} => {
+            obj.insert("border_type".to_string(), Json::String("filled".to_string()));
+            insert_f64(obj, "left_margin", left_margin);
+            insert_f64(obj, "right_margin", right_margin);
+            insert_f64(obj, "top_margin", top_margin);
+            insert_f64(obj, "bottom_margin", bottom_margin);
+            insert_f64(obj, "rounding", rounding);
+            encode_background(background, obj);
+        },
+        &Border::SidedBorder{ref left, ref right, ref top, ref bottom, inset, ref radii,
+                             ref background} => {
+            obj.insert("border_type".to_string(), Json::String("sided".to_string()));
+            for &(name, side) in [("left", left), ("right", right), ("top", top),
+                                  ("bottom", bottom)].iter() {
+                if side.thickness > 0.0 {
+                    obj.insert(name.to_string(), encode_border_side(side));
+                }
+            }
+            insert_f64(obj, "inset", inset);
+            obj.insert("radii".to_string(), encode_corner_radii(radii));
+            encode_background(background, obj);
+        }
+    }
+}
+
+/// Encode `Shape` as JSON
+pub fn encode_shape(shape: &Shape) -> Json {
+    let mut obj = Object::new();
+    let shape_type = match shape.geometry {
+        ShapeGeometry::Rectangle{lower, size, rounding} => {
+            obj.insert("lower".to_string(), encode_point(&lower));
+            insert_f64(&mut obj, "width", size.x);
+            insert_f64(&mut obj, "height", size.y);
+            insert_f64(&mut obj, "rounding", rounding);
+            "rectangle"
+        },
+        ShapeGeometry::Ellipse{centre, radii} => {
+            obj.insert("centre".to_string(), encode_point(&centre));
+            insert_f64(&mut obj, "radius_x", radii.x);
+            insert_f64(&mut obj, "radius_y", radii.y);
+            "ellipse"
+        },
+        ShapeGeometry::Polyline{ref points, closed} => {
+            obj.insert("points".to_string(),
+                       Json::Array(points.iter().map(|p| encode_point(p)).collect()));
+            obj.insert("closed".to_string(), Json::Boolean(closed));
+            "polyline"
+        }
+    };
+    obj.insert("shape_type".to_string(), Json::String(shape_type.to_string()));
+    match shape.fill {
+        Some(ref c) => {obj.insert("fill".to_string(), encode_colour(c));},
+        None => {}
+    }
+    match shape.stroke {
+        Some(ref s) => {
+            let mut stroke = Object::new();
+            insert_f64(&mut stroke, "width", s.width);
+            stroke.insert("colour".to_string(), encode_colour(&s.colour));
+            obj.insert("stroke".to_string(), Json::Object(stroke));
+        },
+        None => {}
+    }
+    Json::Object(obj)
+}
+
+/// Encode `StateBorders` as JSON
+pub fn encode_state_borders(borders: &StateBorders) -> Json {
+    let mut obj = Object::new();
+    for &(name, border) in [("normal", Some(&borders.normal)),
+                            ("hovered", borders.hovered.as_ref()),
+                            ("pressed", borders.pressed.as_ref()),
+                            ("focused", borders.focused.as_ref()),
+                            ("disabled", borders.disabled.as_ref())].iter() {
+        match border {
+            Some(b) => {
+                let mut border_obj = Object::new();
+                encode_border_fields(b, &mut border_obj);
+                obj.insert(name.to_string(), Json::Object(border_obj));
+            },
+            None => {}
+        }
+    }
+    Json::Object(obj)
+}
+
+/// Add the fields that describe the style values that are present in `values` to `obj`
+pub fn encode_style_value_fields(values: &StyleValues, obj: &mut Object) {
+    match values.text_font_family {
+        Some(ref x) => {obj.insert("text_font_family".to_string(), Json::String(x.clone()));},
+        None => {}
+    }
+    match values.text_weight {
+        Some(x) => {obj.insert("text_weight".to_string(), encode_text_weight(x));},
+        None => {}
+    }
+    match values.text_slant {
+        Some(x) => {obj.insert("text_slant".to_string(), encode_text_slant(x));},
+        None => {}
+    }
+    match values.text_size {
+        Some(x) => insert_f64(obj, "text_size", x),
+        None => {}
+    }
+    match values.text_colour {
+        Some(ref x) => {obj.insert("text_colour".to_string(), encode_colour(x));},
+        None => {}
+    }
+    match values.column_y_spacing {
+        Some(x) => insert_f64(obj, "column_y_spacing", x),
+        None => {}
+    }
+    match values.row_x_spacing {
+        Some(x) => insert_f64(obj, "row_x_spacing", x),
+        None => {}
+    }
+    match values.flow_x_spacing {
+        Some(x) => insert_f64(obj, "flow_x_spacing", x),
+        None => {}
+    }
+    match values.flow_y_spacing {
+        Some(x) => insert_f64(obj, "flow_y_spacing", x),
+        None => {}
+    }
+    match values.flow_indentation {
+        Some(ref x) => {obj.insert("flow_indentation".to_string(), encode_flow_indent(x));},
+        None => {}
+    }
+}
+
+/// Encode style values as a JSON object
+pub fn encode_style_values(values: &StyleValues) -> Json {
+    let mut obj = Object::new();
+    encode_style_value_fields(values, &mut obj);
+    Json::Object(obj)
+}
+
+fn text_style_values(style: &TextStyleParams) -> StyleValues {
+    StyleValues{text_font_family: Some(style.font_family.clone()),
+                text_weight: Some(style.weight), text_slant: Some(style.slant),
+                text_size: Some(style.size), text_colour: Some(style.colour),
+                ..StyleValues::new()}
+}
+
+/// Wrap an encoded node in an `ApplyStyleSheet` node, if `values` is not empty
+fn wrap_in_style_values(node: Json, values: &StyleValues) -> Json {
+    if values.is_empty() {
+        node
+    } else {
+        let mut obj = object_with_type("ApplyStyleSheet");
+        encode_style_value_fields(values, &mut obj);
+        obj.insert("child".to_string(), node);
+        Json::Object(obj)
+    }
+}
+
+
+//
+// Presentation encoder
+//
+
+struct Encoder<'a> {
+    path: &'a JsonPath<'a>,
+    result: JsonPresResult<Json>,
+}
+
+impl <'a> Encoder<'a> {
+    fn encode_children(&self, children: &Vec<Pres>) -> JsonPresResult<Json> {
+        let children_path = self.path.field("children");
+        let mut encoded = Vec::with_capacity(children.len());
+        for (i, child) in children.iter().enumerate() {
+            encoded.push(try!(encode_at(child, &children_path.index(i))));
+        }
+        Ok(Json::Array(encoded))
+    }
+
+    fn encode_child(&self, child: &Pres) -> JsonPresResult<Json> {
+        encode_at(child, &self.path.field("child"))
+    }
+
+    fn encode_container(&self, type_name: &str, children: &Vec<Pres>) -> JsonPresResult<Json> {
+        let mut obj = object_with_type(type_name);
+        obj.insert("children".to_string(), try!(self.encode_children(children)));
+        Ok(Json::Object(obj))
+    }
+}
+
+impl <'a> TPresVisitor for Encoder<'a> {
+    fn visit_text(&mut self, p: &primitive::Text) {
+        let mut obj = object_with_type("Text");
+        obj.insert("text".to_string(), Json::String(p.text().clone()));
+        let values = match p.style() {
+            Some(s) => text_style_values(s),
+            None => StyleValues::new()
+        };
+        self.result = Ok(wrap_in_style_values(Json::Object(obj), &values));
+    }
+
+    fn visit_border(&mut self, p: &primitive::Border) {
+        self.result = self.encode_child(p.child()).map(|child| {
+            let mut obj = object_with_type("Border");
+            encode_border_fields(p.border(), &mut obj);
+            obj.insert("child".to_string(), child);
+            Json::Object(obj)
+        });
+    }
+
+    fn visit_shadow(&mut self, p: &primitive::Shadow) {
+        self.result = self.encode_child(p.child()).map(|child| {
+            let shadow = p.shadow();
+            let mut obj = object_with_type("Shadow");
+            insert_f64(&mut obj, "offset_x", shadow.offset.x);
+            insert_f64(&mut obj, "offset_y", shadow.offset.y);
+            insert_f64(&mut obj, "blur", shadow.blur);
+            insert_f64(&mut obj, "spread", shadow.spread);
+            insert_f64(&mut obj, "rounding", shadow.rounding);
+            obj.insert("colour".to_string(), encode_colour(&shadow.colour));
+            obj.insert("child".to_string(), child);
+            Json::Object(obj)
+        });
+    }
+
+    /// The activation function of a state border cannot be encoded, and is omitted
+    fn visit_state_border(&mut self, p: &primitive::StateBorder) {
+        self.result = self.encode_child(p.child()).map(|child| {
+            let mut obj = object_with_type("StateBorder");
+            obj.insert("borders".to_string(), encode_state_borders(p.borders()));
+            if p.focusable() {
+                obj.insert("focusable".to_string(), Json::Boolean(true));
+            }
+            if p.disabled() {
+                obj.insert("disabled".to_string(), Json::Boolean(true));
+            }
+            obj.insert("child".to_string(), child);
+            Json::Object(obj)
+        });
+    }
+
+    fn visit_shape(&mut self, p: &primitive::Shape) {
+        let mut obj = object_with_type("Shape");
+        insert_f64(&mut obj, "width", p.width());
+        insert_f64(&mut obj, "height", p.height());
+        obj.insert("shapes".to_string(),
+                   Json::Array(p.shapes().iter().map(|s| encode_shape(s)).collect()));
+        self.result = Ok(Json::Object(obj));
+    }
+
+    /// The toggle function of an expander cannot be encoded, and is omitted
+    fn visit_expander(&mut self, p: &primitive::Expander) {
+        let header = encode_at(p.header(), &self.path.field("header"));
+        let body = encode_at(p.body(), &self.path.field("body"));
+        self.result = header.and_then(|header| body.map(|body| {
+            let mut obj = object_with_type("Expander");
+            if p.expanded() {
+                obj.insert("expanded".to_string(), Json::Boolean(true));
+            }
+            obj.insert("header".to_string(), header);
+            obj.insert("body".to_string(), body);
+            Json::Object(obj)
+        }));
+    }
+
+    fn visit_column(&mut self, p: &primitive::Column) {
+        let values = StyleValues{column_y_spacing: p.y_spacing(), ..StyleValues::new()};
+        self.result = self.encode_container("Column", p.children()).map(
+            |node| wrap_in_style_values(node, &values));
+    }
+
+    fn visit_row(&mut self, p: &primitive::Row) {
+        let values = StyleValues{row_x_spacing: p.x_spacing(), ..StyleValues::new()};
+        self.result = self.encode_container("Row", p.children()).map(
+            |node| wrap_in_style_values(node, &values));
+    }
+
+    fn visit_flow(&mut self, p: &primitive::Flow) {
+        let values = match p.spacing() {
+            Some((x_spacing, y_spacing, indentation)) => StyleValues{
+                flow_x_spacing: Some(x_spacing), flow_y_spacing: Some(y_spacing),
+                flow_indentation: Some(indentation), ..StyleValues::new()},
+            None => StyleValues::new()
+        };
+        self.result = self.encode_container("Flow", p.children()).map(
+            |node| wrap_in_style_values(node, &values));
+    }
+
+    /// Alignments are only encoded if some child does not fill the stack
+    fn visit_stack(&mut self, p: &primitive::Stack) {
+        self.result = self.encode_container("Stack", p.children()).map(|node| match node {
+            Json::Object(mut obj) => {
+                if p.aligns().iter().any(|a| *a != Alignment::fill()) {
+                    obj.insert("alignments".to_string(),
+                               Json::Array(p.aligns().iter().map(encode_alignment).collect()));
+                }
+                Json::Object(obj)
+            },
+            node => node
+        });
+    }
+
+    fn visit_canvas(&mut self, p: &primitive::Canvas) {
+        self.result = self.encode_container("Canvas", p.children()).map(|node| match node {
+            Json::Object(mut obj) => {
+                obj.insert("positions".to_string(),
+                           Json::Array(p.positions().iter().map(encode_point).collect()));
+                Json::Object(obj)
+            },
+            node => node
+        });
+    }
+
+    /// Every row is presented, so that the encoded column does not depend on the factory
+    fn visit_lazy_column(&mut self, p: &primitive::LazyColumn) {
+        let rows_path = self.path.field("rows");
+        let mut rows = Vec::with_capacity(p.item_count());
+        for i in 0..p.item_count() {
+            match encode_at(&p.present_item(i), &rows_path.index(i)) {
+                Ok(row) => {rows.push(row);},
+                Err(e) => {
+                    self.result = Err(e);
+                    return;
+                }
+            }
+        }
+        let mut obj = object_with_type("LazyColumn");
+        obj.insert("rows".to_string(), Json::Array(rows));
+        insert_f64(&mut obj, "estimated_row_height", p.estimated_row_height());
+        match p.view_height() {
+            Some(h) => {insert_f64(&mut obj, "view_height", h);},
+            None => {}
+        }
+        self.result = Ok(Json::Object(obj));
+    }
+
+    fn visit_apply_style_sheet(&mut self, p: &ApplyStyleSheet) {
+        self.result = self.encode_child(p.child()).map(|child| {
+            let mut obj = object_with_type("ApplyStyleSheet");
+            encode_style_value_fields(p.values(), &mut obj);
+            obj.insert("child".to_string(), child);
+            Json::Object(obj)
+        });
+    }
+
+    fn visit_apply_style_class(&mut self, p: &ApplyStyleClass) {
+        self.result = self.encode_child(p.child()).map(|child| {
+            let mut obj = object_with_type("ApplyStyleClass");
+            obj.insert("class".to_string(), Json::String(p.class_name().clone()));
+            obj.insert("child".to_string(), child);
+            Json::Object(obj)
+        });
+    }
+
+    fn visit_define_style_classes(&mut self, p: &DefineStyleClasses) {
+        self.result = self.encode_child(p.child()).map(|child| {
+            let mut classes = Object::new();
+            for &(ref name, ref values) in p.classes().iter() {
+                classes.insert(name.clone(), encode_style_values(values));
+            }
+            let mut obj = object_with_type("DefineStyleClasses");
+            obj.insert("classes".to_string(), Json::Object(classes));
+            obj.insert("child".to_string(), child);
+            Json::Object(obj)
+        });
+    }
+
+    fn visit_keyed(&mut self, p: &Keyed) {
+        self.result = encode_at(p.child(), self.path).and_then(|node| match node {
+            Json::Object(mut obj) => {
+                obj.insert("key".to_string(), Json::String(p.key_string().clone()));
+                Ok(Json::Object(obj))
+            },
+            _ => Err(JsonPresError::new(self.path, JsonPresErrorKind::Unencodable))
+        });
+    }
+
+    fn visit_event_binding(&mut self, p: &EventBinding) {
+        self.result = encode_at(p.child(), self.path).and_then(|node| match node {
+            Json::Object(mut obj) => {
+                let bindings = p.bindings();
+                let fields = [("on_click", &bindings.on_click), ("on_hover", &bindings.on_hover),
+                              ("on_key", &bindings.on_key)];
+                for &(name, value) in fields.iter() {
+                    match value {
+                        &Some(ref v) => {obj.insert(name.to_string(), Json::String(v.clone()));},
+                        &None => {}
+                    }
+                }
+                Ok(Json::Object(obj))
+            },
+            _ => Err(JsonPresError::new(self.path, JsonPresErrorKind::Unencodable))
+        });
+    }
+
+    fn visit_with_id(&mut self, p: &WithId) {
+        self.result = encode_at(p.child(), self.path).and_then(|node| match node {
+            Json::Object(mut obj) => {
+                obj.insert("id".to_string(), Json::String(p.id().clone()));
+                Ok(Json::Object(obj))
+            },
+            _ => Err(JsonPresError::new(self.path, JsonPresErrorKind::Unencodable))
+        });
+    }
+
+    fn visit_opaque(&mut self) {
+        self.result = Err(JsonPresError::new(self.path, JsonPresErrorKind::Unencodable));
+    }
+}
+
+/// Encode the presentation `p` that is located at `path`
+pub fn encode_at(p: &Pres, path: &JsonPath) -> JsonPresResult<Json> {
+    let mut encoder = Encoder{path: path,
+                              result: Err(JsonPresError::new(path,
+                                                             JsonPresErrorKind::Unencodable))};
+    p.accept(&mut encoder);
+    encoder.result
+}
+
+/// Encode a presentation in the JSON presentation format
+pub fn encode(p: &Pres) -> JsonPresResult<Json> {
+    encode_at(p, &JsonPath::Root)
+}
+
+/// Encode a presentation as a pretty-printed JSON string
+pub fn encode_string(p: &Pres) -> JsonPresResult<String> {
+    encode(p).map(|j| format!("{}", json::as_pretty_json(&j)))
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use rustc_serialize::json::Json;
+    use super::*;
+    use elements::text_element::TextStyleParams;
+    use geom::colour::Colour;
+    use graphics::paint::Paint;
+    use graphics::border::{Border, BorderSide, StrokeStyle, CornerRadii, StateBorders};
+    use pres::primitive;
+    use pres::pres::{TPres, PresBuildCtx};
+    use pres::style::{StyleSheet, StyleValues};
+    use elements::element::{TElement, ElementRef};
+    use elements::element_ctx::ElementContext;
+    use elements::lazy_column::LazyColumnElement;
+    use elements::expander_element::ExpanderElement;
+
+    fn decode_err(s: &str) -> JsonPresError {
+        decode_str(s).err().unwrap()
+    }
+
+    fn round_trip(s: &str) {
+        let j = Json::from_str(s).unwrap();
+        let p = decode(&j).ok().unwrap();
+        assert_eq!(encode(&p), Ok(j));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(r#"{"__type__": "Text", "text": "hello"}"#);
+        round_trip(r#"{"__type__": "Column", "children": [
+                {"__type__": "Text", "text": "a"},
+                {"__type__": "Border", "border_type": "solid", "thickness": 1.0, "inset": 2.0,
+                 "rounding": 0.0, "colour": {"r": 0.0, "g": 0.5, "b": 0.0, "a": 1.0},
+                 "child": {"__type__": "Text", "text": "b"}},
+                {"__type__": "Border", "border_type": "filled", "left_margin": 1.0,
+                 "right_margin": 2.0, "top_margin": 3.0, "bottom_margin": 4.0, "rounding": 5.0,
+                 "background_colour": {"r": 1.0, "g": 1.0, "b": 0.25, "a": 1.0},
+                 "child": {"__type__": "Row", "children": []}},
+                {"__type__": "ApplyStyleSheet", "text_size": 20.0, "text_weight": "bold",
+                 "flow_indentation": {"indent_type": "first", "indent": 10.0},
+                 "child": {"__type__": "Flow", "children": [
+                    {"__type__": "Text", "text": "c"}]}}
+            ]}"#);
+        round_trip(r#"{"__type__": "DefineStyleClasses",
+                "classes": {"heading": {"text_size": 24.0, "text_slant": "italic"}},
+                "child": {"__type__": "ApplyStyleClass", "class": "heading",
+                          "child": {"__type__": "Text", "text": "Title"}}}"#);
+        round_trip(r#"{"__type__": "Column", "children": [
+                {"__type__": "Text", "text": "a", "key": "first"},
+                {"__type__": "Text", "text": "b"}]}"#);
+        round_trip(r#"{"__type__": "Row", "key": "row", "id": "node-42",
+                "on_click": "select-node-42", "on_hover": "hover-node-42",
+                "children": []}"#);
+        round_trip(r#"{"__type__": "Text", "text": "x", "id": "line-42"}"#);
+        round_trip(r#"{"__type__": "Shadow", "offset_x": 2.0, "offset_y": 3.0, "blur": 6.0,
+                "spread": 0.0, "rounding": 4.0, "colour": {"r": 0.0, "g": 0.0, "b": 0.0, "a": 0.5},
+                "child": {"__type__": "Text", "text": "card"}}"#);
+        round_trip(r#"{"__type__": "Border", "border_type": "filled", "left_margin": 1.0,
+                "right_margin": 1.0, "top_margin": 1.0, "bottom_margin": 1.0, "rounding": 0.0,
+                "background": {"paint_type": "linear_gradient", "start": {"x": 0.0, "y": 0.0},
+                    "end": {"x": 0.0, "y": 1.0}, "stops": [
+                        {"offset": 0.0, "colour": {"r": 1.0, "g": 1.0, "b": 1.0, "a": 1.0}},
+                        {"offset": 1.0, "colour": {"r": 0.5, "g": 0.5, "b": 0.5, "a": 1.0}}]},
+                "child": {"__type__": "Text", "text": "header"}}"#);
+        round_trip(r#"{"__type__": "Border", "border_type": "filled", "left_margin": 1.0,
+                "right_margin": 1.0, "top_margin": 1.0, "bottom_margin": 1.0, "rounding": 0.0,
+                "background": {"paint_type": "radial_gradient", "centre": {"x": 0.5, "y": 0.5},
+                    "radius": 0.5, "stops": []},
+                "child": {"__type__": "Border", "border_type": "filled", "left_margin": 0.0,
+                    "right_margin": 0.0, "top_margin": 0.0, "bottom_margin": 0.0,
+                    "rounding": 0.0, "background": {"paint_type": "image", "path": "tile.png"},
+                    "child": {"__type__": "Text", "text": "x"}}}"#);
+    }
+
+    #[test]
+    fn test_encode_explicit_style() {
+        let style = Rc::new(TextStyleParams::default());
+        let p = primitive::Column::new_full(vec![primitive::Text::new("a".to_string(), style)],
+                                            2.0);
+        let j = encode(&p).ok().unwrap();
+        let expected = Json::from_str(r#"{"__type__": "ApplyStyleSheet", "column_y_spacing": 2.0,
+            "child": {"__type__": "Column", "children": [
+                {"__type__": "ApplyStyleSheet", "text_font_family": "Sans serif",
+                 "text_weight": "normal", "text_slant": "normal", "text_size": 14.0,
+                 "text_colour": {"r": 0.0, "g": 0.0, "b": 0.0, "a": 1.0},
+                 "child": {"__type__": "Text", "text": "a"}}]}}"#).unwrap();
+        assert_eq!(j, expected);
+        // The encoded form decodes successfully and re-encodes identically
+        let p2 = decode(&j).ok().unwrap();
+        assert_eq!(encode(&p2), Ok(expected));
+    }
+
+    #[test]
+    fn test_decode_valid() {
+        assert!(decode_str(r#"{"__type__": "Text", "text": "hello"}"#).is_ok());
+        assert!(decode_str(r#"{"__type__": "Column", "children": [
+                {"__type__": "Text", "text": "a"},
+                {"__type__": "Border", "border_type": "solid", "thickness": 1, "inset": 2.0,
+                 "rounding": 0, "colour": {"r": 0, "g": 0, "b": 0, "a": 1},
+                 "child": {"__type__": "Text", "text": "b"}},
+                {"__type__": "ApplyStyleSheet", "text_size": 20, "text_weight": "bold",
+                 "flow_indentation": {"indent_type": "first", "indent": 10},
+                 "child": {"__type__": "Flow", "children": []}}
+            ]}"#).is_ok());
+        assert!(decode_str(r#"{"__type__": "DefineStyleClasses",
+                "classes": {"heading": {"text_size": 24}},
+                "child": {"__type__": "ApplyStyleClass", "class": "heading",
+                          "child": {"__type__": "Text", "text": "Title"}}}"#).is_ok());
+    }
+
+    #[test]
+    fn test_decode_colour_string() {
+        assert_eq!(decode_colour(&Json::String("#ff000080".to_string()), &JsonPath::Root),
+                   Ok(Colour::new(1.0, 0.0, 0.0, 128.0 / 255.0)));
+        assert_eq!(decode_colour(&Json::String("navy".to_string()), &JsonPath::Root),
+                   Ok(Colour::new(0.0, 0.0, 128.0 / 255.0, 1.0)));
+        assert_eq!(decode_err(r#"{"__type__": "ApplyStyleSheet", "text_colour": "blurple",
+                "child": {"__type__": "Text", "text": "a"}}"#),
+                   JsonPresError{path: "text_colour".to_string(),
+                                 kind: JsonPresErrorKind::UnknownValue("colour",
+                                                                       "blurple".to_string())});
+    }
+
+    #[test]
+    fn test_sided_border() {
+        round_trip(r#"{"__type__": "Border", "border_type": "sided", "inset": 2.0,
+                "left": {"thickness": 3.0, "colour": {"r": 0.5, "g": 0.5, "b": 0.5, "a": 1.0},
+                         "style": "solid"},
+                "radii": {"top_left": 0.0, "top_right": 4.0, "bottom_right": 4.0,
+                          "bottom_left": 0.0},
+                "child": {"__type__": "Text", "text": "quote"}}"#);
+        let p = decode_border(&Json::from_str(r#"{"border_type": "sided", "inset": 0,
+                "bottom": {"thickness": 1, "colour": "black", "style": "dashed"},
+                "radii": 2}"#).unwrap(), &JsonPath::Root).ok().unwrap();
+        assert_eq!(p.left_margin(), 0.0);
+        assert_eq!(p.bottom_margin(), 1.0);
+        assert_eq!(p, Border::new_sided(BorderSide::none(), BorderSide::none(), BorderSide::none(),
+                                        BorderSide::new(1.0, Colour::new(0.0, 0.0, 0.0, 1.0),
+                                                        StrokeStyle::Dashed),
+                                        0.0, CornerRadii::uniform(2.0), None));
+        assert_eq!(decode_err(r#"{"__type__": "Border", "border_type": "sided", "inset": 0,
+                "top": {"thickness": 1, "colour": "black", "style": "wavy"},
+                "child": {"__type__": "Text", "text": "a"}}"#),
+                   JsonPresError{path: "top.style".to_string(),
+                                 kind: JsonPresErrorKind::UnknownValue("stroke style",
+                                                                       "wavy".to_string())});
+    }
+
+    #[test]
+    fn test_state_border() {
+        round_trip(r#"{"__type__": "StateBorder", "focusable": true,
+                "borders": {
+                    "normal": {"border_type": "filled", "left_margin": 2.0, "right_margin": 2.0,
+                        "top_margin": 2.0, "bottom_margin": 2.0, "rounding": 3.0},
+                    "hovered": {"border_type": "filled", "left_margin": 2.0,
+                        "right_margin": 2.0, "top_margin": 2.0, "bottom_margin": 2.0,
+                        "rounding": 3.0, "background_colour": {"r": 0.9, "g": 0.9, "b": 1.0,
+                                                               "a": 1.0}}},
+                "child": {"__type__": "Text", "text": "OK"}}"#);
+        let b = decode_state_borders(&Json::from_str(r#"{
+                "normal": {"border_type": "solid", "thickness": 1, "inset": 0, "rounding": 0,
+                    "colour": "black"},
+                "disabled": {"border_type": "solid", "thickness": 1, "inset": 0, "rounding": 0,
+                    "colour": "grey"}}"#).unwrap(), &JsonPath::Root).ok().unwrap();
+        assert_eq!(b, StateBorders::new(Rc::new(Border::new_solid(
+                1.0, 0.0, 0.0, Colour::new(0.0, 0.0, 0.0, 1.0), None))).with_disabled(
+                Rc::new(Border::new_solid(1.0, 0.0, 0.0, Colour::from_name("grey").unwrap(),
+                                          None))));
+        assert_eq!(decode_err(r#"{"__type__": "StateBorder", "borders": {}, "child":
+                {"__type__": "Text", "text": "a"}}"#),
+                   JsonPresError{path: "borders.normal".to_string(),
+                                 kind: JsonPresErrorKind::MissingField});
+    }
+
+    #[test]
+    fn test_shape() {
+        round_trip(r#"{"__type__": "Shape", "width": 16.0, "height": 16.0, "shapes": [
+                {"shape_type": "rectangle", "lower": {"x": 1.0, "y": 1.0}, "width": 14.0,
+                    "height": 14.0, "rounding": 3.0, "fill": {"r": 1.0, "g": 1.0, "b": 1.0,
+                                                              "a": 1.0}},
+                {"shape_type": "ellipse", "centre": {"x": 8.0, "y": 8.0}, "radius_x": 4.0,
+                    "radius_y": 4.0, "stroke": {"width": 1.5, "colour": {"r": 0.0, "g": 0.0,
+                                                                        "b": 0.0, "a": 1.0}}},
+                {"shape_type": "polyline", "points": [{"x": 4.0, "y": 8.0},
+                    {"x": 7.0, "y": 11.0}, {"x": 12.0, "y": 5.0}], "closed": false}]}"#);
+        let e = decode_err(r#"{"__type__": "Shape", "width": 4, "height": 4, "shapes": [
+                {"shape_type": "polyline", "points": [{"x": 0, "y": 0}, {"x": 1}],
+                 "closed": true}]}"#);
+        assert_eq!(e, JsonPresError{path: "shapes[0].points[1].y".to_string(),
+                                    kind: JsonPresErrorKind::MissingField});
+    }
+
+    #[test]
+    fn test_expander() {
+        round_trip(r#"{"__type__": "Expander", "expanded": true,
+                "header": {"__type__": "Text", "text": "children"},
+                "body": {"__type__": "Column", "children": [
+                    {"__type__": "Text", "text": "a"}, {"__type__": "Text", "text": "b"}]}}"#);
+        round_trip(r#"{"__type__": "Expander", "header": {"__type__": "Text", "text": "x"},
+                "body": {"__type__": "Text", "text": "y"}}"#);
+        assert_eq!(decode_err(r#"{"__type__": "Expander", "expanded": "yes",
+                "header": {"__type__": "Text", "text": "x"},
+                "body": {"__type__": "Text", "text": "y"}}"#),
+                   JsonPresError{path: "expanded".to_string(),
+                                 kind: JsonPresErrorKind::WrongType("boolean")});
+        assert_eq!(decode_err(r#"{"__type__": "Expander",
+                "header": {"__type__": "Text", "text": "x"}}"#),
+                   JsonPresError{path: "body".to_string(), kind: JsonPresErrorKind::MissingField});
+
+        // The expanded state is passed to the element
+        let elem_ctx = ElementContext::new();
+        let is_expanded = |expanded: &str| {
+            let s = format!(r#"{{"__type__": "Expander", {}
+                "header": {{"__type__": "Text", "text": "x"}},
+                "body": {{"__type__": "Text", "text": "y"}}}}"#, expanded);
+            let elem = decode_str(s.as_str()).ok().unwrap().build(&PresBuildCtx::new(&elem_ctx));
+            let x = elem.as_any().downcast_ref::<ExpanderElement>().unwrap().is_expanded();
+            x
+        };
+        assert!(is_expanded(r#""expanded": true,"#));
+        assert!(!is_expanded(r#""expanded": false,"#));
+        assert!(!is_expanded(""));
+    }
+
+    #[test]
+    fn test_stack() {
+        round_trip(r#"{"__type__": "Stack", "children": [
+                {"__type__": "Text", "text": "back"}, {"__type__": "Text", "text": "front"}]}"#);
+        round_trip(r#"{"__type__": "Stack", "children": [
+                {"__type__": "Text", "text": "back"}, {"__type__": "Text", "text": "badge"}],
+                "alignments": [{"x": "fill", "y": "fill"}, {"x": "end", "y": "start"}]}"#);
+        let p = decode_str(r#"{"__type__": "Stack", "children": [
+                {"__type__": "Text", "text": "a"}, {"__type__": "Text", "text": "b"}],
+                "alignments": [{"x": "centre"}]}"#).ok().unwrap();
+        assert_eq!(encode(&p).ok().unwrap().find("alignments").unwrap(),
+                   &Json::from_str(r#"[{"x": "centre", "y": "fill"},
+                                       {"x": "fill", "y": "fill"}]"#).unwrap());
+        assert_eq!(decode_err(r#"{"__type__": "Stack", "children": [
+                {"__type__": "Text", "text": "a"}], "alignments": [{"x": "middle"}]}"#),
+                   JsonPresError{path: "alignments[0].x".to_string(),
+                                 kind: JsonPresErrorKind::UnknownValue("alignment",
+                                                                       "middle".to_string())});
+    }
+
+    #[test]
+    fn test_canvas() {
+        round_trip(r#"{"__type__": "Canvas", "children": [
+                {"__type__": "Text", "text": "a"}, {"__type__": "Text", "text": "b"}],
+                "positions": [{"x": 10.0, "y": 20.0}, {"x": 120.5, "y": 4.0}]}"#);
+        round_trip(r#"{"__type__": "Canvas", "children": [], "positions": []}"#);
+        assert_eq!(decode_err(r#"{"__type__": "Canvas", "children": [
+                {"__type__": "Text", "text": "a"}, {"__type__": "Text", "text": "b"}],
+                "positions": [{"x": 0, "y": 0}]}"#),
+                   JsonPresError{path: "positions[1]".to_string(),
+                                 kind: JsonPresErrorKind::MissingField});
+        assert_eq!(decode_err(r#"{"__type__": "Canvas", "children": [
+                {"__type__": "Text", "text": "a"}], "positions": [{"x": 0}]}"#),
+                   JsonPresError{path: "positions[0].y".to_string(),
+                                 kind: JsonPresErrorKind::MissingField});
+    }
+
+    #[test]
+    fn test_lazy_column() {
+        round_trip(r#"{"__type__": "LazyColumn", "estimated_row_height": 12.0, "rows": [
+                {"__type__": "Text", "text": "a"}, {"__type__": "Text", "text": "b"}]}"#);
+        round_trip(r#"{"__type__": "LazyColumn", "estimated_row_height": 12.0, "rows": [],
+                "view_height": 100.0}"#);
+    }
+
+    #[test]
+    fn test_lazy_column_invalid_row() {
+        // The invalid row is reported when it is built, not when the column is decoded
+        let p = decode_str(r#"{"__type__": "LazyColumn", "estimated_row_height": 12.0,
+                "rows": [{"__type__": "Text", "text": "a"}, {"__type__": "Text"}]}"#);
+        assert_eq!(encode(&p.ok().unwrap()), Ok(Json::from_str(
+            r#"{"__type__": "LazyColumn", "estimated_row_height": 12.0, "rows": [
+                {"__type__": "Text", "text": "a"},
+                {"__type__": "Text", "text": "rows[1].text: missing field"}]}"#).unwrap()));
+    }
+
+    #[test]
+    fn test_lazy_column_rebuild() {
+        let a = r#"{"__type__": "LazyColumn", "estimated_row_height": 12.0, "rows": [
+                {"__type__": "Text", "text": "a"}, {"__type__": "Text", "text": "b"}]}"#;
+        let b = r#"{"__type__": "LazyColumn", "estimated_row_height": 12.0, "rows": [
+                {"__type__": "Text", "text": "a"}, {"__type__": "Text", "text": "c"}]}"#;
+        let elem_ctx = ElementContext::new();
+        let pres_ctx = PresBuildCtx::new(&elem_ctx);
+        let row_source = |e: &ElementRef| {
+            e.as_any().downcast_ref::<LazyColumnElement>().unwrap().get_row_source().unwrap()
+        };
+        let elem = decode_str(a).ok().unwrap().build(&pres_ctx);
+        let source = row_source(&elem);
+
+        // Decoding the same rows again keeps the row source, so the built rows are kept
+        let elem = decode_str(a).ok().unwrap().rebuild(&elem, &pres_ctx);
+        assert!(Rc::ptr_eq(&source, &row_source(&elem)));
+
+        // Rows built under a different style sheet must be built again
+        let style = StyleSheet::default().with_values(
+            &StyleValues{text_size: Some(30.0), ..StyleValues::new()});
+        let styled_ctx = PresBuildCtx::new_with_style(&elem_ctx, Rc::new(style));
+        let elem = decode_str(a).ok().unwrap().rebuild(&elem, &styled_ctx);
+        assert!(!Rc::ptr_eq(&source, &row_source(&elem)));
+
+        // As must different rows
+        let source = row_source(&elem);
+        let elem = decode_str(b).ok().unwrap().rebuild(&elem, &styled_ctx);
+        assert!(!Rc::ptr_eq(&source, &row_source(&elem)));
+    }
+
+    #[test]
+    fn test_decode_paint() {
+        assert_eq!(decode_paint(&Json::String("red".to_string()), &JsonPath::Root),
+                   Ok(Paint::Solid(Colour::new(1.0, 0.0, 0.0, 1.0))));
+        let e = decode_err(r#"{"__type__": "Border", "border_type": "filled", "left_margin": 1,
+                "right_margin": 1, "top_margin": 1, "bottom_margin": 1, "rounding": 0,
+                "background": {"paint_type": "linear_gradient", "start": {"x": 0, "y": 0},
+                    "end": {"x": 1, "y": 0}, "stops": [{"offset": 0, "colour": "plaid"}]},
+                "child": {"__type__": "Text", "text": "a"}}"#);
+        assert_eq!(e, JsonPresError{path: "background.stops[0].colour".to_string(),
+                                    kind: JsonPresErrorKind::UnknownValue("colour",
+                                                                          "plaid".to_string())});
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        match decode_err("{\"__type__\": ").kind {
+            JsonPresErrorKind::Syntax(..) => {},
+            k => panic!("Expected syntax error, got {:?}", k)
+        }
+    }
+
+    #[test]
+    fn test_root_errors() {
+        assert_eq!(decode_err("[]"),
+                   JsonPresError{path: String::new(), kind: JsonPresErrorKind::WrongType("object")});
+        assert_eq!(decode_err("{}"),
+                   JsonPresError{path: "__type__".to_string(),
+                                 kind: JsonPresErrorKind::MissingField});
+        assert_eq!(decode_err(r#"{"__type__": "Blob"}"#),
+                   JsonPresError{path: "__type__".to_string(),
+                                 kind: JsonPresErrorKind::UnknownValue("node type",
+                                                                       "Blob".to_string())});
+    }
+
+    #[test]
+    fn test_nested_error_path() {
+        let e = decode_err(r#"{"__type__": "Column", "children": [
+                {"__type__": "Text", "text": "a"},
+                {"__type__": "Row", "children": [
+                    {"__type__": "Text", "text": "b"},
+                    {"__type__": "Border", "border_type": "solid", "thickness": "thick",
+                     "child": {"__type__": "Text", "text": "c"}}
+                ]}
+            ]}"#);
+        assert_eq!(e, JsonPresError{path: "children[1].children[1].thickness".to_string(),
+                                    kind: JsonPresErrorKind::WrongType("number")});
+        assert_eq!(format!("{}", e), "children[1].children[1].thickness: expected number");
+    }
+
+    #[test]
+    fn test_child_error_path() {
+        let e = decode_err(r#"{"__type__": "ApplyStyleSheet",
+                "text_colour": {"r": 0, "g": 0, "b": 0},
+                "child": {"__type__": "Text", "text": "a"}}"#);
+        assert_eq!(e, JsonPresError{path: "text_colour.a".to_string(),
+                                    kind: JsonPresErrorKind::MissingField});
+
+        let e = decode_err(r#"{"__type__": "Border", "border_type": "filled",
+                "child": {"__type__": "Text"}}"#);
+        assert_eq!(e, JsonPresError{path: "child.text".to_string(),
+                                    kind: JsonPresErrorKind::MissingField});
+
+        let e = decode_err(r#"{"__type__": "DefineStyleClasses",
+                "classes": {"heading": {"text_weight": "heavy"}},
+                "child": {"__type__": "Text", "text": "a"}}"#);
+        assert_eq!(e, JsonPresError{path: "classes.heading.text_weight".to_string(),
+                                    kind: JsonPresErrorKind::UnknownValue("text weight",
+                                                                          "heavy".to_string())});
+    }
+}
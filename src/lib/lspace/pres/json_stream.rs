@@ -0,0 +1,321 @@
+//! Streamed updates to documents in the JSON presentation format
+//!
+//! A stream consists of newline-delimited JSON messages. Each message is an object whose
+//! `message` field identifies the kind of update:
+//!
+//! - `{"message": "replace_document", "document": <node>}`: replace the whole document
+//! - `{"message": "replace_subtree", "id": <string>, "node": <node>}`: replace the node whose
+//!   `id` field matches
+//! - `{"message": "set_text", "id": <string>, "text": <string>}`: set the text of a `Text` node
+//! - `{"message": "set_style", "id": <string>, "style": <style values>}`: set the `style` field
+//!   of a node; a `null` style removes it
+//!
+//! Errors in the message itself are reported with paths relative to the message. If the updated
+//! document would fail to decode, it is left unchanged and the error is reported with a path
+//! relative to the root of the document. Only the nodes that an update replaces are checked.
+//!
+//! Updates are not incremental: finding a node by `id` searches the whole document, and
+//! presenting the updated document with `JsonDocument::pres` decodes all of it again. The
+//! element tree is then reconciled with the new presentation, so elements whose presentation did
+//! not change are reused rather than rebuilt.
+
+use rustc_serialize::json::{Json, Object};
+
+use pres::pres::Pres;
+use pres::json_format::{self, JsonPath, JsonPresError, JsonPresErrorKind, JsonPresResult};
+
+
+/// A streamed update message
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamMessage {
+    ReplaceDocument(Json),
+    ReplaceSubtree{id: String, node: Json},
+    SetText{id: String, text: String},
+    SetStyle{id: String, style: Option<Json>},
+}
+
+/// Decode a stream message
+pub fn decode_message(j: &Json) -> JsonPresResult<StreamMessage> {
+    let path = JsonPath::Root;
+    let obj = try!(json_format::as_object(j, &path));
+    match try!(json_format::str_field(obj, "message", &path)) {
+        "replace_document" => Ok(StreamMessage::ReplaceDocument(
+            try!(json_format::field(obj, "document", &path)).clone())),
+        "replace_subtree" => Ok(StreamMessage::ReplaceSubtree{
+            id: try!(json_format::str_field(obj, "id", &path)).to_string(),
+            node: try!(json_format::field(obj, "node", &path)).clone()}),
+        "set_text" => Ok(StreamMessage::SetText{
+            id: try!(json_format::str_field(obj, "id", &path)).to_string(),
+            text: try!(json_format::str_field(obj, "text", &path)).to_string()}),
+        "set_style" => {
+            let style = match try!(json_format::field(obj, "style", &path)) {
+                &Json::Null => None,
+                s => {
+                    // Check that the style values are valid
+                    try!(json_format::decode_style_values(s, &path.field("style")));
+                    Some(s.clone())
+                }
+            };
+            Ok(StreamMessage::SetStyle{
+                id: try!(json_format::str_field(obj, "id", &path)).to_string(), style: style})
+        },
+        x => Err(JsonPresError::new(&path.field("message"),
+                                    JsonPresErrorKind::UnknownValue("message", x.to_string())))
+    }
+}
+
+/// Parse and decode a stream message from a single line of text
+pub fn decode_message_str(s: &str) -> JsonPresResult<StreamMessage> {
+    match Json::from_str(s) {
+        Ok(j) => decode_message(&j),
+        Err(e) => Err(JsonPresError{path: String::new(),
+                                    kind: JsonPresErrorKind::Syntax(format!("{:?}", e))})
+    }
+}
+
+
+fn node_has_id(node: &Json, id: &str) -> bool {
+    match node.as_object() {
+        Some(obj) => obj.contains_key("__type__") && match obj.get("id") {
+            Some(&Json::String(ref s)) => s == id,
+            _ => false
+        },
+        None => false
+    }
+}
+
+/// A step in the location of a value within a document
+enum PathStep {
+    Field(String),
+    Index(usize),
+}
+
+/// Find the presentation node whose `id` field is `id`, appending the steps that lead to it to
+/// `location`; returns `false` if there is no such node
+fn find_node_location(j: &Json, id: &str, location: &mut Vec<PathStep>) -> bool {
+    if node_has_id(j, id) {
+        return true;
+    }
+    match *j {
+        Json::Object(ref obj) => {
+            for (name, v) in obj.iter() {
+                location.push(PathStep::Field(name.clone()));
+                if find_node_location(v, id, location) {
+                    return true;
+                }
+                location.pop();
+            }
+            false
+        },
+        Json::Array(ref items) => {
+            for (i, v) in items.iter().enumerate() {
+                location.push(PathStep::Index(i));
+                if find_node_location(v, id, location) {
+                    return true;
+                }
+                location.pop();
+            }
+            false
+        },
+        _ => false
+    }
+}
+
+/// The value at `location`, which must have been found in `j` by `find_node_location`
+fn value_at_mut<'j>(j: &'j mut Json, location: &[PathStep]) -> &'j mut Json {
+    match location.split_first() {
+        None => j,
+        Some((step, rest)) => {
+            let v = match (step, j) {
+                (&PathStep::Field(ref name), &mut Json::Object(ref mut obj)) => {
+                    obj.get_mut(name).unwrap()
+                },
+                (&PathStep::Index(i), &mut Json::Array(ref mut items)) => &mut items[i],
+                _ => panic!("location does not match the document")
+            };
+            value_at_mut(v, rest)
+        }
+    }
+}
+
+/// Decode the presentation node `j`, which is to be placed at `location`; errors are reported
+/// with paths relative to the root of the document
+fn decode_at_location(j: &Json, location: &[PathStep], path: &JsonPath) -> JsonPresResult<Pres> {
+    match location.split_first() {
+        None => json_format::decode_at(j, path),
+        Some((&PathStep::Field(ref name), rest)) => decode_at_location(j, rest, &path.field(name)),
+        Some((&PathStep::Index(i), rest)) => decode_at_location(j, rest, &path.index(i)),
+    }
+}
+
+/// The object of the presentation node whose `id` field is `id`
+fn node_object_mut<'j>(doc: &'j mut Json, id: &str) -> JsonPresResult<&'j mut Object> {
+    let mut location = vec![];
+    if find_node_location(doc, id, &mut location) {
+        match value_at_mut(doc, &location) {
+            &mut Json::Object(ref mut obj) => return Ok(obj),
+            _ => {}
+        }
+    }
+    Err(JsonPresError::new(&JsonPath::Root.field("id"),
+                           JsonPresErrorKind::UnknownId(id.to_string())))
+}
+
+
+/// A document in the JSON presentation format, to which streamed updates can be applied
+pub struct JsonDocument {
+    root: Json,
+}
+
+impl JsonDocument {
+    /// Create a document; fails if `root` does not decode to a presentation
+    pub fn new(root: Json) -> JsonPresResult<JsonDocument> {
+        try!(json_format::decode(&root));
+        Ok(JsonDocument{root: root})
+    }
+
+    /// Create a document containing an empty column
+    pub fn empty() -> JsonDocument {
+        let mut obj = Object::new();
+        obj.insert("__type__".to_string(), Json::String("Column".to_string()));
+        obj.insert("children".to_string(), Json::Array(vec![]));
+        JsonDocument{root: Json::Object(obj)}
+    }
+
+    pub fn json(&self) -> &Json {
+        &self.root
+    }
+
+    /// Decode the document to a presentation
+    pub fn pres(&self) -> JsonPresResult<Pres> {
+        json_format::decode(&self.root)
+    }
+
+    /// Apply an update; the document is left unchanged if the update fails. The update is
+    /// checked before the document is modified, and only the nodes that it replaces are decoded.
+    pub fn apply(&mut self, msg: &StreamMessage) -> JsonPresResult<()> {
+        match msg {
+            &StreamMessage::ReplaceDocument(ref d) => {
+                try!(json_format::decode(d));
+                self.root = d.clone();
+            },
+            &StreamMessage::ReplaceSubtree{ref id, ref node} => {
+                let mut location = vec![];
+                if !find_node_location(&self.root, id, &mut location) {
+                    return Err(JsonPresError::new(&JsonPath::Root.field("id"),
+                                                  JsonPresErrorKind::UnknownId(id.clone())));
+                }
+                try!(decode_at_location(node, &location, &JsonPath::Root));
+                *value_at_mut(&mut self.root, &location) = node.clone();
+            },
+            &StreamMessage::SetText{ref id, ref text} => {
+                // A text node remains valid whatever its text
+                let obj = try!(node_object_mut(&mut self.root, id));
+                if obj.get("__type__") != Some(&Json::String("Text".to_string())) {
+                    return Err(JsonPresError::new(&JsonPath::Root.field("id"),
+                                                  JsonPresErrorKind::WrongType("id of a Text node")));
+                }
+                obj.insert("text".to_string(), Json::String(text.clone()));
+            },
+            &StreamMessage::SetStyle{ref id, ref style} => {
+                // Only the style values need to be checked; the rest of the node is unchanged
+                match style {
+                    &Some(ref s) => {
+                        try!(json_format::decode_style_values(s, &JsonPath::Root.field("style")));
+                    },
+                    &None => {}
+                }
+                let obj = try!(node_object_mut(&mut self.root, id));
+                match style {
+                    &Some(ref s) => {obj.insert("style".to_string(), s.clone());},
+                    &None => {obj.remove("style");}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use rustc_serialize::json::Json;
+    use super::*;
+    use pres::json_format::{JsonPresError, JsonPresErrorKind};
+
+    fn document() -> JsonDocument {
+        JsonDocument::new(Json::from_str(r#"{"__type__": "Column", "children": [
+                {"__type__": "Text", "text": "a", "id": "first"},
+                {"__type__": "Row", "id": "row", "children": [
+                    {"__type__": "Text", "text": "b", "id": "second"}]}
+            ]}"#).unwrap()).ok().unwrap()
+    }
+
+    fn apply(doc: &mut JsonDocument, msg: &str) -> Result<(), JsonPresError> {
+        doc.apply(&decode_message_str(msg).ok().unwrap())
+    }
+
+    #[test]
+    fn test_decode_message() {
+        assert_eq!(decode_message_str(r#"{"message": "set_text", "id": "x", "text": "y"}"#),
+                   Ok(StreamMessage::SetText{id: "x".to_string(), text: "y".to_string()}));
+        assert_eq!(decode_message_str(r#"{"message": "set_style", "id": "x", "style": null}"#),
+                   Ok(StreamMessage::SetStyle{id: "x".to_string(), style: None}));
+        assert_eq!(decode_message_str(r#"{"message": "frobnicate"}"#),
+                   Err(JsonPresError{path: "message".to_string(),
+                                     kind: JsonPresErrorKind::UnknownValue(
+                                         "message", "frobnicate".to_string())}));
+        assert_eq!(decode_message_str(r#"{"message": "set_style", "id": "x",
+                                          "style": {"text_size": "big"}}"#),
+                   Err(JsonPresError{path: "style.text_size".to_string(),
+                                     kind: JsonPresErrorKind::WrongType("number")}));
+    }
+
+    #[test]
+    fn test_set_text() {
+        let mut doc = document();
+        assert_eq!(apply(&mut doc, r#"{"message": "set_text", "id": "second", "text": "c"}"#),
+                   Ok(()));
+        assert_eq!(doc.json().find_path(&["children"]).unwrap()[1]
+                       .find_path(&["children"]).unwrap()[0].find("text"),
+                   Some(&Json::String("c".to_string())));
+        assert_eq!(apply(&mut doc, r#"{"message": "set_text", "id": "row", "text": "c"}"#),
+                   Err(JsonPresError{path: "id".to_string(),
+                                     kind: JsonPresErrorKind::WrongType("id of a Text node")}));
+        assert_eq!(apply(&mut doc, r#"{"message": "set_text", "id": "nope", "text": "c"}"#),
+                   Err(JsonPresError{path: "id".to_string(),
+                                     kind: JsonPresErrorKind::UnknownId("nope".to_string())}));
+    }
+
+    #[test]
+    fn test_replace_subtree() {
+        let mut doc = document();
+        assert_eq!(apply(&mut doc, r#"{"message": "replace_subtree", "id": "row",
+                                       "node": {"__type__": "Text", "text": "d", "id": "row"}}"#),
+                   Ok(()));
+        assert_eq!(doc.json().find_path(&["children"]).unwrap()[1].find("text"),
+                   Some(&Json::String("d".to_string())));
+        // An invalid replacement leaves the document unchanged
+        let before = doc.json().clone();
+        assert_eq!(apply(&mut doc, r#"{"message": "replace_subtree", "id": "row",
+                                       "node": {"__type__": "Text"}}"#),
+                   Err(JsonPresError{path: "children[1].text".to_string(),
+                                     kind: JsonPresErrorKind::MissingField}));
+        assert_eq!(doc.json(), &before);
+    }
+
+    #[test]
+    fn test_set_style() {
+        let mut doc = document();
+        assert_eq!(apply(&mut doc, r#"{"message": "set_style", "id": "first",
+                                       "style": {"text_size": 20}}"#), Ok(()));
+        assert!(doc.json().find_path(&["children"]).unwrap()[0].find("style").is_some());
+        assert_eq!(apply(&mut doc, r#"{"message": "set_style", "id": "first", "style": null}"#),
+                   Ok(()));
+        assert!(doc.json().find_path(&["children"]).unwrap()[0].find("style").is_none());
+    }
+}
@@ -1,3 +1,10 @@
 pub mod pres;
 pub mod primitive;
 pub mod richtext;
+pub mod style;
+pub mod json_format;
+pub mod json_stream;
+pub mod visitor;
+pub mod reconcile;
+pub mod event_binding;
+pub mod element_id;
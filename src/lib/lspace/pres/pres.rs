@@ -1,22 +1,66 @@
 extern crate cairo;
 
+use std::rc::Rc;
+
 use elements::element::ElementRef;
 use elements::element_ctx::ElementContext;
+use pres::style::StyleSheet;
+use pres::visitor::TPresVisitor;
 
 
 pub struct PresBuildCtx<'a> {
     pub elem_ctx: &'a ElementContext,
+    pub style: Rc<StyleSheet>,
 }
 
 impl <'a> PresBuildCtx<'a> {
     pub fn new(elem_ctx: &'a ElementContext) -> PresBuildCtx<'a> {
-        PresBuildCtx{elem_ctx: elem_ctx}
+        PresBuildCtx{elem_ctx: elem_ctx, style: Rc::new(StyleSheet::default())}
+    }
+
+    pub fn new_with_style(elem_ctx: &'a ElementContext, style: Rc<StyleSheet>) -> PresBuildCtx<'a> {
+        PresBuildCtx{elem_ctx: elem_ctx, style: style}
+    }
+
+    /// Create a build context that uses a different style sheet; used when building children
+    pub fn with_style(&self, style: StyleSheet) -> PresBuildCtx<'a> {
+        PresBuildCtx{elem_ctx: self.elem_ctx, style: Rc::new(style)}
     }
 }
 
 
 pub trait TPres {
     fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef;
+
+    /// Reconcile `self` against `existing`, an element previously built from a presentation.
+    /// Presentation types that can update `existing` in place do so and return it, otherwise a
+    /// new element is built. The default implementation always builds a new element.
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        self.build(pres_ctx)
+    }
+
+    /// Key that identifies this presentation among its siblings when reconciling. Presentations
+    /// that pass their child's element through, such as style and id wrappers, report the key
+    /// of their child; presentations that build an element of their own have no key, as a key
+    /// within them is attached to the child element.
+    fn key(&self) -> Option<&str> {
+        None
+    }
+
+    /// Pass `self` to the matching method of `visitor`; presentation types that do not
+    /// expose their structure are reported via `visit_opaque`
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_opaque();
+    }
 }
 
 pub type Pres = Box<TPres>;
+
+
+/// Return `existing` so that a rebuild can reuse it, clearing its key and id; the `Keyed` and
+/// `WithId` presentations that wrap the rebuilt presentation, if any, set them again
+pub fn reuse_element(existing: &ElementRef) -> ElementRef {
+    existing.set_key(None);
+    existing.set_id(None);
+    existing.clone()
+}
@@ -1,31 +1,81 @@
 use std::rc::Rc;
+use std::any::Any;
 
-use graphics::border;
+use geom::point2::Point2;
+use graphics::{border, shadow, shape};
 use layout::flow_layout;
-use elements::element::{TElement, ElementRef, elem_as_ref};
-use elements::{text_element, column, row, flow, root_element, border_element};
+use layout::stack_layout::Alignment;
+use elements::element::{TElement, ElementRef, elem_as_ref, elems_are_same};
+use elements::{text_element, column, row, flow, stack, canvas, lazy_column, root_element,
+               border_element, shadow_element, state_border_element, shape_element,
+               expander_element};
+use elements::element_ctx::ElementContext;
 use elements::bin::{TBinElement};
 use elements::container_sequence::{TContainerSequenceElement};
-use pres::pres::{Pres, TPres, PresBuildCtx};
+use pres::pres::{Pres, TPres, PresBuildCtx, reuse_element};
+use pres::style::StyleSheet;
+use pres::visitor::TPresVisitor;
+use pres::reconcile::{rebuild_children, elem_lists_are_same};
 
 
 pub struct Text {
     text: String,
-    style: Rc<text_element::TextStyleParams>,
+    style: Option<Rc<text_element::TextStyleParams>>,
 }
 
 impl Text {
     pub fn new(text: String, style: Rc<text_element::TextStyleParams>) -> Pres {
-        return Box::new(Text{text: text, style: style});
+        return Box::new(Text{text: text, style: Some(style)});
+    }
+
+    /// Text that uses the text style from the style sheet
+    pub fn new_inherited(text: String) -> Pres {
+        return Box::new(Text{text: text, style: None});
+    }
+
+    pub fn text(&self) -> &String {
+        &self.text
+    }
+
+    /// The explicitly specified style; `None` if the style is taken from the style sheet
+    pub fn style(&self) -> Option<&Rc<text_element::TextStyleParams>> {
+        self.style.as_ref()
     }
 }
 
 impl TPres for Text {
     fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
-        let elem = text_element::TextElement::new(self.text.clone(), self.style.clone(),
+        let style = match self.style {
+            Some(ref s) => s.clone(),
+            None => pres_ctx.style.text.clone()
+        };
+        let elem = text_element::TextElement::new(self.text.clone(), style,
                                                   &pres_ctx.elem_ctx);
         return elem_as_ref(elem);
     }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_text_element() {
+            Some(t) => {
+                let style = match self.style {
+                    Some(ref s) => s.clone(),
+                    None => pres_ctx.style.text.clone()
+                };
+                if *t.get_text() != self.text {
+                    t.set_text(self.text.clone());
+                }
+                if *t.get_style() != *style {
+                    t.set_style(style);
+                }
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_text(self);
+    }
 }
 
 
@@ -38,6 +88,14 @@ impl Border {
     pub fn new(child: Pres, border: Rc<border::Border>) -> Pres {
         Box::new(Border{child: child, border: border})
     }
+
+    pub fn child(&self) -> &Pres {
+        &self.child
+    }
+
+    pub fn border(&self) -> &Rc<border::Border> {
+        &self.border
+    }
 }
 
 impl TPres for Border {
@@ -47,87 +105,746 @@ impl TPres for Border {
         elem.as_bin().unwrap().set_child(&elem, child);
         elem
     }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<border_element::BorderElement>() {
+            Some(b) => {
+                b.set_border(&self.border);
+                let old_child = b.get_child();
+                let child = match old_child {
+                    Some(ref c) => self.child.rebuild(c, pres_ctx),
+                    None => self.child.build(pres_ctx)
+                };
+                let same = match old_child {
+                    Some(ref c) => elems_are_same(c, &child),
+                    None => false
+                };
+                if !same {
+                    b.set_child(existing, child);
+                }
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_border(self);
+    }
+}
+
+
+pub struct Shadow {
+    child: Pres,
+    shadow: Rc<shadow::Shadow>
+}
+
+impl Shadow {
+    pub fn new(child: Pres, shadow: Rc<shadow::Shadow>) -> Pres {
+        Box::new(Shadow{child: child, shadow: shadow})
+    }
+
+    pub fn child(&self) -> &Pres {
+        &self.child
+    }
+
+    pub fn shadow(&self) -> &Rc<shadow::Shadow> {
+        &self.shadow
+    }
+}
+
+impl TPres for Shadow {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let child = self.child.build(pres_ctx);
+        let elem = elem_as_ref(shadow_element::ShadowElement::new(&self.shadow));
+        elem.as_bin().unwrap().set_child(&elem, child);
+        elem
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<shadow_element::ShadowElement>() {
+            Some(s) => {
+                s.set_shadow(&self.shadow);
+                let old_child = s.get_child();
+                let child = match old_child {
+                    Some(ref c) => self.child.rebuild(c, pres_ctx),
+                    None => self.child.build(pres_ctx)
+                };
+                let same = match old_child {
+                    Some(ref c) => elems_are_same(c, &child),
+                    None => false
+                };
+                if !same {
+                    s.set_child(existing, child);
+                }
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_shadow(self);
+    }
+}
+
+
+pub struct StateBorder {
+    child: Pres,
+    borders: Rc<border::StateBorders>,
+    focusable: bool,
+    disabled: bool,
+    on_activate: Option<Rc<Fn()>>,
+}
+
+impl StateBorder {
+    /// Border that changes with the pointer state; see `StateBorderElement`
+    pub fn new(child: Pres, borders: Rc<border::StateBorders>) -> Pres {
+        Box::new(StateBorder{child: child, borders: borders, focusable: false, disabled: false,
+                             on_activate: None})
+    }
+
+    pub fn new_full(child: Pres, borders: Rc<border::StateBorders>, focusable: bool,
+                    disabled: bool) -> Pres {
+        Box::new(StateBorder{child: child, borders: borders, focusable: focusable,
+                             disabled: disabled, on_activate: None})
+    }
+
+    /// Focusable state border that calls `on_activate` when clicked, or when Space or Enter is
+    /// pressed while it has the focus
+    pub fn new_activatable(child: Pres, borders: Rc<border::StateBorders>,
+                           on_activate: Rc<Fn()>, disabled: bool) -> Pres {
+        Box::new(StateBorder{child: child, borders: borders, focusable: true,
+                             disabled: disabled, on_activate: Some(on_activate)})
+    }
+
+    pub fn child(&self) -> &Pres {
+        &self.child
+    }
+
+    pub fn borders(&self) -> &Rc<border::StateBorders> {
+        &self.borders
+    }
+
+    pub fn focusable(&self) -> bool {
+        self.focusable
+    }
+
+    pub fn disabled(&self) -> bool {
+        self.disabled
+    }
+}
+
+impl TPres for StateBorder {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let child = self.child.build(pres_ctx);
+        let e = state_border_element::StateBorderElement::new(&self.borders, self.focusable);
+        e.set_disabled(self.disabled);
+        e.set_on_activate(self.on_activate.clone());
+        let elem = elem_as_ref(e);
+        elem.as_bin().unwrap().set_child(&elem, child);
+        elem
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<state_border_element::StateBorderElement>() {
+            Some(s) => {
+                // The hover, press and focus state of the existing element is preserved
+                s.set_borders(&self.borders);
+                s.set_focusable(self.focusable);
+                s.set_disabled(self.disabled);
+                s.set_on_activate(self.on_activate.clone());
+                let old_child = s.get_child();
+                let child = match old_child {
+                    Some(ref c) => self.child.rebuild(c, pres_ctx),
+                    None => self.child.build(pres_ctx)
+                };
+                let same = match old_child {
+                    Some(ref c) => elems_are_same(c, &child),
+                    None => false
+                };
+                if !same {
+                    s.set_child(existing, child);
+                }
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_state_border(self);
+    }
+}
+
+
+pub struct Shape {
+    width: f64,
+    height: f64,
+    shapes: Rc<Vec<shape::Shape>>,
+}
+
+impl Shape {
+    /// Shapes drawn in a box of size `width` by `height`; see `ShapeElement`
+    pub fn new(width: f64, height: f64, shapes: Rc<Vec<shape::Shape>>) -> Pres {
+        Box::new(Shape{width: width, height: height, shapes: shapes})
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    pub fn shapes(&self) -> &Rc<Vec<shape::Shape>> {
+        &self.shapes
+    }
+}
+
+impl TPres for Shape {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        elem_as_ref(shape_element::ShapeElement::new(self.width, self.height, &self.shapes))
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<shape_element::ShapeElement>() {
+            Some(s) => {
+                s.set_size(self.width, self.height);
+                s.set_shapes(&self.shapes);
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_shape(self);
+    }
+}
+
+
+pub struct Expander {
+    header: Pres,
+    body: Pres,
+    expanded: bool,
+    on_toggle: Option<Rc<Fn(bool)>>,
+}
+
+impl Expander {
+    /// Header above a body that the user can show and hide; see `ExpanderElement`
+    pub fn new(header: Pres, body: Pres, expanded: bool) -> Pres {
+        Box::new(Expander{header: header, body: body, expanded: expanded, on_toggle: None})
+    }
+
+    /// Expander that calls `on_toggle` with the new state when the user toggles the body
+    pub fn new_with_toggle(header: Pres, body: Pres, expanded: bool,
+                           on_toggle: Rc<Fn(bool)>) -> Pres {
+        Box::new(Expander{header: header, body: body, expanded: expanded,
+                          on_toggle: Some(on_toggle)})
+    }
+
+    pub fn header(&self) -> &Pres {
+        &self.header
+    }
+
+    pub fn body(&self) -> &Pres {
+        &self.body
+    }
+
+    pub fn expanded(&self) -> bool {
+        self.expanded
+    }
+}
+
+impl TPres for Expander {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let header = self.header.build(pres_ctx);
+        let body = self.body.build(pres_ctx);
+        let e = expander_element::ExpanderElement::new(self.expanded);
+        e.set_on_toggle(self.on_toggle.clone());
+        let elem = elem_as_ref(e);
+        {
+            let x = elem.as_any().downcast_ref::<expander_element::ExpanderElement>().unwrap();
+            x.set_header(&elem, header);
+            x.set_body(&elem, body);
+        }
+        elem
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<expander_element::ExpanderElement>() {
+            Some(x) => {
+                // The expanded state is only changed if the presentation's state has changed,
+                // so that the user's toggling is preserved
+                x.present_expanded(self.expanded);
+                x.set_on_toggle(self.on_toggle.clone());
+                let old_header = x.get_header();
+                let header = match old_header {
+                    Some(ref h) => self.header.rebuild(h, pres_ctx),
+                    None => self.header.build(pres_ctx)
+                };
+                if !old_header.map_or(false, |h| elems_are_same(&h, &header)) {
+                    x.set_header(existing, header);
+                }
+                let old_body = x.get_body();
+                let body = match old_body {
+                    Some(ref b) => self.body.rebuild(b, pres_ctx),
+                    None => self.body.build(pres_ctx)
+                };
+                if !old_body.map_or(false, |b| elems_are_same(&b, &body)) {
+                    x.set_body(existing, body);
+                }
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_expander(self);
+    }
 }
 
 
 pub struct Column {
     children: Vec<Pres>,
-    y_spacing: f64,
+    y_spacing: Option<f64>,
 }
 
 impl Column {
+    /// Column whose spacing is taken from the style sheet
     pub fn new(children: Vec<Pres>) -> Pres {
-        return Box::new(Column{children: children, y_spacing: 0.0});
+        return Box::new(Column{children: children, y_spacing: None});
     }
 
     pub fn new_full(children: Vec<Pres>, y_spacing: f64) -> Pres {
-        return Box::new(Column{children: children, y_spacing: y_spacing});
+        return Box::new(Column{children: children, y_spacing: Some(y_spacing)});
+    }
+
+    pub fn children(&self) -> &Vec<Pres> {
+        &self.children
+    }
+
+    /// The explicitly specified spacing; `None` if the spacing is taken from the style sheet
+    pub fn y_spacing(&self) -> Option<f64> {
+        self.y_spacing
     }
 }
 
 impl TPres for Column {
     fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
-        let child_elems = self.children.iter().map(|p| p.build(pres_ctx)).collect();
-        let elem = elem_as_ref(column::ColumnElement::new(self.y_spacing));
+        let y_spacing = self.y_spacing.unwrap_or(pres_ctx.style.column_y_spacing);
+        let child_ctx = pres_ctx.with_style(pres_ctx.style.use_column());
+        let child_elems = self.children.iter().map(|p| p.build(&child_ctx)).collect();
+        let elem = elem_as_ref(column::ColumnElement::new(y_spacing));
         elem.as_container_sequence().unwrap().set_children(&elem, &child_elems);
         return elem;
     }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<column::ColumnElement>() {
+            Some(c) => {
+                c.set_y_spacing(self.y_spacing.unwrap_or(pres_ctx.style.column_y_spacing));
+                let child_ctx = pres_ctx.with_style(pres_ctx.style.use_column());
+                rebuild_sequence_children(c, existing, &self.children, &child_ctx);
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_column(self);
+    }
 }
 
 
 pub struct Row {
     children: Vec<Pres>,
-    x_spacing: f64,
+    x_spacing: Option<f64>,
 }
 
 impl Row {
+    /// Row whose spacing is taken from the style sheet
     pub fn new(children: Vec<Pres>) -> Pres {
-        return Box::new(Row{children: children, x_spacing: 0.0});
+        return Box::new(Row{children: children, x_spacing: None});
     }
 
     pub fn new_full(children: Vec<Pres>, x_spacing: f64) -> Pres {
-        return Box::new(Row{children: children, x_spacing: x_spacing});
+        return Box::new(Row{children: children, x_spacing: Some(x_spacing)});
+    }
+
+    pub fn children(&self) -> &Vec<Pres> {
+        &self.children
+    }
+
+    /// The explicitly specified spacing; `None` if the spacing is taken from the style sheet
+    pub fn x_spacing(&self) -> Option<f64> {
+        self.x_spacing
     }
 }
 
 impl TPres for Row {
     fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
-        let child_elems = self.children.iter().map(|p| p.build(pres_ctx)).collect();
-        let elem = elem_as_ref(row::RowElement::new(self.x_spacing));
+        let x_spacing = self.x_spacing.unwrap_or(pres_ctx.style.row_x_spacing);
+        let child_ctx = pres_ctx.with_style(pres_ctx.style.use_row());
+        let child_elems = self.children.iter().map(|p| p.build(&child_ctx)).collect();
+        let elem = elem_as_ref(row::RowElement::new(x_spacing));
         elem.as_container_sequence().unwrap().set_children(&elem, &child_elems);
         return elem;
     }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<row::RowElement>() {
+            Some(r) => {
+                r.set_x_spacing(self.x_spacing.unwrap_or(pres_ctx.style.row_x_spacing));
+                let child_ctx = pres_ctx.with_style(pres_ctx.style.use_row());
+                rebuild_sequence_children(r, existing, &self.children, &child_ctx);
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_row(self);
+    }
 }
 
 
 pub struct Flow {
     children: Vec<Pres>,
-    x_spacing: f64,
-    y_spacing: f64,
-    indentation: flow_layout::FlowIndent,
+    spacing: Option<(f64, f64, flow_layout::FlowIndent)>,
 }
 
 impl Flow {
+    /// Flow whose spacing and indentation are taken from the style sheet
     pub fn new(children: Vec<Pres>) -> Pres {
-        return Box::new(Flow{children: children, x_spacing: 0.0, y_spacing: 0.0,
-                             indentation: flow_layout::FlowIndent::NoIndent});
+        return Box::new(Flow{children: children, spacing: None});
     }
 
     pub fn new_full(children: Vec<Pres>, x_spacing: f64, y_spacing: f64,
                     indentation: flow_layout::FlowIndent) -> Pres {
-        return Box::new(Flow{children: children, x_spacing: x_spacing, y_spacing: y_spacing,
-                        indentation: indentation});
+        return Box::new(Flow{children: children,
+                             spacing: Some((x_spacing, y_spacing, indentation))});
+    }
+
+    pub fn children(&self) -> &Vec<Pres> {
+        &self.children
+    }
+
+    /// The explicitly specified `(x_spacing, y_spacing, indentation)`; `None` if they are taken
+    /// from the style sheet
+    pub fn spacing(&self) -> Option<(f64, f64, flow_layout::FlowIndent)> {
+        self.spacing
     }
 }
 
 impl TPres for Flow {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let (x_spacing, y_spacing, indentation) = self.spacing.unwrap_or(
+            (pres_ctx.style.flow_x_spacing, pres_ctx.style.flow_y_spacing,
+             pres_ctx.style.flow_indentation));
+        let child_ctx = pres_ctx.with_style(pres_ctx.style.use_flow());
+        let child_elems = self.children.iter().map(|p| p.build(&child_ctx)).collect();
+        let elem = elem_as_ref(flow::FlowElement::new(x_spacing, y_spacing, indentation));
+        elem.as_container_sequence().unwrap().set_children(&elem, &child_elems);
+        return elem;
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<flow::FlowElement>() {
+            Some(f) => {
+                let (x_spacing, y_spacing, indentation) = self.spacing.unwrap_or(
+                    (pres_ctx.style.flow_x_spacing, pres_ctx.style.flow_y_spacing,
+                     pres_ctx.style.flow_indentation));
+                f.set_spacing(x_spacing, y_spacing, indentation);
+                let child_ctx = pres_ctx.with_style(pres_ctx.style.use_flow());
+                rebuild_sequence_children(f, existing, &self.children, &child_ctx);
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_flow(self);
+    }
+}
+
+
+/// Children layered in the same space, from back to front
+pub struct Stack {
+    children: Vec<Pres>,
+    aligns: Vec<Alignment>,
+}
+
+impl Stack {
+    /// Stack whose children all fill the available space
+    pub fn new(children: Vec<Pres>) -> Pres {
+        let aligns = children.iter().map(|_| Alignment::fill()).collect();
+        return Box::new(Stack{children: children, aligns: aligns});
+    }
+
+    pub fn new_aligned(children: Vec<(Pres, Alignment)>) -> Pres {
+        let (children, aligns) = children.into_iter().unzip();
+        return Box::new(Stack{children: children, aligns: aligns});
+    }
+
+    pub fn children(&self) -> &Vec<Pres> {
+        &self.children
+    }
+
+    /// The alignment of each child, in child order
+    pub fn aligns(&self) -> &Vec<Alignment> {
+        &self.aligns
+    }
+}
+
+impl TPres for Stack {
     fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
         let child_elems = self.children.iter().map(|p| p.build(pres_ctx)).collect();
-        let elem = elem_as_ref(flow::FlowElement::new(self.x_spacing, self.y_spacing,
-                                                      self.indentation));
+        let s = stack::StackElement::new();
+        s.set_aligns(self.aligns.clone());
+        let elem = elem_as_ref(s);
         elem.as_container_sequence().unwrap().set_children(&elem, &child_elems);
         return elem;
     }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<stack::StackElement>() {
+            Some(s) => {
+                rebuild_sequence_children(s, existing, &self.children, pres_ctx);
+                s.set_aligns(self.aligns.clone());
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_stack(self);
+    }
+}
+
+
+/// Children placed at explicit positions, at their natural sizes; see `CanvasElement`
+pub struct Canvas {
+    children: Vec<Pres>,
+    positions: Vec<Point2>,
+}
+
+impl Canvas {
+    pub fn new(children: Vec<(Pres, Point2)>) -> Pres {
+        let (children, positions) = children.into_iter().unzip();
+        return Box::new(Canvas{children: children, positions: positions});
+    }
+
+    pub fn children(&self) -> &Vec<Pres> {
+        &self.children
+    }
+
+    /// The position of each child, in child order
+    pub fn positions(&self) -> &Vec<Point2> {
+        &self.positions
+    }
+}
+
+impl TPres for Canvas {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let child_elems: Vec<ElementRef> = self.children.iter().map(
+            |p| p.build(pres_ctx)).collect();
+        let elem = elem_as_ref(canvas::CanvasElement::new());
+        elem.as_any().downcast_ref::<canvas::CanvasElement>().unwrap().set_children(
+            &elem, child_elems.into_iter().zip(self.positions.iter().cloned()).collect());
+        return elem;
+    }
+
+    /// Children that are kept are moved to their new positions
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<canvas::CanvasElement>() {
+            Some(c) => {
+                let old_children: Vec<ElementRef> = c.get_children().clone();
+                let new_children = rebuild_children(&self.children, &old_children, pres_ctx);
+                if elem_lists_are_same(&old_children, &new_children) {
+                    for (e, pos) in new_children.iter().zip(self.positions.iter()) {
+                        c.move_child(e, *pos);
+                    }
+                } else {
+                    c.set_children(existing, new_children.into_iter().zip(
+                        self.positions.iter().cloned()).collect());
+                }
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_canvas(self);
+    }
+}
+
+
+/// Column of `item_count` rows that only builds the rows that are drawn, for very long lists.
+/// `factory` presents the item at an index. Rows are assumed to be `estimated_row_height` high
+/// until they have been laid out.
+pub struct LazyColumn {
+    item_count: usize,
+    factory: Rc<Fn(usize) -> Pres>,
+    estimated_row_height: f64,
+    view_height: Option<f64>,
+    rows_id: Option<u64>,
+}
+
+/// The row source of a lazy column element; identifies the rows that its row builder presents
+/// and the style sheet that they are built with
+struct LazyColumnRows {
+    factory: Rc<Fn(usize) -> Pres>,
+    rows_id: Option<u64>,
+    style: Rc<StyleSheet>,
+}
+
+impl LazyColumn {
+    pub fn new(item_count: usize, factory: Rc<Fn(usize) -> Pres>,
+               estimated_row_height: f64) -> Pres {
+        return Box::new(LazyColumn{item_count: item_count, factory: factory,
+                                   estimated_row_height: estimated_row_height,
+                                   view_height: None, rows_id: None});
+    }
+
+    /// Lazy column that shows `view_height` of its rows, scrolling through them; see
+    /// `LazyColumnElement`
+    pub fn new_with_view(item_count: usize, factory: Rc<Fn(usize) -> Pres>,
+                         estimated_row_height: f64, view_height: f64) -> Pres {
+        return Box::new(LazyColumn{item_count: item_count, factory: factory,
+                                   estimated_row_height: estimated_row_height,
+                                   view_height: Some(view_height), rows_id: None});
+    }
+
+    /// Lazy column whose rows are identified by `rows_id` rather than by `factory`, for
+    /// factories that are created anew each time the presentation is; rebuilding with a
+    /// different factory but the same `rows_id` keeps the rows that have been built
+    pub fn new_with_rows_id(item_count: usize, factory: Rc<Fn(usize) -> Pres>,
+                            estimated_row_height: f64, view_height: Option<f64>,
+                            rows_id: u64) -> Pres {
+        return Box::new(LazyColumn{item_count: item_count, factory: factory,
+                                   estimated_row_height: estimated_row_height,
+                                   view_height: view_height, rows_id: Some(rows_id)});
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.item_count
+    }
+
+    pub fn estimated_row_height(&self) -> f64 {
+        self.estimated_row_height
+    }
+
+    pub fn view_height(&self) -> Option<f64> {
+        self.view_height
+    }
+
+    pub fn rows_id(&self) -> Option<u64> {
+        self.rows_id
+    }
+
+    /// The presentation of the item at `index`
+    pub fn present_item(&self, index: usize) -> Pres {
+        (self.factory)(index)
+    }
+
+    /// Identifies the rows to the element, so that rebuilding with the same rows and style
+    /// keeps the rows that have been built
+    fn row_source(&self, style: &Rc<StyleSheet>) -> Rc<Any> {
+        Rc::new(LazyColumnRows{factory: self.factory.clone(), rows_id: self.rows_id,
+                               style: style.clone()})
+    }
+
+    fn has_same_rows(&self, row_source: Option<Rc<Any>>, style: &StyleSheet) -> bool {
+        let rows = match row_source {
+            Some(ref s) => match s.downcast_ref::<LazyColumnRows>() {
+                Some(r) => r,
+                None => {return false;}
+            },
+            None => {return false;}
+        };
+        let same_rows = match (rows.rows_id, self.rows_id) {
+            (Some(a), Some(b)) => a == b,
+            _ => Rc::ptr_eq(&rows.factory, &self.factory)
+        };
+        same_rows && *rows.style == *style
+    }
+
+    /// Row builder that presents rows with `factory` and `style`, reconciling them against the
+    /// elements of rows that have been scrolled away
+    fn row_builder(&self, style: &Rc<StyleSheet>) -> lazy_column::RowBuilder {
+        let factory = self.factory.clone();
+        let style = style.clone();
+        let builder: lazy_column::RowBuilder = Rc::new(
+            move |i: usize, recycled: Option<&ElementRef>, elem_ctx: &ElementContext| {
+                let row_ctx = PresBuildCtx::new_with_style(elem_ctx, style.clone());
+                let p = factory(i);
+                match recycled {
+                    Some(e) => p.rebuild(e, &row_ctx),
+                    None => p.build(&row_ctx)
+                }
+            });
+        builder
+    }
+}
+
+impl TPres for LazyColumn {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let style = Rc::new(pres_ctx.style.use_column());
+        let elem = lazy_column::LazyColumnElement::new(self.item_count,
+                                                       self.estimated_row_height,
+                                                       self.row_builder(&style));
+        elem.set_view_height(self.view_height);
+        elem.set_row_source(Some(self.row_source(&style)));
+        return elem;
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<lazy_column::LazyColumnElement>() {
+            Some(c) => {
+                c.set_item_count(self.item_count);
+                c.set_estimated_row_height(self.estimated_row_height);
+                if c.get_view_height() != self.view_height {
+                    // The scroll position is preserved
+                    c.set_view_height(self.view_height);
+                }
+                let style = Rc::new(pres_ctx.style.use_column());
+                if self.has_same_rows(c.get_row_source(), &style) {
+                    c.update_row_builder(self.row_builder(&style));
+                } else {
+                    // The rows or the style that they are built with have changed
+                    c.set_row_builder(self.row_builder(&style));
+                    c.set_row_source(Some(self.row_source(&style)));
+                }
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_lazy_column(self);
+    }
+}
+
+
+/// Reconcile the children of the container sequence `elem` against `children`; the child
+/// list is only replaced if it has changed
+fn rebuild_sequence_children(elem: &TContainerSequenceElement, self_ref: &ElementRef,
+                             children: &[Pres], pres_ctx: &PresBuildCtx) {
+    let old_children: Vec<ElementRef> = elem.get_children().to_vec();
+    let new_children = rebuild_children(children, &old_children, pres_ctx);
+    if !elem_lists_are_same(&old_children, &new_children) {
+        elem.set_children(self_ref, &new_children);
+    }
 }
 
 
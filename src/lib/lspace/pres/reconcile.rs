@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+use elements::element::{ElementRef, elems_are_same};
+use pres::pres::{Pres, TPres, PresBuildCtx};
+use pres::visitor::TPresVisitor;
+
+
+/// Reconcile the presentations `children` against the elements `existing`, returning the
+/// new child elements. Keyed presentations are matched with the existing element that has
+/// the same key, wherever it is in the list; presentations without keys are matched in order
+/// with existing elements that have no key. Each existing element is reused at most once.
+pub fn rebuild_children(children: &[Pres], existing: &[ElementRef],
+                        pres_ctx: &PresBuildCtx) -> Vec<ElementRef> {
+    let mut keyed: HashMap<String, ElementRef> = HashMap::new();
+    let mut unkeyed: VecDeque<ElementRef> = VecDeque::new();
+    for e in existing.iter() {
+        match e.get_key() {
+            Some(k) => {keyed.insert(k, e.clone());},
+            None => unkeyed.push_back(e.clone()),
+        }
+    }
+
+    children.iter().map(|p| {
+        let matched = match p.key() {
+            Some(k) => keyed.remove(k),
+            None => unkeyed.pop_front(),
+        };
+        match matched {
+            Some(e) => p.rebuild(&e, pres_ctx),
+            None => p.build(pres_ctx)
+        }
+    }).collect()
+}
+
+/// Check if the element lists `a` and `b` contain the same elements in the same order
+pub fn elem_lists_are_same(a: &[ElementRef], b: &[ElementRef]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| elems_are_same(x, y))
+}
+
+
+/// Attaches a key to the child presentation, allowing its element to be matched and reused
+/// when its position among its siblings changes
+pub struct Keyed {
+    child: Pres,
+    key: String,
+}
+
+impl Keyed {
+    pub fn new(child: Pres, key: String) -> Pres {
+        Box::new(Keyed{child: child, key: key})
+    }
+
+    pub fn child(&self) -> &Pres {
+        &self.child
+    }
+
+    pub fn key_string(&self) -> &String {
+        &self.key
+    }
+}
+
+impl TPres for Keyed {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let elem = self.child.build(pres_ctx);
+        elem.set_key(Some(self.key.clone()));
+        elem
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let elem = self.child.rebuild(existing, pres_ctx);
+        elem.set_key(Some(self.key.clone()));
+        elem
+    }
+
+    fn key(&self) -> Option<&str> {
+        Some(&self.key)
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_keyed(self);
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elements::element::TElement;
+    use elements::element_ctx::ElementContext;
+    use elements::column::ColumnElement;
+    use elements::text_element::{TextElement, TTextElement};
+    use pres::primitive::{Text, Column};
+    use pres::element_id::WithId;
+
+    fn text(t: &str) -> Pres {
+        Text::new_inherited(t.to_string())
+    }
+
+    fn keyed(t: &str) -> Pres {
+        Keyed::new(text(t), t.to_string())
+    }
+
+    fn texts(elems: &[ElementRef]) -> Vec<String> {
+        elems.iter().map(|e| e.as_text_element().unwrap().get_text().clone()).collect()
+    }
+
+    #[test]
+    fn test_reuse_same_type() {
+        let elem_ctx = ElementContext::new();
+        let pres_ctx = PresBuildCtx::new(&elem_ctx);
+        let elem = text("a").build(&pres_ctx);
+        let rebuilt = text("b").rebuild(&elem, &pres_ctx);
+        assert!(elems_are_same(&elem, &rebuilt));
+        assert_eq!(*rebuilt.as_text_element().unwrap().get_text(), "b");
+    }
+
+    #[test]
+    fn test_build_new_type() {
+        let elem_ctx = ElementContext::new();
+        let pres_ctx = PresBuildCtx::new(&elem_ctx);
+        let elem = text("a").build(&pres_ctx);
+        let rebuilt = Column::new(vec![text("a")]).rebuild(&elem, &pres_ctx);
+        assert!(!elems_are_same(&elem, &rebuilt));
+        assert!(rebuilt.as_any().downcast_ref::<ColumnElement>().is_some());
+        let rebuilt_again = text("a").rebuild(&rebuilt, &pres_ctx);
+        assert!(rebuilt_again.as_any().downcast_ref::<TextElement>().is_some());
+    }
+
+    #[test]
+    fn test_keyed_children_reordered() {
+        let elem_ctx = ElementContext::new();
+        let pres_ctx = PresBuildCtx::new(&elem_ctx);
+        let existing = rebuild_children(&[keyed("a"), keyed("b"), keyed("c")], &[], &pres_ctx);
+
+        // Each keyed child is matched with the element that has its key, wherever it is
+        let children = rebuild_children(&[keyed("c"), keyed("a"), keyed("b")], &existing,
+                                        &pres_ctx);
+        assert_eq!(texts(&children), vec!["c", "a", "b"]);
+        assert!(elem_lists_are_same(&children, &[existing[2].clone(), existing[0].clone(),
+                                                 existing[1].clone()]));
+
+        // Within a column
+        let col = Column::new(vec![keyed("a"), keyed("b")]).build(&pres_ctx);
+        let old_children = col.as_container().unwrap().children().to_vec();
+        let rebuilt = Column::new(vec![keyed("b"), keyed("a")]).rebuild(&col, &pres_ctx);
+        assert!(elems_are_same(&col, &rebuilt));
+        let new_children = rebuilt.as_container().unwrap().children().to_vec();
+        assert!(elem_lists_are_same(&new_children, &[old_children[1].clone(),
+                                                     old_children[0].clone()]));
+    }
+
+    #[test]
+    fn test_unkeyed_children_matched_in_order() {
+        let elem_ctx = ElementContext::new();
+        let pres_ctx = PresBuildCtx::new(&elem_ctx);
+        let existing = rebuild_children(&[text("a"), keyed("k"), text("b")], &[], &pres_ctx);
+        let children = rebuild_children(&[text("x"), text("y"), text("z")], &existing,
+                                        &pres_ctx);
+        // The keyed element is not matched by presentations without a key
+        assert!(elems_are_same(&children[0], &existing[0]));
+        assert!(elems_are_same(&children[1], &existing[2]));
+        assert!(!elems_are_same(&children[2], &existing[1]));
+        assert_eq!(texts(&children), vec!["x", "y", "z"]);
+    }
+
+    #[test]
+    fn test_reuse_clears_key_and_id() {
+        let elem_ctx = ElementContext::new();
+        let pres_ctx = PresBuildCtx::new(&elem_ctx);
+        let elem = WithId::new(keyed("a"), "x".to_string()).build(&pres_ctx);
+        assert_eq!(elem.get_key(), Some("a".to_string()));
+        assert_eq!(elem.get_id(), Some("x".to_string()));
+
+        // The element is reused by a presentation without a key or id, which clears them
+        let rebuilt = text("a").rebuild(&elem, &pres_ctx);
+        assert!(elems_are_same(&rebuilt, &elem));
+        assert_eq!(elem.get_key(), None);
+        assert_eq!(elem.get_id(), None);
+    }
+}
@@ -0,0 +1,404 @@
+use std::rc::Rc;
+use std::collections::HashMap;
+
+use geom::colour::Colour;
+use layout::flow_layout::FlowIndent;
+use elements::element::ElementRef;
+use elements::text_element::{TextStyleParams, TextWeight, TextSlant};
+use pres::pres::{Pres, TPres, PresBuildCtx};
+use pres::visitor::TPresVisitor;
+
+
+/// A set of style values that modify a style sheet.
+/// Each value is optional; `None` indicates that the value is inherited from the style sheet
+/// that the values are applied to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleValues {
+    pub text_font_family: Option<String>,
+    pub text_weight: Option<TextWeight>,
+    pub text_slant: Option<TextSlant>,
+    pub text_size: Option<f64>,
+    pub text_colour: Option<Colour>,
+
+    pub column_y_spacing: Option<f64>,
+
+    pub row_x_spacing: Option<f64>,
+
+    pub flow_x_spacing: Option<f64>,
+    pub flow_y_spacing: Option<f64>,
+    pub flow_indentation: Option<FlowIndent>,
+}
+
+impl StyleValues {
+    /// Construct an empty set of style values; applying it leaves a style sheet unchanged
+    pub fn new() -> StyleValues {
+        StyleValues{text_font_family: None, text_weight: None, text_slant: None,
+                    text_size: None, text_colour: None,
+                    column_y_spacing: None,
+                    row_x_spacing: None,
+                    flow_x_spacing: None, flow_y_spacing: None, flow_indentation: None}
+    }
+
+    /// Determine if applying these values would leave a style sheet unchanged
+    pub fn is_empty(&self) -> bool {
+        *self == StyleValues::new()
+    }
+
+    /// Combine two sets of values; values from `other` take precedence over those in `self`
+    pub fn overridden_by(&self, other: &StyleValues) -> StyleValues {
+        StyleValues{
+            text_font_family: other.text_font_family.clone().or(self.text_font_family.clone()),
+            text_weight: other.text_weight.or(self.text_weight),
+            text_slant: other.text_slant.or(self.text_slant),
+            text_size: other.text_size.or(self.text_size),
+            text_colour: other.text_colour.or(self.text_colour),
+
+            column_y_spacing: other.column_y_spacing.or(self.column_y_spacing),
+
+            row_x_spacing: other.row_x_spacing.or(self.row_x_spacing),
+
+            flow_x_spacing: other.flow_x_spacing.or(self.flow_x_spacing),
+            flow_y_spacing: other.flow_y_spacing.or(self.flow_y_spacing),
+            flow_indentation: other.flow_indentation.or(self.flow_indentation),
+        }
+    }
+}
+
+
+/// Style sheet
+///
+/// Style sheets cascade down the presentation tree as it is built; `ApplyStyleSheet` and
+/// `ApplyStyleClass` derive a modified style sheet that is used to build their child.
+///
+/// Text style is inherited by all descendants. Spacing and indentation values are consumed by
+/// the nearest container of the matching type; e.g. `column_y_spacing` is used by the first
+/// `Column` that is encountered and is reset to `0` for the children of that `Column`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleSheet {
+    pub text: Rc<TextStyleParams>,
+
+    pub column_y_spacing: f64,
+
+    pub row_x_spacing: f64,
+
+    pub flow_x_spacing: f64,
+    pub flow_y_spacing: f64,
+    pub flow_indentation: FlowIndent,
+
+    classes: Rc<HashMap<String, StyleValues>>,
+}
+
+impl StyleSheet {
+    pub fn default() -> StyleSheet {
+        StyleSheet{
+            text: Rc::new(TextStyleParams::default()),
+
+            column_y_spacing: 0.0,
+
+            row_x_spacing: 0.0,
+
+            flow_x_spacing: 0.0,
+            flow_y_spacing: 0.0,
+            flow_indentation: FlowIndent::NoIndent,
+
+            classes: Rc::new(HashMap::new()),
+        }
+    }
+
+    /// Derive a style sheet by applying the given values
+    pub fn with_values(&self, values: &StyleValues) -> StyleSheet {
+        let text = match (&values.text_font_family, values.text_weight, values.text_slant,
+                          values.text_size, values.text_colour) {
+            (&None, None, None, None, None) => self.text.clone(),
+            (a, b, c, d, e) => Rc::new(TextStyleParams{
+                font_family: a.clone().unwrap_or(self.text.font_family.clone()),
+                weight: b.unwrap_or(self.text.weight),
+                slant: c.unwrap_or(self.text.slant),
+                size: d.unwrap_or(self.text.size),
+                colour: e.unwrap_or(self.text.colour)})
+        };
+
+        StyleSheet{
+            text: text,
+
+            column_y_spacing: values.column_y_spacing.unwrap_or(self.column_y_spacing),
+
+            row_x_spacing: values.row_x_spacing.unwrap_or(self.row_x_spacing),
+
+            flow_x_spacing: values.flow_x_spacing.unwrap_or(self.flow_x_spacing),
+            flow_y_spacing: values.flow_y_spacing.unwrap_or(self.flow_y_spacing),
+            flow_indentation: values.flow_indentation.unwrap_or(self.flow_indentation),
+
+            classes: self.classes.clone(),
+        }
+    }
+
+    /// Derive a style sheet in which the named style class `name` is defined by `values`.
+    /// Defining a class that already exists replaces it.
+    pub fn with_class(&self, name: &str, values: StyleValues) -> StyleSheet {
+        let mut classes = (*self.classes).clone();
+        classes.insert(name.to_string(), values);
+        let mut s = self.clone();
+        s.classes = Rc::new(classes);
+        s
+    }
+
+    /// Get the values of the named style class
+    pub fn class(&self, name: &str) -> Option<&StyleValues> {
+        self.classes.get(name)
+    }
+
+    /// Derive a style sheet by applying the values of the named style class. Unknown class names
+    /// leave the style sheet unchanged.
+    pub fn with_class_applied(&self, name: &str) -> StyleSheet {
+        match self.classes.get(name) {
+            Some(values) => self.with_values(values),
+            None => self.clone()
+        }
+    }
+
+    /// Derive the style sheet to be used by the children of a column
+    pub fn use_column(&self) -> StyleSheet {
+        let mut s = self.clone();
+        s.column_y_spacing = 0.0;
+        s
+    }
+
+    /// Derive the style sheet to be used by the children of a row
+    pub fn use_row(&self) -> StyleSheet {
+        let mut s = self.clone();
+        s.row_x_spacing = 0.0;
+        s
+    }
+
+    /// Derive the style sheet to be used by the children of a flow
+    pub fn use_flow(&self) -> StyleSheet {
+        let mut s = self.clone();
+        s.flow_x_spacing = 0.0;
+        s.flow_y_spacing = 0.0;
+        s.flow_indentation = FlowIndent::NoIndent;
+        s
+    }
+}
+
+
+/// Build the child presentation using a style sheet derived by applying style values
+pub struct ApplyStyleSheet {
+    child: Pres,
+    values: StyleValues,
+}
+
+impl ApplyStyleSheet {
+    pub fn new(child: Pres, values: StyleValues) -> Pres {
+        Box::new(ApplyStyleSheet{child: child, values: values})
+    }
+
+    pub fn child(&self) -> &Pres {
+        &self.child
+    }
+
+    pub fn values(&self) -> &StyleValues {
+        &self.values
+    }
+}
+
+impl TPres for ApplyStyleSheet {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let child_ctx = pres_ctx.with_style(pres_ctx.style.with_values(&self.values));
+        self.child.build(&child_ctx)
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let child_ctx = pres_ctx.with_style(pres_ctx.style.with_values(&self.values));
+        self.child.rebuild(existing, &child_ctx)
+    }
+
+    fn key(&self) -> Option<&str> {
+        self.child.key()
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_apply_style_sheet(self);
+    }
+}
+
+
+/// Build the child presentation using a style sheet derived by applying a named style class
+pub struct ApplyStyleClass {
+    child: Pres,
+    class_name: String,
+}
+
+impl ApplyStyleClass {
+    pub fn new(child: Pres, class_name: String) -> Pres {
+        Box::new(ApplyStyleClass{child: child, class_name: class_name})
+    }
+
+    pub fn child(&self) -> &Pres {
+        &self.child
+    }
+
+    pub fn class_name(&self) -> &String {
+        &self.class_name
+    }
+}
+
+impl TPres for ApplyStyleClass {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let child_ctx = pres_ctx.with_style(pres_ctx.style.with_class_applied(&self.class_name));
+        self.child.build(&child_ctx)
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let child_ctx = pres_ctx.with_style(pres_ctx.style.with_class_applied(&self.class_name));
+        self.child.rebuild(existing, &child_ctx)
+    }
+
+    fn key(&self) -> Option<&str> {
+        self.child.key()
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_apply_style_class(self);
+    }
+}
+
+
+/// Build the child presentation with additional named style classes defined
+pub struct DefineStyleClasses {
+    child: Pres,
+    classes: Vec<(String, StyleValues)>,
+}
+
+impl DefineStyleClasses {
+    pub fn new(child: Pres, classes: Vec<(String, StyleValues)>) -> Pres {
+        Box::new(DefineStyleClasses{child: child, classes: classes})
+    }
+
+    pub fn child(&self) -> &Pres {
+        &self.child
+    }
+
+    pub fn classes(&self) -> &Vec<(String, StyleValues)> {
+        &self.classes
+    }
+}
+
+impl DefineStyleClasses {
+    fn child_style(&self, pres_ctx: &PresBuildCtx) -> StyleSheet {
+        let mut style = (*pres_ctx.style).clone();
+        for &(ref name, ref values) in self.classes.iter() {
+            style = style.with_class(name, values.clone());
+        }
+        style
+    }
+}
+
+impl TPres for DefineStyleClasses {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let child_ctx = pres_ctx.with_style(self.child_style(pres_ctx));
+        self.child.build(&child_ctx)
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let child_ctx = pres_ctx.with_style(self.child_style(pres_ctx));
+        self.child.rebuild(existing, &child_ctx)
+    }
+
+    fn key(&self) -> Option<&str> {
+        self.child.key()
+    }
+
+    fn accept(&self, visitor: &mut TPresVisitor) {
+        visitor.visit_define_style_classes(self);
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::colour::Colour;
+    use layout::flow_layout::FlowIndent;
+    use elements::text_element::TextWeight;
+
+    #[test]
+    fn test_with_values() {
+        let s = StyleSheet::default();
+        let values = StyleValues{text_size: Some(12.0), column_y_spacing: Some(4.0),
+                                 ..StyleValues::new()};
+        let d = s.with_values(&values);
+        assert_eq!(d.text.size, 12.0);
+        assert_eq!(d.text.font_family, s.text.font_family);
+        assert_eq!(d.text.weight, s.text.weight);
+        assert_eq!(d.column_y_spacing, 4.0);
+        assert_eq!(d.row_x_spacing, 0.0);
+    }
+
+    #[test]
+    fn test_with_empty_values_shares_text_style() {
+        let s = StyleSheet::default();
+        let d = s.with_values(&StyleValues::new());
+        assert!(&*s.text as *const _ == &*d.text as *const _);
+    }
+
+    #[test]
+    fn test_use_container() {
+        let s = StyleSheet::default().with_values(&StyleValues{
+            column_y_spacing: Some(1.0), row_x_spacing: Some(2.0),
+            flow_x_spacing: Some(3.0), flow_y_spacing: Some(4.0),
+            flow_indentation: Some(FlowIndent::First{indent: 5.0}),
+            text_size: Some(20.0), ..StyleValues::new()});
+
+        let c = s.use_column();
+        assert_eq!(c.column_y_spacing, 0.0);
+        assert_eq!(c.row_x_spacing, 2.0);
+        assert_eq!(c.text.size, 20.0);
+
+        let r = s.use_row();
+        assert_eq!(r.row_x_spacing, 0.0);
+        assert_eq!(r.column_y_spacing, 1.0);
+
+        let f = s.use_flow();
+        assert_eq!(f.flow_x_spacing, 0.0);
+        assert_eq!(f.flow_y_spacing, 0.0);
+        assert_eq!(f.flow_indentation, FlowIndent::NoIndent);
+        assert_eq!(f.column_y_spacing, 1.0);
+        assert_eq!(f.text.size, 20.0);
+    }
+
+    #[test]
+    fn test_classes() {
+        let heading = StyleValues{text_weight: Some(TextWeight::Bold),
+                                  text_colour: Some(Colour::new(1.0, 0.0, 0.0, 1.0)),
+                                  ..StyleValues::new()};
+        let s = StyleSheet::default().with_class("heading", heading.clone());
+        assert_eq!(s.class("heading"), Some(&heading));
+        assert_eq!(s.class("body"), None);
+
+        let h = s.with_class_applied("heading");
+        assert_eq!(h.text.weight, TextWeight::Bold);
+        assert_eq!(h.text.colour, Colour::new(1.0, 0.0, 0.0, 1.0));
+        // Classes are inherited by derived style sheets
+        assert_eq!(h.with_values(&StyleValues::new()).class("heading"), Some(&heading));
+
+        let u = s.with_class_applied("unknown");
+        assert_eq!(u.text.weight, TextWeight::Normal);
+    }
+
+    #[test]
+    fn test_overridden_by() {
+        let a = StyleValues{text_size: Some(10.0), row_x_spacing: Some(1.0),
+                            ..StyleValues::new()};
+        let b = StyleValues{text_size: Some(12.0), ..StyleValues::new()};
+        let c = a.overridden_by(&b);
+        assert_eq!(c.text_size, Some(12.0));
+        assert_eq!(c.row_x_spacing, Some(1.0));
+        assert!(StyleValues::new().is_empty());
+        assert!(!c.is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+use pres::primitive::{Text, Border, Shadow, StateBorder, Shape, Expander, Column, Row, Flow,
+                      Stack, Canvas, LazyColumn};
+use pres::style::{ApplyStyleSheet, ApplyStyleClass, DefineStyleClasses};
+use pres::reconcile::Keyed;
+use pres::event_binding::EventBinding;
+use pres::element_id::WithId;
+
+
+/// Presentation visitor; allows the structure of a presentation tree to be inspected.
+/// Each presentation type passes itself to the matching method via `TPres::accept`.
+pub trait TPresVisitor {
+    fn visit_text(&mut self, p: &Text);
+    fn visit_border(&mut self, p: &Border);
+    fn visit_shadow(&mut self, p: &Shadow);
+    fn visit_state_border(&mut self, p: &StateBorder);
+    fn visit_shape(&mut self, p: &Shape);
+    fn visit_expander(&mut self, p: &Expander);
+    fn visit_column(&mut self, p: &Column);
+    fn visit_row(&mut self, p: &Row);
+    fn visit_flow(&mut self, p: &Flow);
+    fn visit_stack(&mut self, p: &Stack);
+    fn visit_canvas(&mut self, p: &Canvas);
+    fn visit_lazy_column(&mut self, p: &LazyColumn);
+    fn visit_apply_style_sheet(&mut self, p: &ApplyStyleSheet);
+    fn visit_apply_style_class(&mut self, p: &ApplyStyleClass);
+    fn visit_define_style_classes(&mut self, p: &DefineStyleClasses);
+    fn visit_keyed(&mut self, p: &Keyed);
+    fn visit_event_binding(&mut self, p: &EventBinding);
+    fn visit_with_id(&mut self, p: &WithId);
+
+    /// Called for presentation types that do not expose their structure
+    fn visit_opaque(&mut self);
+}
@@ -0,0 +1,19 @@
+use std::rc::Rc;
+
+use pres::pres::Pres;
+use pres::primitive::StateBorder;
+use widgets::callback::WidgetCallback;
+use widgets::theme::WidgetTheme;
+
+
+/// Push button showing `label`; `on_activate` is called when the button is clicked, or when
+/// Space or Enter is pressed while it has the focus
+pub fn button(label: Pres, on_activate: WidgetCallback<()>) -> Pres {
+    button_full(label, on_activate, false, &WidgetTheme::default())
+}
+
+pub fn button_full(label: Pres, on_activate: WidgetCallback<()>, disabled: bool,
+                   theme: &WidgetTheme) -> Pres {
+    StateBorder::new_activatable(theme.label(label, disabled), Rc::new(theme.button_borders()),
+                                 Rc::new(move || on_activate(())), disabled)
+}
@@ -0,0 +1,48 @@
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+
+
+/// Function called by a widget when the user interacts with it; the argument describes the
+/// interaction, e.g. the new state of a checkbox
+pub type WidgetCallback<A> = Rc<Fn(A)>;
+
+/// Callback that calls the closure `f`
+pub fn callback<A, F: Fn(A) + 'static>(f: F) -> WidgetCallback<A> {
+    Rc::new(f)
+}
+
+/// Callback that sends the message `message(x)` to `sender`, for applications that handle
+/// widget events in an event loop. Messages sent after the receiver has gone are dropped.
+pub fn send_to<A, T, F>(sender: Sender<T>, message: F) -> WidgetCallback<A>
+        where T: 'static, F: Fn(A) -> T + 'static {
+    Rc::new(move |x| {
+        let _ = sender.send(message(x));
+    })
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Message {
+        Checked(bool),
+    }
+
+    #[test]
+    fn test_send_to() {
+        let (tx, rx) = channel();
+        let cb = send_to(tx, |x| Message::Checked(x));
+        cb(true);
+        cb(false);
+        assert_eq!(rx.try_recv(), Ok(Message::Checked(true)));
+        assert_eq!(rx.try_recv(), Ok(Message::Checked(false)));
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,22 @@
+use std::rc::Rc;
+
+use pres::pres::Pres;
+use pres::primitive::{StateBorder, Row, Shape};
+use widgets::callback::WidgetCallback;
+use widgets::theme::WidgetTheme;
+
+
+/// Checkbox followed by `label`. Activating it calls `on_change` with the new state; the
+/// checkbox itself does not change until the application presents it again with that state.
+pub fn checkbox(label: Pres, checked: bool, on_change: WidgetCallback<bool>) -> Pres {
+    checkbox_full(label, checked, on_change, false, &WidgetTheme::default())
+}
+
+pub fn checkbox_full(label: Pres, checked: bool, on_change: WidgetCallback<bool>,
+                     disabled: bool, theme: &WidgetTheme) -> Pres {
+    let size = theme.indicator_size;
+    let indicator = Shape::new(size, size, Rc::new(theme.checkbox_shapes(checked, disabled)));
+    let content = Row::new_full(vec![indicator, theme.label(label, disabled)], theme.spacing);
+    StateBorder::new_activatable(content, Rc::new(theme.indicator_borders()),
+                                 Rc::new(move || on_change(!checked)), disabled)
+}
@@ -0,0 +1,8 @@
+pub mod callback;
+pub mod theme;
+pub mod button;
+pub mod checkbox;
+pub mod radio;
+pub mod toggle;
+pub mod text_entry;
+pub mod text_editor;
@@ -0,0 +1,32 @@
+use std::rc::Rc;
+
+use pres::pres::Pres;
+use pres::primitive::{StateBorder, Row, Column, Shape};
+use widgets::callback::WidgetCallback;
+use widgets::theme::WidgetTheme;
+
+
+/// Column of radio buttons, one for each of `options`, of which the one at index `selected`
+/// is shown selected. Activating an option calls `on_select` with its index; the application
+/// then presents the group again with that option selected.
+pub fn radio_group(options: Vec<Pres>, selected: Option<usize>,
+                   on_select: WidgetCallback<usize>) -> Pres {
+    radio_group_full(options, selected, on_select, false, &WidgetTheme::default())
+}
+
+pub fn radio_group_full(options: Vec<Pres>, selected: Option<usize>,
+                        on_select: WidgetCallback<usize>, disabled: bool,
+                        theme: &WidgetTheme) -> Pres {
+    let size = theme.indicator_size;
+    let borders = Rc::new(theme.indicator_borders());
+    let buttons = options.into_iter().enumerate().map(|(i, label)| {
+        let shapes = theme.radio_shapes(selected == Some(i), disabled);
+        let indicator = Shape::new(size, size, Rc::new(shapes));
+        let content = Row::new_full(vec![indicator, theme.label(label, disabled)],
+                                    theme.spacing);
+        let on_select = on_select.clone();
+        StateBorder::new_activatable(content, borders.clone(), Rc::new(move || on_select(i)),
+                                     disabled)
+    }).collect();
+    Column::new_full(buttons, 0.0)
+}
@@ -0,0 +1,108 @@
+use std::rc::Rc;
+
+use elements::element::ElementRef;
+use elements::text_element::TextStyleParams;
+use elements::text_editor_element::TextEditorElement;
+use pres::pres::{Pres, TPres, PresBuildCtx, reuse_element};
+use pres::primitive::Border;
+use widgets::callback::WidgetCallback;
+use widgets::theme::WidgetTheme;
+
+
+/// Presentation of a `TextEditorElement`. Rebuilding preserves the caret, selection and scroll
+/// position of the existing element, unless the text differs from the text that it is
+/// displaying.
+pub struct TextEditor {
+    text: String,
+    style: Option<Rc<TextStyleParams>>,
+    view_height: Option<f64>,
+    min_width: f64,
+    on_change: Option<WidgetCallback<String>>,
+}
+
+impl TextEditor {
+    /// Text editor that uses the text style from the style sheet
+    pub fn new(text: &str, on_change: Option<WidgetCallback<String>>) -> TextEditor {
+        TextEditor{text: text.to_string(), style: None, view_height: None, min_width: 0.0,
+                   on_change: on_change}
+    }
+
+    pub fn with_style(mut self, style: Rc<TextStyleParams>) -> TextEditor {
+        self.style = Some(style);
+        self
+    }
+
+    /// Show `view_height` of the text, scrolling to keep the caret in view
+    pub fn with_view_height(mut self, view_height: f64) -> TextEditor {
+        self.view_height = Some(view_height);
+        self
+    }
+
+    pub fn with_min_width(mut self, min_width: f64) -> TextEditor {
+        self.min_width = min_width;
+        self
+    }
+
+    pub fn into_pres(self) -> Pres {
+        Box::new(self)
+    }
+
+    fn style(&self, pres_ctx: &PresBuildCtx) -> Rc<TextStyleParams> {
+        match self.style {
+            Some(ref s) => s.clone(),
+            None => pres_ctx.style.text.clone()
+        }
+    }
+
+    fn on_change_fn(&self) -> Option<Rc<Fn(&str)>> {
+        self.on_change.as_ref().map(|f| {
+            let f = f.clone();
+            let g: Rc<Fn(&str)> = Rc::new(move |text: &str| f(text.to_string()));
+            g
+        })
+    }
+}
+
+impl TPres for TextEditor {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let e = TextEditorElement::new(&self.text, self.style(pres_ctx));
+        e.set_view_height(self.view_height);
+        e.set_min_width(self.min_width);
+        e.set_on_change(self.on_change_fn());
+        e
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<TextEditorElement>() {
+            Some(e) => {
+                let style = self.style(pres_ctx);
+                if *e.get_style() != *style {
+                    e.set_style(style);
+                }
+                if e.get_view_height() != self.view_height {
+                    e.set_view_height(self.view_height);
+                }
+                if e.get_min_width() != self.min_width {
+                    e.set_min_width(self.min_width);
+                }
+                e.set_text(&self.text);
+                e.set_on_change(self.on_change_fn());
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+}
+
+
+/// Multi-line text editor in a frame, showing `view_height` of the text; `on_change` is called
+/// with the new text whenever the user edits it
+pub fn text_editor(text: &str, view_height: f64, on_change: WidgetCallback<String>) -> Pres {
+    text_editor_full(TextEditor::new(text, Some(on_change)).with_view_height(view_height),
+                     &WidgetTheme::default())
+}
+
+/// Frame `editor`
+pub fn text_editor_full(editor: TextEditor, theme: &WidgetTheme) -> Pres {
+    Border::new(editor.into_pres(), Rc::new(theme.entry_border()))
+}
@@ -0,0 +1,131 @@
+use std::rc::Rc;
+
+use elements::element::{ElementRef, elem_as_ref};
+use elements::text_element::TextStyleParams;
+use elements::text_entry_element::{TextEntryElement, default_placeholder_style};
+use pres::pres::{Pres, TPres, PresBuildCtx, reuse_element};
+use pres::primitive::Border;
+use widgets::callback::WidgetCallback;
+use widgets::theme::WidgetTheme;
+
+
+/// Presentation of a `TextEntryElement`. Rebuilding preserves the caret and selection of the
+/// existing element, unless the text differs from the text that it is displaying.
+pub struct TextEntry {
+    text: String,
+    style: Option<Rc<TextStyleParams>>,
+    placeholder: Option<String>,
+    placeholder_style: Option<Rc<TextStyleParams>>,
+    min_width: f64,
+    max_length: Option<usize>,
+    on_change: Option<WidgetCallback<String>>,
+}
+
+impl TextEntry {
+    /// Text entry that uses the text style from the style sheet
+    pub fn new(text: &str, on_change: Option<WidgetCallback<String>>) -> TextEntry {
+        TextEntry{text: text.to_string(), style: None, placeholder: None,
+                  placeholder_style: None, min_width: 0.0, max_length: None,
+                  on_change: on_change}
+    }
+
+    pub fn with_style(mut self, style: Rc<TextStyleParams>) -> TextEntry {
+        self.style = Some(style);
+        self
+    }
+
+    /// Show `placeholder` while the text is empty; the placeholder style defaults to a faded
+    /// version of the text style
+    pub fn with_placeholder(mut self, placeholder: &str,
+                            style: Option<Rc<TextStyleParams>>) -> TextEntry {
+        self.placeholder = Some(placeholder.to_string());
+        self.placeholder_style = style;
+        self
+    }
+
+    pub fn with_min_width(mut self, min_width: f64) -> TextEntry {
+        self.min_width = min_width;
+        self
+    }
+
+    pub fn with_max_length(mut self, max_length: usize) -> TextEntry {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    pub fn into_pres(self) -> Pres {
+        Box::new(self)
+    }
+
+    fn styles(&self, pres_ctx: &PresBuildCtx) -> (Rc<TextStyleParams>, Rc<TextStyleParams>) {
+        let style = match self.style {
+            Some(ref s) => s.clone(),
+            None => pres_ctx.style.text.clone()
+        };
+        let placeholder_style = match self.placeholder_style {
+            Some(ref s) => s.clone(),
+            None => Rc::new(default_placeholder_style(&style))
+        };
+        (style, placeholder_style)
+    }
+
+    fn on_change_fn(&self) -> Option<Rc<Fn(&str)>> {
+        self.on_change.as_ref().map(|f| {
+            let f = f.clone();
+            let g: Rc<Fn(&str)> = Rc::new(move |text: &str| f(text.to_string()));
+            g
+        })
+    }
+}
+
+impl TPres for TextEntry {
+    fn build(&self, pres_ctx: &PresBuildCtx) -> ElementRef {
+        let (style, placeholder_style) = self.styles(pres_ctx);
+        let e = TextEntryElement::new(&self.text, style);
+        e.set_placeholder(self.placeholder.clone());
+        e.set_placeholder_style(placeholder_style);
+        e.set_min_width(self.min_width);
+        e.set_max_length(self.max_length);
+        e.set_on_change(self.on_change_fn());
+        elem_as_ref(e)
+    }
+
+    fn rebuild(&self, existing: &ElementRef, pres_ctx: &PresBuildCtx) -> ElementRef {
+        match existing.as_any().downcast_ref::<TextEntryElement>() {
+            Some(e) => {
+                let (style, placeholder_style) = self.styles(pres_ctx);
+                if *e.get_style() != *style {
+                    e.set_style(style);
+                }
+                if e.get_placeholder() != self.placeholder {
+                    e.set_placeholder(self.placeholder.clone());
+                }
+                if *e.get_placeholder_style() != *placeholder_style {
+                    e.set_placeholder_style(placeholder_style);
+                }
+                if e.get_min_width() != self.min_width {
+                    e.set_min_width(self.min_width);
+                }
+                if e.get_max_length() != self.max_length {
+                    e.set_max_length(self.max_length);
+                }
+                e.set_text(&self.text);
+                e.set_on_change(self.on_change_fn());
+                reuse_element(existing)
+            },
+            None => self.build(pres_ctx)
+        }
+    }
+}
+
+
+/// Single line text entry in a frame; `on_change` is called with the new text whenever the
+/// user edits it
+pub fn text_entry(text: &str, on_change: WidgetCallback<String>) -> Pres {
+    text_entry_full(TextEntry::new(text, Some(on_change)), &WidgetTheme::default())
+}
+
+/// Frame `entry`, which may have a placeholder, a maximum length or a minimum width
+pub fn text_entry_full(entry: TextEntry, theme: &WidgetTheme) -> Pres {
+    Border::new(entry.into_pres(), Rc::new(theme.entry_border()))
+}
@@ -0,0 +1,172 @@
+use std::rc::Rc;
+
+use geom::colour::{Colour, WHITE};
+use geom::point2::Point2;
+use geom::vector2::Vector2;
+use graphics::border::{Border, StateBorders};
+use graphics::shape::Shape;
+use pres::pres::Pres;
+use pres::style::{StyleValues, ApplyStyleSheet};
+
+
+/// Colours and dimensions used to draw the built-in widgets
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetTheme {
+    pub background: Colour,
+    pub hover_background: Colour,
+    pub pressed_background: Colour,
+    pub border_colour: Colour,
+    pub focus_colour: Colour,
+    /// Colour of checked checkboxes, selected radio buttons and toggles that are on
+    pub accent: Colour,
+    pub disabled_colour: Colour,
+    /// Space between the border of a button and its label
+    pub padding: f64,
+    pub rounding: f64,
+    /// Size of checkbox and radio button indicators; toggles are twice as wide
+    pub indicator_size: f64,
+    /// Space between an indicator and its label
+    pub spacing: f64,
+}
+
+impl WidgetTheme {
+    pub fn default() -> WidgetTheme {
+        WidgetTheme{
+            background: Colour::from_u8(0xf4, 0xf4, 0xf4, 0xff),
+            hover_background: Colour::from_u8(0xe4, 0xec, 0xf7, 0xff),
+            pressed_background: Colour::from_u8(0xc8, 0xd8, 0xef, 0xff),
+            border_colour: Colour::from_u8(0x8c, 0x8c, 0x8c, 0xff),
+            focus_colour: Colour::from_u8(0x35, 0x84, 0xe4, 0xff),
+            accent: Colour::from_u8(0x35, 0x84, 0xe4, 0xff),
+            disabled_colour: Colour::from_u8(0xb8, 0xb8, 0xb8, 0xff),
+            padding: 4.0,
+            rounding: 3.0,
+            indicator_size: 14.0,
+            spacing: 6.0,
+        }
+    }
+
+    /// Border of a push button in each state. Every state has the same margins, so that
+    /// changes of state only require a redraw.
+    pub fn button_borders(&self) -> StateBorders {
+        let border = |colour: Colour, background: Colour| Rc::new(Border::new_solid(
+            1.0, self.padding, self.rounding, colour, Some(background)));
+        StateBorders::new(border(self.border_colour, self.background))
+            .with_hovered(border(self.border_colour, self.hover_background))
+            .with_pressed(border(self.border_colour, self.pressed_background))
+            .with_focused(border(self.focus_colour, self.background))
+            .with_disabled(border(self.disabled_colour, self.background))
+    }
+
+    /// Frame around a text entry or text editor
+    pub fn entry_border(&self) -> Border {
+        Border::new_solid(1.0, self.padding, self.rounding, self.border_colour, Some(WHITE))
+    }
+
+    /// Border around a checkbox, radio button or toggle and its label; it is only visible
+    /// while the widget is hovered, pressed or focused
+    pub fn indicator_borders(&self) -> StateBorders {
+        let inset = (self.padding * 0.5).max(1.0);
+        let filled = |background: Option<Colour>| Rc::new(Border::new_filled(
+            inset + 1.0, inset + 1.0, inset + 1.0, inset + 1.0, self.rounding, background));
+        StateBorders::new(filled(None))
+            .with_hovered(filled(Some(self.hover_background)))
+            .with_pressed(filled(Some(self.pressed_background)))
+            .with_focused(Rc::new(Border::new_solid(1.0, inset, self.rounding,
+                                                    self.focus_colour, None)))
+    }
+
+    /// Shapes of a checkbox indicator
+    pub fn checkbox_shapes(&self, checked: bool, disabled: bool) -> Vec<Shape> {
+        let s = self.indicator_size;
+        let colour = if disabled {self.disabled_colour} else {self.accent};
+        let mut shapes = vec![];
+        if checked {
+            shapes.push(Shape::rectangle(Point2::new(0.5, 0.5), Vector2::new(s - 1.0, s - 1.0),
+                                         self.rounding).filled(colour).stroked(1.0, colour));
+            shapes.push(Shape::polyline(vec![Point2::new(s * 0.25, s * 0.5),
+                                             Point2::new(s * 0.43, s * 0.7),
+                                             Point2::new(s * 0.75, s * 0.3)], false)
+                        .stroked(2.0, WHITE));
+        } else {
+            let border = if disabled {self.disabled_colour} else {self.border_colour};
+            shapes.push(Shape::rectangle(Point2::new(0.5, 0.5), Vector2::new(s - 1.0, s - 1.0),
+                                         self.rounding).filled(WHITE).stroked(1.0, border));
+        }
+        shapes
+    }
+
+    /// Shapes of a radio button indicator
+    pub fn radio_shapes(&self, selected: bool, disabled: bool) -> Vec<Shape> {
+        let s = self.indicator_size;
+        let centre = Point2::new(s * 0.5, s * 0.5);
+        let colour = if disabled {self.disabled_colour} else {self.accent};
+        let border = if selected || disabled {colour} else {self.border_colour};
+        let mut shapes = vec![Shape::circle(centre, s * 0.5 - 0.5).filled(WHITE)
+                              .stroked(1.0, border)];
+        if selected {
+            shapes.push(Shape::circle(centre, s * 0.25).filled(colour));
+        }
+        shapes
+    }
+
+    /// Size of a toggle switch indicator
+    pub fn toggle_size(&self) -> (f64, f64) {
+        (self.indicator_size * 2.0, self.indicator_size)
+    }
+
+    /// Shapes of a toggle switch indicator: a rounded track with a knob that sits at the right
+    /// hand end when the toggle is on
+    pub fn toggle_shapes(&self, on: bool, disabled: bool) -> Vec<Shape> {
+        let (w, h) = self.toggle_size();
+        let track = if disabled {
+            self.disabled_colour
+        } else if on {
+            self.accent
+        } else {
+            self.border_colour
+        };
+        let knob_x = if on {w - h * 0.5} else {h * 0.5};
+        vec![Shape::rectangle(Point2::origin(), Vector2::new(w, h), h * 0.5).filled(track),
+             Shape::circle(Point2::new(knob_x, h * 0.5), h * 0.5 - 2.0).filled(WHITE)]
+    }
+
+    /// Show the label of a disabled widget in the disabled colour
+    pub fn label(&self, label: Pres, disabled: bool) -> Pres {
+        if disabled {
+            ApplyStyleSheet::new(label, StyleValues{text_colour: Some(self.disabled_colour),
+                                                    ..StyleValues::new()})
+        } else {
+            label
+        }
+    }
+}
+
+
+//
+// TESTS
+//
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_margins() {
+        // Widget borders keep the same margins in every state
+        let theme = WidgetTheme::default();
+        for borders in [theme.button_borders(), theme.indicator_borders()].iter() {
+            let n = borders.normal.clone();
+            for b in [&borders.hovered, &borders.pressed, &borders.focused,
+                      &borders.disabled].iter() {
+                match **b {
+                    Some(ref b) => {
+                        assert_eq!(b.left_margin(), n.left_margin());
+                        assert_eq!(b.top_margin(), n.top_margin());
+                    },
+                    None => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use std::rc::Rc;
+
+use pres::pres::Pres;
+use pres::primitive::{StateBorder, Row, Shape};
+use widgets::callback::WidgetCallback;
+use widgets::theme::WidgetTheme;
+
+
+/// Toggle switch followed by `label`. Activating it calls `on_change` with the new state; as
+/// with `checkbox`, the application presents the switch again to show that state.
+pub fn toggle(label: Pres, on: bool, on_change: WidgetCallback<bool>) -> Pres {
+    toggle_full(label, on, on_change, false, &WidgetTheme::default())
+}
+
+pub fn toggle_full(label: Pres, on: bool, on_change: WidgetCallback<bool>, disabled: bool,
+                   theme: &WidgetTheme) -> Pres {
+    let (w, h) = theme.toggle_size();
+    let switch = Shape::new(w, h, Rc::new(theme.toggle_shapes(on, disabled)));
+    let content = Row::new_full(vec![switch, theme.label(label, disabled)], theme.spacing);
+    StateBorder::new_activatable(content, Rc::new(theme.indicator_borders()),
+                                 Rc::new(move || on_change(!on)), disabled)
+}